@@ -53,6 +53,20 @@ impl<E: Curve, H: Digest + Clone> HomoELGamalProof<E, H> {
     pub fn prove(
         w: &HomoElGamalWitness<E>,
         delta: &HomoElGamalStatement<E>,
+    ) -> HomoELGamalProof<E, H> {
+        Self::prove_with_ctx(w, delta, &[])
+    }
+
+    pub fn verify(&self, delta: &HomoElGamalStatement<E>) -> Result<(), ProofError> {
+        self.verify_with_ctx(delta, &[])
+    }
+
+    // modify: bind the challenge to a caller-defined context (session, prover, round),
+    // so that a proof cannot be replayed outside the context it was created for.
+    pub fn prove_with_ctx(
+        w: &HomoElGamalWitness<E>,
+        delta: &HomoElGamalStatement<E>,
+        ctx: &[u8],
     ) -> HomoELGamalProof<E, H> {
         let s1: Scalar<E> = Scalar::random();
         let s2: Scalar<E> = Scalar::random();
@@ -60,15 +74,7 @@ impl<E: Curve, H: Digest + Clone> HomoELGamalProof<E, H> {
         let A2 = &delta.Y * &s2;
         let A3 = &delta.G * &s2;
         let T = A1 + A2;
-        let e = H::new()
-            .chain_point(&T)
-            .chain_point(&A3)
-            .chain_point(&delta.G)
-            .chain_point(&delta.H)
-            .chain_point(&delta.Y)
-            .chain_point(&delta.D)
-            .chain_point(&delta.E)
-            .result_scalar();
+        let e = Self::challenge(&T, &A3, delta, ctx);
         // dealing with zero field element
         let z1 = &s1 + &w.x * &e;
         let z2 = s2 + &w.r * e;
@@ -80,16 +86,13 @@ impl<E: Curve, H: Digest + Clone> HomoELGamalProof<E, H> {
             hash_choice: HashChoice::new(),
         }
     }
-    pub fn verify(&self, delta: &HomoElGamalStatement<E>) -> Result<(), ProofError> {
-        let e = H::new()
-            .chain_point(&self.T)
-            .chain_point(&self.A3)
-            .chain_point(&delta.G)
-            .chain_point(&delta.H)
-            .chain_point(&delta.Y)
-            .chain_point(&delta.D)
-            .chain_point(&delta.E)
-            .result_scalar();
+
+    pub fn verify_with_ctx(
+        &self,
+        delta: &HomoElGamalStatement<E>,
+        ctx: &[u8],
+    ) -> Result<(), ProofError> {
+        let e = Self::challenge(&self.T, &self.A3, delta, ctx);
         let z1H_plus_z2Y = &delta.H * &self.z1 + &delta.Y * &self.z2;
         let T_plus_eD = &self.T + &delta.D * &e;
         let z2G = &delta.G * &self.z2;
@@ -100,6 +103,28 @@ impl<E: Curve, H: Digest + Clone> HomoELGamalProof<E, H> {
             Err(ProofError)
         }
     }
+
+    // an empty context yields the original (context-free) challenge
+    fn challenge(
+        T: &Point<E>,
+        A3: &Point<E>,
+        delta: &HomoElGamalStatement<E>,
+        ctx: &[u8],
+    ) -> Scalar<E> {
+        let mut hasher = H::new();
+        if !ctx.is_empty() {
+            hasher.update(ctx);
+        }
+        hasher
+            .chain_point(T)
+            .chain_point(A3)
+            .chain_point(&delta.G)
+            .chain_point(&delta.H)
+            .chain_point(&delta.Y)
+            .chain_point(&delta.D)
+            .chain_point(&delta.E)
+            .result_scalar()
+    }
 }
 
 #[cfg(test)]
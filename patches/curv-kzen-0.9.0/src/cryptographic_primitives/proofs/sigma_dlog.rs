@@ -35,6 +35,16 @@ pub struct DLogProof<E: Curve, H: Digest + Clone> {
 
 impl<E: Curve, H: Digest + Clone> DLogProof<E, H> {
     pub fn prove(sk: &Scalar<E>) -> DLogProof<E, H> {
+        Self::prove_with_ctx(sk, &[])
+    }
+
+    pub fn verify(proof: &DLogProof<E, H>) -> Result<(), ProofError> {
+        Self::verify_with_ctx(proof, &[])
+    }
+
+    // modify: bind the challenge to a caller-defined context (session, prover, round),
+    // so that a proof cannot be replayed outside the context it was created for.
+    pub fn prove_with_ctx(sk: &Scalar<E>, ctx: &[u8]) -> DLogProof<E, H> {
         let generator = Point::<E>::generator();
 
         let sk_t_rand_commitment = Scalar::random();
@@ -42,11 +52,7 @@ impl<E: Curve, H: Digest + Clone> DLogProof<E, H> {
 
         let pk = Point::generator() * sk;
 
-        let challenge = H::new()
-            .chain_point(&pk_t_rand_commitment)
-            .chain_point(&generator.to_point())
-            .chain_point(&pk)
-            .result_scalar();
+        let challenge = Self::challenge(&pk_t_rand_commitment, &pk, ctx);
 
         let challenge_mul_sk = challenge * sk;
         let challenge_response = &sk_t_rand_commitment - &challenge_mul_sk;
@@ -58,14 +64,10 @@ impl<E: Curve, H: Digest + Clone> DLogProof<E, H> {
         }
     }
 
-    pub fn verify(proof: &DLogProof<E, H>) -> Result<(), ProofError> {
+    pub fn verify_with_ctx(proof: &DLogProof<E, H>, ctx: &[u8]) -> Result<(), ProofError> {
         let generator = Point::<E>::generator();
 
-        let challenge = H::new()
-            .chain_point(&proof.pk_t_rand_commitment)
-            .chain_point(&generator.to_point())
-            .chain_point(&proof.pk)
-            .result_scalar();
+        let challenge = Self::challenge(&proof.pk_t_rand_commitment, &proof.pk, ctx);
 
         let pk_challenge = &proof.pk * &challenge;
 
@@ -77,6 +79,19 @@ impl<E: Curve, H: Digest + Clone> DLogProof<E, H> {
             Err(ProofError)
         }
     }
+
+    // an empty context yields the original (context-free) challenge
+    fn challenge(pk_t_rand_commitment: &Point<E>, pk: &Point<E>, ctx: &[u8]) -> Scalar<E> {
+        let mut hasher = H::new();
+        if !ctx.is_empty() {
+            hasher.update(ctx);
+        }
+        hasher
+            .chain_point(pk_t_rand_commitment)
+            .chain_point(&Point::<E>::generator().to_point())
+            .chain_point(pk)
+            .result_scalar()
+    }
 }
 
 #[cfg(test)]
@@ -89,4 +104,13 @@ mod tests {
         let dlog_proof = DLogProof::<E, H>::prove(&witness);
         assert!(DLogProof::verify(&dlog_proof).is_ok());
     }
+
+    crate::test_for_all_curves_and_hashes!(test_dlog_proof_with_ctx);
+    fn test_dlog_proof_with_ctx<E: Curve, H: Digest + Clone>() {
+        let witness = Scalar::random();
+        let dlog_proof = DLogProof::<E, H>::prove_with_ctx(&witness, b"session-1");
+        assert!(DLogProof::verify_with_ctx(&dlog_proof, b"session-1").is_ok());
+        assert!(DLogProof::verify_with_ctx(&dlog_proof, b"session-2").is_err());
+        assert!(DLogProof::verify(&dlog_proof).is_err());
+    }
 }
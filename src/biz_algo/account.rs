@@ -0,0 +1,322 @@
+// hardened child key shares without reconstructing the parent key
+//
+// A hardened step of BIP-32 hashes the parent private key. Instead, t' signers
+// jointly evaluate a threshold DDH-PRF: for a point P hashed from the parent
+// key, chain code and index, each signer j reveals x_j * P with a DLEQ proof
+// against x_j * G, and the Lagrange combination gives x * P. Then
+//   (IL, IR) = HMAC-SHA512(c, x * P || index)
+// and every share becomes x_j + IL, the public key Y + IL * G and the chain
+// code IR, as in a normal step. The result is written as an account keys file
+// below which `sign -p` keeps applying the non-hardened tweaks.
+use std::time;
+
+use crate::mp_ecdsa::{
+    feldman_vss::VerifiableSS,
+    party_i::*,
+    secret::Secret,
+    validate::{check_point, check_scalar, Validate, ValidationError},
+};
+use bip32::{ChainCode, DerivationPath};
+use curv::{
+    arithmetic::Converter,
+    cryptographic_primitives::proofs::sigma_ec_ddh::{ECDDHProof, ECDDHStatement, ECDDHWitness},
+    elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar},
+    BigInt,
+};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use std::str::FromStr;
+
+use crate::biz_algo::{
+    aes_decrypt, aes_encrypt, hd, parse_msg, poll_for_p2p, sendp2p,
+    sign::{agree, SignAgreement},
+    signup, Error, Params, AEAD,
+};
+
+/// Parts of the keys file that change at the account; the rest is kept
+#[derive(Clone, Debug)]
+pub struct AccountShare {
+    pub shared_keys: SharedKeys,
+    pub vss_scheme_vec: Vec<VerifiableSS<Secp256k1>>,
+    pub y_sum: Point<Secp256k1>,
+    pub chain_code: ChainCode,
+}
+
+/// Keys file item of an account: its hardened levels are not BIP-32, so the
+/// account key has no BIP-32 origin below the root
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountOrigin {
+    /// path of the account below the root key
+    pub account: String,
+}
+
+impl AccountOrigin {
+    /// Origin of the account at `path` below the key of `parent`
+    pub fn new(parent: Option<&AccountOrigin>, path: &str) -> Self {
+        let account = match parent {
+            Some(parent) => format!("{}/{}", parent.account, path.trim_start_matches("m/")),
+            None => path.to_string(),
+        };
+        Self { account }
+    }
+}
+
+/// Origin recorded in a keys file, None for a root key
+pub fn account_of(data: &str) -> Option<AccountOrigin> {
+    let keys: serde_json::Value = serde_json::from_str(data).unwrap();
+    let item = keys
+        .as_array()?
+        .iter()
+        .skip(7)
+        .find(|item| item.get("account").is_some())?;
+    Some(serde_json::from_value(item.clone()).unwrap())
+}
+
+/// `x_j * P` of one signer and the proof that it uses the same `x_j` as `x_j * G`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HardenedShare {
+    pub d_j: Point<Secp256k1>,
+    pub proof: ECDDHProof<Secp256k1, Sha256>,
+}
+
+// state shared by the hardened levels of a derivation
+struct AccountSession<'a> {
+    addr: &'a String,
+    client: Client,
+    delay: time::Duration,
+    party_num_int: u16,
+    parties: u16,
+    uuid: String,
+    signers_vec: Vec<u16>,
+    lambda_vec: Vec<Scalar<Secp256k1>>,
+    xi_com_vec: Vec<Point<Secp256k1>>,
+    enc_keys: Vec<Vec<u8>>,
+}
+
+impl Validate for HardenedShare {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_point(&self.d_j, "hardened_share.d_j")?;
+        check_point(&self.proof.a1, "hardened_share.proof.a1")?;
+        check_point(&self.proof.a2, "hardened_share.proof.a2")?;
+        check_scalar(&self.proof.z, "hardened_share.proof.z")
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn derive_account(
+    addr: &String,
+    shared_keys: &SharedKeys,
+    party_id: u16,
+    vss_scheme_vec: &[VerifiableSS<Secp256k1>],
+    root_y_sum: &Point<Secp256k1>,
+    chain_code: ChainCode,
+    params: &Params,
+    path: &str,
+) -> Result<AccountShare, Error> {
+    let derivation = DerivationPath::from_str(path)
+        .map_err(|e| Error::ParamInvalid(format!("invalid path {:?}: {}", path, e)))?;
+    if !derivation.iter().any(|child| child.is_hardened()) {
+        return Err(Error::ParamInvalid(format!(
+            "{} has no hardened level, use it with `sign -p` directly",
+            path
+        )));
+    }
+
+    let client = Client::new();
+    let delay = time::Duration::from_millis(25);
+    let threshold: u16 = params.threshold.parse::<u16>().unwrap();
+    let parties: u16 = params.parties.parse::<u16>().unwrap();
+    let share_count: u16 = params.share_count.parse::<u16>().unwrap();
+    println!(
+        "threshold: {}, parties: {}, share count: {}",
+        threshold, parties, share_count
+    );
+    assert!(parties > threshold, "PARTIES smaller than THRESHOLD + 1");
+    assert!(parties < share_count + 1, "PARTIES bigger than SHARE_COUNT");
+
+    let party_signup = signup(addr, &client, "signupsign", params).await;
+    let party_num_int = party_signup.number;
+    let uuid = party_signup.uuid;
+    let debug = json!({"manager_addr": &addr, "party_num": party_num_int, "uuid": uuid});
+    println!("{}", serde_json::to_string_pretty(&debug).unwrap());
+
+    // round 0: agree on the path; the path doubles as the message
+    let agreement = SignAgreement::new(
+        path.as_bytes(),
+        path,
+        threshold,
+        parties,
+        share_count,
+        root_y_sum,
+        party_id,
+    );
+    let signers_vec = agree(
+        addr,
+        &client,
+        delay,
+        party_num_int,
+        parties,
+        &uuid,
+        &agreement,
+    )
+    .await?;
+    let lambda_vec = signers_vec
+        .iter()
+        .map(|signer_id| {
+            VerifiableSS::<Secp256k1>::map_share_to_new_params(
+                &vss_scheme_vec[0].parameters,
+                *signer_id,
+                &signers_vec,
+            )
+        })
+        .collect::<Vec<_>>();
+    let xi_com_vec = Keys::get_commitments_to_xi(vss_scheme_vec);
+    let x_i = &*shared_keys.x_i;
+    // pairwise keys x_i * x_j * G of the signers
+    let enc_keys = signers_vec
+        .iter()
+        .map(|signer_id| {
+            BigInt::to_bytes(
+                &(&xi_com_vec[usize::from(*signer_id)] * x_i)
+                    .x_coord()
+                    .unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let session = AccountSession {
+        addr,
+        client,
+        delay,
+        party_num_int,
+        parties,
+        uuid,
+        signers_vec,
+        lambda_vec,
+        xi_com_vec,
+        enc_keys,
+    };
+
+    // tweak, public key and chain code of the current level
+    let mut tweak_sk = Scalar::<Secp256k1>::zero();
+    let mut y_sum = root_y_sum.clone();
+    let mut chain_code = chain_code;
+    for (level, child) in derivation.iter().enumerate() {
+        let (tweak, child_chain_code) = match child.is_hardened() {
+            false => hd::child_tweak(&chain_code, &y_sum.to_bytes(true), child),
+            true => {
+                let base = hd::hardened_base(&y_sum, &chain_code, child);
+                let round = format!("round1-{}", level);
+                let x_prf = joint_prf(&session, &round, x_i, &tweak_sk, &base).await?;
+                hd::child_tweak(&chain_code, &x_prf.to_bytes(true), child)
+            }
+        }
+        .map_err(|e| Error::KeyshareError(format!("cannot derive level {}: {}", level, e)))?;
+        tweak_sk = tweak_sk + &tweak;
+        y_sum = y_sum + Point::generator() * &tweak;
+        chain_code = child_chain_code;
+    }
+
+    // shift the constant term of one dealer, as `sign` does with HD tweaks
+    let mut vss_scheme_vec = vss_scheme_vec.to_vec();
+    vss_scheme_vec[0].commitments[0] =
+        vss_scheme_vec[0].commitments[0].clone() + Point::generator() * &tweak_sk;
+    Ok(AccountShare {
+        shared_keys: SharedKeys {
+            y: y_sum.clone(),
+            x_i: Secret::new(x_i + &tweak_sk),
+        },
+        vss_scheme_vec,
+        y_sum,
+        chain_code,
+    })
+}
+
+// one hardened level: x * P from the verified x_j * P of all signers, where
+// the key is the root share `x_i` moved by the `tweak_sk` of the levels above
+async fn joint_prf(
+    session: &AccountSession<'_>,
+    round: &str,
+    x_i: &Scalar<Secp256k1>,
+    tweak_sk: &Scalar<Secp256k1>,
+    base: &Point<Secp256k1>,
+) -> Result<Point<Secp256k1>, Error> {
+    let AccountSession {
+        addr,
+        client,
+        delay,
+        party_num_int,
+        parties,
+        uuid,
+        signers_vec,
+        lambda_vec,
+        xi_com_vec,
+        enc_keys,
+    } = session;
+    let (delay, party_num_int, parties) = (*delay, *party_num_int, *parties);
+    let x_i = x_i + tweak_sk;
+    let d_i = base * &x_i;
+    let proof = ECDDHProof::<Secp256k1, Sha256>::prove(
+        &ECDDHWitness { x: x_i.clone() },
+        &ECDDHStatement {
+            g1: Point::generator().to_point(),
+            h1: Point::generator() * &x_i,
+            g2: base.clone(),
+            h2: d_i.clone(),
+        },
+    );
+    let share = serde_json::to_string(&HardenedShare {
+        d_j: d_i.clone(),
+        proof,
+    })
+    .unwrap();
+
+    // x * P gives away the hardened tweak, so x_j * P only goes to the signers
+    for i in (1..=parties).filter(|i| *i != party_num_int) {
+        let aead_pack = aes_encrypt(&enc_keys[usize::from(i - 1)], share.as_bytes());
+        assert!(sendp2p(
+            addr,
+            client,
+            party_num_int,
+            i,
+            round,
+            &aead_pack,
+            uuid.to_string(),
+        )
+        .await
+        .is_ok());
+    }
+    let ans_vec = poll_for_p2p(
+        addr,
+        client,
+        party_num_int,
+        parties,
+        delay,
+        round,
+        uuid.to_string(),
+    )
+    .await;
+
+    let mut x_prf = &d_i * &lambda_vec[usize::from(party_num_int - 1)];
+    let peers = (1..=parties).filter(|i| *i != party_num_int);
+    for (i, msg) in peers.zip(&ans_vec) {
+        let aead_pack: AEAD = parse_msg(msg, &(), i, round)?;
+        let plaintext = aes_decrypt(&enc_keys[usize::from(i - 1)], aead_pack);
+        let share: HardenedShare = parse_msg(&String::from_utf8_lossy(&plaintext), &(), i, round)?;
+        let statement = ECDDHStatement {
+            g1: Point::generator().to_point(),
+            h1: &xi_com_vec[usize::from(signers_vec[usize::from(i - 1)])]
+                + Point::generator() * tweak_sk,
+            g2: base.clone(),
+            h2: share.d_j.clone(),
+        };
+        share.proof.verify(&statement).map_err(|_| {
+            Error::KeyshareError(format!("party {} in {}: invalid DLEQ proof", i, round))
+        })?;
+        x_prf = x_prf + &share.d_j * &lambda_vec[usize::from(i - 1)];
+    }
+    Ok(x_prf)
+}
@@ -0,0 +1,610 @@
+// CGGMP21 mode next to GG18, on the same transport and keys files
+//
+// A keys file becomes a CGGMP21 one with `refresh`: all n parties re-randomize their
+// key shares with a sharing of zero, replace their Paillier keys with ones over safe
+// primes and publish ring-Pedersen parameters over the same moduli, all with the
+// proofs of mp_cggmp::zk. The auxiliary info is saved as an 8th element of the keys
+// file, which GG18 commands ignore.
+//
+// Signing runs the three presigning rounds of mp_cggmp::presign at the root key and
+// then the signing round of `presign::sign_round`, which adds the HD tweak exactly
+// like GG18 presignatures do. Any party that deviates is named when the protocol
+// aborts.
+use std::iter::zip;
+
+use crate::mp_cggmp::{
+    self,
+    aux::{AuxInfo, RingPedersen},
+    presign::{Blame, Fault, Presigner, Round1, Round2, Round3, Signers},
+    zk::{fac::FacProof, modulus::ModProof, prm::PrmProof},
+};
+use crate::mp_ecdsa::{
+    feldman_vss::{ShamirSecretSharing, VerifiableSS},
+    paillier_serde,
+    party_i::*,
+    secret::Secret,
+    validate::{
+        check_ciphertext, check_encryption_key, check_len, check_point, Validate, ValidationError,
+    },
+};
+use curv::{
+    arithmetic::traits::*,
+    elliptic::curves::{Curve, Point, Scalar},
+    BigInt,
+};
+use paillier::EncryptionKey;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::biz_algo::{
+    account, broadcast, ot, parse_msg, poll_for_broadcasts, poll_for_p2p, pool,
+    presign::{sign_round, Presignature, ShareCheck},
+    sendp2p,
+    sign::{open_session, SignAgreement, SignRequest, SignSession},
+    write_secret_file, Error, Params,
+};
+
+/// Keys file of GG18, the first seven elements of every keys file
+pub type KeysFile<E> = (
+    Keys<E>,
+    SharedKeys<E>,
+    u16,
+    Vec<VerifiableSS<E>>,
+    Vec<EncryptionKey>,
+    Point<E>,
+    bip32::ChainCode,
+);
+
+/// Splits a keys file into its GG18 part and the auxiliary info of CGGMP21, if any.
+/// The base OTs of the OT-based MtA, if any, are read by `ot::ot_setup_of`.
+pub fn split_keys_file(data: &str) -> (serde_json::Value, Option<AuxInfo>) {
+    let keys: serde_json::Value = serde_json::from_str(data).unwrap();
+    // two-party keys files are objects recording their protocol
+    if let Some(protocol) = keys["protocol"].as_str() {
+        panic!("{} keys only sign, verify and derive public keys", protocol);
+    }
+    let mut keys: Vec<serde_json::Value> = serde_json::from_value(keys).unwrap();
+    let aux = keys
+        .split_off(7)
+        .into_iter()
+        .find(|item| item["protocol"].as_str() == Some(mp_cggmp::aux::PROTOCOL))
+        .map(|aux| serde_json::from_value(aux).unwrap());
+    (serde_json::Value::Array(keys), aux)
+}
+
+/// Round 1 message of `refresh`: commitment to the round 2 message
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RefreshCommit {
+    pub com: String,
+}
+
+impl Validate for RefreshCommit {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_len(self.com.len(), 64, "com")
+    }
+}
+
+/// Round 2 message of `refresh`: new auxiliary info of the sender and the
+/// commitments to its sharing of zero, without the zero constant term
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct RefreshDecommit<E: Curve> {
+    #[serde(with = "paillier_serde::encryption_key")]
+    pub ek: EncryptionKey,
+    pub ring_pedersen: RingPedersen,
+    pub commitments: Vec<Point<E>>,
+    pub mod_proof: ModProof,
+    pub prm_proof: PrmProof,
+    pub blind: BigInt,
+}
+
+impl<E: Curve> RefreshDecommit<E> {
+    fn commit(&self) -> String {
+        hex::encode(Sha256::digest(&serde_json::to_vec(self).unwrap()))
+    }
+}
+
+impl<E: Curve> Validate for RefreshDecommit<E> {
+    /// threshold
+    type Context = u16;
+
+    fn validate(&self, threshold: &u16) -> Result<(), ValidationError> {
+        check_encryption_key(&self.ek)?;
+        self.ring_pedersen.validate(&())?;
+        if self.ring_pedersen.n != self.ek.n {
+            return Err(ValidationError::OutOfRange("ring_pedersen"));
+        }
+        check_len(
+            self.commitments.len(),
+            usize::from(*threshold),
+            "commitments",
+        )?;
+        self.commitments
+            .iter()
+            .try_for_each(|c| check_point(c, "commitments"))
+    }
+}
+
+/// Round 3 message of `refresh`: the share of the receiver under its new Paillier
+/// key and a proof that the modulus of the sender has no small factor
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RefreshShare {
+    pub share: BigInt,
+    pub fac_proof: FacProof,
+}
+
+impl Validate for RefreshShare {
+    /// new key of the receiver
+    type Context = EncryptionKey;
+
+    fn validate(&self, ek: &EncryptionKey) -> Result<(), ValidationError> {
+        check_ciphertext(&self.share, ek, "share")
+    }
+}
+
+// broadcasts `msg` in `round` and returns the messages of all parties, ours included
+async fn exchange<T>(
+    session: &SignSession<'_>,
+    round: &str,
+    msg: T,
+    ctx: impl Fn(u16) -> T::Context,
+) -> Result<Vec<T>, Error>
+where
+    T: Serialize + DeserializeOwned + Validate,
+{
+    let SignSession {
+        addr,
+        client,
+        delay,
+        party_num_int,
+        parties,
+        uuid,
+        peers,
+        ..
+    } = session;
+    broadcast(addr, client, *party_num_int, round, &msg, uuid.clone()).await;
+    let ans_vec = poll_for_broadcasts(
+        addr,
+        client,
+        *party_num_int,
+        *parties,
+        *delay,
+        round,
+        uuid.clone(),
+    )
+    .await;
+    let mut msgs = zip(&ans_vec, peers)
+        .map(|(m, i)| parse_msg(m, &ctx(*i), *i, round))
+        .collect::<Result<Vec<T>, Error>>()?;
+    msgs.insert(usize::from(*party_num_int) - 1, msg);
+    Ok(msgs)
+}
+
+// identifiable abort
+fn abort(session: &SignSession<'_>, fault: Fault) -> ! {
+    panic!(
+        "party {} (key share {}) deviated from the protocol: {}",
+        fault.party,
+        session.signers_vec[usize::from(fault.party) - 1] + 1,
+        fault.reason
+    )
+}
+
+/// Re-randomizes the key shares of all n parties and replaces their auxiliary info.
+/// Also turns a GG18 keys file into a CGGMP21 one. The public key is unchanged.
+pub async fn refresh<E: Curve>(
+    addr: &String,
+    keysfile_path: &str,
+    params: &Params,
+) -> Result<(), Error> {
+    let data = std::fs::read_to_string(keysfile_path)
+        .unwrap_or_else(|_| panic!("Unable to load keys file at location: {}", keysfile_path));
+    let (keys, _) = split_keys_file(&data);
+    let ot_setup = ot::ot_setup_of(&data);
+    let (
+        mut party_keys,
+        mut shared_keys,
+        party_id,
+        mut vss_scheme_vec,
+        mut paillier_key_vec,
+        y_sum,
+        chain_code,
+    ): KeysFile<E> = serde_json::from_value(keys).unwrap();
+    let threshold: u16 = params.threshold.parse::<u16>().unwrap();
+    let share_count: u16 = params.share_count.parse::<u16>().unwrap();
+    if params.parties != params.share_count {
+        return Err(Error::ParamInvalid(
+            "all key shares take part in a refresh".to_string(),
+        ));
+    }
+    if usize::from(share_count) != vss_scheme_vec.len() {
+        return Err(Error::ParamInvalid(
+            "share count does not match the keys file".to_string(),
+        ));
+    }
+
+    let session = open_session(addr, party_id, &y_sum, params, &[], "refresh").await?;
+    let me = session.party_num_int;
+    let share_index = |party: u16| session.signers_vec[usize::from(party) - 1];
+    let tag = |round: &str, party: u16| SessionContext::new(&session.uuid, round).tag(party);
+
+    let (ek, dk) = pool::keypair(true);
+    let (ring_pedersen, lambda) = RingPedersen::generate(&dk);
+    let (vss_scheme, secret_shares) =
+        VerifiableSS::<E>::share(threshold, share_count, &Scalar::zero());
+    let decommit = RefreshDecommit {
+        ek: ek.clone(),
+        ring_pedersen: ring_pedersen.clone(),
+        commitments: vss_scheme.commitments[1..].to_vec(),
+        mod_proof: ModProof::prove(&tag("refresh-mod", me), &dk),
+        prm_proof: PrmProof::prove(&tag("refresh-prm", me), &ring_pedersen, &lambda, &dk),
+        blind: BigInt::sample(256),
+    };
+
+    // round 1: commit to the new auxiliary info and sharing of zero
+    let com = RefreshCommit {
+        com: decommit.commit(),
+    };
+    let coms = exchange(&session, "round1", com, |_| ()).await?;
+
+    // round 2: open it
+    let decommits = exchange(&session, "round2", decommit, |_| threshold).await?;
+    for j in session.peers.iter().copied() {
+        let d = &decommits[usize::from(j) - 1];
+        let fault = |reason: &str| {
+            abort(
+                &session,
+                Fault {
+                    party: j,
+                    reason: reason.to_string(),
+                },
+            )
+        };
+        if d.commit() != coms[usize::from(j) - 1].com {
+            fault("opened another commitment");
+        }
+        d.mod_proof
+            .verify(&tag("refresh-mod", j), &d.ek.n)
+            .unwrap_or_else(|e| fault(&e.to_string()));
+        d.prm_proof
+            .verify(&tag("refresh-prm", j), &d.ring_pedersen)
+            .unwrap_or_else(|e| fault(&e.to_string()));
+    }
+
+    // round 3: send shares of zero under the new key of their receiver
+    for j in session.peers.iter().copied() {
+        let d = &decommits[usize::from(j) - 1];
+        let share = &secret_shares[usize::from(share_index(j))];
+        let msg = RefreshShare {
+            share: mp_cggmp::encrypt(&d.ek, &share.to_bigint()).0,
+            fac_proof: FacProof::prove::<E>(&tag("refresh-fac", me), &dk, &d.ring_pedersen),
+        };
+        assert!(sendp2p(
+            addr,
+            &session.client,
+            me,
+            j,
+            "round3",
+            &msg,
+            session.uuid.clone(),
+        )
+        .await
+        .is_ok());
+    }
+    let round3_ans_vec = poll_for_p2p(
+        addr,
+        &session.client,
+        me,
+        session.parties,
+        session.delay,
+        "round3",
+        session.uuid.clone(),
+    )
+    .await;
+
+    let vss_params = ShamirSecretSharing {
+        threshold,
+        share_count,
+    };
+    let my_index = share_index(me);
+    let mut x_i = &*shared_keys.x_i + &secret_shares[usize::from(my_index)];
+    for (m, j) in zip(&round3_ans_vec, session.peers.iter().copied()) {
+        let msg: RefreshShare = parse_msg(m, &ek, j, "round3")?;
+        let d = &decommits[usize::from(j) - 1];
+        let fault = |reason: &str| {
+            abort(
+                &session,
+                Fault {
+                    party: j,
+                    reason: reason.to_string(),
+                },
+            )
+        };
+        msg.fac_proof
+            .verify::<E>(&tag("refresh-fac", j), &d.ek.n, &ring_pedersen)
+            .unwrap_or_else(|e| fault(&e.to_string()));
+        let share = Scalar::<E>::from_bigint(&mp_cggmp::decrypt(&dk, &msg.share));
+        let mut commitments = vec![Point::zero()];
+        commitments.extend(d.commitments.iter().cloned());
+        let vss_j = VerifiableSS {
+            parameters: vss_params.clone(),
+            commitments,
+        };
+        if vss_j.validate_share(&share, my_index + 1).is_err() {
+            fault("sent a share that does not match its commitments");
+        }
+        x_i = x_i + share;
+    }
+
+    // add the sharings of zero to the commitments to x_j of every key share
+    for (j, d) in decommits.iter().enumerate() {
+        let k = usize::from(share_index(j as u16 + 1));
+        for (c, c_j) in vss_scheme_vec[k].commitments[1..]
+            .iter_mut()
+            .zip(&d.commitments)
+        {
+            *c = &*c + c_j;
+        }
+        paillier_key_vec[k] = d.ek.clone();
+    }
+    let mut ring_pedersen_vec = vec![ring_pedersen; usize::from(share_count)];
+    for (j, d) in decommits.into_iter().enumerate() {
+        ring_pedersen_vec[usize::from(share_index(j as u16 + 1))] = d.ring_pedersen;
+    }
+    let xi_com_vec = Keys::<E>::get_commitments_to_xi(&vss_scheme_vec);
+    assert_eq!(
+        Point::generator() * &x_i,
+        xi_com_vec[usize::from(my_index)],
+        "refreshed key share does not match the commitments"
+    );
+    shared_keys.x_i = Secret::new(x_i);
+    party_keys.dk = Secret::new(dk);
+    party_keys.ek = ek;
+
+    let keys_json = ot::keys_file_json(
+        &(
+            party_keys,
+            shared_keys,
+            party_id,
+            vss_scheme_vec,
+            paillier_key_vec,
+            y_sum,
+            chain_code,
+        ),
+        Some(&AuxInfo::new(ring_pedersen_vec)),
+        ot_setup.as_ref(),
+        account::account_of(&data).as_ref(),
+    );
+    write_secret_file(keysfile_path, keys_json.as_bytes()).expect("Unable to save !");
+    println!("Keys data written to file: {:?}", keysfile_path);
+    Ok(())
+}
+
+/// Presigning rounds for the `k`-th presignature of the session, for the key at
+/// `path`. Its shares stay at the root key, `sign_round` adds the tweak.
+#[allow(clippy::too_many_arguments)]
+async fn presign_one<E: Curve>(
+    session: &SignSession<'_>,
+    party_keys: &Keys<E>,
+    shared_keys: &SharedKeys<E>,
+    party_id: u16,
+    vss_scheme_vec: &[VerifiableSS<E>],
+    paillier_key_vector: &[EncryptionKey],
+    aux: &AuxInfo,
+    root_y_sum: &Point<E>,
+    path: &str,
+    k: usize,
+) -> Result<Presignature<E>, Error> {
+    let round = |name: &str| format!("{}-{}", name, k);
+    let signers_vec = &session.signers_vec;
+    let me = session.party_num_int;
+    let others = usize::from(session.parties) - 1;
+
+    // w_j = lambda_j * x_j for the signers of this session
+    let xi_com_vec = Keys::<E>::get_commitments_to_xi(vss_scheme_vec);
+    let my_index = signers_vec[usize::from(me) - 1];
+    let w_i = VerifiableSS::<E>::map_share_to_new_params(
+        &vss_scheme_vec[usize::from(my_index)].parameters,
+        my_index,
+        signers_vec,
+    ) * &*shared_keys.x_i;
+    let signers = Signers {
+        eks: signers_vec
+            .iter()
+            .map(|s| paillier_key_vector[usize::from(*s)].clone())
+            .collect(),
+        ring_pedersen: signers_vec
+            .iter()
+            .map(|s| aux.ring_pedersen[usize::from(*s)].clone())
+            .collect(),
+        W: signers_vec
+            .iter()
+            .map(|s| {
+                Keys::<E>::update_commitments_to_xi(
+                    &xi_com_vec[usize::from(*s)],
+                    &vss_scheme_vec[usize::from(*s)],
+                    *s,
+                    signers_vec,
+                )
+            })
+            .collect(),
+    };
+    assert_eq!(
+        signers.W[usize::from(me) - 1],
+        Point::generator() * &w_i,
+        "key share does not match the commitments"
+    );
+    let eks = signers.eks.clone();
+    let ek = |j: u16| eks[usize::from(j) - 1].clone();
+    let mut presigner = Presigner::new(
+        me,
+        signers,
+        &party_keys.dk,
+        &w_i,
+        &session.uuid,
+        &round("cggmp-presign"),
+    );
+
+    // round 1: encrypt k_i and gamma_i
+    let msg = presigner.round1();
+    let msgs: Vec<Round1> = exchange(session, &round("round1"), msg, |j| (ek(j), others)).await?;
+    presigner
+        .receive_round1(msgs)
+        .unwrap_or_else(|f| abort(session, f));
+
+    // round 2: MtA for delta and chi with every other signer
+    let msg = presigner.round2();
+    let msgs: Vec<Round2<E>> =
+        exchange(session, &round("round2"), msg, |j| (eks.clone(), j)).await?;
+    presigner
+        .receive_round2(msgs)
+        .unwrap_or_else(|f| abort(session, f));
+
+    // round 3: reveal delta_i and Delta_i = k_i * Gamma
+    let msg = presigner.round3();
+    let msgs: Vec<Round3<E>> =
+        exchange(session, &round("round3"), msg, |j| (ek(j), others)).await?;
+    let output = match presigner.receive_round3(&msgs) {
+        Ok(output) => output,
+        Err(Some(fault)) => abort(session, fault),
+        Err(None) => {
+            // blame round: open the delta path
+            let reveals: Vec<Blame> =
+                exchange(session, &round("blame"), presigner.blame(), |_| others).await?;
+            abort(session, presigner.find_culprit(&reveals, &msgs))
+        }
+    };
+
+    Ok(Presignature {
+        id: format!("{}-{}", session.uuid, k),
+        key_id: hex::encode(&*root_y_sum.to_bytes(true)),
+        signers: signers_vec.clone(),
+        party_id,
+        path: path.to_string(),
+        k_i: output.k_i,
+        sigma_i: output.chi_i,
+        R: output.R,
+        checks: zip(signers_vec, output.checks)
+            .map(|(s, (k_r, chi_r))| ShareCheck {
+                party_id: s + 1,
+                k_r,
+                chi_r,
+            })
+            .collect(),
+    })
+}
+
+/// CGGMP21 counterpart of `presign::presign`.
+#[allow(clippy::too_many_arguments)]
+pub async fn presign<E: Curve>(
+    addr: &String,
+    party_keys: &Keys<E>,
+    shared_keys: &SharedKeys<E>,
+    party_id: u16,
+    vss_scheme_vec: &[VerifiableSS<E>],
+    paillier_key_vector: &[EncryptionKey],
+    aux: &AuxInfo,
+    root_y_sum: &Point<E>,
+    params: &Params,
+    path: &str,
+    count: usize,
+) -> Result<Vec<Presignature<E>>, Error> {
+    assert!(count > 0, "nothing to presign");
+    let session = open_session(
+        addr,
+        party_id,
+        root_y_sum,
+        params,
+        &[],
+        &format!("presign-{}:{}:{}", mp_cggmp::aux::PROTOCOL, count, path),
+    )
+    .await?;
+    let mut presigs = Vec::new();
+    for k in 0..count {
+        presigs.push(
+            presign_one(
+                &session,
+                party_keys,
+                shared_keys,
+                party_id,
+                vss_scheme_vec,
+                paillier_key_vector,
+                aux,
+                root_y_sum,
+                path,
+                k,
+            )
+            .await?,
+        );
+    }
+    Ok(presigs)
+}
+
+/// CGGMP21 counterpart of `sign::sign_batch`: presigning and signing rounds for
+/// each message in turn.
+#[allow(clippy::too_many_arguments)]
+pub async fn sign_batch<E: Curve>(
+    addr: &String,
+    party_keys: &Keys<E>,
+    shared_keys: &SharedKeys<E>,
+    party_id: u16,
+    vss_scheme_vec: &[VerifiableSS<E>],
+    paillier_key_vector: &[EncryptionKey],
+    aux: &AuxInfo,
+    root_y_sum: &Point<E>,
+    params: &Params,
+    requests: &[SignRequest<E>],
+) -> Result<Vec<SignatureRecid<E>>, Error> {
+    assert!(!requests.is_empty(), "nothing to sign");
+    let threshold: u16 = params.threshold.parse::<u16>().unwrap();
+    let parties: u16 = params.parties.parse::<u16>().unwrap();
+    let share_count: u16 = params.share_count.parse::<u16>().unwrap();
+    let messages = requests.iter().flat_map(|r| r.message).collect::<Vec<u8>>();
+    // GG18 signers disagree on the paths instead of failing in round 1
+    let paths = requests
+        .iter()
+        .map(|r| format!("{}:{}", mp_cggmp::aux::PROTOCOL, r.path))
+        .collect::<Vec<String>>()
+        .join(",");
+    let session = open_session(addr, party_id, root_y_sum, params, &messages, &paths).await?;
+    let mut sigs = Vec::new();
+    for (k, request) in requests.iter().enumerate() {
+        let presig = presign_one(
+            &session,
+            party_keys,
+            shared_keys,
+            party_id,
+            vss_scheme_vec,
+            paillier_key_vector,
+            aux,
+            root_y_sum,
+            &request.path,
+            k,
+        )
+        .await?;
+        let agreement = SignAgreement::new(
+            &request.message,
+            &request.path,
+            threshold,
+            parties,
+            share_count,
+            root_y_sum,
+            party_id,
+        );
+        sigs.push(
+            sign_round(
+                &session,
+                &format!("round4-{}", k),
+                &agreement,
+                &presig,
+                &request.message,
+                &request.tweak_sk,
+                &request.y_sum,
+            )
+            .await?,
+        );
+    }
+    Ok(sigs)
+}
@@ -0,0 +1,479 @@
+// Threshold Ed25519 (RFC 8032): keygen and FROST-style signing on the manager transport
+//
+// Keygen runs the rounds of `keygen` over edwards25519 with `EddsaKeys`, which
+// commit to y_i alone: there is no Paillier key to publish or prove. The keys
+// file keeps the layout of the secp256k1 one.
+//
+// Signing follows FROST(Ed25519, SHA-512) of RFC 9591: round 1 commits to two
+// nonces (D_j, E_j), round 2 sends z_j = d_j + rho_j * e_j + c * lambda_j * x_j.
+// The challenge is the one of RFC 8032, c = SHA-512(R || A || M), so the
+// signature verifies under any Ed25519 verifier.
+use std::{str::FromStr, time};
+
+use crate::mp_ecdsa::{
+    feldman_vss::VerifiableSS,
+    party_i::{KeyGenDecommitMessage1, Parameters, SessionContext, SharedKeys},
+    secret::Secret,
+    validate::{check_point, check_scalar, Validate, ValidationError},
+};
+use bip32::{ChainCode, DerivationPath};
+use curv::{
+    arithmetic::{Converter, Samplable},
+    cryptographic_primitives::commitments::{hash_commitment::HashCommitment, traits::Commitment},
+    elliptic::curves::{Ed25519, Point, Scalar},
+    BigInt,
+};
+use hmac::{Mac, NewMac};
+use paillier::EncryptionKey;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::biz_algo::{
+    broadcast,
+    hd::HmacSha512,
+    keygen::PartyKeys,
+    parse_msg, poll_for_broadcasts,
+    sign::{agree, SignAgreement},
+    signup, Error, Params,
+};
+
+const CONTEXT: &[u8] = b"FROST-ED25519-SHA512-v1";
+const SECURITY: usize = 256;
+
+/// SHA-512 of the parts, as a little-endian integer mod l
+fn hash_to_scalar(parts: &[&[u8]]) -> Scalar<Ed25519> {
+    let hash = parts
+        .iter()
+        .fold(Sha512::new(), |hasher, part| hasher.chain(part))
+        .finalize();
+    scalar_from_le(&hash)
+}
+
+fn scalar_from_le(bytes: &[u8]) -> Scalar<Ed25519> {
+    let mut be = bytes.to_vec();
+    be.reverse();
+    Scalar::from_bigint(&BigInt::from_bytes(&be))
+}
+
+fn encode(p: &Point<Ed25519>) -> [u8; 32] {
+    p.to_bytes(true)[..].try_into().unwrap()
+}
+
+/// Signing key `x + offset` for the root key `x`
+#[derive(Clone, Debug)]
+pub struct EddsaKey {
+    pub pubkey: Point<Ed25519>,
+    offset: Scalar<Ed25519>,
+}
+
+impl EddsaKey {
+    /// Key at a non-hardened `path` below the root. SLIP-10 only defines hardened
+    /// derivation for ed25519; public derivation uses the BIP-32 formula
+    ///   I = HMAC-SHA512(c, A || ser32(i)),  A' = A + (IL mod l) * B
+    /// with IL read as a little-endian integer.
+    pub fn new(
+        root_y_sum: &Point<Ed25519>,
+        chain_code: ChainCode,
+        path: &str,
+    ) -> Result<Self, Error> {
+        let mut key = Self {
+            pubkey: root_y_sum.clone(),
+            offset: Scalar::zero(),
+        };
+        if path.is_empty() {
+            return Ok(key);
+        }
+        let derivation = DerivationPath::from_str(path)
+            .map_err(|e| Error::ParamInvalid(format!("invalid path {:?}: {}", path, e)))?;
+        let mut chain_code = chain_code;
+        for child in derivation.as_ref() {
+            if child.is_hardened() {
+                return Err(Error::ParamInvalid(format!(
+                    "{} has a hardened level: ed25519 keys only derive non-hardened children",
+                    path
+                )));
+            }
+            let mut hmac = HmacSha512::new_from_slice(&chain_code).unwrap();
+            hmac.update(&encode(&key.pubkey));
+            hmac.update(&child.to_bytes());
+            let result = hmac.finalize().into_bytes();
+            let (il, ir) = result.split_at(32);
+            let tweak = scalar_from_le(il);
+            key.pubkey = key.pubkey + Point::generator() * &tweak;
+            key.offset = key.offset + tweak;
+            chain_code = ir.try_into().unwrap();
+        }
+        Ok(key)
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        encode(&self.pubkey)
+    }
+}
+
+/// RFC 8032 signature: R || S, S little-endian
+#[derive(Clone, Debug, PartialEq)]
+pub struct EddsaSignature {
+    pub r: [u8; 32],
+    pub s: Scalar<Ed25519>,
+}
+
+impl EddsaSignature {
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..].copy_from_slice(&self.s.to_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = |e: &str| Error::ParamInvalid(format!("invalid ed25519 signature: {}", e));
+        if bytes.len() != 64 {
+            return Err(invalid("expected 64 bytes"));
+        }
+        let mut s_be = bytes[32..].to_vec();
+        s_be.reverse();
+        if &BigInt::from_bytes(&s_be) >= Scalar::<Ed25519>::group_order() {
+            return Err(invalid("S is not below l"));
+        }
+        Ok(Self {
+            r: bytes[..32].try_into().unwrap(),
+            s: scalar_from_le(&bytes[32..]),
+        })
+    }
+
+    /// S * B == R + SHA-512(R || A || M) * A; R and A must be in the prime-order subgroup
+    pub fn verify(&self, message: &[u8], pubkey: &Point<Ed25519>) -> Result<(), Error> {
+        let invalid = || Error::SignError("invalid ed25519 signature".to_string());
+        let r = Point::<Ed25519>::from_bytes(&self.r).map_err(|_| invalid())?;
+        let challenge = hash_to_scalar(&[&self.r, &encode(pubkey), message]);
+        match Point::generator() * &self.s == r + pubkey * challenge {
+            true => Ok(()),
+            false => Err(invalid()),
+        }
+    }
+}
+
+/// Keygen round 1: commitment to y_i
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyGenCommitMessage {
+    pub com: BigInt,
+}
+
+impl Validate for KeyGenCommitMessage {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
+fn commitment(ctx: &SessionContext, party: u16, y_i: &Point<Ed25519>) -> BigInt {
+    BigInt::from_bytes(&[ctx.tag(party), encode(y_i).to_vec()].concat())
+}
+
+/// Party key of an ed25519 keys file; unlike the ECDSA `Keys` it has no
+/// Paillier key, which no EdDSA round uses
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EddsaKeys {
+    pub u_i: Secret<Scalar<Ed25519>>,
+    pub y_i: Point<Ed25519>,
+    pub party_index: u16,
+}
+
+impl PartyKeys<Ed25519> for EddsaKeys {
+    type Commit = KeyGenCommitMessage;
+
+    fn create(u_i: Scalar<Ed25519>, party_index: u16) -> Self {
+        Self {
+            y_i: Point::generator() * &u_i,
+            u_i: Secret::new(u_i),
+            party_index,
+        }
+    }
+
+    fn u_i(&self) -> &Scalar<Ed25519> {
+        &self.u_i
+    }
+
+    fn commit(
+        &self,
+        ctx: &SessionContext,
+    ) -> (KeyGenCommitMessage, KeyGenDecommitMessage1<Ed25519>) {
+        let blind_factor = BigInt::sample(SECURITY);
+        let com = HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+            &commitment(ctx, self.party_index, &self.y_i),
+            &blind_factor,
+        );
+        let decom = KeyGenDecommitMessage1 {
+            blind_factor,
+            y_i: self.y_i.clone(),
+        };
+        (KeyGenCommitMessage { com }, decom)
+    }
+
+    fn verify_commits(
+        &self,
+        params: &Parameters,
+        decom_vec: &[KeyGenDecommitMessage1<Ed25519>],
+        com_vec: &[KeyGenCommitMessage],
+        ctx: &SessionContext,
+    ) -> Result<(VerifiableSS<Ed25519>, Vec<Scalar<Ed25519>>), Error> {
+        for (i, (decom, com)) in (1..).zip(decom_vec.iter().zip(com_vec)) {
+            if HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+                &commitment(ctx, i, &decom.y_i),
+                &decom.blind_factor,
+            ) != com.com
+            {
+                return Err(Error::KeygenError(format!(
+                    "party {} in round2: invalid decommitment",
+                    i
+                )));
+            }
+        }
+        let (vss_scheme, secret_shares) =
+            VerifiableSS::share(params.threshold, params.share_count, &self.u_i);
+        Ok((vss_scheme, secret_shares.to_vec()))
+    }
+
+    fn encryption_keys(_: &[KeyGenCommitMessage]) -> Vec<EncryptionKey> {
+        Vec::new()
+    }
+}
+
+/// Keys file of `keygen --curve ed25519`, in the layout of the ECDSA one
+pub type KeysFile = (
+    EddsaKeys,
+    SharedKeys<Ed25519>,
+    u16,
+    Vec<VerifiableSS<Ed25519>>,
+    Vec<EncryptionKey>,
+    Point<Ed25519>,
+    ChainCode,
+);
+
+/// Round 1 message: commitments to the two nonces of a signer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NonceCommitment {
+    pub d_j: Point<Ed25519>,
+    pub e_j: Point<Ed25519>,
+}
+
+impl Validate for NonceCommitment {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_point(&self.d_j, "nonce_commitment.d_j")?;
+        check_point(&self.e_j, "nonce_commitment.e_j")
+    }
+}
+
+/// Round 2 message
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignatureShare {
+    pub z_j: Scalar<Ed25519>,
+}
+
+impl Validate for SignatureShare {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_scalar(&self.z_j, "signature_share.z_j")
+    }
+}
+
+// hedged nonce of RFC 9591: fresh randomness, bound to the share and the message
+fn nonce(x_i: &Scalar<Ed25519>, message: &[u8]) -> Scalar<Ed25519> {
+    let random: [u8; 32] = rand::random();
+    hash_to_scalar(&[CONTEXT, b"nonce", &random, &x_i.to_bytes(), message])
+}
+
+/// FROST signing round trip. The nonces are hedged, not deterministic as in
+/// RFC 8032: with several signers, R depends on the commitments of the others,
+/// so a peer that changes its own between two runs on the same message would
+/// get two shares z_j with the same nonce and different challenges, and solve
+/// for x_j.
+#[allow(clippy::too_many_arguments)]
+pub async fn sign(
+    addr: &String,
+    shared_keys: &SharedKeys<Ed25519>,
+    party_id: u16,
+    vss_scheme_vec: &[VerifiableSS<Ed25519>],
+    root_y_sum: &Point<Ed25519>,
+    params: &Params,
+    message: &[u8],
+    path: &str,
+    key: &EddsaKey,
+) -> Result<EddsaSignature, Error> {
+    let client = Client::new();
+    let delay = time::Duration::from_millis(25);
+    let threshold: u16 = params.threshold.parse::<u16>().unwrap();
+    let parties: u16 = params.parties.parse::<u16>().unwrap();
+    let share_count: u16 = params.share_count.parse::<u16>().unwrap();
+    println!(
+        "threshold: {}, parties: {}, share count: {}",
+        threshold, parties, share_count
+    );
+    assert!(parties > threshold, "PARTIES smaller than THRESHOLD + 1");
+    assert!(parties < share_count + 1, "PARTIES bigger than SHARE_COUNT");
+
+    let party_signup = signup(addr, &client, "signupsign", params).await;
+    let party_num_int = party_signup.number;
+    let uuid = party_signup.uuid;
+    let debug = json!({"manager_addr": &addr, "party_num": party_num_int, "uuid": uuid});
+    println!("{}", serde_json::to_string_pretty(&debug).unwrap());
+
+    // round 0
+    let agreement = SignAgreement::new(
+        message,
+        path,
+        threshold,
+        parties,
+        share_count,
+        root_y_sum,
+        party_id,
+    );
+    let signers_vec = agree(
+        addr,
+        &client,
+        delay,
+        party_num_int,
+        parties,
+        &uuid,
+        &agreement,
+    )
+    .await?;
+    let xi_com_vec = (1..=share_count)
+        .map(|i| {
+            vss_scheme_vec
+                .iter()
+                .map(|vss| vss.get_point_commitment(i))
+                .sum::<Point<Ed25519>>()
+        })
+        .collect::<Vec<_>>();
+
+    // round 1: nonce commitments
+    let (d_i, e_i) = (
+        nonce(&shared_keys.x_i, message),
+        nonce(&shared_keys.x_i, message),
+    );
+    let commitment = NonceCommitment {
+        d_j: Point::generator() * &d_i,
+        e_j: Point::generator() * &e_i,
+    };
+    broadcast(
+        addr,
+        &client,
+        party_num_int,
+        "round1",
+        &commitment,
+        uuid.clone(),
+    )
+    .await;
+    let round1_ans_vec = poll_for_broadcasts(
+        addr,
+        &client,
+        party_num_int,
+        parties,
+        delay,
+        "round1",
+        uuid.clone(),
+    )
+    .await;
+    let mut commitments = Vec::new();
+    let mut ans = round1_ans_vec.iter();
+    for i in 1..=parties {
+        match i == party_num_int {
+            true => commitments.push(commitment.clone()),
+            false => commitments.push(parse_msg(ans.next().unwrap(), &(), i, "round1")?),
+        }
+    }
+
+    // binding factors over the group key, the message and the whole commitment list
+    let identifier = |signer_id: u16| Scalar::<Ed25519>::from(signer_id + 1).to_bytes();
+    let mut encoded = Vec::new();
+    for (signer_id, c) in signers_vec.iter().zip(&commitments) {
+        encoded.extend(&*identifier(*signer_id));
+        encoded.extend(encode(&c.d_j));
+        encoded.extend(encode(&c.e_j));
+    }
+    let msg_hash = Sha512::new()
+        .chain(CONTEXT)
+        .chain(b"msg")
+        .chain(message)
+        .finalize();
+    let com_hash = Sha512::new()
+        .chain(CONTEXT)
+        .chain(b"com")
+        .chain(&encoded)
+        .finalize();
+    let rho_vec = signers_vec
+        .iter()
+        .map(|signer_id| {
+            hash_to_scalar(&[
+                CONTEXT,
+                b"rho",
+                &key.to_bytes(),
+                &msg_hash,
+                &com_hash,
+                &identifier(*signer_id),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let nonce_vec = commitments
+        .iter()
+        .zip(&rho_vec)
+        .map(|(c, rho)| &c.d_j + &c.e_j * rho)
+        .collect::<Vec<_>>();
+    let r = encode(&nonce_vec.iter().sum::<Point<Ed25519>>());
+    let challenge = hash_to_scalar(&[&r, &key.to_bytes(), message]);
+    let lambda = |signer_id: u16| {
+        VerifiableSS::<Ed25519>::map_share_to_new_params(
+            &vss_scheme_vec[0].parameters,
+            signer_id,
+            &signers_vec,
+        )
+    };
+
+    // round 2: signature shares
+    let k = usize::from(party_num_int - 1);
+    let share = SignatureShare {
+        z_j: d_i + e_i * &rho_vec[k] + &challenge * lambda(signers_vec[k]) * &*shared_keys.x_i,
+    };
+    broadcast(addr, &client, party_num_int, "round2", &share, uuid.clone()).await;
+    let round2_ans_vec = poll_for_broadcasts(
+        addr,
+        &client,
+        party_num_int,
+        parties,
+        delay,
+        "round2",
+        uuid.clone(),
+    )
+    .await;
+    let mut s = &challenge * &key.offset;
+    let mut ans = round2_ans_vec.iter();
+    for i in 1..=parties {
+        let j = usize::from(i - 1);
+        let share_j = match i == party_num_int {
+            true => share.clone(),
+            false => parse_msg::<SignatureShare>(ans.next().unwrap(), &(), i, "round2")?,
+        };
+        // z_j * B = D_j + rho_j * E_j + c * lambda_j * X_j
+        let expected = &nonce_vec[j]
+            + &xi_com_vec[usize::from(signers_vec[j])] * (&challenge * lambda(signers_vec[j]));
+        if Point::generator() * &share_j.z_j != expected {
+            return Err(Error::SignError(format!(
+                "party {} in round2: invalid signature share",
+                i
+            )));
+        }
+        s = s + share_j.z_j;
+    }
+
+    let sig = EddsaSignature { r, s };
+    sig.verify(message, &key.pubkey)?;
+    Ok(sig)
+}
@@ -0,0 +1,543 @@
+// EIP-712 typed structured data
+use std::collections::{BTreeMap, BTreeSet};
+
+use curv::{
+    arithmetic::{BasicOps, Converter},
+    BigInt,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::biz_algo::{
+    eth::{checksum, keccak256},
+    message::DIGEST_SIZE,
+    Error,
+};
+
+const DOMAIN_TYPE: &str = "EIP712Domain";
+
+// fields EIP712Domain may have, in the order of the standard
+const DOMAIN_FIELDS: [(&str, &str); 5] = [
+    ("name", "string"),
+    ("version", "string"),
+    ("chainId", "uint256"),
+    ("verifyingContract", "address"),
+    ("salt", "bytes32"),
+];
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Member {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+/// JSON document of `eth_signTypedData_v4`
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedData {
+    pub types: BTreeMap<String, Vec<Member>>,
+    pub primary_type: String,
+    pub domain: Map<String, Value>,
+    #[serde(default)]
+    pub message: Map<String, Value>,
+}
+
+fn invalid(e: String) -> Error {
+    Error::ParamInvalid(format!("invalid typed data: {}", e))
+}
+
+impl TypedData {
+    /// Parses the document and checks that every referenced type is defined.
+    /// `EIP712Domain` is inferred from the domain when it is not given.
+    pub fn from_json(data: &[u8]) -> Result<Self, Error> {
+        let mut typed: TypedData =
+            serde_json::from_slice(data).map_err(|e| invalid(e.to_string()))?;
+        if !typed.types.contains_key(DOMAIN_TYPE) {
+            if let Some(key) = typed
+                .domain
+                .keys()
+                .find(|k| !DOMAIN_FIELDS.iter().any(|(name, _)| name == k))
+            {
+                return Err(invalid(format!("unknown domain field {:?}", key)));
+            }
+            let members = DOMAIN_FIELDS
+                .iter()
+                .filter(|(name, _)| typed.domain.contains_key(*name))
+                .map(|(name, ty)| Member {
+                    name: name.to_string(),
+                    ty: ty.to_string(),
+                })
+                .collect();
+            typed.types.insert(DOMAIN_TYPE.to_string(), members);
+        }
+        if !typed.types.contains_key(&typed.primary_type) {
+            return Err(invalid(format!(
+                "primary type {:?} is not defined",
+                typed.primary_type
+            )));
+        }
+        for (name, members) in &typed.types {
+            let mut seen = BTreeSet::new();
+            for member in members {
+                if !seen.insert(&member.name) {
+                    return Err(invalid(format!("{}.{} defined twice", name, member.name)));
+                }
+                typed.check_type(&member.ty)?;
+            }
+        }
+        Ok(typed)
+    }
+
+    fn check_type(&self, ty: &str) -> Result<(), Error> {
+        if let Some((base, _)) = split_array(ty)? {
+            return self.check_type(base);
+        }
+        if self.types.contains_key(ty) || atomic_width(ty).is_some() {
+            return Ok(());
+        }
+        Err(invalid(format!("unknown type {:?}", ty)))
+    }
+
+    /// `keccak256(0x1901 || domainSeparator || hashStruct(message))`; the struct
+    /// hash is left out when the primary type is the domain itself
+    pub fn digest(&self) -> Result<[u8; DIGEST_SIZE], Error> {
+        let mut data = vec![0x19, 0x01];
+        data.extend(self.hash_struct(DOMAIN_TYPE, &self.domain)?);
+        if self.primary_type != DOMAIN_TYPE {
+            data.extend(self.hash_struct(&self.primary_type, &self.message)?);
+        }
+        Ok(keccak256(&data))
+    }
+
+    pub fn domain_separator(&self) -> Result<[u8; DIGEST_SIZE], Error> {
+        self.hash_struct(DOMAIN_TYPE, &self.domain)
+    }
+
+    /// `keccak256(typeHash || encodeData(s))`
+    pub fn hash_struct(
+        &self,
+        name: &str,
+        value: &Map<String, Value>,
+    ) -> Result<[u8; DIGEST_SIZE], Error> {
+        let members = &self.types[name];
+        if let Some(key) = value
+            .keys()
+            .find(|k| !members.iter().any(|m| &m.name == *k))
+        {
+            return Err(invalid(format!("{} has no field {:?}", name, key)));
+        }
+        let mut data = keccak256(self.encode_type(name).as_bytes()).to_vec();
+        for member in members {
+            let field = format!("{}.{}", name, member.name);
+            let v = value
+                .get(&member.name)
+                .ok_or_else(|| invalid(format!("{} is missing", field)))?;
+            data.extend(self.encode_value(&member.ty, v, &field)?);
+        }
+        Ok(keccak256(&data))
+    }
+
+    /// `Name(type1 name1,...)` followed by the referenced struct types sorted by name
+    pub fn encode_type(&self, name: &str) -> String {
+        let mut deps = BTreeSet::new();
+        self.dependencies(name, &mut deps);
+        deps.remove(name);
+        [name]
+            .into_iter()
+            .chain(deps.iter().map(|s| s.as_str()))
+            .map(|ty| {
+                let members = self.types[ty]
+                    .iter()
+                    .map(|m| format!("{} {}", m.ty, m.name))
+                    .collect::<Vec<_>>();
+                format!("{}({})", ty, members.join(","))
+            })
+            .collect()
+    }
+
+    fn dependencies(&self, name: &str, deps: &mut BTreeSet<String>) {
+        if deps.contains(name) {
+            return;
+        }
+        if let Some(members) = self.types.get(name) {
+            deps.insert(name.to_string());
+            for member in members {
+                self.dependencies(base_type(&member.ty), deps);
+            }
+        }
+    }
+
+    fn encode_value(&self, ty: &str, value: &Value, field: &str) -> Result<[u8; 32], Error> {
+        if let Some((base, len)) = split_array(ty)? {
+            let items = array_items(value, len, field)?;
+            let mut data = Vec::new();
+            for (i, item) in items.iter().enumerate() {
+                data.extend(self.encode_value(base, item, &format!("{}[{}]", field, i))?);
+            }
+            return Ok(keccak256(&data));
+        }
+        if self.types.contains_key(ty) {
+            let object = value
+                .as_object()
+                .ok_or_else(|| invalid(format!("{} is not an object", field)))?;
+            return self.hash_struct(ty, object);
+        }
+        Ok(atomic(ty, value, field)?.0)
+    }
+
+    /// Canonical, human-readable form of the domain and message: fields follow
+    /// the type definitions and values are normalized, so every party sees the
+    /// same text for the same digest.
+    pub fn summary(&self) -> Result<String, Error> {
+        let mut out = format!(
+            "EIP-712 {}\n  domain ({}):\n",
+            self.primary_type, DOMAIN_TYPE
+        );
+        self.format_struct(DOMAIN_TYPE, &self.domain, 2, &mut out)?;
+        if self.primary_type != DOMAIN_TYPE {
+            out.push_str(&format!("  message ({}):\n", self.primary_type));
+            self.format_struct(&self.primary_type, &self.message, 2, &mut out)?;
+        }
+        Ok(out.trim_end().to_string())
+    }
+
+    fn format_struct(
+        &self,
+        name: &str,
+        value: &Map<String, Value>,
+        depth: usize,
+        out: &mut String,
+    ) -> Result<(), Error> {
+        for member in &self.types[name] {
+            let field = format!("{}.{}", name, member.name);
+            let v = value
+                .get(&member.name)
+                .ok_or_else(|| invalid(format!("{} is missing", field)))?;
+            out.push_str(&format!(
+                "{}{} ({}):",
+                "  ".repeat(depth),
+                member.name,
+                member.ty
+            ));
+            self.format_value(&member.ty, v, &field, depth, out)?;
+        }
+        Ok(())
+    }
+
+    fn format_value(
+        &self,
+        ty: &str,
+        value: &Value,
+        field: &str,
+        depth: usize,
+        out: &mut String,
+    ) -> Result<(), Error> {
+        if let Some((base, len)) = split_array(ty)? {
+            let items = array_items(value, len, field)?;
+            out.push_str(&format!(" {} item(s)\n", items.len()));
+            for (i, item) in items.iter().enumerate() {
+                out.push_str(&format!("{}[{}]:", "  ".repeat(depth + 1), i));
+                self.format_value(base, item, &format!("{}[{}]", field, i), depth + 1, out)?;
+            }
+        } else if self.types.contains_key(ty) {
+            let object = value
+                .as_object()
+                .ok_or_else(|| invalid(format!("{} is not an object", field)))?;
+            out.push('\n');
+            self.format_struct(ty, object, depth + 1, out)?;
+        } else {
+            out.push_str(&format!(" {}\n", atomic(ty, value, field)?.1));
+        }
+        Ok(())
+    }
+}
+
+fn base_type(ty: &str) -> &str {
+    ty.split('[').next().unwrap()
+}
+
+// `T[]` or `T[n]` into `(T, n)`
+fn split_array(ty: &str) -> Result<Option<(&str, Option<usize>)>, Error> {
+    if !ty.ends_with(']') {
+        return Ok(None);
+    }
+    let open = ty
+        .rfind('[')
+        .ok_or_else(|| invalid(format!("bad type {:?}", ty)))?;
+    let len = &ty[open + 1..ty.len() - 1];
+    let len = match len.is_empty() {
+        true => None,
+        false => Some(
+            len.parse::<usize>()
+                .map_err(|_| invalid(format!("bad array length in {:?}", ty)))?,
+        ),
+    };
+    Ok(Some((&ty[..open], len)))
+}
+
+fn array_items<'a>(
+    value: &'a Value,
+    len: Option<usize>,
+    field: &str,
+) -> Result<&'a Vec<Value>, Error> {
+    let items = value
+        .as_array()
+        .ok_or_else(|| invalid(format!("{} is not an array", field)))?;
+    match len {
+        Some(len) if len != items.len() => Err(invalid(format!(
+            "{} has {} items, expected {}",
+            field,
+            items.len(),
+            len
+        ))),
+        _ => Ok(items),
+    }
+}
+
+// width in bytes of an atomic value as decoded, 0 for dynamic types
+fn atomic_width(ty: &str) -> Option<usize> {
+    let bits = |s: &str| match s.parse::<usize>() {
+        Ok(n) if n % 8 == 0 && (8..=256).contains(&n) => Some(n / 8),
+        _ => None,
+    };
+    match ty {
+        "address" => Some(20),
+        "bool" => Some(1),
+        "string" | "bytes" => Some(0),
+        _ if ty.starts_with("uint") => bits(&ty[4..]),
+        _ if ty.starts_with("int") => bits(&ty[3..]),
+        _ if ty.starts_with("bytes") => match ty[5..].parse::<usize>() {
+            Ok(n) if (1..=32).contains(&n) => Some(n),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn hex_value(value: &Value, field: &str) -> Result<Vec<u8>, Error> {
+    value
+        .as_str()
+        .and_then(|s| s.strip_prefix("0x"))
+        .and_then(|s| hex::decode(s).ok())
+        .ok_or_else(|| invalid(format!("{} is not 0x-prefixed hex", field)))
+}
+
+fn int_value(value: &Value, field: &str) -> Result<BigInt, Error> {
+    let bad = || invalid(format!("{} is not an integer", field));
+    match value {
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(u), _) => Ok(BigInt::from(u)),
+            (_, Some(i)) => Ok(BigInt::from(0) - BigInt::from(i.unsigned_abs())),
+            _ => Err(bad()),
+        },
+        Value::String(s) => {
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, s.as_str()),
+            };
+            let n = match digits.strip_prefix("0x") {
+                Some(hex) => BigInt::from_str_radix(hex, 16),
+                None => BigInt::from_str_radix(digits, 10),
+            }
+            .map_err(|_| bad())?;
+            if digits.is_empty() || digits.starts_with('+') {
+                return Err(bad());
+            }
+            Ok(if negative { BigInt::from(0) - n } else { n })
+        }
+        _ => Err(bad()),
+    }
+}
+
+fn left_pad(bytes: &[u8]) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(bytes);
+    word
+}
+
+// 32-byte encoding and canonical display of an atomic value
+fn atomic(ty: &str, value: &Value, field: &str) -> Result<([u8; 32], String), Error> {
+    let width = atomic_width(ty).ok_or_else(|| invalid(format!("unknown type {:?}", ty)))?;
+    match ty {
+        "address" => {
+            let s = value
+                .as_str()
+                .ok_or_else(|| invalid(format!("{} is not an address", field)))?;
+            let bytes = hex_value(value, field)?;
+            if bytes.len() != 20 {
+                return Err(invalid(format!("{} is not 20 bytes", field)));
+            }
+            let address = checksum(&bytes);
+            // mixed case is a checksum and has to match
+            if s[2..] != s[2..].to_lowercase() && s[2..] != s[2..].to_uppercase() && s != address {
+                return Err(invalid(format!("{} fails the EIP-55 checksum", field)));
+            }
+            Ok((left_pad(&bytes), address))
+        }
+        "bool" => {
+            let b = value
+                .as_bool()
+                .ok_or_else(|| invalid(format!("{} is not a bool", field)))?;
+            Ok((left_pad(&[b as u8]), b.to_string()))
+        }
+        "string" => {
+            let s = value
+                .as_str()
+                .ok_or_else(|| invalid(format!("{} is not a string", field)))?;
+            // quoted and escaped so that control characters cannot fake other lines
+            Ok((
+                keccak256(s.as_bytes()),
+                Value::String(s.to_string()).to_string(),
+            ))
+        }
+        "bytes" => {
+            let bytes = hex_value(value, field)?;
+            Ok((keccak256(&bytes), format!("0x{}", hex::encode(&bytes))))
+        }
+        _ if ty.starts_with("bytes") => {
+            let bytes = hex_value(value, field)?;
+            if bytes.len() != width {
+                return Err(invalid(format!("{} is not {} bytes", field, width)));
+            }
+            let mut word = [0u8; 32];
+            word[..width].copy_from_slice(&bytes);
+            Ok((word, format!("0x{}", hex::encode(&bytes))))
+        }
+        _ => {
+            let n = int_value(value, field)?;
+            let bits = (width * 8) as u32;
+            let signed = ty.starts_with("int");
+            let (min, max) = match signed {
+                true => (
+                    BigInt::from(0) - BigInt::from(2).pow(bits - 1),
+                    BigInt::from(2).pow(bits - 1) - BigInt::from(1),
+                ),
+                false => (BigInt::from(0), BigInt::from(2).pow(bits) - BigInt::from(1)),
+            };
+            if n < min || n > max {
+                return Err(invalid(format!("{} is out of range for {}", field, ty)));
+            }
+            // two's complement over 256 bits
+            let word = match n < BigInt::from(0) {
+                true => BigInt::from(2).pow(256) + &n,
+                false => n.clone(),
+            };
+            Ok((left_pad(&word.to_bytes()), n.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biz_algo::eth::address;
+    use crate::mp_ecdsa::party_i::{recover, SignatureRecid};
+
+    // the example of the EIP-712 specification
+    const MAIL: &str = r#"{
+        "types": {
+            "EIP712Domain": [
+                {"name": "name", "type": "string"},
+                {"name": "version", "type": "string"},
+                {"name": "chainId", "type": "uint256"},
+                {"name": "verifyingContract", "type": "address"}
+            ],
+            "Person": [
+                {"name": "name", "type": "string"},
+                {"name": "wallet", "type": "address"}
+            ],
+            "Mail": [
+                {"name": "from", "type": "Person"},
+                {"name": "to", "type": "Person"},
+                {"name": "contents", "type": "string"}
+            ]
+        },
+        "primaryType": "Mail",
+        "domain": {
+            "name": "Ether Mail",
+            "version": "1",
+            "chainId": 1,
+            "verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+        },
+        "message": {
+            "from": {"name": "Cow", "wallet": "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826"},
+            "to": {"name": "Bob", "wallet": "0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"},
+            "contents": "Hello, Bob!"
+        }
+    }"#;
+
+    fn mail() -> TypedData {
+        TypedData::from_json(MAIL.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn encode_type() {
+        let typed = mail();
+        assert_eq!(
+            typed.encode_type("Mail"),
+            "Mail(Person from,Person to,string contents)Person(string name,address wallet)"
+        );
+        assert_eq!(
+            hex::encode(keccak256(typed.encode_type("Mail").as_bytes())),
+            "a0cedeb2dc280ba39b857546d74f5549c3a1d7bdc2dd96bf881f76108e23dac2"
+        );
+    }
+
+    #[test]
+    fn hash_struct() {
+        let typed = mail();
+        assert_eq!(
+            hex::encode(typed.domain_separator().unwrap()),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+        assert_eq!(
+            hex::encode(typed.hash_struct("Mail", &typed.message).unwrap()),
+            "c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e"
+        );
+        assert_eq!(
+            hex::encode(typed.digest().unwrap()),
+            "be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2"
+        );
+    }
+
+    #[test]
+    fn signature_recovers_sender() {
+        let typed = mail();
+        let mut rs = hex::decode(
+            "4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d\
+             07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562",
+        )
+        .unwrap();
+        rs.push(28);
+        let sig = SignatureRecid::from_recoverable(&rs).unwrap();
+        let y = recover(&sig, &BigInt::from_bytes(&typed.digest().unwrap())).unwrap();
+        assert_eq!(address(&y), "0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826");
+    }
+
+    #[test]
+    fn domain_type_is_inferred() {
+        let mut value: Value = serde_json::from_str(MAIL).unwrap();
+        value["types"].as_object_mut().unwrap().remove(DOMAIN_TYPE);
+        let typed = TypedData::from_json(value.to_string().as_bytes()).unwrap();
+        assert_eq!(typed.digest().unwrap(), mail().digest().unwrap());
+    }
+
+    #[test]
+    fn rejects_bad_checksum_and_unknown_fields() {
+        let bad_checksum = MAIL.replace("0xCD2a3d9F", "0xcD2a3d9F");
+        let typed = TypedData::from_json(bad_checksum.as_bytes()).unwrap();
+        assert!(typed.digest().is_err());
+
+        let extra = MAIL.replace(
+            r#""contents": "Hello, Bob!""#,
+            r#""contents": "Hi", "cc": 1"#,
+        );
+        let typed = TypedData::from_json(extra.as_bytes()).unwrap();
+        assert!(typed.digest().is_err());
+
+        let undefined = MAIL.replace(r#""type": "Person"}"#, r#""type": "Human"}"#);
+        assert!(TypedData::from_json(undefined.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,543 @@
+// Ethereum addresses, transactions and personal_sign on top of `sign`
+use std::{fmt, str::FromStr};
+
+use curv::{
+    arithmetic::Converter,
+    elliptic::curves::{secp256_k1::Secp256k1, Point},
+    BigInt,
+};
+use sha3::{Digest, Keccak256};
+
+use crate::biz_algo::{message::DIGEST_SIZE, Error};
+use crate::mp_ecdsa::party_i::SignatureRecid;
+
+pub fn keccak256(data: &[u8]) -> [u8; DIGEST_SIZE] {
+    Keccak256::digest(data).into()
+}
+
+/// EIP-55 checksummed address of a public key
+pub fn address(pk: &Point<Secp256k1>) -> String {
+    let hash = keccak256(&pk.to_bytes(false)[1..]);
+    checksum(&hash[12..])
+}
+
+/// EIP-55 checksummed form of a 20-byte address
+pub fn checksum(address: &[u8]) -> String {
+    let lower = hex::encode(address);
+    let hash = keccak256(lower.as_bytes());
+    let mixed = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0xf;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+    format!("0x{}", mixed)
+}
+
+/// EIP-191 version 0x45 digest, as used by `personal_sign`
+pub fn personal_message_hash(message: &[u8]) -> [u8; DIGEST_SIZE] {
+    let prefix = format!("\x19Ethereum Signed Message:\n{}", message.len());
+    keccak256(&[prefix.as_bytes(), message].concat())
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Rlp {
+    Bytes(Vec<u8>),
+    List(Vec<Rlp>),
+}
+
+impl Rlp {
+    /// big-endian integer without leading zeros
+    pub fn uint(bytes: &[u8]) -> Self {
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+        Rlp::Bytes(bytes[start..].to_vec())
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Rlp::Bytes(b) if b.len() == 1 && b[0] < 0x80 => b.clone(),
+            Rlp::Bytes(b) => [Self::header(0x80, b.len()), b.clone()].concat(),
+            Rlp::List(items) => {
+                let payload = items.iter().flat_map(Rlp::encode).collect::<Vec<u8>>();
+                [Self::header(0xc0, payload.len()), payload].concat()
+            }
+        }
+    }
+
+    fn header(offset: u8, len: usize) -> Vec<u8> {
+        if len < 56 {
+            return vec![offset + len as u8];
+        }
+        let len_bytes = len.to_be_bytes();
+        let start = len_bytes.iter().position(|b| *b != 0).unwrap();
+        [
+            vec![offset + 55 + (len_bytes.len() - start) as u8],
+            len_bytes[start..].to_vec(),
+        ]
+        .concat()
+    }
+
+    /// Decodes exactly one canonical item
+    pub fn decode(data: &[u8]) -> Result<Self, Error> {
+        let (item, len) = Self::decode_item(data)?;
+        if len != data.len() {
+            return Err(rlp_error("trailing bytes"));
+        }
+        Ok(item)
+    }
+
+    // item at the start of `data` and its encoded length
+    fn decode_item(data: &[u8]) -> Result<(Self, usize), Error> {
+        let prefix = *data.first().ok_or_else(|| rlp_error("unexpected end"))?;
+        let (is_list, offset, len) = match prefix {
+            0x00..=0x7f => return Ok((Rlp::Bytes(vec![prefix]), 1)),
+            0x80..=0xb7 => (false, 1, (prefix - 0x80) as usize),
+            0xb8..=0xbf => Self::long_len(data, false, (prefix - 0xb7) as usize)?,
+            0xc0..=0xf7 => (true, 1, (prefix - 0xc0) as usize),
+            0xf8..=0xff => Self::long_len(data, true, (prefix - 0xf7) as usize)?,
+        };
+        let payload = data
+            .get(offset..offset + len)
+            .ok_or_else(|| rlp_error("unexpected end"))?;
+        if !is_list {
+            if len == 1 && payload[0] < 0x80 {
+                return Err(rlp_error("non-canonical single byte"));
+            }
+            return Ok((Rlp::Bytes(payload.to_vec()), offset + len));
+        }
+        let mut items = Vec::new();
+        let mut pos = 0;
+        while pos < len {
+            let (item, item_len) = Self::decode_item(&payload[pos..])?;
+            items.push(item);
+            pos += item_len;
+        }
+        Ok((Rlp::List(items), offset + len))
+    }
+
+    fn long_len(
+        data: &[u8],
+        is_list: bool,
+        len_of_len: usize,
+    ) -> Result<(bool, usize, usize), Error> {
+        let len_bytes = data
+            .get(1..1 + len_of_len)
+            .ok_or_else(|| rlp_error("unexpected end"))?;
+        if len_bytes[0] == 0 || len_of_len > 4 {
+            return Err(rlp_error("non-canonical length"));
+        }
+        let len = len_bytes
+            .iter()
+            .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        if len < 56 {
+            return Err(rlp_error("non-canonical length"));
+        }
+        Ok((is_list, 1 + len_of_len, len))
+    }
+
+    fn as_bytes(&self, field: &str) -> Result<&[u8], Error> {
+        match self {
+            Rlp::Bytes(b) => Ok(b),
+            Rlp::List(_) => Err(tx_error(format!("{} is a list", field))),
+        }
+    }
+
+    fn as_uint(&self, field: &str) -> Result<BigInt, Error> {
+        let bytes = self.as_bytes(field)?;
+        if bytes.len() > 32 || bytes.first() == Some(&0) {
+            return Err(tx_error(format!("{} is not a canonical integer", field)));
+        }
+        Ok(BigInt::from_bytes(bytes))
+    }
+}
+
+fn rlp_error(e: &str) -> Error {
+    Error::ParamInvalid(format!("invalid RLP: {}", e))
+}
+
+fn tx_error(e: String) -> Error {
+    Error::ParamInvalid(format!("invalid transaction: {}", e))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TxType {
+    /// EIP-155 replay-protected legacy transaction
+    Legacy,
+    Eip1559,
+}
+
+const EIP1559_TYPE: u8 = 0x02;
+
+/// Unsigned transaction as produced by wallets for an external signer
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct UnsignedTx {
+    pub tx_type: TxType,
+    pub chain_id: BigInt,
+    fields: Vec<Rlp>,
+}
+
+impl UnsignedTx {
+    /// `rlp([nonce, gasPrice, gas, to, value, data, chainId, 0, 0])`, or
+    /// `0x02 || rlp([chainId, nonce, maxPriorityFeePerGas, maxFeePerGas, gas, to, value, data, accessList])`
+    pub fn decode(raw: &[u8]) -> Result<Self, Error> {
+        let (tx_type, payload) = match raw.first() {
+            Some(&EIP1559_TYPE) => (TxType::Eip1559, &raw[1..]),
+            Some(b) if *b >= 0xc0 => (TxType::Legacy, raw),
+            Some(b) => return Err(tx_error(format!("unsupported type 0x{:02x}", b))),
+            None => return Err(tx_error("empty".to_string())),
+        };
+        let fields = match Rlp::decode(payload)? {
+            Rlp::List(fields) => fields,
+            Rlp::Bytes(_) => return Err(tx_error("not a list".to_string())),
+        };
+        let tx = match tx_type {
+            TxType::Legacy => {
+                if fields.len() == 6 {
+                    return Err(tx_error(
+                        "legacy transaction without chain id is not replay protected".to_string(),
+                    ));
+                }
+                if fields.len() != 9 {
+                    return Err(tx_error(format!("{} fields, expected 9", fields.len())));
+                }
+                if fields[7] != Rlp::Bytes(vec![]) || fields[8] != Rlp::Bytes(vec![]) {
+                    return Err(tx_error("already signed".to_string()));
+                }
+                Self {
+                    tx_type,
+                    chain_id: fields[6].as_uint("chainId")?,
+                    fields,
+                }
+            }
+            TxType::Eip1559 => {
+                if fields.len() != 9 {
+                    return Err(tx_error(format!(
+                        "{} fields, expected 9 (is it signed?)",
+                        fields.len()
+                    )));
+                }
+                if !matches!(fields[8], Rlp::List(_)) {
+                    return Err(tx_error("accessList is not a list".to_string()));
+                }
+                Self {
+                    tx_type,
+                    chain_id: fields[0].as_uint("chainId")?,
+                    fields,
+                }
+            }
+        };
+        if tx.chain_id == BigInt::from(0) {
+            return Err(tx_error("chain id 0".to_string()));
+        }
+        let names = tx.names();
+        for (field, name) in tx.fields.iter().zip(names) {
+            match *name {
+                "to" => {
+                    let to = field.as_bytes(name)?;
+                    if !to.is_empty() && to.len() != 20 {
+                        return Err(tx_error(format!("to has {} bytes", to.len())));
+                    }
+                }
+                "data" => {
+                    field.as_bytes(name)?;
+                }
+                "accessList" | "v" | "r" | "s" => {}
+                _ => {
+                    field.as_uint(name)?;
+                }
+            }
+        }
+        Ok(tx)
+    }
+
+    fn names(&self) -> &'static [&'static str] {
+        match self.tx_type {
+            TxType::Legacy => &[
+                "nonce", "gasPrice", "gas", "to", "value", "data", "chainId", "r", "s",
+            ],
+            TxType::Eip1559 => &[
+                "chainId",
+                "nonce",
+                "maxPriorityFeePerGas",
+                "maxFeePerGas",
+                "gas",
+                "to",
+                "value",
+                "data",
+                "accessList",
+            ],
+        }
+    }
+
+    fn field(&self, name: &str) -> &Rlp {
+        let i = self.names().iter().position(|n| *n == name).unwrap();
+        &self.fields[i]
+    }
+
+    /// Keccak-256 of the unsigned encoding
+    pub fn signing_hash(&self) -> [u8; DIGEST_SIZE] {
+        keccak256(&self.encode(self.fields.clone()))
+    }
+
+    fn encode(&self, fields: Vec<Rlp>) -> Vec<u8> {
+        let rlp = Rlp::List(fields).encode();
+        match self.tx_type {
+            TxType::Legacy => rlp,
+            TxType::Eip1559 => [vec![EIP1559_TYPE], rlp].concat(),
+        }
+    }
+
+    /// Signed raw transaction; `v` is `chainId * 2 + 35 + recid` for legacy
+    /// transactions and `yParity = recid` for EIP-1559 ones.
+    pub fn encode_signed(&self, sig: &SignatureRecid) -> Result<Vec<u8>, Error> {
+        if sig.recid > 1 {
+            return Err(Error::SignError(format!(
+                "recovery id {} cannot be expressed as v",
+                sig.recid
+            )));
+        }
+        let (mut fields, v) = match self.tx_type {
+            TxType::Legacy => (
+                self.fields[..6].to_vec(),
+                &self.chain_id * BigInt::from(2) + BigInt::from(35 + sig.recid as u32),
+            ),
+            TxType::Eip1559 => (self.fields.clone(), BigInt::from(sig.recid as u32)),
+        };
+        fields.push(Rlp::uint(&v.to_bytes()));
+        fields.push(Rlp::uint(&sig.r.to_bytes()));
+        fields.push(Rlp::uint(&sig.s.to_bytes()));
+        Ok(self.encode(fields))
+    }
+}
+
+impl fmt::Display for UnsignedTx {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let uint = |name: &str| self.field(name).as_uint(name).unwrap().to_string();
+        let to = match self.field("to").as_bytes("to").unwrap() {
+            [] => "(contract creation)".to_string(),
+            to => checksum(to),
+        };
+        let data = self.field("data").as_bytes("data").unwrap();
+        write!(
+            f,
+            "{} chain {}, nonce {}, to {}, value {} wei, gas {}",
+            match self.tx_type {
+                TxType::Legacy => "legacy",
+                TxType::Eip1559 => "EIP-1559",
+            },
+            self.chain_id,
+            uint("nonce"),
+            to,
+            uint("value"),
+            uint("gas")
+        )?;
+        match self.tx_type {
+            TxType::Legacy => write!(f, ", gas price {} wei", uint("gasPrice"))?,
+            TxType::Eip1559 => write!(
+                f,
+                ", max fee {} wei, max priority fee {} wei",
+                uint("maxFeePerGas"),
+                uint("maxPriorityFeePerGas")
+            )?,
+        }
+        write!(f, ", data 0x{}", hex::encode(data))
+    }
+}
+
+/// What `eth-sign` signs
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EthPayload {
+    /// unsigned raw transaction
+    Tx,
+    /// EIP-191 `personal_sign` message
+    Personal,
+    /// EIP-712 JSON document
+    TypedData,
+}
+
+impl EthPayload {
+    pub const NAMES: [&'static str; 3] = ["tx", "personal", "typed-data"];
+}
+
+impl FromStr for EthPayload {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "tx" => Ok(EthPayload::Tx),
+            "personal" => Ok(EthPayload::Personal),
+            "typed-data" => Ok(EthPayload::TypedData),
+            _ => Err(Error::ParamInvalid(format!("unknown payload kind {:?}", s))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_ecdsa::party_i::recover;
+    use bitcoin::secp256k1;
+    use curv::elliptic::curves::Scalar;
+
+    // EIP-155 example key, address 0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F
+    const SECRET: [u8; 32] = [0x46; 32];
+    const SENDER: &str = "0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F";
+
+    fn secret() -> Scalar<Secp256k1> {
+        Scalar::from_bytes(&SECRET).unwrap()
+    }
+
+    // RFC 6979 signature with the recovery id filled in
+    fn sign(hash: [u8; DIGEST_SIZE]) -> SignatureRecid {
+        let sk = secp256k1::SecretKey::from_slice(&SECRET).unwrap();
+        let msg = secp256k1::Message::from_digest(hash);
+        let compact = secp256k1::Secp256k1::new()
+            .sign_ecdsa(&msg, &sk)
+            .serialize_compact();
+        let mut sig = SignatureRecid::from_compact(&compact).unwrap();
+        let y = Point::generator() * secret();
+        sig.recid = (0..2)
+            .find(|recid| {
+                let sig = SignatureRecid {
+                    recid: *recid,
+                    ..sig.clone()
+                };
+                recover(&sig, &BigInt::from_bytes(&hash)).ok() == Some(y.clone())
+            })
+            .unwrap();
+        sig
+    }
+
+    #[test]
+    fn eip55_checksum() {
+        let pk = Point::generator() * secret();
+        assert_eq!(address(&pk), SENDER);
+        for addr in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            assert_eq!(checksum(&hex::decode(&addr[2..]).unwrap()), addr);
+        }
+    }
+
+    #[test]
+    fn personal_message() {
+        assert_eq!(
+            hex::encode(personal_message_hash(b"hello world")),
+            "d9eba16ed0ecae432b71fe008c98cc872bb4cc214d3220a36f365326cf807d68"
+        );
+    }
+
+    #[test]
+    fn rlp_vectors() {
+        let bytes = |b: &[u8]| Rlp::Bytes(b.to_vec());
+        let list = Rlp::List;
+        let lorem = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        let cases = vec![
+            (bytes(b""), "80".to_string()),
+            (bytes(b"\x0f"), "0f".to_string()),
+            (bytes(b"\x04\x00"), "820400".to_string()),
+            (bytes(b"dog"), "83646f67".to_string()),
+            (
+                list(vec![bytes(b"cat"), bytes(b"dog")]),
+                "c88363617483646f67".to_string(),
+            ),
+            (list(vec![]), "c0".to_string()),
+            (
+                list(vec![
+                    list(vec![]),
+                    list(vec![list(vec![])]),
+                    list(vec![list(vec![]), list(vec![list(vec![])])]),
+                ]),
+                "c7c0c1c0c3c0c1c0".to_string(),
+            ),
+            (bytes(lorem), format!("b838{}", hex::encode(lorem))),
+        ];
+        for (item, encoded) in cases {
+            assert_eq!(hex::encode(item.encode()), encoded);
+            assert_eq!(Rlp::decode(&hex::decode(&encoded).unwrap()).unwrap(), item);
+        }
+        assert_eq!(Rlp::uint(&[0, 0, 4, 0]), bytes(b"\x04\x00"));
+    }
+
+    #[test]
+    fn rlp_rejects_non_canonical() {
+        for encoded in ["", "8100", "b800", "b80100", "83646f", "83646f6700", "c1"] {
+            assert!(Rlp::decode(&hex::decode(encoded).unwrap()).is_err());
+        }
+    }
+
+    #[test]
+    fn eip155_vector() {
+        let unsigned = hex::decode(
+            "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080",
+        )
+        .unwrap();
+        let tx = UnsignedTx::decode(&unsigned).unwrap();
+        assert_eq!(tx.tx_type, TxType::Legacy);
+        assert_eq!(tx.chain_id, BigInt::from(1));
+        assert_eq!(
+            hex::encode(tx.signing_hash()),
+            "daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+        let signed = tx.encode_signed(&sign(tx.signing_hash())).unwrap();
+        assert_eq!(
+            hex::encode(signed),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a7640000\
+             8025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d899\
+             7f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+    }
+
+    #[test]
+    fn eip1559_round_trip() {
+        let uint = |n: u64| Rlp::uint(&n.to_be_bytes());
+        let fields = vec![
+            uint(5),
+            uint(7),
+            uint(2_000_000_000),
+            uint(30_000_000_000),
+            uint(21_000),
+            Rlp::Bytes(vec![0x35; 20]),
+            uint(1_000_000_000_000_000_000),
+            Rlp::Bytes(vec![]),
+            Rlp::List(vec![]),
+        ];
+        let unsigned = [vec![EIP1559_TYPE], Rlp::List(fields.clone()).encode()].concat();
+        let tx = UnsignedTx::decode(&unsigned).unwrap();
+        assert_eq!(tx.tx_type, TxType::Eip1559);
+        assert_eq!(tx.chain_id, BigInt::from(5));
+        assert_eq!(tx.signing_hash(), keccak256(&unsigned));
+
+        let sig = sign(tx.signing_hash());
+        let signed = tx.encode_signed(&sig).unwrap();
+        assert_eq!(signed[0], EIP1559_TYPE);
+        let signed_fields = match Rlp::decode(&signed[1..]).unwrap() {
+            Rlp::List(fields) => fields,
+            Rlp::Bytes(_) => panic!("not a list"),
+        };
+        assert_eq!(signed_fields[..9], fields[..]);
+        assert_eq!(signed_fields[9], Rlp::uint(&[sig.recid]));
+        assert_eq!(signed_fields[10], Rlp::uint(&sig.r.to_bytes()));
+        assert_eq!(signed_fields[11], Rlp::uint(&sig.s.to_bytes()));
+        let y = recover(&sig, &BigInt::from_bytes(&tx.signing_hash())).unwrap();
+        assert_eq!(address(&y), SENDER);
+
+        // a signed transaction is not accepted for signing again
+        assert!(UnsignedTx::decode(&signed).is_err());
+    }
+
+    #[test]
+    fn rejects_unprotected_legacy() {
+        let fields = (0..6).map(|_| Rlp::Bytes(vec![])).collect();
+        assert!(UnsignedTx::decode(&Rlp::List(fields).encode()).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+// watch-only export: account extended public keys and output descriptors
+use bip32::ChainCode;
+use bitcoin::{
+    base58,
+    bip32::{ChildNumber, DerivationPath, Xpub},
+    secp256k1, Network, NetworkKind,
+};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point};
+use serde::Serialize;
+
+use crate::biz_algo::{
+    account::AccountOrigin, btc::AddressType, hd::get_hd_key, psbt::fingerprint, Error,
+};
+
+/// Public data of one account; nothing in it allows signing
+#[derive(Clone, Debug, Serialize)]
+pub struct AccountExport {
+    pub fingerprint: String,
+    pub path: String,
+    #[serde(rename = "type")]
+    pub address_type: String,
+    pub network: String,
+    /// SLIP-132 encoding: xpub/ypub/zpub, tpub/upub/vpub on test networks
+    pub xpub: String,
+    pub descriptors: Descriptors,
+    /// set for `derive-account` keys, whose descriptors have no key origin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Descriptors {
+    pub receive: String,
+    pub change: String,
+}
+
+impl AccountExport {
+    pub fn new(
+        root_y_sum: &Point<Secp256k1>,
+        chain_code: ChainCode,
+        path: &str,
+        address_type: AddressType,
+        network: Network,
+        account: Option<&AccountOrigin>,
+    ) -> Result<Self, Error> {
+        let derivation = path
+            .parse::<DerivationPath>()
+            .map_err(|e| Error::ParamInvalid(format!("invalid path {:?}: {}", path, e)))?;
+        let xpub = account_xpub(root_y_sum, chain_code, &derivation, network)?;
+
+        // the account key must be the one `sign -p` uses
+        if !derivation.is_empty() {
+            let (_, y_sum) = get_hd_key(path, root_y_sum.clone(), chain_code)
+                .map_err(|e| Error::ParamInvalid(format!("cannot derive {}: {}", path, e)))?;
+            if *y_sum.to_bytes(true) != xpub.public_key.serialize()[..] {
+                return Err(Error::KeyshareError(format!(
+                    "xpub at {} does not match the signing key {}",
+                    path,
+                    hex::encode(&*y_sum.to_bytes(true))
+                )));
+            }
+        }
+
+        // the hardened levels of an account are not BIP-32: a `[fingerprint/path]`
+        // origin would name keys that no BIP-32 wallet derives
+        let origin = match (account, derivation.is_empty()) {
+            (Some(_), _) => String::new(),
+            (None, true) => format!("[{}]", fingerprint(root_y_sum)),
+            (None, false) => format!("[{}/{}]", fingerprint(root_y_sum), derivation),
+        };
+        let warning = account.map(|account| {
+            format!(
+                "account {} was derived by derive-account, whose hardened levels are not \
+                 BIP-32: the descriptors have no key origin, and wallets restored from \
+                 a seed will not find these addresses",
+                account.account
+            )
+        });
+        let descriptor = |branch: u32| {
+            let key = format!("{}{}/{}/*", origin, xpub, branch);
+            let script = match address_type {
+                AddressType::P2pkh => format!("pkh({})", key),
+                AddressType::P2shP2wpkh => format!("sh(wpkh({}))", key),
+                AddressType::P2wpkh => format!("wpkh({})", key),
+            };
+            format!("{}#{}", script, descriptor_checksum(&script))
+        };
+        Ok(Self {
+            fingerprint: fingerprint(root_y_sum).to_string(),
+            path: path.to_string(),
+            address_type: address_type.to_string(),
+            network: network.to_string(),
+            xpub: slip132(&xpub, address_type),
+            descriptors: Descriptors {
+                receive: descriptor(0),
+                change: descriptor(1),
+            },
+            warning,
+        })
+    }
+}
+
+/// Extended public key at `path` with its depth, parent fingerprint and child number
+pub fn account_xpub(
+    root_y_sum: &Point<Secp256k1>,
+    chain_code: ChainCode,
+    path: &DerivationPath,
+    network: Network,
+) -> Result<Xpub, Error> {
+    if path.into_iter().any(ChildNumber::is_hardened) {
+        return Err(Error::ParamInvalid(format!(
+            "hardened path m/{} cannot be derived from public data",
+            path
+        )));
+    }
+    let root = Xpub {
+        network: NetworkKind::from(network),
+        depth: 0,
+        parent_fingerprint: Default::default(),
+        child_number: ChildNumber::Normal { index: 0 },
+        public_key: secp256k1::PublicKey::from_slice(&root_y_sum.to_bytes(true)).unwrap(),
+        chain_code: chain_code.into(),
+    };
+    root.derive_pub(&secp256k1::Secp256k1::verification_only(), path)
+        .map_err(|e| Error::ParamInvalid(e.to_string()))
+}
+
+/// Base58 extended key with the SLIP-132 version of the address type
+pub fn slip132(xpub: &Xpub, address_type: AddressType) -> String {
+    let version: [u8; 4] = match (xpub.network, address_type) {
+        (NetworkKind::Main, AddressType::P2pkh) => [0x04, 0x88, 0xb2, 0x1e],
+        (NetworkKind::Main, AddressType::P2shP2wpkh) => [0x04, 0x9d, 0x7c, 0xb2],
+        (NetworkKind::Main, AddressType::P2wpkh) => [0x04, 0xb2, 0x47, 0x46],
+        (NetworkKind::Test, AddressType::P2pkh) => [0x04, 0x35, 0x87, 0xcf],
+        (NetworkKind::Test, AddressType::P2shP2wpkh) => [0x04, 0x4a, 0x52, 0x62],
+        (NetworkKind::Test, AddressType::P2wpkh) => [0x04, 0x5f, 0x1c, 0xf6],
+    };
+    let mut data = xpub.encode();
+    data[..4].copy_from_slice(&version);
+    base58::encode_check(&data)
+}
+
+/// BIP-380 descriptor checksum
+pub fn descriptor_checksum(descriptor: &str) -> String {
+    const INPUT_CHARSET: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+    const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+    fn poly_mod(mut c: u64, val: u64) -> u64 {
+        let c0 = c >> 35;
+        c = ((c & 0x7ffffffff) << 5) ^ val;
+        for (i, g) in [
+            0xf5dee51989,
+            0xa9fdca3312,
+            0x1bab10e32d,
+            0x3706b1677a,
+            0x644d626ffd,
+        ]
+        .iter()
+        .enumerate()
+        {
+            if c0 & (1 << i) != 0 {
+                c ^= g;
+            }
+        }
+        c
+    }
+
+    let mut c = 1;
+    let mut cls = 0;
+    let mut clscount = 0;
+    for ch in descriptor.chars() {
+        let pos = INPUT_CHARSET
+            .find(ch)
+            .expect("character outside the descriptor charset") as u64;
+        c = poly_mod(c, pos & 31);
+        cls = cls * 3 + (pos >> 5);
+        clscount += 1;
+        if clscount == 3 {
+            c = poly_mod(c, cls);
+            cls = 0;
+            clscount = 0;
+        }
+    }
+    if clscount > 0 {
+        c = poly_mod(c, cls);
+    }
+    for _ in 0..8 {
+        c = poly_mod(c, 0);
+    }
+    c ^= 1;
+    (0..8)
+        .map(|j| CHECKSUM_CHARSET[((c >> (5 * (7 - j))) & 31) as usize] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use curv::elliptic::curves::Scalar;
+
+    #[test]
+    fn checksum_vectors() {
+        // BIP-380
+        assert_eq!(descriptor_checksum("raw(deadbeef)"), "89f8spxm");
+        // any single character change is detected
+        let descriptor = "wpkh([d34db33f/84/0/0]xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL/0/*)";
+        let checksum = descriptor_checksum(descriptor);
+        for (i, c) in descriptor.char_indices() {
+            let other = if c == '0' { '1' } else { '0' };
+            let changed = format!("{}{}{}", &descriptor[..i], other, &descriptor[i + 1..]);
+            assert_ne!(descriptor_checksum(&changed), checksum);
+        }
+    }
+
+    #[test]
+    fn descriptors_carry_checksums() {
+        let y = Point::generator() * Scalar::<Secp256k1>::from(7u64);
+        let export = AccountExport::new(
+            &y,
+            [1u8; 32],
+            "m/84/0/0",
+            AddressType::P2wpkh,
+            Network::Bitcoin,
+            None,
+        )
+        .unwrap();
+        assert!(export.xpub.starts_with("zpub"));
+        assert!(export.warning.is_none());
+        for descriptor in [&export.descriptors.receive, &export.descriptors.change] {
+            let (script, checksum) = descriptor.split_once('#').unwrap();
+            assert!(script.starts_with("wpkh(["));
+            assert_eq!(descriptor_checksum(script), checksum);
+        }
+    }
+
+    #[test]
+    fn refuses_hardened_path() {
+        let y = Point::generator() * Scalar::<Secp256k1>::from(7u64);
+        assert!(AccountExport::new(
+            &y,
+            [1u8; 32],
+            "m/84'/0'/0'",
+            AddressType::P2wpkh,
+            Network::Bitcoin,
+            None,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn account_has_no_origin() {
+        let y = Point::generator() * Scalar::<Secp256k1>::from(7u64);
+        let account = AccountOrigin::new(None, "m/84'/0'/0'");
+        let export = AccountExport::new(
+            &y,
+            [1u8; 32],
+            "m/0",
+            AddressType::P2wpkh,
+            Network::Bitcoin,
+            Some(&account),
+        )
+        .unwrap();
+        assert!(
+            export.descriptors.receive.starts_with("wpkh(zpub")
+                || export.descriptors.receive.starts_with("wpkh(xpub")
+        );
+        assert!(!export.descriptors.receive.contains('['));
+        assert!(export.warning.unwrap().contains("m/84'/0'/0'"));
+    }
+}
@@ -6,6 +6,7 @@ use bip32::{
 };
 use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
 use hmac::{Hmac, Mac, NewMac};
+use sha2::{Digest, Sha256};
 use std::{
     convert::{TryFrom, TryInto},
     str::FromStr,
@@ -84,3 +85,44 @@ pub fn get_hd_key(
     let child_pk = Point::<Secp256k1>::from_bytes(&pk.public_key().to_bytes()).unwrap();
     Ok((tweak_sk, child_pk))
 }
+
+/// One CKD step: `HMAC-SHA512(chain_code, data || index)` split into the child
+/// tweak and the child chain code. `data` is the parent public key for normal
+/// children and the jointly computed `x * P` for hardened ones.
+pub fn child_tweak(
+    chain_code: &ChainCode,
+    data: &[u8],
+    index: ChildNumber,
+) -> Result<(Scalar<Secp256k1>, ChainCode)> {
+    let mut hmac = HmacSha512::new_from_slice(chain_code).map_err(|_| Error::Crypto)?;
+    hmac.update(data);
+    hmac.update(&index.to_bytes());
+    let result = hmac.finalize().into_bytes();
+    let (tweak, chain_code) = result.split_at(KEY_SIZE);
+    let tweak = Scalar::<Secp256k1>::from_bytes(tweak).map_err(|_| Error::Crypto)?;
+    Ok((tweak, chain_code.try_into()?))
+}
+
+/// Point `P` of a hardened child, hashed from the parent key, chain code and
+/// index by try-and-increment. Nobody knows its discrete logarithm, so `x * P`
+/// can only be computed by a quorum of the holders of `x`.
+pub fn hardened_base(
+    par_pk: &Point<Secp256k1>,
+    chain_code: &ChainCode,
+    index: ChildNumber,
+) -> Point<Secp256k1> {
+    (0u32..)
+        .find_map(|counter| {
+            let hash = Sha256::new()
+                .chain(b"MPC_HD_GG18 hardened child")
+                .chain(&*par_pk.to_bytes(true))
+                .chain(chain_code)
+                .chain(index.to_bytes())
+                .chain(counter.to_be_bytes())
+                .finalize();
+            let mut bytes = [2u8; 33];
+            bytes[1..].copy_from_slice(&hash);
+            Point::<Secp256k1>::from_bytes(&bytes).ok()
+        })
+        .unwrap()
+}
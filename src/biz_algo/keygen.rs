@@ -1,10 +1,19 @@
-// chain_code = left half of SHA512(pk)
-use std::convert::TryInto;
-use std::{fs, ops::Deref, time};
+// chain_code = SHA256 of the committed random contributions of all parties
+//
+// The rounds are the same for every curve; what a party commits to next to
+// y_i in round 1 is up to its `PartyKeys`: GG18 publishes its Paillier key
+// with a proof of correctness, EdDSA nothing.
+use std::iter::zip;
+use std::time;
 
 use crate::mp_ecdsa::{
-    feldman_vss::VerifiableSS,
-    party_i::{KeyGenBroadcastMessage1, KeyGenDecommitMessage1, Keys, Parameters},
+    feldman_vss::{ShamirSecretSharing, VerifiableSS},
+    party_i::{
+        ChainCodeCommitMessage, ChainCodeDecommitMessage, KeyGenBroadcastMessage1,
+        KeyGenDecommitMessage1, Keys, Parameters, SessionContext,
+    },
+    secret::Secret,
+    validate::Validate,
 };
 use anyhow::Result;
 use bip32::ChainCode;
@@ -12,23 +21,88 @@ use bip39::{Language, Mnemonic};
 use curv::{
     arithmetic::traits::Converter,
     cryptographic_primitives::proofs::sigma_dlog::DLogProof,
-    elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar},
+    elliptic::curves::{Curve, Point, Scalar},
     BigInt,
 };
 use paillier::EncryptionKey;
 use reqwest::Client;
-use sha2::{Digest, Sha256, Sha512};
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::Sha256;
 
 use crate::biz_algo::{
-    aes_decrypt, aes_encrypt, broadcast, poll_for_broadcasts, poll_for_p2p, postb, sendp2p, Params,
-    PartySignup, AEAD, *,
+    aes_decrypt, aes_encrypt, broadcast, parse_msg, poll_for_broadcasts, poll_for_p2p, postb,
+    sendp2p, Params, PartySignup, AEAD, *,
 };
+use crate::util::{MEMBER_ID, SESSION_ID};
 
-pub async fn run_keygen(
+/// Key material of one party, written first in the keys file
+pub trait PartyKeys<E: Curve>: Serialize {
+    /// round 1 broadcast of the party, next to its chain code commitment
+    type Commit: Serialize + DeserializeOwned + Validate<Context = ()>;
+
+    fn create(u_i: Scalar<E>, party_index: u16) -> Self;
+
+    fn u_i(&self) -> &Scalar<E>;
+
+    /// commitment to y_i and its opening
+    fn commit(&self, ctx: &SessionContext) -> (Self::Commit, KeyGenDecommitMessage1<E>);
+
+    /// Checks the openings of all parties, and whatever else their commitments
+    /// carry, then deals the VSS shares of u_i
+    fn verify_commits(
+        &self,
+        params: &Parameters,
+        decom_vec: &[KeyGenDecommitMessage1<E>],
+        com_vec: &[Self::Commit],
+        ctx: &SessionContext,
+    ) -> Result<(VerifiableSS<E>, Vec<Scalar<E>>), Error>;
+
+    /// Paillier keys of all parties, empty if the scheme has none
+    fn encryption_keys(com_vec: &[Self::Commit]) -> Vec<EncryptionKey>;
+}
+
+impl<E: Curve> PartyKeys<E> for Keys<E> {
+    type Commit = KeyGenBroadcastMessage1;
+
+    fn create(u_i: Scalar<E>, party_index: u16) -> Self {
+        Keys::create_with_keypair(u_i, party_index, pool::keypair(false))
+    }
+
+    fn u_i(&self) -> &Scalar<E> {
+        &self.u_i
+    }
+
+    fn commit(&self, ctx: &SessionContext) -> (Self::Commit, KeyGenDecommitMessage1<E>) {
+        self.phase1_broadcast_phase3_proof_of_correct_key(ctx)
+    }
+
+    fn verify_commits(
+        &self,
+        params: &Parameters,
+        decom_vec: &[KeyGenDecommitMessage1<E>],
+        com_vec: &[Self::Commit],
+        ctx: &SessionContext,
+    ) -> Result<(VerifiableSS<E>, Vec<Scalar<E>>), Error> {
+        let (vss_scheme, secret_shares, _index) = self
+            .phase1_verify_com_phase3_verify_correct_key_phase2_distribute(
+                params, decom_vec, com_vec, ctx,
+            )
+            .map_err(|_| Error::KeygenError("invalid key".to_string()))?;
+        Ok((vss_scheme, secret_shares))
+    }
+
+    fn encryption_keys(com_vec: &[Self::Commit]) -> Vec<EncryptionKey> {
+        com_vec.iter().map(|bc1| bc1.e.clone()).collect()
+    }
+}
+
+/// Runs keygen and writes the keys file; returns the mnemonic and the keys file
+pub async fn run_keygen<E: Curve, K: PartyKeys<E>>(
     addr: &String,
     keysfile_path: &String,
     params: &Vec<&str>,
-) -> Result<(String, String), &'static str> {
+    mnemonic_out: &MnemonicOutput,
+) -> Result<(Secret<String>, Secret<String>), Error> {
     let threshold: u16 = params[0].parse::<u16>().unwrap();
     let parties: u16 = params[1].parse::<u16>().unwrap();
 
@@ -52,24 +126,22 @@ pub async fn run_keygen(
     let uuid = party_signup.uuid;
 
     println!("number: {:?}, uuid: {:?}", party_num_int, uuid);
-    unsafe {
-        use crate::util::{MEMBER_ID, SESSION_ID};
-        SESSION_ID.set(uuid.clone()).unwrap();
-        MEMBER_ID.set(party_num_int).unwrap();
-    }
+    SESSION_ID.set(uuid.clone()).unwrap();
+    MEMBER_ID.set(party_num_int).unwrap();
 
-    let party_keys = Keys::create(party_num_int);
-    let mnemonic = Mnemonic::from_entropy(&party_keys.u_i.to_bytes(), Language::English).unwrap(); // 24-word mnemonic
-    let phrase: &str = mnemonic.phrase();
+    let party_keys = K::create(Scalar::random(), party_num_int);
+    let mnemonic = Mnemonic::from_entropy(&party_keys.u_i().to_bytes(), Language::English).unwrap(); // 24-word mnemonic
 
-    let (bc_i, decom_i) = party_keys.phase1_broadcast_phase3_proof_of_correct_key();
-    // round 1: send commitment to ephemeral public keys
+    let round1_ctx = SessionContext::new(&uuid, "keygen-round1");
+    let (bc_i, decom_i) = party_keys.commit(&round1_ctx);
+    let (cc_com_i, cc_decom_i) = Keys::<E>::phase1_commit_chain_code(party_num_int, &round1_ctx);
+    // round 1: send commitment to ephemeral public keys and chain code contribution
     broadcast(
         &addr,
         &client,
         party_num_int,
         "round1",
-        serde_json::to_string(&bc_i).unwrap(),
+        &(&bc_i, &cc_com_i),
         uuid.clone(),
     )
     .await;
@@ -84,19 +156,24 @@ pub async fn run_keygen(
     )
     .await;
 
-    let mut bc1_vec = round1_ans_vec
-        .iter()
-        .map(|m| serde_json::from_str::<KeyGenBroadcastMessage1>(m).unwrap())
-        .collect::<Vec<_>>();
+    let peers = (1..=parties)
+        .filter(|i| *i != party_num_int)
+        .collect::<Vec<u16>>();
+    let (mut bc1_vec, mut cc_com_vec): (Vec<_>, Vec<_>) = zip(&round1_ans_vec, &peers)
+        .map(|(m, i)| parse_msg::<(K::Commit, ChainCodeCommitMessage)>(m, &((), ()), *i, "round1"))
+        .collect::<Result<Vec<_>, Error>>()?
+        .into_iter()
+        .unzip();
     bc1_vec.insert(party_num_int as usize - 1, bc_i);
+    cc_com_vec.insert(party_num_int as usize - 1, cc_com_i);
 
-    // round 2: send ephemeral public keys
+    // round 2: send ephemeral public keys and reveal chain code contribution
     broadcast(
         &addr,
         &client,
         party_num_int,
         "round2",
-        serde_json::to_string(&decom_i).unwrap(),
+        &(&decom_i, &cc_decom_i),
         uuid.clone(),
     )
     .await;
@@ -112,22 +189,22 @@ pub async fn run_keygen(
     .await;
 
     let mut j = 0;
-    let mut point_vec: Vec<Point<Secp256k1>> = Vec::new();
-    let mut decom_vec: Vec<KeyGenDecommitMessage1> = Vec::new();
+    let mut point_vec: Vec<Point<E>> = Vec::new();
+    let mut decom_vec: Vec<KeyGenDecommitMessage1<E>> = Vec::new();
+    let mut cc_decom_vec: Vec<ChainCodeDecommitMessage> = Vec::new();
     let mut enc_keys: Vec<BigInt> = Vec::new();
     for i in 1..=parties {
         if i == party_num_int {
             point_vec.push(decom_i.y_i.clone());
             decom_vec.push(decom_i.clone());
+            cc_decom_vec.push(cc_decom_i.clone());
         } else {
-            let decom_j: KeyGenDecommitMessage1 = serde_json::from_str(&round2_ans_vec[j]).unwrap();
+            let (decom_j, cc_decom_j): (KeyGenDecommitMessage1<E>, ChainCodeDecommitMessage) =
+                parse_msg(&round2_ans_vec[j], &((), ()), i, "round2")?;
             point_vec.push(decom_j.y_i.clone());
             decom_vec.push(decom_j.clone());
-            enc_keys.push(
-                (decom_j.y_i.clone() * party_keys.u_i.clone())
-                    .x_coord()
-                    .unwrap(),
-            );
+            cc_decom_vec.push(cc_decom_j);
+            enc_keys.push((decom_j.y_i.clone() * party_keys.u_i()).x_coord().unwrap());
             j = j + 1;
         }
     }
@@ -136,11 +213,15 @@ pub async fn run_keygen(
     let y_sum = tail.iter().fold(head[0].clone(), |acc, x| acc + x);
 
     // check commitment correctness
-    let (vss_scheme, secret_shares, _index) = party_keys
-        .phase1_verify_com_phase3_verify_correct_key_phase2_distribute(
-            &params, &decom_vec, &bc1_vec,
-        )
-        .expect("invalid key");
+    let (vss_scheme, secret_shares) =
+        worker::compute(|| party_keys.verify_commits(&params, &decom_vec, &bc1_vec, &round1_ctx))?;
+    let chain_code: ChainCode = Keys::<E>::phase2_verify_com_combine_chain_code(
+        &params,
+        &cc_com_vec,
+        &cc_decom_vec,
+        &round1_ctx,
+    )
+    .expect("invalid chain code");
 
     // round 3: send secret shares via aes-p2p
     let mut j = 0;
@@ -156,7 +237,7 @@ pub async fn run_keygen(
                 party_num_int,
                 i,
                 "round3",
-                serde_json::to_string(&aead_pack_i).unwrap(),
+                &aead_pack_i,
                 uuid.clone(),
             )
             .await
@@ -176,16 +257,16 @@ pub async fn run_keygen(
     .await;
 
     let mut j = 0;
-    let mut party_shares: Vec<Scalar<Secp256k1>> = Vec::new();
+    let mut party_shares: Vec<Scalar<E>> = Vec::new();
     for i in 1..=parties {
         if i == party_num_int {
             party_shares.push(secret_shares[(i - 1) as usize].clone());
         } else {
-            let aead_pack: AEAD = serde_json::from_str(&round3_ans_vec[j]).unwrap();
+            let aead_pack: AEAD = parse_msg(&round3_ans_vec[j], &(), i, "round3")?;
             let key_i = BigInt::to_bytes(&enc_keys[j]);
             let out = aes_decrypt(&key_i, aead_pack);
             let out_bn = BigInt::from_bytes(&out);
-            let out_fe = Scalar::<Secp256k1>::from(&out_bn);
+            let out_fe = Scalar::<E>::from(&out_bn);
             party_shares.push(out_fe);
             j += 1;
         }
@@ -197,7 +278,7 @@ pub async fn run_keygen(
         &client,
         party_num_int,
         "round4",
-        serde_json::to_string(&vss_scheme).unwrap(),
+        &vss_scheme,
         uuid.clone(),
     )
     .await;
@@ -212,28 +293,37 @@ pub async fn run_keygen(
     )
     .await;
 
+    let vss_params = ShamirSecretSharing {
+        threshold,
+        share_count: parties,
+    };
     let mut j = 0;
-    let mut vss_scheme_vec: Vec<VerifiableSS<Secp256k1>> = Vec::new();
+    let mut vss_scheme_vec: Vec<VerifiableSS<E>> = Vec::new();
     for i in 1..=parties {
         if i == party_num_int {
             vss_scheme_vec.push(vss_scheme.clone());
         } else {
-            let vss_scheme_j: VerifiableSS<Secp256k1> =
-                serde_json::from_str(&round4_ans_vec[j]).unwrap();
+            let vss_scheme_j: VerifiableSS<E> =
+                parse_msg(&round4_ans_vec[j], &vss_params, i, "round4")?;
             vss_scheme_vec.push(vss_scheme_j);
             j += 1;
         }
     }
 
-    let (shared_keys, dlog_proof) = party_keys
-        .phase2_verify_vss_construct_keypair_phase3_pok_dlog(
+    // every party must end up with the same chain code, otherwise the dlog proofs fail
+    let round5_ctx =
+        SessionContext::new(&uuid, &format!("keygen-round5-{}", hex::encode(chain_code)));
+    let (shared_keys, dlog_proof) = worker::compute(|| {
+        Keys::<E>::phase2_verify_vss_construct_keypair_phase3_pok_dlog(
             &params,
             &point_vec,
             &party_shares,
             &vss_scheme_vec,
             party_num_int,
+            &round5_ctx,
         )
-        .expect("invalid vss");
+    })
+    .expect("invalid vss");
 
     // round 5: send dlog proof
     broadcast(
@@ -241,7 +331,7 @@ pub async fn run_keygen(
         &client,
         party_num_int,
         "round5",
-        serde_json::to_string(&dlog_proof).unwrap(),
+        &dlog_proof,
         uuid.clone(),
     )
     .await;
@@ -257,28 +347,25 @@ pub async fn run_keygen(
     .await;
 
     let mut j = 0;
-    let mut dlog_proof_vec: Vec<DLogProof<Secp256k1, Sha256>> = Vec::new();
+    let mut dlog_proof_vec: Vec<DLogProof<E, Sha256>> = Vec::new();
     for i in 1..=parties {
         if i == party_num_int {
             dlog_proof_vec.push(dlog_proof.clone());
         } else {
-            let dlog_proof_j: DLogProof<Secp256k1, Sha256> =
-                serde_json::from_str(&round5_ans_vec[j]).unwrap();
+            let dlog_proof_j: DLogProof<E, Sha256> =
+                parse_msg(&round5_ans_vec[j], &(), i, "round5")?;
             dlog_proof_vec.push(dlog_proof_j);
             j += 1;
         }
     }
-    Keys::verify_dlog_proofs(&params, &dlog_proof_vec, &point_vec).expect("bad dlog proof");
+    worker::compute(|| {
+        Keys::<E>::verify_dlog_proofs(&params, &dlog_proof_vec, &point_vec, &round5_ctx)
+    })
+    .expect("bad dlog proof");
 
     // save key to file
-    let paillier_key_vec = (0..parties)
-        .map(|i| bc1_vec[i as usize].e.clone())
-        .collect::<Vec<EncryptionKey>>();
-    // let chain_code = ChainCode::from(&Sha512::digest(y_sum.to_bytes(false).deref())[..32]);
-    let chain_code: ChainCode = Sha512::digest(y_sum.to_bytes(false).deref())[..32]
-        .try_into()
-        .unwrap();
-    let keygen_json = serde_json::to_string(&(
+    let paillier_key_vec = K::encryption_keys(&bc1_vec);
+    let keygen_json: Secret<String> = serde_json::to_string(&(
         party_keys,
         shared_keys,
         party_num_int,
@@ -287,11 +374,13 @@ pub async fn run_keygen(
         y_sum,
         chain_code,
     ))
-    .unwrap();
+    .unwrap()
+    .into();
+    write_secret_file(&keysfile_path, keygen_json.as_bytes()).expect("Unable to save !");
     println!("Keys data written to file: {:?}", keysfile_path);
-    println!("Phrase: {}", phrase);
-    fs::write(&keysfile_path, keygen_json.clone()).expect("Unable to save !");
-    Ok((phrase.to_string(), keygen_json))
+    let phrase: Secret<String> = mnemonic.phrase().to_string().into();
+    emit_mnemonic(&phrase, mnemonic_out).expect("Unable to emit mnemonic !");
+    Ok((phrase, keygen_json))
 }
 
 pub async fn keygen_signup(
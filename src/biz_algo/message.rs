@@ -0,0 +1,167 @@
+// prepare the 32-byte digest that `sign` signs
+use std::{
+    fs,
+    io::{self, Read},
+    str::FromStr,
+};
+
+use sha2::{Digest, Sha256, Sha512Trunc256};
+use sha3::Keccak256;
+
+use crate::biz_algo::Error;
+
+pub const DIGEST_SIZE: usize = 32;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HashMode {
+    /// the message already is a 32-byte digest
+    None,
+    Sha256,
+    /// SHA256(SHA256(m)), as used by Bitcoin
+    DoubleSha256,
+    /// original Keccak, as used by Ethereum
+    Keccak256,
+    Sha512_256,
+}
+
+impl HashMode {
+    pub const NAMES: [&'static str; 5] =
+        ["none", "sha256", "double-sha256", "keccak256", "sha512-256"];
+
+    pub fn digest(&self, message: &[u8]) -> Result<[u8; DIGEST_SIZE], Error> {
+        let digest: [u8; DIGEST_SIZE] = match self {
+            HashMode::None => message.try_into().map_err(|_| {
+                Error::ParamInvalid(format!(
+                    "--hash none requires a {}-byte digest, got {} bytes",
+                    DIGEST_SIZE,
+                    message.len()
+                ))
+            })?,
+            HashMode::Sha256 => Sha256::digest(message).into(),
+            HashMode::DoubleSha256 => Sha256::digest(&Sha256::digest(message)).into(),
+            HashMode::Keccak256 => Keccak256::digest(message).into(),
+            HashMode::Sha512_256 => Sha512Trunc256::digest(message).into(),
+        };
+        Ok(digest)
+    }
+}
+
+impl FromStr for HashMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "none" => Ok(HashMode::None),
+            "sha256" => Ok(HashMode::Sha256),
+            "double-sha256" => Ok(HashMode::DoubleSha256),
+            "keccak256" => Ok(HashMode::Keccak256),
+            "sha512-256" => Ok(HashMode::Sha512_256),
+            _ => Err(Error::ParamInvalid(format!("unknown hash mode {:?}", s))),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MessageSource {
+    /// hex string given on the command line, with or without 0x prefix
+    Hex(String),
+    /// raw bytes of a file, or of stdin if the path is "-"
+    File(String),
+}
+
+pub fn read_message(source: &MessageSource) -> Result<Vec<u8>, Error> {
+    read_from(source, io::stdin())
+}
+
+// `source`, with "-" read from `stdin`
+fn read_from(source: &MessageSource, mut stdin: impl Read) -> Result<Vec<u8>, Error> {
+    match source {
+        MessageSource::Hex(s) => {
+            let s = s.strip_prefix("0x").unwrap_or(s);
+            // text used to be signed as its UTF-8 bytes whenever it was not valid hex,
+            // so "cafe" and "cafe!" meant different things; text now has to go through a file
+            hex::decode(s).map_err(|e| {
+                Error::ParamInvalid(format!(
+                    "message is not hex ({}); pass text with --message-file",
+                    e
+                ))
+            })
+        }
+        MessageSource::File(path) if path == "-" => {
+            let mut message = Vec::new();
+            stdin
+                .read_to_end(&mut message)
+                .map_err(|e| Error::ParamInvalid(format!("unable to read stdin: {}", e)))?;
+            Ok(message)
+        }
+        MessageSource::File(path) => fs::read(path)
+            .map_err(|e| Error::ParamInvalid(format!("unable to read {}: {}", path, e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest_hex(mode: &str, message: &[u8]) -> String {
+        hex::encode(mode.parse::<HashMode>().unwrap().digest(message).unwrap())
+    }
+
+    #[test]
+    fn hash_mode_vectors() {
+        let vectors = [
+            (
+                "sha256",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                "double-sha256",
+                "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358",
+            ),
+            (
+                "keccak256",
+                "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45",
+            ),
+            (
+                "sha512-256",
+                "53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23",
+            ),
+        ];
+        for (mode, digest) in vectors {
+            assert_eq!(digest_hex(mode, b"abc"), digest, "{}", mode);
+        }
+        assert!("md5".parse::<HashMode>().is_err());
+    }
+
+    #[test]
+    fn none_needs_a_digest() {
+        let digest = [7u8; DIGEST_SIZE];
+        assert_eq!(HashMode::None.digest(&digest).unwrap(), digest);
+        assert!(HashMode::None.digest(&digest[1..]).is_err());
+        assert!(HashMode::None.digest(&[0u8; DIGEST_SIZE + 1]).is_err());
+    }
+
+    #[test]
+    fn reads_hex_with_or_without_prefix() {
+        for s in ["cafe", "0xcafe"] {
+            let message = read_message(&MessageSource::Hex(s.to_string())).unwrap();
+            assert_eq!(message, [0xca, 0xfe]);
+        }
+        for s in ["cafe!", "caf", "hello"] {
+            assert!(read_message(&MessageSource::Hex(s.to_string())).is_err());
+        }
+    }
+
+    #[test]
+    fn reads_files_and_stdin() {
+        let stdin = MessageSource::File("-".to_string());
+        assert_eq!(read_from(&stdin, &b"cafe!"[..]).unwrap(), b"cafe!");
+
+        let path = std::env::temp_dir().join(format!("message-{}.bin", std::process::id()));
+        fs::write(&path, b"\x00text\n").unwrap();
+        let file = MessageSource::File(path.to_str().unwrap().to_string());
+        assert_eq!(read_from(&file, io::empty()).unwrap(), b"\x00text\n");
+        fs::remove_file(&path).unwrap();
+        assert!(read_message(&file).is_err());
+    }
+}
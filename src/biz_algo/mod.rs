@@ -1,10 +1,34 @@
+pub mod account;
+pub mod btc;
+pub mod cggmp;
+pub mod eddsa;
+pub mod eip712;
+pub mod eth;
+pub mod export;
 pub mod hd;
 pub mod keygen;
 pub mod manager;
+pub mod message;
+pub mod ot;
+pub mod pool;
+pub mod presign;
+pub mod psbt;
 pub mod reshare;
+pub mod schnorr;
 pub mod sign;
-
-use std::{iter::repeat, thread, time, time::Duration};
+pub mod signature;
+pub mod two_party;
+pub mod wire;
+pub mod worker;
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, BufRead, IsTerminal, Write},
+    iter::repeat,
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    thread, time,
+    time::Duration,
+};
 
 use aes_gcm::{
     aead::{Aead, NewAead, Payload},
@@ -14,11 +38,13 @@ use aes_gcm::{
 use rand::{rngs::OsRng, RngCore};
 
 use curv::{
-    elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar},
+    elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar},
     BigInt,
 };
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::mp_ecdsa::validate::{check_len, Validate, ValidationError};
 
 use thiserror::Error;
 #[derive(Error, PartialEq, Eq, Clone, Debug, Deserialize)]
@@ -43,16 +69,44 @@ pub enum Error {
     ParamInvalid(String),
     #[error("Keyshare-Error")]
     KeyshareError(String),
+    #[error("Invalid-Message")]
+    InvalidMessage(String),
 }
 
 pub type Key = String;
 
+// polling interval of the wait for announcements before the first message
+const SETTLE_DELAY: Duration = Duration::from_millis(100);
+
+/// Where the recovery phrase of a key share goes. It is never printed unless asked for.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MnemonicOutput {
+    Skip,
+    /// write it to this file, readable by the owner only
+    File(String),
+    /// show it on the terminal once the user confirms
+    Prompt,
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct AEAD {
     pub ciphertext: Vec<u8>,
     pub tag: Vec<u8>,
 }
 
+impl Validate for AEAD {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> std::result::Result<(), ValidationError> {
+        // tag holds the 96-bit GCM nonce, ciphertext carries the 128-bit auth tag
+        check_len(self.tag.len(), 12, "aead.tag")?;
+        if self.ciphertext.len() < 16 {
+            return Err(ValidationError::OutOfRange("aead.ciphertext"));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct PartySignup {
     pub number: u16,
@@ -125,11 +179,27 @@ pub fn aes_decrypt(key: &[u8], aead_pack: AEAD) -> Vec<u8> {
     out.unwrap_or_default()
 }
 
+/// Deserialize the message `party` sent in `round` and validate it before use.
+pub fn parse_msg<T>(msg: &str, ctx: &T::Context, party: u16, round: &str) -> Result<T, Error>
+where
+    T: DeserializeOwned + Validate,
+{
+    let value: T = wire::decode(msg)
+        .map_err(|e| Error::InvalidMessage(format!("party {} in {}: {}", party, round, e)))?;
+    value
+        .validate(ctx)
+        .map_err(|e| Error::InvalidMessage(format!("party {} in {}: {}", party, round, e)))?;
+    Ok(value)
+}
+
 pub async fn signup(addr: &String, client: &Client, path: &str, params: &Params) -> PartySignup {
     let res_body = postb(&addr, &client, path, params).await;
     println!("{}", &res_body);
     let res: Result<PartySignup, ()> = serde_json::from_str(&res_body).unwrap();
-    res.unwrap()
+    let party_signup = res.unwrap();
+    let parties = params.parties.parse::<u16>().unwrap();
+    wire::announce(addr, client, &party_signup, parties).await;
+    party_signup
 }
 
 pub async fn postb<T>(addr: &String, client: &Client, path: &str, body: T) -> String
@@ -150,15 +220,18 @@ where
     res.text().await.unwrap()
 }
 
-pub async fn broadcast(
+pub async fn broadcast<T: Serialize + ?Sized>(
     addr: &String,
     client: &Client,
     party_num: u16,
     round: &str,
-    data: String,
+    data: &T,
     sender_uuid: String,
 ) {
     let key = format!("{}-{}-{}", party_num, round, sender_uuid);
+    let sent = |i| format!("{}-{}-{}", i, round, sender_uuid);
+    wire::settle(addr, client, &sender_uuid, SETTLE_DELAY, sent).await;
+    let data = wire::encode(data);
     let entry = Entry {
         key: key.clone(),
         value: data,
@@ -169,16 +242,19 @@ pub async fn broadcast(
     res.unwrap();
 }
 
-pub async fn sendp2p(
+pub async fn sendp2p<T: Serialize + ?Sized>(
     addr: &String,
     client: &Client,
     party_from: u16,
     party_to: u16,
     round: &str,
-    data: String,
+    data: &T,
     sender_uuid: String,
 ) -> Result<(), ()> {
     let key = format!("{}-{}-{}-{}", party_from, party_to, round, sender_uuid);
+    let sent = |i| format!("{}-{}-{}-{}", i, party_from, round, sender_uuid);
+    wire::settle(addr, client, &sender_uuid, SETTLE_DELAY, sent).await;
+    let data = wire::encode(data);
 
     let entry = Entry {
         key: key.clone(),
@@ -217,6 +293,8 @@ pub async fn poll_for_broadcasts(
             }
         }
     }
+    let senders = (1..=n).collect::<Vec<u16>>();
+    wire::negotiate(addr, client, &sender_uuid, &senders).await;
     ans_vec
 }
 
@@ -305,22 +383,19 @@ pub async fn single_poll_for_p2p(
             break;
         }
     }
+    wire::negotiate(addr, client, &sender_uuid, &[sender_index]).await;
     ans
 }
 
 #[allow(dead_code)]
-pub fn check_sig(
-    r: &Scalar<Secp256k1>,
-    s: &Scalar<Secp256k1>,
-    msg: &BigInt,
-    pk: &Point<Secp256k1>,
-) {
-    let s_inv: Scalar<Secp256k1> = s.invert().unwrap_or_else(|| Scalar::<Secp256k1>::zero());
+pub fn check_sig<E: Curve>(r: &Scalar<E>, s: &Scalar<E>, msg: &BigInt, pk: &Point<E>) {
+    let s_inv: Scalar<E> = s.invert().unwrap_or_else(|| Scalar::<E>::zero());
     let r_prime =
-        (&s_inv * &Scalar::<Secp256k1>::from_bigint(&msg)) * Point::generator() + (r * &s_inv) * pk;
+        (&s_inv * &Scalar::<E>::from_bigint(&msg)) * Point::generator() + (r * &s_inv) * pk;
+    // x is reduced mod n; only matters on curves whose field is larger than the group order
     assert_eq!(
-        r_prime.x_coord().unwrap_or_else(|| BigInt::from(0u16)),
-        r.to_bigint()
+        Scalar::<E>::from_bigint(&r_prime.x_coord().unwrap_or_else(|| BigInt::from(0u16))),
+        *r
     );
 }
 
@@ -333,3 +408,51 @@ pub fn scalar_split(num: &Scalar<Secp256k1>, count: &u16) -> Vec<Scalar<Secp256k
     partition.push(num - partial_sum);
     partition
 }
+
+/// Writes key material to `path` with 0600 permissions, also when the file already exists.
+pub fn write_secret_file(path: &str, contents: &[u8]) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(contents)
+}
+
+pub fn emit_mnemonic(phrase: &str, output: &MnemonicOutput) -> Result<(), Error> {
+    match output {
+        MnemonicOutput::Skip => Ok(()),
+        MnemonicOutput::File(path) => {
+            write_secret_file(path, phrase.as_bytes())
+                .map_err(|e| Error::KeyshareError(format!("unable to write mnemonic: {}", e)))?;
+            println!("Mnemonic written to file: {:?}", path);
+            Ok(())
+        }
+        MnemonicOutput::Prompt => {
+            if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+                return Err(Error::KeyshareError(
+                    "mnemonic can only be shown on an interactive terminal".to_string(),
+                ));
+            }
+            let stdin = io::stdin();
+            let mut answer = String::new();
+            print!("Show the recovery phrase of this key share on screen? [y/N] ");
+            io::stdout().flush().ok();
+            stdin.lock().read_line(&mut answer).ok();
+            if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                println!("Mnemonic not shown.");
+                return Ok(());
+            }
+            println!("{}", phrase);
+            print!("Press Enter once it is written down to clear the screen.");
+            io::stdout().flush().ok();
+            stdin.lock().read_line(&mut answer).ok();
+            // clear screen and scrollback
+            print!("\x1b[2J\x1b[3J\x1b[H");
+            io::stdout().flush().ok();
+            Ok(())
+        }
+    }
+}
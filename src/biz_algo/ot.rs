@@ -0,0 +1,170 @@
+// Base OTs for the OT-based MtA of `sign` (see mp_ecdsa::mta::ot)
+//
+// All key shares take part. In round 1 each party broadcasts its base OT sender key
+// S = y * G with a proof of knowledge of y; in round 2 it sends each peer its
+// choices R in the base OTs that peer offers. Each pair then holds KAPPA base OTs
+// in each direction, which are appended to the keys file and extended at every
+// signature. They do not depend on the key shares, so refresh and derive-account
+// keep them; reshare, which changes the key shares, drops them.
+use crate::mp_cggmp::aux::AuxInfo;
+use crate::mp_ecdsa::{
+    mta::ot::{base_ot_send, BaseOtChoice, BaseOtReceiver, OtSetup, MTA},
+    party_i::*,
+    secret::Secret,
+};
+use curv::{
+    cryptographic_primitives::proofs::sigma_dlog::DLogProof,
+    elliptic::curves::{Curve, Point, Scalar},
+};
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::biz_algo::{
+    account::{account_of, AccountOrigin},
+    broadcast,
+    cggmp::{split_keys_file, KeysFile},
+    parse_msg, poll_for_broadcasts, poll_for_p2p, sendp2p,
+    sign::open_session,
+    write_secret_file, Error, Params,
+};
+
+/// Runs the base OTs of every pair of key shares and appends them to the keys file,
+/// replacing earlier ones.
+pub async fn setup<E: Curve>(
+    addr: &String,
+    keysfile_path: &str,
+    params: &Params,
+) -> Result<(), Error> {
+    let data = std::fs::read_to_string(keysfile_path)
+        .unwrap_or_else(|_| panic!("Unable to load keys file at location: {}", keysfile_path));
+    let (keys, aux) = split_keys_file(&data);
+    let keys: KeysFile<E> = serde_json::from_value(keys).unwrap();
+    let (_, _, party_id, vss_scheme_vec, _, y_sum, _) = &keys;
+    let share_count: u16 = params.share_count.parse::<u16>().unwrap();
+    if params.parties != params.share_count {
+        return Err(Error::ParamInvalid(
+            "all key shares take part in the OT setup".to_string(),
+        ));
+    }
+    if usize::from(share_count) != vss_scheme_vec.len() {
+        return Err(Error::ParamInvalid(
+            "share count does not match the keys file".to_string(),
+        ));
+    }
+
+    let session = open_session(addr, *party_id, y_sum, params, &[], "ot-setup").await?;
+    let me = session.party_num_int;
+    let ctx = SessionContext::new(&session.uuid, "ot-setup");
+    // base OTs from `sender` to `receiver`
+    let tag = |receiver: u16, sender: u16| [ctx.tag(receiver), ctx.tag(sender)].concat();
+
+    // round 1: base OT sender key
+    let y = Secret::new(Scalar::<E>::random());
+    let proof = DLogProof::<E, Sha256>::prove_with_ctx(&y, &ctx.tag(me));
+    broadcast(
+        addr,
+        &session.client,
+        me,
+        "round1",
+        &proof,
+        session.uuid.clone(),
+    )
+    .await;
+    let round1_ans_vec = poll_for_broadcasts(
+        addr,
+        &session.client,
+        me,
+        session.parties,
+        session.delay,
+        "round1",
+        session.uuid.clone(),
+    )
+    .await;
+    let mut sender_keys = Vec::new();
+    for (msg, j) in round1_ans_vec.iter().zip(&session.peers) {
+        let proof_j: DLogProof<E, Sha256> = parse_msg(msg, &(), *j, "round1")?;
+        if DLogProof::verify_with_ctx(&proof_j, &ctx.tag(*j)).is_err() {
+            return Err(Error::InvalidMessage(format!(
+                "party {} in round1: invalid base OT sender key",
+                j
+            )));
+        }
+        sender_keys.push(proof_j.pk);
+    }
+
+    // round 2: choose in the base OTs of each peer
+    let mut receivers = Vec::new();
+    for (S, j) in sender_keys.iter().zip(&session.peers) {
+        let (receiver, choice) = BaseOtReceiver::new(S, &tag(me, *j));
+        assert!(sendp2p(
+            addr,
+            &session.client,
+            me,
+            *j,
+            "round2",
+            &choice,
+            session.uuid.clone(),
+        )
+        .await
+        .is_ok());
+        receivers.push(receiver);
+    }
+    let round2_ans_vec = poll_for_p2p(
+        addr,
+        &session.client,
+        me,
+        session.parties,
+        session.delay,
+        "round2",
+        session.uuid.clone(),
+    )
+    .await;
+    let S = Point::generator() * &*y;
+    let mut pairs = vec![None; usize::from(share_count)];
+    for ((msg, j), receiver) in round2_ans_vec.iter().zip(&session.peers).zip(receivers) {
+        let choice: BaseOtChoice<E> = parse_msg(msg, &(), *j, "round2")?;
+        let sent = base_ot_send(&y, &S, &choice, &tag(*j, me)).map_err(|_| {
+            Error::InvalidMessage(format!("party {} in round2: invalid base OT choice", j))
+        })?;
+        let index = session.signers_vec[usize::from(*j) - 1];
+        pairs[usize::from(index)] = Some(receiver.pair(sent));
+    }
+
+    let ot_setup = OtSetup::new(pairs);
+    let keys_json = keys_file_json(
+        &keys,
+        aux.as_ref(),
+        Some(&ot_setup),
+        account_of(&data).as_ref(),
+    );
+    write_secret_file(keysfile_path, keys_json.as_bytes()).expect("Unable to save !");
+    println!("Base OTs written to file: {:?}", keysfile_path);
+    Ok(())
+}
+
+/// Base OTs of a keys file, if it has any
+pub fn ot_setup_of(data: &str) -> Option<OtSetup> {
+    let keys: serde_json::Value = serde_json::from_str(data).unwrap();
+    let item = keys
+        .as_array()?
+        .iter()
+        .skip(7)
+        .find(|item| item["mta"].as_str() == Some(MTA))?;
+    Some(serde_json::from_value(item.clone()).unwrap())
+}
+
+/// Keys file of the GG18 tuple `keys`, followed by `aux`, `ot_setup` and
+/// `account` if any
+pub fn keys_file_json<T: Serialize>(
+    keys: &T,
+    aux: Option<&AuxInfo>,
+    ot_setup: Option<&OtSetup>,
+    account: Option<&AccountOrigin>,
+) -> Secret<String> {
+    let mut items: Vec<serde_json::Value> =
+        serde_json::from_value(serde_json::to_value(keys).unwrap()).unwrap();
+    items.extend(aux.map(|aux| serde_json::to_value(aux).unwrap()));
+    items.extend(ot_setup.map(|setup| serde_json::to_value(setup).unwrap()));
+    items.extend(account.map(|account| serde_json::to_value(account).unwrap()));
+    serde_json::to_string(&items).unwrap().into()
+}
@@ -0,0 +1,319 @@
+// Pools of Paillier material generated ahead of time
+//
+// A Paillier keypair takes seconds to generate (minutes over safe primes) and each
+// MtA encryption spends most of its time on r^N mod N^2. With `--pool <dir>`,
+// keygen and reshare take their keypair, refresh its safe-prime keypair, and
+// signing its encryption randomness from files that `pool` keeps topped up in
+// the background:
+//
+//   <dir>/paillier/<id>.key                  keypairs for keygen and reshare
+//   <dir>/paillier-safe/<id>.key             keypairs over safe primes for refresh
+//   <dir>/randomness/<digest of N>/<id>.rn   chunks of (r, r^N mod N^2) under N
+//
+// Everything in the pool is secret: a keypair is a future key share's Paillier
+// key and r opens the ciphertexts it is used in. Files are written under a
+// temporary name and renamed into place, and taken the way presignatures are:
+// renamed away before they are read, so no keypair or randomness is handed out
+// twice, also to concurrent processes. Keypairs are validated when generated and
+// again when taken. An empty or missing pool falls back to generating on the spot.
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use curv::{
+    arithmetic::traits::{BitManipulation, Converter, Primes, Samplable},
+    BigInt,
+};
+use paillier::{
+    Decrypt, DecryptionKey, Encrypt, EncryptionKey, KeyGeneration, Paillier, RawCiphertext,
+    RawPlaintext,
+};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::biz_algo::{worker, write_secret_file, Error};
+use crate::mp_ecdsa::mta::{PaillierRandomness, RandomnessSource};
+
+/// randomness values per file
+const CHUNK: usize = 16;
+const PRIME_REPS: u32 = 40;
+
+static POOL: OnceLock<PathBuf> = OnceLock::new();
+
+/// Makes keygen, reshare, refresh and signing draw from the pool at `dir`.
+pub fn init(dir: &str) -> Result<(), Error> {
+    let dir = PathBuf::from(dir);
+    for sub in [
+        keypair_dir(&dir, false),
+        keypair_dir(&dir, true),
+        dir.join("randomness"),
+    ] {
+        fs::create_dir_all(&sub).map_err(|e| {
+            Error::ParamInvalid(format!("cannot create pool {}: {}", sub.display(), e))
+        })?;
+    }
+    POOL.set(dir)
+        .map_err(|_| Error::ParamInvalid("pool already set".to_string()))
+}
+
+#[derive(Serialize, Deserialize)]
+struct PooledKeypair {
+    ek: EncryptionKey,
+    dk: DecryptionKey,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RandomnessChunk {
+    n: BigInt,
+    values: Vec<PaillierRandomness>,
+}
+
+fn keypair_dir(dir: &Path, safe: bool) -> PathBuf {
+    dir.join(if safe { "paillier-safe" } else { "paillier" })
+}
+
+fn randomness_dir(dir: &Path, ek: &EncryptionKey) -> PathBuf {
+    let digest = Sha256::digest(&ek.n.to_bytes());
+    dir.join("randomness").join(hex::encode(&digest[..16]))
+}
+
+fn entries(dir: &Path, ext: &str) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|e| e == ext))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// takers never see a partly written file
+fn put(dir: &Path, ext: &str, contents: &[u8]) -> Result<(), Error> {
+    let mut id = [0u8; 16];
+    OsRng.fill_bytes(&mut id);
+    let id = hex::encode(id);
+    let tmp = dir.join(format!("{}.tmp", id));
+    write_secret_file(&tmp.to_string_lossy(), contents)
+        .and_then(|_| fs::rename(&tmp, dir.join(format!("{}.{}", id, ext))))
+        .map_err(|e| Error::KeyshareError(format!("cannot write to {}: {}", dir.display(), e)))
+}
+
+// a rename succeeds for one taker only
+fn claim(dir: &Path, ext: &str) -> Option<Vec<u8>> {
+    for path in entries(dir, ext) {
+        let claimed = path.with_extension("taken");
+        if fs::rename(&path, &claimed).is_err() {
+            continue;
+        }
+        let data = fs::read(&claimed);
+        let _ = fs::remove_file(&claimed);
+        if let Ok(data) = data {
+            return Some(data);
+        }
+    }
+    None
+}
+
+fn generate_keypair(safe: bool) -> (EncryptionKey, DecryptionKey) {
+    if safe {
+        Paillier::keypair_safe_primes().keys()
+    } else {
+        Paillier::keypair().keys()
+    }
+}
+
+fn validate_keypair(ek: &EncryptionKey, dk: &DecryptionKey, safe: bool) -> Result<(), String> {
+    let (p, q) = (&dk.p, &dk.q);
+    if p == q || ek.n != p * q || ek.nn != &ek.n * &ek.n {
+        return Err("N is not p * q".to_string());
+    }
+    if ek.n.bit_length() < 2047 {
+        return Err(format!("N has {} bits", ek.n.bit_length()));
+    }
+    if !p.is_probable_prime(PRIME_REPS) || !q.is_probable_prime(PRIME_REPS) {
+        return Err("p or q is not prime".to_string());
+    }
+    let half = |x: &BigInt| (x - BigInt::from(1)) >> 1;
+    if safe && !(half(p).is_probable_prime(PRIME_REPS) && half(q).is_probable_prime(PRIME_REPS)) {
+        return Err("p or q is not a safe prime".to_string());
+    }
+    let m = BigInt::sample_below(&ek.n);
+    let c = Paillier::encrypt(ek, RawPlaintext::from(&m));
+    let d = Paillier::decrypt(dk, RawCiphertext::from(c.0.into_owned()));
+    if d.0.as_ref() != &m {
+        return Err("decryption does not invert encryption".to_string());
+    }
+    Ok(())
+}
+
+/// A Paillier keypair, over safe primes if `safe`: from the pool if there is one
+/// and it is not empty, else generated now.
+pub fn keypair(safe: bool) -> (EncryptionKey, DecryptionKey) {
+    if let Some(root) = POOL.get() {
+        let dir = keypair_dir(root, safe);
+        while let Some(data) = claim(&dir, "key") {
+            let pooled = serde_json::from_slice::<PooledKeypair>(&data)
+                .map_err(|e| e.to_string())
+                .and_then(|kp| validate_keypair(&kp.ek, &kp.dk, safe).map(|_| kp));
+            match pooled {
+                Ok(kp) => {
+                    println!("Paillier key taken from pool {}", dir.display());
+                    return (kp.ek, kp.dk);
+                }
+                Err(e) => eprintln!("Discarding Paillier key from pool: {}", e),
+            }
+        }
+        println!("Paillier key pool {} is empty", dir.display());
+    }
+    if safe {
+        println!("Generating Paillier key over safe primes...");
+    }
+    generate_keypair(safe)
+}
+
+/// Encryption randomness from the pool, a chunk at a time; sampled on the spot if
+/// there is no pool or it has none left under a key. What was taken but not used
+/// goes back to the pool on drop.
+pub struct PooledRandomness {
+    root: Option<&'static Path>,
+    taken: Mutex<HashMap<PathBuf, RandomnessChunk>>,
+}
+
+/// The randomness source for signing
+pub fn randomness() -> PooledRandomness {
+    PooledRandomness {
+        root: POOL.get().map(|dir| dir.as_path()),
+        taken: Mutex::new(HashMap::new()),
+    }
+}
+
+impl RandomnessSource for PooledRandomness {
+    fn take(&self, ek: &EncryptionKey) -> PaillierRandomness {
+        let Some(root) = self.root else {
+            return PaillierRandomness::sample(ek);
+        };
+        let dir = randomness_dir(root, ek);
+        let mut taken = self.taken.lock().unwrap();
+        let left = taken.entry(dir.clone()).or_insert_with(|| RandomnessChunk {
+            n: ek.n.clone(),
+            values: Vec::new(),
+        });
+        if left.values.is_empty() {
+            let chunk = claim(&dir, "rn")
+                .and_then(|data| serde_json::from_slice::<RandomnessChunk>(&data).ok());
+            if let Some(chunk) = chunk.filter(|chunk| chunk.n == ek.n) {
+                *left = chunk;
+            }
+        }
+        match left.values.pop() {
+            Some(value) => value,
+            None => {
+                drop(taken);
+                PaillierRandomness::sample(ek)
+            }
+        }
+    }
+}
+
+impl Drop for PooledRandomness {
+    fn drop(&mut self) {
+        for (dir, left) in self.taken.get_mut().unwrap().drain() {
+            if !left.values.is_empty() {
+                let _ = put(&dir, "rn", &serde_json::to_vec(&left).unwrap());
+            }
+        }
+    }
+}
+
+/// How much `run` keeps in the pool
+pub struct Targets {
+    pub keypairs: usize,
+    pub safe_keypairs: usize,
+    /// randomness values under each Paillier key of the keys files
+    pub randomness: usize,
+}
+
+// Paillier keys of every key share in a keys file: the fifth element of the
+// GG18 tuple; keys files without one (Lindell'17, EdDSA) have none to pool for
+fn keysfile_eks(path: &str) -> Vec<EncryptionKey> {
+    let eks = fs::read_to_string(path)
+        .ok()
+        .and_then(|data| serde_json::from_str::<Vec<serde_json::Value>>(&data).ok())
+        .and_then(|items| items.into_iter().nth(4))
+        .and_then(|eks| serde_json::from_value::<Vec<EncryptionKey>>(eks).ok());
+    match eks {
+        Some(eks) => eks,
+        None => {
+            eprintln!("{}: no Paillier keys to pool randomness for", path);
+            Vec::new()
+        }
+    }
+}
+
+fn fill_keypairs(dir: &Path, safe: bool, target: usize) -> Result<usize, Error> {
+    let missing = target.saturating_sub(entries(dir, "key").len());
+    let written = worker::par_map(vec![(); missing], |_| {
+        let (ek, dk) = generate_keypair(safe);
+        validate_keypair(&ek, &dk, safe).map_err(Error::KeygenError)?;
+        let contents = serde_json::to_vec(&PooledKeypair { ek, dk }).unwrap();
+        put(dir, "key", &contents)
+    });
+    written.into_iter().collect::<Result<Vec<()>, Error>>()?;
+    Ok(missing)
+}
+
+fn fill_randomness(root: &Path, ek: &EncryptionKey, target: usize) -> Result<usize, Error> {
+    let dir = randomness_dir(root, ek);
+    fs::create_dir_all(&dir)
+        .map_err(|e| Error::KeyshareError(format!("cannot create {}: {}", dir.display(), e)))?;
+    let missing = target
+        .saturating_sub(entries(&dir, "rn").len() * CHUNK)
+        .div_ceil(CHUNK);
+    let written = worker::par_map(vec![(); missing], |_| {
+        let chunk = RandomnessChunk {
+            n: ek.n.clone(),
+            values: (0..CHUNK).map(|_| PaillierRandomness::sample(ek)).collect(),
+        };
+        put(&dir, "rn", &serde_json::to_vec(&chunk).unwrap())
+    });
+    written.into_iter().collect::<Result<Vec<()>, Error>>()?;
+    Ok(missing * CHUNK)
+}
+
+/// Tops the pool up to `targets`, then again whenever something was taken; only
+/// once if `once`. Randomness is pooled for the Paillier keys in `keysfiles`,
+/// read again on each pass so that reshared or refreshed keys are picked up.
+pub async fn run(keysfiles: &[String], targets: &Targets, once: bool) -> Result<(), Error> {
+    let root = POOL
+        .get()
+        .ok_or_else(|| Error::ParamInvalid("pool needs --pool <dir>".to_string()))?;
+    loop {
+        let keypairs = fill_keypairs(&keypair_dir(root, false), false, targets.keypairs)?;
+        let safe_keypairs = fill_keypairs(&keypair_dir(root, true), true, targets.safe_keypairs)?;
+        let mut randomness = 0;
+        for path in keysfiles {
+            for ek in keysfile_eks(path) {
+                randomness += fill_randomness(root, &ek, targets.randomness)?;
+            }
+        }
+        if keypairs + safe_keypairs + randomness > 0 {
+            println!(
+                "Pool {}: added {} Paillier keys, {} over safe primes, {} randomness values",
+                root.display(),
+                keypairs,
+                safe_keypairs,
+                randomness
+            );
+        }
+        if once {
+            return Ok(());
+        }
+        tokio::time::sleep(Duration::from_secs(10)).await;
+    }
+}
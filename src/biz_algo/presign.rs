@@ -0,0 +1,450 @@
+// offline/online split of GG18 signing
+//
+// Rounds 1 to 3 of `sign` (MtA, delta, R) do not depend on the message. `presign`
+// runs them ahead of time and leaves every signer with k_i, sigma_i (its additive
+// shares of k and k * x) and R = k^-1 * G. Signing a message m then needs only
+// s_i = m * k_i + r * sigma_i of every signer; the sum is a signature, which is
+// checked against the public key before it is returned.
+//
+// A GG18 presignature gives no way to check an s_j on its own, and a wrong s_j
+// would make the others' shares leak the key. Its s_i is therefore released in
+// phase 5E only, after the commitments and proofs of phases 5A to 5D show that
+// the shares add up to a valid signature, as in `sign`. Presignatures of CGGMP21
+// (`cggmp::presign`) also carry k_j * R and chi_j * R of every signer, so that
+// s_i is sent in one round and each s_j is checked on its own, naming the
+// signer of a wrong one.
+//
+// A presignature is bound to the derivation path it was made for, which the
+// signers agree on at presign time; it cannot sign at another path.
+//
+// A presignature signs at most one message: two signatures with the same R
+// reveal the key. Each is stored in its own file, encrypted under the key share,
+// and `take` renames it away before it is read, so concurrent signers cannot
+// both get it and a failed signing attempt still burns it.
+use std::{fs, io, iter::zip, path::Path, time};
+
+use crate::mp_ecdsa::{
+    mta::{ot::OtSetup, OtMta, PaillierMta},
+    party_i::*,
+    secret::Secret,
+    validate::{Validate, ValidationError},
+};
+use curv::{
+    arithmetic::Converter,
+    elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar},
+    BigInt,
+};
+use paillier::EncryptionKey;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::biz_algo::{
+    aes_decrypt, aes_encrypt, broadcast,
+    message::DIGEST_SIZE,
+    parse_msg, poll_for_broadcasts, pool,
+    sign::{
+        join_session, presign_batch, sign_rounds, with_mta, SignAgreement, SignRequest, SignSession,
+    },
+    signup, write_secret_file, Error, Params, AEAD,
+};
+use crate::mp_ecdsa::feldman_vss::VerifiableSS;
+
+/// One party's part of a presignature
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Presignature<E: Curve = Secp256k1> {
+    pub id: String,
+    /// root public key, as `SignAgreement::key_id`
+    pub key_id: String,
+    /// key share index of every signer, ordered by party number
+    pub signers: Vec<u16>,
+    pub party_id: u16,
+    /// derivation path of the key it signs for, empty for the root key
+    #[serde(default)]
+    pub path: String,
+    pub k_i: Secret<Scalar<E>>,
+    pub sigma_i: Secret<Scalar<E>>,
+    pub R: Point<E>,
+    /// empty for GG18 presignatures, whose shares are only checked as a sum
+    #[serde(default)]
+    pub checks: Vec<ShareCheck<E>>,
+}
+
+/// Values a signature share s_j = m * k_j + r * sigma_j must be consistent with:
+/// s_j * R = m * k_r + r * (chi_r + tweak * k_r)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct ShareCheck<E: Curve = Secp256k1> {
+    pub party_id: u16,
+    pub k_r: Point<E>,
+    pub chi_r: Point<E>,
+}
+
+/// Content of a presignature file; the tags are kept in the clear to pick a file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SealedPresignature {
+    id: String,
+    key_id: String,
+    signers: Vec<u16>,
+    aead: AEAD,
+}
+
+/// First message of an online signing round
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct PresignedShare<E: Curve = Secp256k1> {
+    pub agreement: SignAgreement,
+    pub presig_id: String,
+    /// None for GG18 presignatures, whose s_i is only sent in phase 5E
+    #[serde(default)]
+    pub s_i: Option<Scalar<E>>,
+}
+
+impl<E: Curve> Validate for PresignedShare<E> {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        self.agreement.validate(&())?;
+        match &self.s_i {
+            Some(s_i) => s_i.validate(&()),
+            None => Ok(()),
+        }
+    }
+}
+
+// AES key of the presignature files of a key share
+fn sealing_key<E: Curve>(shared_keys: &SharedKeys<E>, id: &str) -> Vec<u8> {
+    Sha256::new()
+        .chain(b"presignature")
+        .chain(&*shared_keys.x_i.to_bytes())
+        .chain(id.as_bytes())
+        .finalize()
+        .to_vec()
+}
+
+/// Writes `presig` to `<dir>/<id>.presig`, readable by the owner only.
+pub fn store<E: Curve>(
+    dir: &str,
+    presig: &Presignature<E>,
+    shared_keys: &SharedKeys<E>,
+) -> io::Result<String> {
+    fs::create_dir_all(dir)?;
+    let plaintext = serde_json::to_vec(presig).unwrap();
+    let sealed = SealedPresignature {
+        id: presig.id.clone(),
+        key_id: presig.key_id.clone(),
+        signers: presig.signers.clone(),
+        aead: aes_encrypt(&sealing_key(shared_keys, &presig.id), &plaintext),
+    };
+    let path = Path::new(dir).join(format!("{}.presig", presig.id));
+    let path = path.to_string_lossy().to_string();
+    write_secret_file(&path, &serde_json::to_vec_pretty(&sealed).unwrap())?;
+    Ok(path)
+}
+
+/// Claims and deletes the presignature file at `path`, then decrypts it. A file
+/// can be taken only once, also by concurrent callers.
+pub fn take<E: Curve>(path: &str, shared_keys: &SharedKeys<E>) -> Result<Presignature<E>, Error> {
+    // burn before use: a rename succeeds for one caller only
+    let claimed = format!("{}.burn", path);
+    fs::rename(path, &claimed).map_err(|e| {
+        Error::ParamInvalid(format!("presignature {} is not available: {}", path, e))
+    })?;
+    let data = fs::read(&claimed);
+    fs::remove_file(&claimed)
+        .map_err(|e| Error::KeyshareError(format!("cannot delete {}: {}", claimed, e)))?;
+    let invalid = |e: String| Error::ParamInvalid(format!("invalid presignature {}: {}", path, e));
+    let sealed: SealedPresignature =
+        serde_json::from_slice(&data.map_err(|e| invalid(e.to_string()))?)
+            .map_err(|e| invalid(e.to_string()))?;
+    let plaintext = aes_decrypt(&sealing_key(shared_keys, &sealed.id), sealed.aead);
+    if plaintext.is_empty() {
+        return Err(invalid("not sealed under this key share".to_string()));
+    }
+    let presig: Presignature<E> =
+        serde_json::from_slice(&plaintext).map_err(|e| invalid(e.to_string()))?;
+    if (&presig.id, &presig.key_id, &presig.signers)
+        != (&sealed.id, &sealed.key_id, &sealed.signers)
+    {
+        return Err(invalid("tags do not match the content".to_string()));
+    }
+    Ok(presig)
+}
+
+/// Runs rounds 1 to 3 of `sign` for `count` presignatures at once, for the key at
+/// `path`.
+#[allow(clippy::too_many_arguments)]
+pub async fn presign<E: Curve>(
+    addr: &String,
+    party_keys: &Keys<E>,
+    shared_keys: &SharedKeys<E>,
+    party_id: u16,
+    vss_scheme_vec: &[VerifiableSS<E>],
+    paillier_key_vector: &[EncryptionKey],
+    ot_setup: Option<&OtSetup>,
+    root_y_sum: &Point<E>,
+    params: &Params,
+    path: &str,
+    count: usize,
+) -> Result<Vec<Presignature<E>>, Error> {
+    assert!(count > 0, "nothing to presign");
+    // there is no message yet, signers agree on the path and the number of
+    // presignatures
+    let (mut session, agreement) = join_session(
+        addr,
+        party_id,
+        root_y_sum,
+        params,
+        &[],
+        &with_mta(&format!("presign:{}:{}", count, path), ot_setup),
+    )
+    .await;
+    // sigma_i stays at the root key, `sign` adds the tweak of `path`
+    let zero = Scalar::<E>::zero();
+    let tweaks = vec![&zero; count];
+    let shares = match ot_setup {
+        Some(setup) => {
+            presign_batch(
+                &mut session,
+                &agreement,
+                &OtMta { setup },
+                party_keys,
+                shared_keys,
+                vss_scheme_vec,
+                &tweaks,
+            )
+            .await?
+        }
+        None => {
+            let randomness = pool::randomness();
+            let mta = PaillierMta {
+                keys: party_keys,
+                paillier_key_vec: paillier_key_vector,
+                randomness: &randomness,
+            };
+            presign_batch(
+                &mut session,
+                &agreement,
+                &mta,
+                party_keys,
+                shared_keys,
+                vss_scheme_vec,
+                &tweaks,
+            )
+            .await?
+        }
+    };
+    let mut presigs = Vec::new();
+    for (k, (k_i, sigma_i, R)) in shares.into_iter().enumerate() {
+        presigs.push(Presignature {
+            id: format!("{}-{}", session.uuid, k),
+            key_id: hex::encode(&*root_y_sum.to_bytes(true)),
+            signers: session.signers_vec.clone(),
+            party_id,
+            path: path.to_string(),
+            k_i,
+            sigma_i,
+            R,
+            checks: Vec::new(),
+        });
+    }
+    Ok(presigs)
+}
+
+/// Signs `message` at `path` with a presignature made for `path`. All signers must
+/// use their part of the same presignature.
+#[allow(clippy::too_many_arguments)]
+pub async fn sign<E: Curve>(
+    addr: &String,
+    party_id: u16,
+    root_y_sum: &Point<E>,
+    params: &Params,
+    message: &[u8; DIGEST_SIZE],
+    path: &str,
+    tweak_sk: &Scalar<E>,
+    y_sum: &Point<E>,
+    presig: Presignature<E>,
+) -> Result<SignatureRecid<E>, Error> {
+    let client = Client::new();
+    let delay = time::Duration::from_millis(25);
+    let threshold: u16 = params.threshold.parse::<u16>().unwrap();
+    let parties: u16 = params.parties.parse::<u16>().unwrap();
+    let share_count: u16 = params.share_count.parse::<u16>().unwrap();
+    println!(
+        "threshold: {}, parties: {}, share count: {}",
+        threshold, parties, share_count
+    );
+    assert_eq!(
+        presig.party_id, party_id,
+        "presignature {} belongs to key share {}",
+        presig.id, presig.party_id
+    );
+    assert_eq!(
+        presig.key_id,
+        hex::encode(&*root_y_sum.to_bytes(true)),
+        "presignature {} is for another key",
+        presig.id
+    );
+    assert_eq!(
+        usize::from(parties),
+        presig.signers.len(),
+        "presignature {} has {} signers",
+        presig.id,
+        presig.signers.len()
+    );
+    if presig.path != path {
+        return Err(Error::ParamInvalid(format!(
+            "presignature {} is for path {:?}, not {:?}",
+            presig.id, presig.path, path
+        )));
+    }
+
+    let party_signup = signup(addr, &client, "signupsign", params).await;
+    let party_num_int = party_signup.number;
+    let uuid = party_signup.uuid;
+    let debug = json!({"manager_addr": &addr, "party_num": party_num_int, "uuid": uuid});
+    println!("{}", serde_json::to_string_pretty(&debug).unwrap());
+    let session = SignSession {
+        addr,
+        client,
+        delay,
+        party_num_int,
+        parties,
+        uuid,
+        // the signers of the presignature are checked in the round itself
+        signers_vec: Vec::new(),
+        peers: (1..=parties).filter(|i| *i != party_num_int).collect(),
+    };
+
+    let agreement = SignAgreement::new(
+        message,
+        path,
+        threshold,
+        parties,
+        share_count,
+        root_y_sum,
+        party_id,
+    );
+    sign_round(
+        &session, "round1", &agreement, &presig, message, tweak_sk, y_sum,
+    )
+    .await
+}
+
+/// The signing round: agrees on what is signed and combines the shares of all
+/// signers of `presig` into a signature of `message` under `y_sum`. s_i is sent
+/// in this round when the presignature has the checks of every share, and after
+/// GG18 phases 5A to 5D otherwise.
+pub(crate) async fn sign_round<E: Curve>(
+    session: &SignSession<'_>,
+    round: &str,
+    agreement: &SignAgreement,
+    presig: &Presignature<E>,
+    message: &[u8; DIGEST_SIZE],
+    tweak_sk: &Scalar<E>,
+    y_sum: &Point<E>,
+) -> Result<SignatureRecid<E>, Error> {
+    let SignSession {
+        addr,
+        client,
+        delay,
+        party_num_int,
+        parties,
+        uuid,
+        peers,
+        ..
+    } = session;
+    let (delay, party_num_int, parties) = (*delay, *party_num_int, *parties);
+
+    // sigma_i of the key at `path`: k * (x + tweak) = k * x + k * tweak
+    let sigma_i = &*presig.sigma_i + &*presig.k_i * tweak_sk;
+    let message_bn = BigInt::from_bytes(message);
+    let local_sig =
+        LocalSignature::phase5_local_sig(&presig.k_i, &message_bn, &presig.R, &sigma_i, y_sum);
+    let checked = !presig.checks.is_empty();
+    let share = PresignedShare {
+        agreement: agreement.clone(),
+        presig_id: presig.id.clone(),
+        s_i: checked.then(|| local_sig.s_i.clone()),
+    };
+
+    broadcast(addr, client, party_num_int, round, &share, uuid.clone()).await;
+    let round_ans_vec = poll_for_broadcasts(
+        addr,
+        client,
+        party_num_int,
+        parties,
+        delay,
+        round,
+        uuid.clone(),
+    )
+    .await;
+    let mut shares = Vec::new();
+    for (m, i) in zip(&round_ans_vec, peers) {
+        let share_j: PresignedShare<E> = parse_msg(m, &(), *i, round)?;
+        if let Some(mismatch) = agreement.mismatch(&share_j.agreement) {
+            panic!("party {} disagrees on {}", i, mismatch);
+        }
+        if share_j.presig_id != presig.id {
+            panic!(
+                "party {} uses presignature {}, not {}",
+                i, share_j.presig_id, presig.id
+            );
+        }
+        shares.push((*i, share_j));
+    }
+    let mut signer_ids = shares
+        .iter()
+        .map(|(_, share_j)| share_j.agreement.party_id - 1)
+        .collect::<Vec<u16>>();
+    signer_ids.push(agreement.party_id - 1);
+    let mut signers = presig.signers.clone();
+    signers.sort_unstable();
+    signer_ids.sort_unstable();
+    assert_eq!(
+        signer_ids, signers,
+        "signers differ from those of presignature {}",
+        presig.id
+    );
+
+    if !checked {
+        // GG18: s_i is released in phase 5E, once phases 5A to 5D passed
+        let request = SignRequest {
+            message: *message,
+            path: agreement.path.clone(),
+            tweak_sk: tweak_sk.clone(),
+            y_sum: y_sum.clone(),
+        };
+        let presigs = vec![(presig.k_i.clone(), Secret::new(sigma_i), presig.R.clone())];
+        let mut sigs = sign_rounds(session, presigs, &[request]).await?;
+        return Ok(sigs.remove(0));
+    }
+
+    // s_j * R = m * k_j * R + r * (chi_j * R + tweak * k_j * R)
+    let m = Scalar::<E>::from_bigint(&message_bn);
+    let r = Scalar::<E>::from_bigint(&presig.R.x_coord().unwrap());
+    let mut s_vec = Vec::new();
+    for (i, share_j) in shares {
+        let s_j = share_j
+            .s_i
+            .ok_or_else(|| Error::SignError(format!("party {} sent no signature share", i)))?;
+        if let Some(check) = presig
+            .checks
+            .iter()
+            .find(|c| c.party_id == share_j.agreement.party_id)
+        {
+            if &presig.R * &s_j != &check.k_r * &m + (&check.chi_r + &check.k_r * tweak_sk) * &r {
+                panic!(
+                    "party {} (key share {}) sent an invalid signature share",
+                    i, check.party_id
+                );
+            }
+        }
+        s_vec.push(s_j);
+    }
+    Ok(local_sig
+        .output_signature(&s_vec)
+        .expect("verification failed"))
+}
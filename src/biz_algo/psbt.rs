@@ -0,0 +1,257 @@
+// sign the inputs of a BIP-174 PSBT that belong to our key
+use bip32::ChainCode;
+use bitcoin::{
+    bip32::{ChildNumber, Fingerprint},
+    ecdsa,
+    hashes::{hash160, Hash},
+    psbt::Psbt,
+    secp256k1,
+    sighash::{EcdsaSighashType, SighashCache},
+};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+
+use crate::biz_algo::{hd::get_hd_key, sign::SignRequest, Error};
+use crate::mp_ecdsa::party_i::SignatureRecid;
+
+/// An input signed by one of our keys
+#[derive(Clone, Debug)]
+pub struct PsbtInput {
+    pub index: usize,
+    pub pubkey: secp256k1::PublicKey,
+    pub sighash_type: EcdsaSighashType,
+    pub request: SignRequest,
+}
+
+/// PSBT in binary or base64 form
+pub fn read_psbt(data: &[u8]) -> Result<Psbt, Error> {
+    let invalid = |e: String| Error::ParamInvalid(format!("invalid PSBT: {}", e));
+    if data.starts_with(b"psbt\xff") {
+        return Psbt::deserialize(data).map_err(|e| invalid(e.to_string()));
+    }
+    let text = std::str::from_utf8(data).map_err(|e| invalid(e.to_string()))?;
+    text.trim()
+        .parse()
+        .map_err(|e: bitcoin::psbt::PsbtParseError| invalid(e.to_string()))
+}
+
+/// BIP-32 fingerprint of the root key
+pub fn fingerprint(root_y_sum: &Point<Secp256k1>) -> Fingerprint {
+    let hash = hash160::Hash::hash(&root_y_sum.to_bytes(true));
+    Fingerprint::from(<[u8; 4]>::try_from(&hash[..4]).unwrap())
+}
+
+/// Sighash and child key of every input whose `bip32_derivation` starts at our
+/// root. Inputs of other signers are skipped; an input is refused when the key
+/// derived at its path is not the key it names.
+pub fn prepare(
+    psbt: &Psbt,
+    root_y_sum: &Point<Secp256k1>,
+    chain_code: ChainCode,
+) -> Result<Vec<PsbtInput>, Error> {
+    let root_fingerprint = fingerprint(root_y_sum);
+    let mut cache = SighashCache::new(&psbt.unsigned_tx);
+    let mut inputs = Vec::new();
+    for (index, input) in psbt.inputs.iter().enumerate() {
+        let refuse = |e: String| Error::SignError(format!("input {}: {}", index, e));
+        let ours = input
+            .bip32_derivation
+            .iter()
+            .filter(|(_, (fp, _))| *fp == root_fingerprint)
+            .collect::<Vec<_>>();
+        if ours.is_empty() {
+            continue;
+        }
+
+        // the amount is only committed to by segwit sighashes
+        if let Some(prev_tx) = &input.non_witness_utxo {
+            let outpoint = psbt.unsigned_tx.input[index].previous_output;
+            if prev_tx.compute_txid() != outpoint.txid {
+                return Err(refuse(
+                    "non_witness_utxo does not match the outpoint".to_string(),
+                ));
+            }
+        }
+        let (sighash, sighash_type) = psbt
+            .sighash_ecdsa(index, &mut cache)
+            .map_err(|e| refuse(e.to_string()))?;
+
+        for (pubkey, (_, derivation)) in ours {
+            let mut path = String::from("m");
+            for child in derivation {
+                match child {
+                    ChildNumber::Normal { index } => path.push_str(&format!("/{}", index)),
+                    ChildNumber::Hardened { .. } => {
+                        return Err(refuse(format!(
+                            "hardened path m/{} cannot be derived",
+                            derivation
+                        )))
+                    }
+                }
+            }
+            let (path, tweak_sk, y_sum) = match derivation.is_empty() {
+                true => (
+                    String::new(),
+                    Scalar::<Secp256k1>::zero(),
+                    root_y_sum.clone(),
+                ),
+                false => {
+                    let (tweak_sk, y_sum) = get_hd_key(&path, root_y_sum.clone(), chain_code)
+                        .map_err(|e| refuse(e.to_string()))?;
+                    (path, tweak_sk, y_sum)
+                }
+            };
+            if *y_sum.to_bytes(true) != pubkey.serialize()[..] {
+                return Err(refuse(format!(
+                    "key at {} is {}, PSBT names {}",
+                    path,
+                    hex::encode(&*y_sum.to_bytes(true)),
+                    pubkey
+                )));
+            }
+            inputs.push(PsbtInput {
+                index,
+                pubkey: *pubkey,
+                sighash_type,
+                request: SignRequest {
+                    message: *sighash.as_ref(),
+                    path,
+                    tweak_sk,
+                    y_sum,
+                },
+            });
+        }
+    }
+    Ok(inputs)
+}
+
+/// Adds the signatures, in the order of `inputs`, to `partial_sigs`
+pub fn insert_signatures(
+    psbt: &mut Psbt,
+    inputs: &[PsbtInput],
+    sigs: &[SignatureRecid],
+) -> Result<(), Error> {
+    for (input, sig) in inputs.iter().zip(sigs) {
+        let signature = secp256k1::ecdsa::Signature::from_compact(&sig.to_compact())
+            .map_err(|e| Error::SignError(e.to_string()))?;
+        psbt.inputs[input.index].partial_sigs.insert(
+            bitcoin::PublicKey::new(input.pubkey),
+            ecdsa::Signature {
+                signature,
+                sighash_type: input.sighash_type,
+            },
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::{
+        absolute::LockTime, bip32::DerivationPath, transaction::Version, Amount,
+        CompressedPublicKey, OutPoint, ScriptBuf, Transaction, TxIn, TxOut,
+    };
+    use std::str::FromStr;
+
+    const CHAIN_CODE: ChainCode = [7u8; 32];
+
+    fn root() -> (Scalar<Secp256k1>, Point<Secp256k1>) {
+        let x = Scalar::<Secp256k1>::from(0x1234_5678u64);
+        let y = Point::generator() * &x;
+        (x, y)
+    }
+
+    // one P2WPKH input paying to `pubkey`, named at `path` under `fp`
+    fn psbt_for(pubkey: secp256k1::PublicKey, fp: Fingerprint, path: &str) -> Psbt {
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                ..Default::default()
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(90_000),
+                script_pubkey: ScriptBuf::new(),
+            }],
+        };
+        let mut psbt = Psbt::from_unsigned_tx(tx).unwrap();
+        psbt.inputs[0].witness_utxo = Some(TxOut {
+            value: Amount::from_sat(100_000),
+            script_pubkey: ScriptBuf::new_p2wpkh(&CompressedPublicKey(pubkey).wpubkey_hash()),
+        });
+        psbt.inputs[0]
+            .bip32_derivation
+            .insert(pubkey, (fp, DerivationPath::from_str(path).unwrap()));
+        psbt
+    }
+
+    fn child_pubkey(path: &str) -> secp256k1::PublicKey {
+        let (_, y) = root();
+        let (_, child) = get_hd_key(path, y, CHAIN_CODE).unwrap();
+        secp256k1::PublicKey::from_slice(&child.to_bytes(true)).unwrap()
+    }
+
+    #[test]
+    fn signs_our_input() {
+        let (x, y) = root();
+        let mut psbt = psbt_for(child_pubkey("m/0/1"), fingerprint(&y), "m/0/1");
+        let inputs = prepare(&psbt, &y, CHAIN_CODE).unwrap();
+        assert_eq!(inputs.len(), 1);
+        let request = &inputs[0].request;
+        assert_eq!(request.path, "m/0/1");
+        assert_eq!(Point::generator() * (&x + &request.tweak_sk), request.y_sum);
+
+        let sighash = psbt
+            .sighash_ecdsa(0, &mut SighashCache::new(&psbt.unsigned_tx))
+            .unwrap()
+            .0;
+        assert_eq!(request.message, *sighash.as_ref());
+
+        let secp = secp256k1::Secp256k1::new();
+        let sk = secp256k1::SecretKey::from_slice(&(&x + &request.tweak_sk).to_bytes()).unwrap();
+        let msg = secp256k1::Message::from_digest(request.message);
+        let sig = secp.sign_ecdsa(&msg, &sk);
+        let sig = SignatureRecid::from_compact(&sig.serialize_compact()).unwrap();
+        insert_signatures(&mut psbt, &inputs, &[sig]).unwrap();
+
+        let partial = &psbt.inputs[0].partial_sigs[&bitcoin::PublicKey::new(inputs[0].pubkey)];
+        assert_eq!(partial.sighash_type, EcdsaSighashType::All);
+        secp.verify_ecdsa(&msg, &partial.signature, &inputs[0].pubkey)
+            .unwrap();
+    }
+
+    #[test]
+    fn skips_other_signers() {
+        let (_, y) = root();
+        let psbt = psbt_for(
+            child_pubkey("m/0/1"),
+            Fingerprint::from([1, 2, 3, 4]),
+            "m/0/1",
+        );
+        assert!(prepare(&psbt, &y, CHAIN_CODE).unwrap().is_empty());
+    }
+
+    #[test]
+    fn refuses_wrong_key() {
+        let (_, y) = root();
+        let psbt = psbt_for(child_pubkey("m/0/2"), fingerprint(&y), "m/0/1");
+        assert!(prepare(&psbt, &y, CHAIN_CODE).is_err());
+    }
+
+    #[test]
+    fn refuses_hardened_path() {
+        let (_, y) = root();
+        let psbt = psbt_for(child_pubkey("m/0/1"), fingerprint(&y), "m/0'/1");
+        assert!(prepare(&psbt, &y, CHAIN_CODE).is_err());
+    }
+
+    #[test]
+    fn reads_binary_and_base64() {
+        let (_, y) = root();
+        let psbt = psbt_for(child_pubkey("m/0/1"), fingerprint(&y), "m/0/1");
+        assert_eq!(read_psbt(&psbt.serialize()).unwrap(), psbt);
+        assert_eq!(read_psbt(format!("{}\n", psbt).as_bytes()).unwrap(), psbt);
+        assert!(read_psbt(b"not a psbt").is_err());
+    }
+}
@@ -3,11 +3,12 @@
 // t' (t' > t) parties give x_i
 #![allow(unused_variables, unused_assignments, dead_code)]
 use std::iter::zip;
-use std::{fs, ops::Deref, time};
+use std::{fs, time};
 
 use crate::mp_ecdsa::{
     feldman_vss::{ShamirSecretSharing, VerifiableSS},
     party_i::*,
+    secret::Secret,
 };
 use bip32::ChainCode;
 use bip39::{Language, Mnemonic};
@@ -23,13 +24,15 @@ use curv::{
 use paillier::*;
 use reqwest::Client;
 use serde_json::json;
-use sha2::{Digest, Sha256, Sha512};
+use sha2::Sha256;
 use std::convert::TryInto;
 
 use crate::biz_algo::{
-    aes_decrypt, aes_encrypt, broadcast, poll_all_for_p2p, poll_for_broadcasts, scalar_split,
-    sendp2p, Params, PartySignup, AEAD,
+    aes_decrypt, aes_encrypt, broadcast, cggmp::split_keys_file, emit_mnemonic, parse_msg,
+    poll_all_for_p2p, poll_for_broadcasts, pool, scalar_split, sendp2p, wire, worker,
+    write_secret_file, Error, MnemonicOutput, Params, PartySignup, AEAD,
 };
+use crate::util::{MEMBER_ID, SESSION_ID};
 
 pub async fn reshare_all_xi(
     addr: String,
@@ -38,7 +41,8 @@ pub async fn reshare_all_xi(
     if_give: bool,    // if give x_i
     if_hold: bool,    // if hold old shares
     if_receive: bool, // if receive new shares
-) {
+    mnemonic_out: &MnemonicOutput,
+) -> Result<(), Error> {
     let client = Client::new();
     let delay = time::Duration::from_millis(25);
     let threshold: u16 = params.threshold.parse::<u16>().unwrap();
@@ -63,20 +67,21 @@ pub async fn reshare_all_xi(
         PartySignup { number, uuid } => (number, uuid),
     };
 
-    unsafe {
-        use crate::util::{MEMBER_ID, SESSION_ID};
-        SESSION_ID.set(uuid.clone()).unwrap();
-        MEMBER_ID.set(party_num_int).unwrap();
-    }
+    SESSION_ID.set(uuid.clone()).unwrap();
+    MEMBER_ID.set(party_num_int).unwrap();
 
     let debug = json!({"manager_addr": &addr, "party_num": party_num_int, "uuid": uuid});
     println!("{}", serde_json::to_string_pretty(&debug).unwrap());
 
     // initialization
-    let mut party_keys = Keys::create(party_num_int);
+    let mut party_keys = Keys::<Secp256k1>::create_with_keypair(
+        Scalar::random(),
+        party_num_int,
+        pool::keypair(false),
+    );
     let mut shared_keys = SharedKeys {
         y: Point::<Secp256k1>::zero(),
-        x_i: Scalar::<Secp256k1>::random(),
+        x_i: Secret::new(Scalar::<Secp256k1>::random()),
     };
     let mut party_id = parties + 1;
     let vss_scheme_zero = VerifiableSS::<Secp256k1> {
@@ -89,11 +94,8 @@ pub async fn reshare_all_xi(
     let mut vss_scheme_vec: Vec<VerifiableSS<Secp256k1>> = Vec::with_capacity(share_count as usize);
     let mut paillier_key_vec: Vec<EncryptionKey> = Vec::with_capacity(share_count as usize);
     let mut expected_y_sum = Point::<Secp256k1>::generator().to_point();
-    // let mut chain_code =
-    //     ChainCode::from(&Sha512::digest(expected_y_sum.to_bytes(false).deref())[..32]);
-    let mut chain_code: ChainCode = Sha512::digest(expected_y_sum.to_bytes(false).deref())[..32]
-        .try_into()
-        .unwrap();
+    // givers load the jointly generated chain code from the keys file
+    let mut chain_code: ChainCode = [0u8; 32];
 
     // read data from keys file
     if if_give {
@@ -107,7 +109,7 @@ pub async fn reshare_all_xi(
             paillier_key_vec,
             expected_y_sum,
             chain_code,
-        ) = serde_json::from_str(&data).unwrap();
+        ) = serde_json::from_value(split_keys_file(&data).0).unwrap();
     }
 
     // round 0: collect party info
@@ -116,7 +118,7 @@ pub async fn reshare_all_xi(
         &client,
         party_num_int,
         "round0",
-        serde_json::to_string(&(party_num_int, party_id, if_give, if_hold, if_receive)).unwrap(),
+        &(party_num_int, party_id, if_give, if_hold, if_receive),
         uuid.clone(),
     )
     .await;
@@ -124,8 +126,9 @@ pub async fn reshare_all_xi(
         poll_for_broadcasts(&addr, &client, 0u16, parties, delay, "round0", uuid.clone()).await;
     let reshare_info_vec = round0_ans_vec
         .iter()
-        .map(|m| serde_json::from_str::<(u16, u16, bool, bool, bool)>(m).unwrap())
-        .collect::<Vec<_>>();
+        .map(|m| wire::decode::<(u16, u16, bool, bool, bool)>(m))
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(|e| Error::InvalidMessage(format!("in round0: {}", e)))?;
     assert_eq!(reshare_info_vec.len(), parties as usize);
 
     let (givers_vec, givers_id_vec): (Vec<_>, Vec<_>) = reshare_info_vec
@@ -172,12 +175,14 @@ pub async fn reshare_all_xi(
             party_id - 1,
             &givers_id_vec,
         );
-        w_i = lambda * &shared_keys.x_i;
+        w_i = lambda * &*shared_keys.x_i;
     }
     let w_i_partition = scalar_split(&w_i, &share_count);
 
-    let mut party_keys_wi = Keys::create_from(w_i.clone(), party_num_int);
-    let (com_i, decom_i) = party_keys_wi.phase1_broadcast_phase3_proof_of_correct_key();
+    let mut party_keys_wi =
+        Keys::create_with_keypair(w_i.clone(), party_num_int, pool::keypair(false));
+    let round1_ctx = SessionContext::new(&uuid, "reshare-round1");
+    let (com_i, decom_i) = party_keys_wi.phase1_broadcast_phase3_proof_of_correct_key(&round1_ctx);
 
     // round 1: send commitment to g_w_i
     broadcast(
@@ -185,7 +190,7 @@ pub async fn reshare_all_xi(
         &client,
         party_num_int,
         "round1",
-        serde_json::to_string(&com_i).unwrap(),
+        &com_i,
         uuid.clone(),
     )
     .await;
@@ -199,10 +204,12 @@ pub async fn reshare_all_xi(
         uuid.clone(),
     )
     .await;
-    let mut com_vec = round1_ans_vec
-        .iter()
-        .map(|m| serde_json::from_str::<KeyGenBroadcastMessage1>(m).unwrap())
-        .collect::<Vec<_>>();
+    let peers = (1..=parties)
+        .filter(|i| *i != party_num_int)
+        .collect::<Vec<u16>>();
+    let mut com_vec = zip(&round1_ans_vec, &peers)
+        .map(|(m, j)| parse_msg::<KeyGenBroadcastMessage1>(m, &(), *j, "round1"))
+        .collect::<Result<Vec<_>, Error>>()?;
     com_vec.insert(party_num_int as usize - 1, com_i);
 
     // round 2: send decommitment to g_w_i
@@ -211,7 +218,7 @@ pub async fn reshare_all_xi(
         &client,
         party_num_int,
         "round2",
-        serde_json::to_string(&decom_i).unwrap(),
+        &decom_i,
         uuid.clone(),
     )
     .await;
@@ -222,12 +229,12 @@ pub async fn reshare_all_xi(
     let mut point_vec: Vec<Point<Secp256k1>> = Vec::new();
     let mut decom_vec: Vec<KeyGenDecommitMessage1> = Vec::new();
     let mut enc_keys: Vec<BigInt> = Vec::new();
-    for x in round2_ans_vec.iter() {
-        let decom_j: KeyGenDecommitMessage1 = serde_json::from_str(&x).unwrap();
+    for (j, x) in round2_ans_vec.iter().enumerate() {
+        let decom_j: KeyGenDecommitMessage1 = parse_msg(x, &(), j as u16 + 1, "round2")?;
         point_vec.push(decom_j.y_i.clone());
         decom_vec.push(decom_j.clone());
         enc_keys.push(
-            (decom_j.y_i.clone() * party_keys_wi.u_i.clone())
+            (decom_j.y_i.clone() * &*party_keys_wi.u_i)
                 .x_coord()
                 .unwrap(),
         );
@@ -244,7 +251,7 @@ pub async fn reshare_all_xi(
         ) == com_vec[i].com
             && com_vec[i]
                 .correct_key_proof
-                .verify(&com_vec[i].e, zk_paillier::zkproofs::SALT_STRING)
+                .verify(&com_vec[i].e, &round1_ctx.tag(i as u16 + 1))
                 .is_ok()
     });
     if !correct_key_correct_decom_all {
@@ -270,17 +277,20 @@ pub async fn reshare_all_xi(
             &client,
             &pos_give + 1,
             "round3",
-            serde_json::to_string(&vss_scheme).unwrap(),
+            &vss_scheme,
             uuid.clone(),
         )
         .await;
     }
     let round3_ans_vec =
         poll_for_broadcasts(&addr, &client, 0u16, givers, delay, "round3", uuid.clone()).await;
-    vss_scheme_vec = round3_ans_vec
-        .iter()
-        .map(|m| serde_json::from_str::<VerifiableSS<Secp256k1>>(m).unwrap())
-        .collect::<Vec<_>>();
+    let vss_params = ShamirSecretSharing {
+        threshold,
+        share_count,
+    };
+    vss_scheme_vec = zip(&round3_ans_vec, &givers_vec)
+        .map(|(m, j)| parse_msg::<VerifiableSS<Secp256k1>>(m, &vss_params, *j, "round3"))
+        .collect::<Result<Vec<_>, Error>>()?;
     assert_eq!(vss_scheme_vec.len(), usize::from(givers));
     if givers < share_count {
         vss_scheme_vec.resize(share_count.into(), vss_scheme_zero);
@@ -305,7 +315,7 @@ pub async fn reshare_all_xi(
                 &pos_give + 1,
                 *receiver_index,
                 "round4",
-                serde_json::to_string(&(aead_pack_i1, aead_pack_i2, aead_pack_i3)).unwrap(),
+                &(aead_pack_i1, aead_pack_i2, aead_pack_i3),
                 uuid.clone()
             )
             .await
@@ -331,7 +341,7 @@ pub async fn reshare_all_xi(
         let mut chain_code_vec: Vec<ChainCode> = Vec::new();
         for (round4_ans, giver_index) in zip(&round4_ans_vec, &givers_vec) {
             let (aead_pack_i1, aead_pack_i2, aead_pack_i3) =
-                serde_json::from_str::<(AEAD, AEAD, AEAD)>(round4_ans).unwrap();
+                parse_msg::<(AEAD, AEAD, AEAD)>(round4_ans, &((), (), ()), *giver_index, "round4")?;
             let key_i = BigInt::to_bytes(&enc_keys[*giver_index as usize - 1]);
             let mut out = aes_decrypt(&key_i, aead_pack_i1);
             let mut out_bn = BigInt::from_bytes(&out[..]);
@@ -368,19 +378,24 @@ pub async fn reshare_all_xi(
             panic!("invalid vss")
         }
 
-        shared_keys.x_i = party_shares.iter().sum();
+        shared_keys.x_i = Secret::new(party_shares.iter().sum());
         shared_keys.y = y_sum.clone();
-        println!("NEW x_{:?}: {:#?}", &pos_rec + 1, &shared_keys.x_i);
+        println!("NEW x_{:?} received", &pos_rec + 1);
 
         // proof of x_i by Schnorr identification protocol
-        let dlog_proof: DLogProof<Secp256k1, Sha256> = DLogProof::prove(&shared_keys.x_i);
+        let round5_ctx = SessionContext::new(
+            &uuid,
+            &format!("reshare-round5-{}", hex::encode(chain_code_vec[0])),
+        );
+        let dlog_proof: DLogProof<Secp256k1, Sha256> =
+            DLogProof::prove_with_ctx(&shared_keys.x_i, &round5_ctx.tag(&pos_rec + 1));
         // round 5: send dlog proof
         broadcast(
             &addr,
             &client,
             &pos_rec + 1,
             "round5",
-            serde_json::to_string(&dlog_proof).unwrap(),
+            &dlog_proof,
             uuid.clone(),
         )
         .await;
@@ -394,22 +409,24 @@ pub async fn reshare_all_xi(
             uuid.clone(),
         )
         .await;
-        let dlog_proof_vec = round5_ans_vec
-            .iter()
-            .map(|m| serde_json::from_str::<DLogProof<Secp256k1, Sha256>>(m).unwrap())
-            .collect::<Vec<_>>();
+        let dlog_proof_vec = zip(&round5_ans_vec, &receivers_vec)
+            .map(|(m, j)| parse_msg::<DLogProof<Secp256k1, Sha256>>(m, &(), *j, "round5"))
+            .collect::<Result<Vec<_>, Error>>()?;
         let point_vec_new: Vec<Point<Secp256k1>> = receivers_vec
             .iter()
             .map(|x| point_vec[*x as usize - 1].clone())
             .collect::<Vec<_>>();
-        Keys::verify_dlog_proofs(
-            &Parameters {
-                threshold,
-                share_count,
-            },
-            &dlog_proof_vec,
-            &point_vec_new,
-        )
+        worker::compute(|| {
+            Keys::verify_dlog_proofs(
+                &Parameters {
+                    threshold,
+                    share_count,
+                },
+                &dlog_proof_vec,
+                &point_vec_new,
+                &round5_ctx,
+            )
+        })
         .expect("bad dlog proof");
 
         if parties == (holders_vec.len() as u16) {
@@ -424,7 +441,7 @@ pub async fn reshare_all_xi(
                 Vec<EncryptionKey>,
                 Point<Secp256k1>,
                 ChainCode,
-            ) = serde_json::from_str(&data).unwrap();
+            ) = serde_json::from_value(split_keys_file(&data).0).unwrap();
             assert!(chain_code == chain_code_vec[0], "chain code not match!");
             party_keys.party_index = &pos_rec + 1;
             paillier_key_vec = receivers_vec
@@ -434,8 +451,7 @@ pub async fn reshare_all_xi(
             // save key to file:
             let mnemonic =
                 Mnemonic::from_entropy(&party_keys.u_i.to_bytes(), Language::English).unwrap();
-            let share_phrase: &str = mnemonic.phrase(); // 24-word
-            let keygen_json = serde_json::to_string(&(
+            let keygen_json: Secret<String> = serde_json::to_string(&(
                 party_keys,
                 shared_keys,
                 &pos_rec + 1,
@@ -444,13 +460,14 @@ pub async fn reshare_all_xi(
                 y_sum,
                 chain_code,
             ))
-            .unwrap();
+            .unwrap()
+            .into();
+            write_secret_file(keysfile_path, keygen_json.as_bytes()).expect("Unable to save !");
             println!("Keys data written to file: {:?}", keysfile_path);
-            println!("Phrase of u_i: {}", share_phrase);
-            fs::write(&keysfile_path, keygen_json).expect("Unable to save !");
+            emit_mnemonic(mnemonic.phrase(), mnemonic_out).expect("Unable to emit mnemonic !");
         } else {
-            party_keys_wi.u_i = w_ji_vec.iter().sum();
-            party_keys_wi.y_i = &party_keys_wi.u_i * Point::<Secp256k1>::generator();
+            party_keys_wi.u_i = Secret::new(w_ji_vec.iter().sum());
+            party_keys_wi.y_i = &*party_keys_wi.u_i * Point::<Secp256k1>::generator();
             party_keys_wi.party_index = &pos_rec + 1;
             paillier_key_vec = receivers_vec
                 .iter()
@@ -459,8 +476,7 @@ pub async fn reshare_all_xi(
             //save key to file:
             let mnemonic =
                 Mnemonic::from_entropy(&party_keys_wi.u_i.to_bytes(), Language::English).unwrap();
-            let share_phrase: &str = mnemonic.phrase(); // 24-word
-            let keygen_json = serde_json::to_string(&(
+            let keygen_json: Secret<String> = serde_json::to_string(&(
                 party_keys_wi,
                 shared_keys,
                 &pos_rec + 1,
@@ -469,13 +485,15 @@ pub async fn reshare_all_xi(
                 y_sum,
                 chain_code_vec[0],
             ))
-            .unwrap();
+            .unwrap()
+            .into();
+            write_secret_file(keysfile_path, keygen_json.as_bytes()).expect("Unable to save !");
             println!("Keys data written to file: {:?}", keysfile_path);
-            println!("Phrase of u_i: {}", share_phrase);
-            fs::write(&keysfile_path, keygen_json).expect("Unable to save !");
+            emit_mnemonic(mnemonic.phrase(), mnemonic_out).expect("Unable to emit mnemonic !");
         }
     }
     println!("THE END!");
+    Ok(())
 }
 
 pub async fn signup(addr: &String, client: &Client, params: &Params) -> Result<PartySignup, ()> {
@@ -483,6 +501,10 @@ pub async fn signup(addr: &String, client: &Client, params: &Params) -> Result<P
         .await
         .unwrap();
     let answer: Result<PartySignup, ()> = serde_json::from_str(&res_body).unwrap();
+    if let Ok(party_signup) = &answer {
+        let parties = params.parties.parse::<u16>().unwrap();
+        wire::announce(addr, client, party_signup, parties).await;
+    }
     return answer;
 }
 
@@ -0,0 +1,335 @@
+// FROST-style threshold Schnorr signatures (BIP-340) with the GG18 key shares
+//
+// Round 0 is the agreement of `sign`. In round 1 every signer commits to two
+// nonces (D_j, E_j); in round 2 it sends
+//   z_j = k_j + c * lambda_j * x_j,  k_j = d_j + rho_j * e_j
+// where rho_j binds the nonces to the message and the whole commitment list,
+// R = sum(D_j + rho_j * E_j) and c is the BIP-340 challenge. BIP-340 keys and
+// nonces have an even y: the shares are never changed, signers negate their
+// part of the equation instead.
+use std::time;
+
+use crate::mp_ecdsa::{
+    feldman_vss::VerifiableSS,
+    party_i::*,
+    validate::{check_point, check_scalar, Validate, ValidationError},
+};
+use bitcoin::secp256k1;
+use curv::{
+    arithmetic::Converter,
+    elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar},
+    BigInt,
+};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::biz_algo::{
+    broadcast,
+    btc::tagged_hash,
+    message::DIGEST_SIZE,
+    parse_msg, poll_for_broadcasts,
+    sign::{agree, SignAgreement},
+    signup, Error, Params,
+};
+
+/// Signing key as BIP-340 sees it: `sign * x + offset` for the root key `x`,
+/// with an even-y public key
+#[derive(Clone, Debug)]
+pub struct SchnorrKey {
+    pub pubkey: Point<Secp256k1>,
+    sign: Scalar<Secp256k1>,
+    offset: Scalar<Secp256k1>,
+}
+
+impl SchnorrKey {
+    /// Key `tweak_sk` above the root, whose public key is `y_sum`. With `taproot`
+    /// it is the BIP-341 output key of a key-path-only output.
+    pub fn new(y_sum: &Point<Secp256k1>, tweak_sk: &Scalar<Secp256k1>, taproot: bool) -> Self {
+        let mut key = Self {
+            pubkey: y_sum.clone(),
+            sign: Scalar::from(1u16),
+            offset: tweak_sk.clone(),
+        }
+        .even_y();
+        if taproot {
+            let tweak = taproot_tweak(&key.pubkey);
+            key.pubkey = key.pubkey + Point::generator() * &tweak;
+            key.offset = key.offset + tweak;
+            key = key.even_y();
+        }
+        key
+    }
+
+    fn even_y(self) -> Self {
+        match has_even_y(&self.pubkey) {
+            true => self,
+            false => Self {
+                pubkey: -self.pubkey,
+                sign: -self.sign,
+                offset: -self.offset,
+            },
+        }
+    }
+
+    pub fn x_only(&self) -> [u8; 32] {
+        x_only(&self.pubkey)
+    }
+}
+
+/// `t = H_TapTweak(P)` of an output without script tree
+pub fn taproot_tweak(internal_key: &Point<Secp256k1>) -> Scalar<Secp256k1> {
+    hash_to_scalar(tagged_hash(b"TapTweak", &x_only(internal_key)))
+}
+
+fn has_even_y(p: &Point<Secp256k1>) -> bool {
+    p.to_bytes(true)[0] == 2
+}
+
+fn x_only(p: &Point<Secp256k1>) -> [u8; 32] {
+    p.to_bytes(true)[1..].try_into().unwrap()
+}
+
+fn hash_to_scalar(hash: [u8; DIGEST_SIZE]) -> Scalar<Secp256k1> {
+    Scalar::from_bigint(&BigInt::from_bytes(&hash))
+}
+
+/// BIP-340 signature: x(R) || s
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchnorrSignature {
+    pub r: [u8; 32],
+    pub s: Scalar<Secp256k1>,
+}
+
+impl SchnorrSignature {
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.r);
+        bytes[32..].copy_from_slice(&self.s.to_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let invalid = |e: &str| Error::ParamInvalid(format!("invalid schnorr signature: {}", e));
+        if bytes.len() != 64 {
+            return Err(invalid("expected 64 bytes"));
+        }
+        Ok(Self {
+            r: bytes[..32].try_into().unwrap(),
+            s: Scalar::from_bytes(&bytes[32..]).map_err(|_| invalid("s is not below n"))?,
+        })
+    }
+
+    /// BIP-340 verification against an x-only public key
+    pub fn verify(&self, message: &[u8; DIGEST_SIZE], pubkey: &[u8; 32]) -> Result<(), Error> {
+        let invalid = |e: secp256k1::Error| Error::SignError(e.to_string());
+        let sig = secp256k1::schnorr::Signature::from_slice(&self.to_bytes()).map_err(invalid)?;
+        let pubkey = secp256k1::XOnlyPublicKey::from_slice(pubkey).map_err(invalid)?;
+        secp256k1::Secp256k1::verification_only()
+            .verify_schnorr(&sig, &secp256k1::Message::from_digest(*message), &pubkey)
+            .map_err(invalid)
+    }
+}
+
+/// Round 1 message: commitments to the two nonces of a signer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NonceCommitment {
+    pub d_j: Point<Secp256k1>,
+    pub e_j: Point<Secp256k1>,
+}
+
+impl Validate for NonceCommitment {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_point(&self.d_j, "nonce_commitment.d_j")?;
+        check_point(&self.e_j, "nonce_commitment.e_j")
+    }
+}
+
+/// Round 2 message
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignatureShare {
+    pub z_j: Scalar<Secp256k1>,
+}
+
+impl Validate for SignatureShare {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_scalar(&self.z_j, "signature_share.z_j")
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn sign(
+    addr: &String,
+    shared_keys: &SharedKeys,
+    party_id: u16,
+    vss_scheme_vec: &[VerifiableSS<Secp256k1>],
+    root_y_sum: &Point<Secp256k1>,
+    params: &Params,
+    message: &[u8; DIGEST_SIZE],
+    path: &str,
+    key: &SchnorrKey,
+) -> Result<SchnorrSignature, Error> {
+    let client = Client::new();
+    let delay = time::Duration::from_millis(25);
+    let threshold: u16 = params.threshold.parse::<u16>().unwrap();
+    let parties: u16 = params.parties.parse::<u16>().unwrap();
+    let share_count: u16 = params.share_count.parse::<u16>().unwrap();
+    println!(
+        "threshold: {}, parties: {}, share count: {}",
+        threshold, parties, share_count
+    );
+    assert!(parties > threshold, "PARTIES smaller than THRESHOLD + 1");
+    assert!(parties < share_count + 1, "PARTIES bigger than SHARE_COUNT");
+
+    let party_signup = signup(addr, &client, "signupsign", params).await;
+    let party_num_int = party_signup.number;
+    let uuid = party_signup.uuid;
+    let debug = json!({"manager_addr": &addr, "party_num": party_num_int, "uuid": uuid});
+    println!("{}", serde_json::to_string_pretty(&debug).unwrap());
+
+    // round 0: the output key stands in for the path, it also covers the taproot tweak
+    let agreement = SignAgreement::new(
+        message,
+        &format!("{}:{}", path, hex::encode(key.x_only())),
+        threshold,
+        parties,
+        share_count,
+        root_y_sum,
+        party_id,
+    );
+    let signers_vec = agree(
+        addr,
+        &client,
+        delay,
+        party_num_int,
+        parties,
+        &uuid,
+        &agreement,
+    )
+    .await?;
+    let xi_com_vec = Keys::get_commitments_to_xi(vss_scheme_vec);
+
+    // round 1: nonce commitments
+    let (d_i, e_i) = (Scalar::<Secp256k1>::random(), Scalar::<Secp256k1>::random());
+    let commitment = NonceCommitment {
+        d_j: Point::generator() * &d_i,
+        e_j: Point::generator() * &e_i,
+    };
+    broadcast(
+        addr,
+        &client,
+        party_num_int,
+        "round1",
+        &commitment,
+        uuid.clone(),
+    )
+    .await;
+    let round1_ans_vec = poll_for_broadcasts(
+        addr,
+        &client,
+        party_num_int,
+        parties,
+        delay,
+        "round1",
+        uuid.clone(),
+    )
+    .await;
+    let mut commitments = Vec::new();
+    let mut ans = round1_ans_vec.iter();
+    for i in 1..=parties {
+        match i == party_num_int {
+            true => commitments.push(commitment.clone()),
+            false => commitments.push(parse_msg(ans.next().unwrap(), &(), i, "round1")?),
+        }
+    }
+
+    // binding factors over the signer identifiers and the whole commitment list
+    let mut encoded = Vec::new();
+    for (signer_id, c) in signers_vec.iter().zip(&commitments) {
+        encoded.extend((signer_id + 1).to_be_bytes());
+        encoded.extend(&*c.d_j.to_bytes(true));
+        encoded.extend(&*c.e_j.to_bytes(true));
+    }
+    let rho_vec = signers_vec
+        .iter()
+        .map(|signer_id| {
+            let data = [
+                &(signer_id + 1).to_be_bytes()[..],
+                message,
+                &key.x_only(),
+                &encoded,
+            ]
+            .concat();
+            hash_to_scalar(tagged_hash(b"FROST/rho", &data))
+        })
+        .collect::<Vec<_>>();
+    let nonce_vec = commitments
+        .iter()
+        .zip(&rho_vec)
+        .map(|(c, rho)| &c.d_j + &c.e_j * rho)
+        .collect::<Vec<_>>();
+    let r_point = nonce_vec.iter().sum::<Point<Secp256k1>>();
+    // negate all nonces when R has an odd y
+    let nonce_sign = match has_even_y(&r_point) {
+        true => Scalar::<Secp256k1>::from(1u16),
+        false => -Scalar::<Secp256k1>::from(1u16),
+    };
+    let r = x_only(&r_point);
+    let challenge = hash_to_scalar(tagged_hash(
+        b"BIP0340/challenge",
+        &[&r[..], &key.x_only(), message].concat(),
+    ));
+    let lambda = |signer_id: u16| {
+        VerifiableSS::<Secp256k1>::map_share_to_new_params(
+            &vss_scheme_vec[0].parameters,
+            signer_id,
+            &signers_vec,
+        )
+    };
+
+    // round 2: signature shares
+    let k = usize::from(party_num_int - 1);
+    let share = SignatureShare {
+        z_j: &nonce_sign * (d_i + e_i * &rho_vec[k])
+            + &challenge * lambda(signers_vec[k]) * &key.sign * &*shared_keys.x_i,
+    };
+    broadcast(addr, &client, party_num_int, "round2", &share, uuid.clone()).await;
+    let round2_ans_vec = poll_for_broadcasts(
+        addr,
+        &client,
+        party_num_int,
+        parties,
+        delay,
+        "round2",
+        uuid.clone(),
+    )
+    .await;
+    let mut s = &challenge * &key.offset;
+    let mut ans = round2_ans_vec.iter();
+    for i in 1..=parties {
+        let j = usize::from(i - 1);
+        let share_j = match i == party_num_int {
+            true => share.clone(),
+            false => parse_msg::<SignatureShare>(ans.next().unwrap(), &(), i, "round2")?,
+        };
+        // z_j * G = k_j * G + c * lambda_j * X_j
+        let expected = &nonce_sign * &nonce_vec[j]
+            + &xi_com_vec[usize::from(signers_vec[j])]
+                * (&challenge * lambda(signers_vec[j]) * &key.sign);
+        if Point::generator() * &share_j.z_j != expected {
+            return Err(Error::SignError(format!(
+                "party {} in round2: invalid signature share",
+                i
+            )));
+        }
+        s = s + share_j.z_j;
+    }
+
+    let sig = SchnorrSignature { r, s };
+    sig.verify(message, &key.x_only())?;
+    Ok(sig)
+}
@@ -1,35 +1,282 @@
-use std::time;
+// GG18 signing (https://eprint.iacr.org/2019/114.pdf section 4.3)
+//
+// Rounds hold everything that does not depend on other messages of the same round:
+//
+//   round1       agreement, commitment to g_gamma_i (broadcast); MtA (a) if it
+//                only needs k_i, i.e. Paillier (p2p)
+//   round1-mta   OT MtA (a), which needs the key share and w_i of the peer (p2p)
+//   round2       MtA (b) to (d) (p2p)
+//   round3       delta_i and the decommitment to g_gamma_i (broadcast)
+//   round4..8    phases 5A to 5E (broadcast)
+//
+// k_i and gamma_i are sampled before the signers are known, w_i once round1 is
+// in. Opening g_gamma_i with delta_i is safe as it was committed in round1. The
+// phase 5 rounds do not merge: each commitment must be fixed before what it hides
+// is opened, and s_i only goes out after the 5D check. Every message of a batch
+// runs each round at once, so a batch takes as many rounds as one message.
+use std::{iter::zip, time};
 
-use crate::mp_ecdsa::{feldman_vss::*, mta::*, party_i::*};
+use crate::mp_ecdsa::{
+    feldman_vss::*,
+    mta::{
+        ot::{self, OtSetup},
+        *,
+    },
+    party_i::*,
+    secret::Secret,
+    validate::{Validate, ValidationError},
+};
 use curv::{
-    arithmetic::{BasicOps, Converter, Modulo},
+    arithmetic::Converter,
     cryptographic_primitives::proofs::{
         sigma_correct_homomorphic_elgamal_enc::HomoELGamalProof, sigma_dlog::DLogProof,
     },
-    elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar},
+    elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar},
     BigInt,
 };
 use paillier::*;
 use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::json;
-use sha2::Sha256;
+use sha2::{Digest, Sha256};
 
 use crate::biz_algo::{
-    broadcast, check_sig, poll_for_broadcasts, poll_for_p2p, sendp2p, signup, Params,
+    broadcast, check_sig, message::DIGEST_SIZE, parse_msg, poll_for_broadcasts, poll_for_p2p, pool,
+    sendp2p, signup, worker, Error, Params,
 };
+use crate::util::{MEMBER_ID, SESSION_ID};
+
+/// What signers must agree on, everything but `party_id`, before the expensive
+/// rounds start: sent in round 0 of `open_session`, or with round 1 of GG18
+/// signing. The message itself is only revealed by its digest.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct SignAgreement {
+    pub message_digest: String,
+    pub path: String,
+    pub threshold: u16,
+    pub parties: u16,
+    pub share_count: u16,
+    pub key_id: String,
+    pub party_id: u16,
+}
+
+impl SignAgreement {
+    pub fn new<E: Curve>(
+        message: &[u8],
+        path: &str,
+        threshold: u16,
+        parties: u16,
+        share_count: u16,
+        root_y_sum: &Point<E>,
+        party_id: u16,
+    ) -> Self {
+        Self {
+            message_digest: hex::encode(Sha256::digest(message)),
+            path: path.to_string(),
+            threshold,
+            parties,
+            share_count,
+            key_id: hex::encode(&*root_y_sum.to_bytes(true)),
+            party_id,
+        }
+    }
+
+    // first field in which `other` disagrees with us
+    pub(crate) fn mismatch(&self, other: &Self) -> Option<String> {
+        if self.message_digest != other.message_digest {
+            Some(format!(
+                "message digest {} != {}",
+                other.message_digest, self.message_digest
+            ))
+        } else if self.path != other.path {
+            Some(format!("path {:?} != {:?}", other.path, self.path))
+        } else if (self.threshold, self.parties, self.share_count)
+            != (other.threshold, other.parties, other.share_count)
+        {
+            Some(format!(
+                "params {}/{}/{} != {}/{}/{}",
+                other.threshold,
+                other.parties,
+                other.share_count,
+                self.threshold,
+                self.parties,
+                self.share_count
+            ))
+        } else if self.key_id != other.key_id {
+            Some(format!("key id {} != {}", other.key_id, self.key_id))
+        } else {
+            None
+        }
+    }
+}
+
+impl Validate for SignAgreement {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        if self.party_id < 1 || self.party_id > self.share_count {
+            return Err(ValidationError::OutOfRange("party_id"));
+        }
+        Ok(())
+    }
+}
 
-pub async fn sign(
+/// One message of a signing session
+#[derive(Clone, Debug)]
+pub struct SignRequest<E: Curve = Secp256k1> {
+    /// digest prepared by message::HashMode
+    pub message: [u8; DIGEST_SIZE],
+    pub path: String,
+    /// HD tweak for `path`, zero at the root
+    pub tweak_sk: Scalar<E>,
+    /// public key at `path`
+    pub y_sum: Point<E>,
+}
+
+// k_i, sigma_i and R of a presignature, rounds 1 to 3 for one message
+pub(crate) type PresigShares<E> = (Secret<Scalar<E>>, Secret<Scalar<E>>, Point<E>);
+
+// state shared by the rounds of every message of a session
+pub(crate) struct SignSession<'a> {
+    pub(crate) addr: &'a String,
+    pub(crate) client: Client,
+    pub(crate) delay: time::Duration,
+    pub(crate) party_num_int: u16,
+    pub(crate) parties: u16,
+    pub(crate) uuid: String,
+    pub(crate) signers_vec: Vec<u16>,
+    pub(crate) peers: Vec<u16>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn sign<E: Curve>(
     addr: &String,
-    party_keys: Keys,
-    shared_keys: SharedKeys,
+    party_keys: Keys<E>,
+    shared_keys: SharedKeys<E>,
     party_id: u16,
-    vss_scheme_vec: &mut Vec<VerifiableSS<Secp256k1>>,
+    vss_scheme_vec: &[VerifiableSS<E>],
     paillier_key_vector: Vec<EncryptionKey>,
-    y_sum: &Point<Secp256k1>,
+    ot_setup: Option<&OtSetup>,
+    root_y_sum: &Point<E>,
+    y_sum: &Point<E>,
+    params: &Params,
+    message: &[u8; DIGEST_SIZE],
+    path: &str,
+    tweak_sk: &Scalar<E>,
+) -> Result<SignatureRecid<E>, Error> {
+    let request = SignRequest {
+        message: *message,
+        path: path.to_string(),
+        tweak_sk: tweak_sk.clone(),
+        y_sum: y_sum.clone(),
+    };
+    sign_batch(
+        addr,
+        &party_keys,
+        &shared_keys,
+        party_id,
+        vss_scheme_vec,
+        &paillier_key_vector,
+        ot_setup,
+        root_y_sum,
+        params,
+        &[request],
+    )
+    .await
+    .map(|mut sigs| sigs.remove(0))
+}
+
+/// Signs all `requests` in one session: signers sign up once and run every round
+/// for the whole batch at once, so a batch takes as many rounds as one message.
+/// MtA runs over the base OTs of `ot_setup` if given, else over Paillier.
+#[allow(clippy::too_many_arguments)]
+pub async fn sign_batch<E: Curve>(
+    addr: &String,
+    party_keys: &Keys<E>,
+    shared_keys: &SharedKeys<E>,
+    party_id: u16,
+    vss_scheme_vec: &[VerifiableSS<E>],
+    paillier_key_vector: &[EncryptionKey],
+    ot_setup: Option<&OtSetup>,
+    root_y_sum: &Point<E>,
     params: &Params,
-    message: &[u8],
-    tweak_sk: &Scalar<Secp256k1>,
-) {
+    requests: &[SignRequest<E>],
+) -> Result<Vec<SignatureRecid<E>>, Error> {
+    assert!(!requests.is_empty(), "nothing to sign");
+    // a batch is agreed on as the concatenation of its digests and paths
+    let messages = requests.iter().flat_map(|r| r.message).collect::<Vec<u8>>();
+    let paths = requests
+        .iter()
+        .map(|r| r.path.as_str())
+        .collect::<Vec<&str>>()
+        .join(",");
+    let (mut session, agreement) = join_session(
+        addr,
+        party_id,
+        root_y_sum,
+        params,
+        &messages,
+        &with_mta(&paths, ot_setup),
+    )
+    .await;
+    let tweaks = requests
+        .iter()
+        .map(|r| &r.tweak_sk)
+        .collect::<Vec<&Scalar<E>>>();
+    let presigs = match ot_setup {
+        Some(setup) => {
+            presign_batch(
+                &mut session,
+                &agreement,
+                &OtMta { setup },
+                party_keys,
+                shared_keys,
+                vss_scheme_vec,
+                &tweaks,
+            )
+            .await?
+        }
+        None => {
+            let randomness = pool::randomness();
+            let mta = PaillierMta {
+                keys: party_keys,
+                paillier_key_vec: paillier_key_vector,
+                randomness: &randomness,
+            };
+            presign_batch(
+                &mut session,
+                &agreement,
+                &mta,
+                party_keys,
+                shared_keys,
+                vss_scheme_vec,
+                &tweaks,
+            )
+            .await?
+        }
+    };
+    sign_rounds(&session, presigs, requests).await
+}
+
+// signers must also agree on the MtA, which they check with the paths
+pub(crate) fn with_mta(paths: &str, ot_setup: Option<&OtSetup>) -> String {
+    match ot_setup {
+        Some(_) => format!("{} (mta: {})", paths, ot::MTA),
+        None => paths.to_string(),
+    }
+}
+
+/// Signs up for a session on `messages` and `paths`. The signers are not known yet:
+/// the returned agreement must be broadcast first, as `open_session` does in a
+/// round of its own and `presign_batch` with its round 1.
+pub(crate) async fn join_session<'a, E: Curve>(
+    addr: &'a String,
+    party_id: u16,
+    root_y_sum: &Point<E>,
+    params: &Params,
+    messages: &[u8],
+    paths: &str,
+) -> (SignSession<'a>, SignAgreement) {
     let client = Client::new();
     let delay = time::Duration::from_millis(25);
     let threshold: u16 = params.threshold.parse::<u16>().unwrap();
@@ -45,526 +292,776 @@ pub async fn sign(
     let party_signup = signup(&addr, &client, "signupkeygen", &params).await;
     let party_num_int = party_signup.number;
     let uuid = party_signup.uuid;
-    unsafe {
-        use crate::util::{MEMBER_ID, SESSION_ID};
-        SESSION_ID.set(uuid.clone()).unwrap();
-        MEMBER_ID.set(party_num_int).unwrap();
-    }
+    // already set when a refresh follows keygen in the same process
+    SESSION_ID.set(uuid.clone()).ok();
+    MEMBER_ID.set(party_num_int).ok();
 
     let debug = json!({"manager_addr": &addr, "party_num": party_num_int, "uuid": uuid});
     println!("{}", serde_json::to_string_pretty(&debug).unwrap());
 
-    // round 0: collect signer IDs
+    let agreement = SignAgreement::new(
+        messages,
+        paths,
+        threshold,
+        parties,
+        share_count,
+        root_y_sum,
+        party_id,
+    );
+    let peers = (1..=parties)
+        .filter(|i| *i != party_num_int)
+        .collect::<Vec<u16>>();
+
+    let session = SignSession {
+        addr,
+        client,
+        delay,
+        party_num_int,
+        parties,
+        uuid,
+        signers_vec: Vec::new(),
+        peers,
+    };
+    (session, agreement)
+}
+
+/// Signs up for a session and runs round 0 on `messages` and `paths`.
+pub(crate) async fn open_session<'a, E: Curve>(
+    addr: &'a String,
+    party_id: u16,
+    root_y_sum: &Point<E>,
+    params: &Params,
+    messages: &[u8],
+    paths: &str,
+) -> Result<SignSession<'a>, Error> {
+    let (mut session, agreement) =
+        join_session(addr, party_id, root_y_sum, params, messages, paths).await;
+    // round 0: collect signer IDs and check that all signers sign the same thing
+    session.signers_vec = agree(
+        addr,
+        &session.client,
+        session.delay,
+        session.party_num_int,
+        session.parties,
+        &session.uuid,
+        &agreement,
+    )
+    .await?;
+    Ok(session)
+}
+
+/// Round 0: broadcasts `agreement` and checks that every party sent the same one.
+/// Returns the key share index of each party, ordered by party number.
+pub async fn agree(
+    addr: &String,
+    client: &Client,
+    delay: time::Duration,
+    party_num_int: u16,
+    parties: u16,
+    uuid: &str,
+    agreement: &SignAgreement,
+) -> Result<Vec<u16>, Error> {
     broadcast(
-        &addr,
-        &client,
+        addr,
+        client,
         party_num_int,
         "round0",
-        serde_json::to_string(&party_id).unwrap(),
-        uuid.clone(),
+        agreement,
+        uuid.to_string(),
     )
     .await;
     let round0_ans_vec = poll_for_broadcasts(
-        &addr,
-        &client,
+        addr,
+        client,
         party_num_int,
         parties,
         delay,
         "round0",
-        uuid.clone(),
+        uuid.to_string(),
     )
     .await;
-    let mut signers_vec = round0_ans_vec
-        .iter()
-        .map(|m| serde_json::from_str::<u16>(m).unwrap() - 1)
-        .collect::<Vec<_>>();
-    signers_vec.insert(party_num_int as usize - 1, party_id - 1);
-
-    /*
-    // to be theoretically correct everywhere
-    if sign_at_path == true {
-        // update uj * G as (uj + tweak_sk) * G
-        // where j = party_id of party_num_int == 1
-        vss_scheme_vec[usize::from(signers_vec[usize::from(0u16)])].commitments[0] =
-            vss_scheme_vec[usize::from(signers_vec[usize::from(0u16)])].commitments[0].clone()
-            + Point::generator() * tweak_sk;
-    }
-    let mut private = PartyPrivate::set_private(party_keys.clone(), shared_keys);
-    if sign_at_path == true {
-        if party_num_int == 1 {
-            // update uj as (uj + tweak_sk) and xj as (xj + tweak_sk)
-            private = private.update_private_key(&tweak_sk, &tweak_sk);
-        } else {
-            // only update xi as (xi + tweak_sk)
-            private = private.update_private_key(&Scalar::<Secp256k1>::zero(), &tweak_sk);
+    let peers = (1..=parties).filter(|i| *i != party_num_int);
+    let agreements = zip(&round0_ans_vec, peers)
+        .map(|(m, i)| parse_msg(m, &(), i, "round0"))
+        .collect::<Result<Vec<SignAgreement>, Error>>()?;
+    check_agreement(agreement, party_num_int, &agreements)
+}
+
+// checks the agreements of the peers, ordered by party number, against ours and
+// returns the key share index of each party
+fn check_agreement(
+    agreement: &SignAgreement,
+    party_num_int: u16,
+    agreements: &[SignAgreement],
+) -> Result<Vec<u16>, Error> {
+    let peers = (1..=agreements.len() as u16 + 1).filter(|i| *i != party_num_int);
+    let mut signers_vec = zip(agreements, peers)
+        .map(|(agreement_j, i)| match agreement.mismatch(agreement_j) {
+            Some(mismatch) => Err(Error::SignError(format!(
+                "party {} disagrees on {}",
+                i, mismatch
+            ))),
+            None => Ok(agreement_j.party_id - 1),
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    signers_vec.insert(party_num_int as usize - 1, agreement.party_id - 1);
+    for (i, signer_id) in signers_vec.iter().enumerate() {
+        if let Some(j) = signers_vec[..i].iter().position(|x| x == signer_id) {
+            return Err(Error::SignError(format!(
+                "party {} and party {} both hold key share {}",
+                j + 1,
+                i + 1,
+                signer_id + 1
+            )));
         }
     }
-    */
-
-    // to be practically tricky, only applicable to sign
-    // (1) ignore sign_at_path
-    // (2) omit updates for all ui
-    // (3) only update u1 * G as (u1 + tweak_sk) * G and all xi as (xi + tweak_sk)
-    vss_scheme_vec[0].commitments[0] =
-        vss_scheme_vec[0].commitments[0].clone() + Point::generator() * tweak_sk;
-    let mut private = PartyPrivate::set_private(party_keys.clone(), shared_keys);
-    private = private.update_private_key(&Scalar::<Secp256k1>::zero(), &tweak_sk);
-
-    let sign_keys = SignKeys::create(
-        &private,
-        &vss_scheme_vec[usize::from(signers_vec[usize::from(party_num_int - 1)])],
-        signers_vec[usize::from(party_num_int - 1)],
-        &signers_vec,
+    Ok(signers_vec)
+}
+
+/// Rounds 1 to 3 for every message of a session joined with `join_session`, the
+/// `k`-th at HD tweak `tweaks[k]`: they do not depend on the messages, only on the
+/// keys at their paths. Round 1 carries `agreement` and sets the signers of
+/// `session`. Returns k_i, sigma_i and R of each message.
+pub(crate) async fn presign_batch<E: Curve, M: Mta<E>>(
+    session: &mut SignSession<'_>,
+    agreement: &SignAgreement,
+    mta: &M,
+    party_keys: &Keys<E>,
+    shared_keys: &SharedKeys<E>,
+    vss_scheme_vec: &[VerifiableSS<E>],
+    tweaks: &[&Scalar<E>],
+) -> Result<Vec<PresigShares<E>>, Error> {
+    let (addr, client, delay, uuid) = (
+        session.addr,
+        session.client.clone(),
+        session.delay,
+        session.uuid.clone(),
+    );
+    let (party_num_int, parties, peers) = (
+        session.party_num_int,
+        session.parties,
+        session.peers.clone(),
     );
+    let count = tweaks.len();
+    // proof contexts are scoped to the message
+    let label = |name: &str, k: usize| format!("sign-{}-{}", name, k);
 
-    let (com, decommit) = sign_keys.phase1_broadcast();
-    let (m_a_k, _) = MessageA::a(&sign_keys.k_i, &party_keys.ek, &[]);
-    // round 1: send commitment and do MtA/MtAwc (a) (b)
+    // k_i and gamma_i do not depend on the signers
+    let mut sign_keys_vec = (0..count)
+        .map(|_| SignKeys::<E>::sample())
+        .collect::<Vec<SignKeys<E>>>();
+    let (com_vec, decommit_vec): (Vec<_>, Vec<_>) = sign_keys_vec
+        .iter()
+        .map(|sign_keys| sign_keys.phase1_broadcast())
+        .unzip();
+    let early = sign_keys_vec
+        .iter()
+        .map(|sign_keys| mta.early_round1(sign_keys))
+        .collect::<Option<Vec<(M::State, M::Round1)>>>();
+
+    // round 1: broadcast the agreement with the commitments, and send MtA/MtAwc (a)
+    // to each peer unless it needs the signers
     broadcast(
-        &addr,
+        addr,
         &client,
         party_num_int,
         "round1",
-        serde_json::to_string(&(com.clone(), m_a_k.clone())).unwrap(),
-        uuid.clone(),
-    )
-    .await;
-    let round1_ans_vec = poll_for_broadcasts(
-        &addr,
-        &client,
-        party_num_int,
-        parties,
-        delay,
-        "round1",
+        &(agreement, &com_vec),
         uuid.clone(),
     )
     .await;
-
-    let mut j = 0;
-    let mut bc1_vec: Vec<SignBroadcastPhase1> = Vec::new();
-    let mut m_a_vec: Vec<MessageA> = Vec::new();
-
-    for i in 1..=parties {
-        if i == party_num_int {
-            bc1_vec.push(com.clone());
-        } else {
-            let (bc1_j, m_a_party_j): (SignBroadcastPhase1, MessageA) =
-                serde_json::from_str(&round1_ans_vec[j]).unwrap();
-            bc1_vec.push(bc1_j);
-            m_a_vec.push(m_a_party_j);
-            j = j + 1;
-        }
-    }
-    assert_eq!(signers_vec.len(), bc1_vec.len());
-
-    // do MtA/MtAwc (c) (d)
-    let mut m_b_gamma_send_vec: Vec<MessageB> = Vec::new();
-    let mut beta_vec: Vec<Scalar<Secp256k1>> = Vec::new();
-    let mut m_b_w_send_vec: Vec<MessageB> = Vec::new();
-    let mut ni_vec: Vec<Scalar<Secp256k1>> = Vec::new();
-    let mut j = 0;
-    for i in 1..=parties {
-        if i != party_num_int {
-            let (m_b_gamma, beta_gamma, _, _) = MessageB::b(
-                &sign_keys.gamma_i,
-                &paillier_key_vector[usize::from(signers_vec[usize::from(i - 1)])],
-                m_a_vec[j].clone(),
-                &[],
-            )
-            .unwrap();
-            let (m_b_w, beta_wi, _, _) = MessageB::b(
-                &sign_keys.w_i,
-                &paillier_key_vector[usize::from(signers_vec[usize::from(i - 1)])],
-                m_a_vec[j].clone(),
-                &[],
-            )
-            .unwrap();
-            m_b_gamma_send_vec.push(m_b_gamma);
-            m_b_w_send_vec.push(m_b_w);
-            beta_vec.push(beta_gamma);
-            ni_vec.push(beta_wi);
-            j = j + 1;
-        }
-    }
-
-    // round 2: send Paillier ciphertext
-    let mut j = 0;
-    for i in 1..=parties {
-        if i != party_num_int {
+    if let Some(early) = &early {
+        let msgs = early.iter().map(|(_, msg)| msg).collect::<Vec<_>>();
+        for j in &peers {
             assert!(sendp2p(
-                &addr,
+                addr,
                 &client,
                 party_num_int,
-                i,
-                "round2",
-                serde_json::to_string(&(m_b_gamma_send_vec[j].clone(), m_b_w_send_vec[j].clone()))
-                    .unwrap(),
+                *j,
+                "round1",
+                &msgs,
                 uuid.clone(),
             )
             .await
             .is_ok());
-            j = j + 1;
         }
     }
-
-    let round2_ans_vec = poll_for_p2p(
-        &addr,
+    let round1_ans_vec = poll_for_broadcasts(
+        addr,
         &client,
         party_num_int,
         parties,
         delay,
-        "round2",
+        "round1",
         uuid.clone(),
     )
     .await;
+    let (agreements, bc1_vecs): (Vec<SignAgreement>, Vec<Vec<SignBroadcastPhase1>>) =
+        zip(&round1_ans_vec, &peers)
+            .map(|(msg, j)| parse_msg(msg, &((), (count, ())), *j, "round1"))
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .unzip();
+    let signers_vec = check_agreement(agreement, party_num_int, &agreements)?;
+    session.signers_vec = signers_vec.clone();
+    // the commitments of the peers to each message
+    let bc1_vecs = transpose(bc1_vecs);
 
-    let mut m_b_gamma_rec_vec: Vec<MessageB> = Vec::new();
-    let mut m_b_w_rec_vec: Vec<MessageB> = Vec::new();
+    let index = signers_vec[usize::from(party_num_int - 1)];
+    let mut vss_scheme_vecs = Vec::new();
+    let mut mta_ctxs = Vec::new();
+    for (k, (sign_keys, tweak_sk)) in zip(&mut sign_keys_vec, tweaks).enumerate() {
+        let mut vss_scheme_vec = vss_scheme_vec.to_vec();
 
-    for i in 0..parties - 1 {
-        let (m_b_gamma_i, m_b_w_i): (MessageB, MessageB) =
-            serde_json::from_str(&round2_ans_vec[i as usize]).unwrap();
-        m_b_gamma_rec_vec.push(m_b_gamma_i);
-        m_b_w_rec_vec.push(m_b_w_i);
-    }
+        /*
+        // to be theoretically correct everywhere
+        if sign_at_path == true {
+            // update uj * G as (uj + tweak_sk) * G
+            // where j = party_id of party_num_int == 1
+            vss_scheme_vec[usize::from(signers_vec[usize::from(0u16)])].commitments[0] =
+                vss_scheme_vec[usize::from(signers_vec[usize::from(0u16)])].commitments[0].clone()
+                + Point::generator() * tweak_sk;
+        }
+        let mut private = PartyPrivate::set_private(party_keys.clone(), shared_keys.clone());
+        if sign_at_path == true {
+            if party_num_int == 1 {
+                // update uj as (uj + tweak_sk) and xj as (xj + tweak_sk)
+                private = private.update_private_key(&tweak_sk, &tweak_sk);
+            } else {
+                // only update xi as (xi + tweak_sk)
+                private = private.update_private_key(&Scalar::<E>::zero(), &tweak_sk);
+            }
+        }
+        */
 
-    // do MtA (e) / MtAwc (e) (f)
-    let mut alpha_vec: Vec<Scalar<Secp256k1>> = Vec::new();
-    let mut miu_vec: Vec<Scalar<Secp256k1>> = Vec::new();
+        // to be practically tricky, only applicable to sign
+        // (1) ignore sign_at_path
+        // (2) omit updates for all ui
+        // (3) only update u1 * G as (u1 + tweak_sk) * G and all xi as (xi + tweak_sk)
+        vss_scheme_vec[0].commitments[0] =
+            vss_scheme_vec[0].commitments[0].clone() + Point::generator() * *tweak_sk;
+        let mut private = PartyPrivate::set_private(party_keys.clone(), shared_keys.clone());
+        private = private.update_private_key(&Scalar::<E>::zero(), tweak_sk);
 
-    let xi_com_vec = Keys::get_commitments_to_xi(&vss_scheme_vec);
-    let mut j = 0;
-    for i in 1..=parties {
-        if i != party_num_int {
-            let m_b = m_b_gamma_rec_vec[j].clone();
-            let alpha_ij_gamma = m_b
-                .verify_proofs_get_alpha(&party_keys.dk, &sign_keys.k_i)
-                .expect("wrong dlog or m_b");
-            let m_b = m_b_w_rec_vec[j].clone();
-            let alpha_ij_wi = m_b
-                .verify_proofs_get_alpha(&party_keys.dk, &sign_keys.k_i)
-                .expect("wrong dlog or m_b");
-            alpha_vec.push(alpha_ij_gamma.0);
-            miu_vec.push(alpha_ij_wi.0);
-            let g_w_i = Keys::update_commitments_to_xi(
-                &xi_com_vec[usize::from(signers_vec[usize::from(i - 1)])],
-                &vss_scheme_vec[usize::from(signers_vec[usize::from(i - 1)])],
-                signers_vec[usize::from(i - 1)],
-                &signers_vec,
-            );
-            assert_eq!(m_b.b_proof.pk.clone(), g_w_i);
-            j = j + 1;
-        }
+        sign_keys.set_signers(
+            &private,
+            &vss_scheme_vec[usize::from(index)],
+            index,
+            &signers_vec,
+        );
+        vss_scheme_vecs.push(vss_scheme_vec);
+        mta_ctxs.push(MtaContext {
+            me: party_num_int,
+            gamma: SessionContext::new(&uuid, &label("round2-gamma", k)),
+            w: SessionContext::new(&uuid, &label("round2-w", k)),
+        });
     }
+    let mta_peers = peers
+        .iter()
+        .map(|i| Peer {
+            party: *i,
+            index: signers_vec[usize::from(i - 1)],
+        })
+        .collect::<Vec<Peer>>();
 
-    let delta_i = sign_keys.phase2_delta_i(&alpha_vec, &beta_vec);
-    let sigma = sign_keys.phase2_sigma_i(&miu_vec, &ni_vec);
-
-    // round 3: send delta_i
-    broadcast(
-        &addr,
-        &client,
-        party_num_int,
-        "round3",
-        serde_json::to_string(&delta_i).unwrap(),
-        uuid.clone(),
-    )
-    .await;
-    let round3_ans_vec = poll_for_broadcasts(
-        &addr,
+    // each peer with each message, for the worker pool
+    let tasks = || {
+        mta_peers
+            .iter()
+            .flat_map(|peer| (0..count).map(move |k| (peer, k)))
+            .collect::<Vec<(&Peer, usize)>>()
+    };
+    // the MtA states with each peer for each message
+    let (round1, mta_states) = match early {
+        Some(early) => {
+            let states = early
+                .into_iter()
+                .map(|(state, _)| state)
+                .collect::<Vec<M::State>>();
+            ("round1", vec![states; mta_peers.len()])
+        }
+        None => {
+            // MtA/MtAwc (a) needs the key share of each peer: one more round
+            let round1_vec = worker::par_map(tasks(), |(peer, k)| {
+                mta.round1(&mta_ctxs[k], &sign_keys_vec[k], peer)
+            });
+            let mut states = Vec::new();
+            for (peer, round1_j) in zip(&mta_peers, chunks(round1_vec, count)) {
+                let (states_j, msgs): (Vec<_>, Vec<_>) = round1_j.into_iter().unzip();
+                assert!(sendp2p(
+                    addr,
+                    &client,
+                    party_num_int,
+                    peer.party,
+                    "round1-mta",
+                    &msgs,
+                    uuid.clone(),
+                )
+                .await
+                .is_ok());
+                states.push(states_j);
+            }
+            ("round1-mta", states)
+        }
+    };
+    let round1_p2p_vec = poll_for_p2p(
+        addr,
         &client,
         party_num_int,
         parties,
         delay,
-        "round3",
+        round1,
         uuid.clone(),
     )
     .await;
-    let mut delta_vec: Vec<Scalar<Secp256k1>> = Vec::new();
-    format_vec_from_reads(
-        &round3_ans_vec,
-        party_num_int as usize,
-        delta_i,
-        &mut delta_vec,
-    );
-    let delta_inv = SignKeys::phase3_reconstruct_delta(&delta_vec);
 
-    // round 4: send decommitment to g_gamma_i
-    broadcast(
-        &addr,
-        &client,
-        party_num_int,
-        "round4",
-        serde_json::to_string(&decommit).unwrap(),
-        uuid.clone(),
-    )
-    .await;
-    let round4_ans_vec = poll_for_broadcasts(
-        &addr,
-        &client,
-        party_num_int,
-        parties,
-        delay,
-        "round4",
-        uuid.clone(),
-    )
-    .await;
-    let mut decommit_vec: Vec<SignDecommitPhase1> = Vec::new();
-    format_vec_from_reads(
-        &round4_ans_vec,
-        party_num_int as usize,
-        decommit,
-        &mut decommit_vec,
-    );
-    let decomm_i = decommit_vec.remove((party_num_int - 1) as usize);
-    bc1_vec.remove((party_num_int - 1) as usize);
-    let b_proof_vec = (0..m_b_gamma_rec_vec.len())
-        .map(|i| &m_b_gamma_rec_vec[i].b_proof)
-        .collect::<Vec<&DLogProof<Secp256k1, Sha256>>>();
-    let R = SignKeys::phase4(&delta_inv, &b_proof_vec, decommit_vec, &bc1_vec)
-        .expect("bad gamma_i decommit");
-
-    // add local g_gamma_i
-    let R = R + decomm_i.g_gamma_i * &delta_inv;
-
-    // assume the message is already hashed (by the signer)
-    let message_bn = BigInt::from_bytes(message);
-    let message_int = BigInt::from_bytes(message);
-    let two = BigInt::from(2);
-    let message_bn = message_bn.modulus(&two.pow(256));
-    let local_sig =
-        LocalSignature::phase5_local_sig(&sign_keys.k_i, &message_bn, &R, &sigma, &y_sum);
-
-    let (phase5_com, phase_5a_decom, helgamal_proof, dlog_proof_rho) =
-        local_sig.phase5a_broadcast_5b_zkproof();
-
-    // round 5: GG18 Phase(5A)
-    broadcast(
-        &addr,
-        &client,
-        party_num_int,
-        "round5",
-        serde_json::to_string(&phase5_com).unwrap(),
-        uuid.clone(),
+    // do MtA/MtAwc (b) (c) (d)
+    let mut msgs = Vec::new();
+    for (peer, msg) in zip(&mta_peers, &round1_p2p_vec) {
+        let ctx = (count, mta.round1_context(peer));
+        let msgs_j: Vec<M::Round1> = parse_msg(msg, &ctx, peer.party, round1)?;
+        msgs.extend(msgs_j);
+    }
+    let round2_vec = worker::par_map(
+        zip(tasks(), zip(mta_states.into_iter().flatten(), msgs)).collect(),
+        |((peer, k), (mut state, msg))| {
+            let reply = mta
+                .round2(&mta_ctxs[k], &sign_keys_vec[k], peer, &mut state, &msg)
+                .map_err(|_| {
+                    Error::SignError(format!("party {}: invalid MtA request", peer.party))
+                })?;
+            Ok((state, reply))
+        },
     )
-    .await;
-    let round5_ans_vec = poll_for_broadcasts(
-        &addr,
+    .into_iter()
+    .collect::<Result<Vec<_>, Error>>()?;
+    // round 2: reply to each peer
+    let mut mta_states = Vec::new();
+    for (peer, round2_j) in zip(&mta_peers, chunks(round2_vec, count)) {
+        let (states_j, replies): (Vec<_>, Vec<_>) = round2_j.into_iter().unzip();
+        assert!(sendp2p(
+            addr,
+            &client,
+            party_num_int,
+            peer.party,
+            "round2",
+            &replies,
+            uuid.clone(),
+        )
+        .await
+        .is_ok());
+        mta_states.push(states_j);
+    }
+
+    let round2_ans_vec = poll_for_p2p(
+        addr,
         &client,
         party_num_int,
         parties,
         delay,
-        "round5",
+        "round2",
         uuid.clone(),
     )
     .await;
-    let mut commit5a_vec: Vec<Phase5Com1> = Vec::new();
-    format_vec_from_reads(
-        &round5_ans_vec,
-        party_num_int.clone() as usize,
-        phase5_com,
-        &mut commit5a_vec,
-    );
+    let mut replies = Vec::new();
+    for (peer, msg) in zip(&mta_peers, &round2_ans_vec) {
+        let ctx = (count, mta.round2_context(peer));
+        let replies_j: Vec<M::Round2> = parse_msg(msg, &ctx, peer.party, "round2")?;
+        replies.extend(replies_j);
+    }
 
-    // round 6: GG18 Phase(5B)
-    broadcast(
-        &addr,
-        &client,
-        party_num_int,
-        "round6",
-        serde_json::to_string(&(
-            phase_5a_decom.clone(),
-            helgamal_proof.clone(),
-            dlog_proof_rho.clone(),
-        ))
-        .unwrap(),
-        uuid.clone(),
+    // do MtA (e) / MtAwc (e) (f)
+    let xi_com_vecs = vss_scheme_vecs
+        .iter()
+        .map(|vss_scheme_vec| Keys::<E>::get_commitments_to_xi(vss_scheme_vec))
+        .collect::<Vec<Vec<Point<E>>>>();
+    let shares_vec = worker::par_map(
+        zip(tasks(), zip(mta_states.into_iter().flatten(), replies)).collect(),
+        |((peer, k), (state, msg))| {
+            let shares = mta
+                .finish(&mta_ctxs[k], &sign_keys_vec[k], peer, state, &msg)
+                .map_err(|_| {
+                    Error::SignError(format!("party {}: invalid MtA reply", peer.party))
+                })?;
+            let g_w_i = Keys::<E>::update_commitments_to_xi(
+                &xi_com_vecs[k][usize::from(peer.index)],
+                &vss_scheme_vecs[k][usize::from(peer.index)],
+                peer.index,
+                &signers_vec,
+            );
+            if shares.w_proof.pk != g_w_i {
+                return Err(Error::SignError(format!(
+                    "party {}: W_j does not match its VSS commitments",
+                    peer.party
+                )));
+            }
+            Ok(shares)
+        },
     )
-    .await;
-    let round6_ans_vec = poll_for_broadcasts(
-        &addr,
-        &client,
-        party_num_int,
-        parties,
-        delay,
-        "round6",
-        uuid.clone(),
+    .into_iter()
+    .collect::<Result<Vec<_>, Error>>()?;
+    if M::CHECK_SHARES {
+        // MtA (f) where round 2 did not check Bob's inputs: before delta_i and sigma_i
+        // depend on them, each peer shows its shares fit Gamma_j and W_j
+        for (peer, shares_j) in zip(&mta_peers, shares_vec.chunks(count)) {
+            let msgs = shares_j
+                .iter()
+                .map(|shares| shares.check_message())
+                .collect::<Vec<MtaCheck<E>>>();
+            assert!(sendp2p(
+                addr,
+                &client,
+                party_num_int,
+                peer.party,
+                "round2-check",
+                &msgs,
+                uuid.clone(),
+            )
+            .await
+            .is_ok());
+        }
+        let check_ans_vec = poll_for_p2p(
+            addr,
+            &client,
+            party_num_int,
+            parties,
+            delay,
+            "round2-check",
+            uuid.clone(),
+        )
+        .await;
+        for ((peer, msg), shares_j) in zip(&mta_peers, &check_ans_vec).zip(shares_vec.chunks(count))
+        {
+            let checks: Vec<MtaCheck<E>> =
+                parse_msg(msg, &(count, ()), peer.party, "round2-check")?;
+            for ((shares, check), sign_keys) in zip(shares_j, &checks).zip(&sign_keys_vec) {
+                if shares.check(&sign_keys.k_i, check).is_err() {
+                    return Err(Error::SignError(format!(
+                        "party {} did not multiply by its gamma_i and w_i",
+                        peer.party
+                    )));
+                }
+            }
+        }
+    }
+
+    let mut delta_i_vec: Vec<Scalar<E>> = Vec::new();
+    let mut sigma_vec: Vec<Scalar<E>> = Vec::new();
+    let mut gamma_proof_vecs: Vec<Vec<DLogProof<E, Sha256>>> = Vec::new();
+    for (sign_keys, shares_k) in zip(&sign_keys_vec, transpose(chunks(shares_vec, count))) {
+        let mut alpha_vec: Vec<Scalar<E>> = Vec::new();
+        let mut beta_vec: Vec<Scalar<E>> = Vec::new();
+        let mut miu_vec: Vec<Scalar<E>> = Vec::new();
+        let mut ni_vec: Vec<Scalar<E>> = Vec::new();
+        let mut gamma_proof_vec: Vec<DLogProof<E, Sha256>> = Vec::new();
+        for shares in shares_k {
+            alpha_vec.push(shares.alpha);
+            beta_vec.push(shares.beta);
+            miu_vec.push(shares.miu);
+            ni_vec.push(shares.ni);
+            gamma_proof_vec.push(shares.gamma_proof);
+        }
+
+        delta_i_vec.push(sign_keys.phase2_delta_i(&alpha_vec, &beta_vec));
+        sigma_vec.push(sign_keys.phase2_sigma_i(&miu_vec, &ni_vec));
+        gamma_proof_vecs.push(gamma_proof_vec);
+    }
+
+    // round 3: send delta_i with the decommitment to g_gamma_i, which reveals
+    // nothing more once every delta_i is out
+    let round3_vecs = exchange_batch(
+        session,
+        "round3",
+        zip(delta_i_vec, decommit_vec).collect::<Vec<_>>(),
+        &((), ()),
     )
-    .await;
-    let mut decommit5a_and_elgamal_and_dlog_vec: Vec<(
-        Phase5ADecom1,
-        HomoELGamalProof<Secp256k1, Sha256>,
-        DLogProof<Secp256k1, Sha256>,
-    )> = Vec::new();
-    format_vec_from_reads(
-        &round6_ans_vec,
-        party_num_int as usize,
-        (
-            phase_5a_decom.clone(),
-            helgamal_proof.clone(),
-            dlog_proof_rho.clone(),
-        ),
-        &mut decommit5a_and_elgamal_and_dlog_vec,
-    );
-    let decommit5a_and_elgamal_vec_includes_i = decommit5a_and_elgamal_and_dlog_vec.clone();
-    decommit5a_and_elgamal_and_dlog_vec.remove((party_num_int - 1) as usize);
-    commit5a_vec.remove((party_num_int - 1) as usize);
-    let phase_5a_decomm_vec = (0..parties - 1)
-        .map(|i| decommit5a_and_elgamal_and_dlog_vec[i as usize].0.clone())
-        .collect::<Vec<Phase5ADecom1>>();
-    let phase_5a_elgamal_vec = (0..parties - 1)
-        .map(|i| decommit5a_and_elgamal_and_dlog_vec[i as usize].1.clone())
-        .collect::<Vec<HomoELGamalProof<Secp256k1, Sha256>>>();
-    let phase_5a_dlog_vec = (0..parties - 1)
-        .map(|i| decommit5a_and_elgamal_and_dlog_vec[i as usize].2.clone())
-        .collect::<Vec<DLogProof<Secp256k1, Sha256>>>();
-    let (phase5_com2, phase_5d_decom2) = local_sig
-        .phase5c(
+    .await?;
+
+    let mut presigs = Vec::new();
+    for (((sign_keys, sigma), (mut round3_vec, bc1_vec)), gamma_proof_vec) in
+        zip(sign_keys_vec, sigma_vec)
+            .zip(zip(round3_vecs, bc1_vecs))
+            .zip(gamma_proof_vecs)
+    {
+        let delta_vec = round3_vec
+            .iter()
+            .map(|(delta_i, _)| delta_i.clone())
+            .collect::<Vec<Scalar<E>>>();
+        let delta_inv = SignKeys::phase3_reconstruct_delta(&delta_vec);
+        let (_, decomm_i) = round3_vec.remove((party_num_int - 1) as usize);
+        let decommit_vec = round3_vec
+            .into_iter()
+            .map(|(_, decommit)| decommit)
+            .collect::<Vec<SignDecommitPhase1<E>>>();
+        let b_proof_vec = gamma_proof_vec
+            .iter()
+            .collect::<Vec<&DLogProof<E, Sha256>>>();
+        let R = SignKeys::phase4(&delta_inv, &b_proof_vec, decommit_vec.clone(), &bc1_vec)
+            .map_err(|_| {
+                let fails = |i: usize| {
+                    SignKeys::phase4(
+                        &delta_inv,
+                        &b_proof_vec[i..=i],
+                        decommit_vec[i..=i].to_vec(),
+                        &bc1_vec[i..=i],
+                    )
+                    .is_err()
+                };
+                blame(&peers, fails, "Gamma_j does not open its commitment")
+            })?;
+
+        // add local g_gamma_i
+        let R = R + decomm_i.g_gamma_i * &delta_inv;
+        presigs.push((sign_keys.k_i, Secret::new(sigma), R));
+    }
+    Ok(presigs)
+}
+
+// rounds 4 to 8 (GG18 phase 5) for every message of the session, the `k`-th with
+// the `k`-th presignature
+pub(crate) async fn sign_rounds<E: Curve>(
+    session: &SignSession<'_>,
+    presigs: Vec<PresigShares<E>>,
+    requests: &[SignRequest<E>],
+) -> Result<Vec<SignatureRecid<E>>, Error> {
+    let (party_num_int, parties) = (session.party_num_int, session.parties);
+    let label = |name: &str, k: usize| format!("sign-{}-{}", name, k);
+    let peers = (1..=parties)
+        .filter(|i| *i != party_num_int)
+        .collect::<Vec<u16>>();
+
+    // message is the digest prepared by message::HashMode
+    let local_sigs = zip(&presigs, requests)
+        .map(|((k_i, sigma, R), request)| {
+            let message_bn = BigInt::from_bytes(&request.message);
+            LocalSignature::phase5_local_sig(k_i, &message_bn, R, sigma, &request.y_sum)
+        })
+        .collect::<Vec<LocalSignature<E>>>();
+    let round5_ctxs = (0..requests.len())
+        .map(|k| SessionContext::new(&session.uuid, &label("round5", k)))
+        .collect::<Vec<SessionContext>>();
+    let (phase5_com_vec, phase5_decom_vec): (Vec<_>, Vec<_>) = zip(&local_sigs, &round5_ctxs)
+        .map(|(local_sig, ctx)| {
+            let (phase5_com, phase_5a_decom, helgamal_proof, dlog_proof_rho) =
+                local_sig.phase5a_broadcast_5b_zkproof(ctx, party_num_int);
+            (phase5_com, (phase_5a_decom, helgamal_proof, dlog_proof_rho))
+        })
+        .unzip();
+
+    // round 4: GG18 Phase(5A)
+    let commit5a_vecs = exchange_batch(session, "round4", phase5_com_vec, &()).await?;
+
+    // round 5: GG18 Phase(5B)
+    let decommit5a_vecs =
+        exchange_batch(session, "round5", phase5_decom_vec.clone(), &((), (), ())).await?;
+
+    let mut phase5c_vec = Vec::new();
+    for (k, (mut commit5a_vec, mut decommit5a_and_elgamal_and_dlog_vec)) in
+        zip(commit5a_vecs, decommit5a_vecs.clone()).enumerate()
+    {
+        decommit5a_and_elgamal_and_dlog_vec.remove((party_num_int - 1) as usize);
+        commit5a_vec.remove((party_num_int - 1) as usize);
+        let phase_5a_decomm_vec = (0..parties - 1)
+            .map(|i| decommit5a_and_elgamal_and_dlog_vec[i as usize].0.clone())
+            .collect::<Vec<Phase5ADecom1<E>>>();
+        let phase_5a_elgamal_vec = (0..parties - 1)
+            .map(|i| decommit5a_and_elgamal_and_dlog_vec[i as usize].1.clone())
+            .collect::<Vec<HomoELGamalProof<E, Sha256>>>();
+        let phase_5a_dlog_vec = (0..parties - 1)
+            .map(|i| decommit5a_and_elgamal_and_dlog_vec[i as usize].2.clone())
+            .collect::<Vec<DLogProof<E, Sha256>>>();
+        let phase5c = local_sigs[k].phase5c(
             &phase_5a_decomm_vec,
             &commit5a_vec,
             &phase_5a_elgamal_vec,
             &phase_5a_dlog_vec,
-            &phase_5a_decom.V_i,
-            &R.clone(),
-        )
-        .expect("error phase5");
+            &phase5_decom_vec[k].0.V_i,
+            &presigs[k].2,
+            &round5_ctxs[k],
+            &peers,
+        );
+        let phase5c = phase5c.map_err(|_| {
+            let fails = |i: usize| {
+                local_sigs[k]
+                    .phase5c(
+                        &phase_5a_decomm_vec[i..=i],
+                        &commit5a_vec[i..=i],
+                        &phase_5a_elgamal_vec[i..=i],
+                        &phase_5a_dlog_vec[i..=i],
+                        &phase5_decom_vec[k].0.V_i,
+                        &presigs[k].2,
+                        &round5_ctxs[k],
+                        &peers[i..=i],
+                    )
+                    .is_err()
+            };
+            blame(&peers, fails, "bad phase 5A decommitment or proof")
+        })?;
+        phase5c_vec.push(phase5c);
+    }
+    let (phase5_com2_vec, phase_5d_decom2_vec): (Vec<_>, Vec<_>) = phase5c_vec.into_iter().unzip();
 
-    // round 7: GG18 Phase(5C)
-    broadcast(
-        &addr,
-        &client,
-        party_num_int,
-        "round7",
-        serde_json::to_string(&phase5_com2).unwrap(),
-        uuid.clone(),
-    )
-    .await;
-    let round7_ans_vec = poll_for_broadcasts(
-        &addr,
-        &client,
-        party_num_int,
-        parties,
-        delay,
-        "round7",
-        uuid.clone(),
-    )
-    .await;
-    let mut commit5c_vec: Vec<Phase5Com2> = Vec::new();
-    format_vec_from_reads(
-        &round7_ans_vec,
-        party_num_int.clone() as usize,
-        phase5_com2,
-        &mut commit5c_vec,
-    );
+    // round 6: GG18 Phase(5C)
+    let commit5c_vecs = exchange_batch(session, "round6", phase5_com2_vec, &()).await?;
 
-    // round 8: GG18 Phase(5D)
-    broadcast(
-        &addr,
-        &client,
-        party_num_int,
-        "round8",
-        serde_json::to_string(&phase_5d_decom2).unwrap(),
-        uuid.clone(),
-    )
-    .await;
-    let round8_ans_vec = poll_for_broadcasts(
-        &addr,
-        &client,
-        party_num_int,
-        parties,
-        delay,
-        "round8",
-        uuid.clone(),
-    )
-    .await;
-    let mut decommit5d_vec: Vec<Phase5DDecom2> = Vec::new();
-    format_vec_from_reads(
-        &round8_ans_vec,
-        party_num_int.clone() as usize,
-        phase_5d_decom2.clone(),
-        &mut decommit5d_vec,
-    );
+    // round 7: GG18 Phase(5D)
+    let decommit5d_vecs = exchange_batch(session, "round7", phase_5d_decom2_vec, &()).await?;
 
-    let phase_5a_decomm_vec_includes_i = (0..parties)
-        .map(|i| decommit5a_and_elgamal_vec_includes_i[i as usize].0.clone())
-        .collect::<Vec<Phase5ADecom1>>();
-    let s_i = local_sig
-        .phase5d(
-            &decommit5d_vec,
-            &commit5c_vec,
-            &phase_5a_decomm_vec_includes_i,
-        )
-        .expect("bad com 5d");
+    let mut s_i_vec = Vec::new();
+    for (k, (commit5c_vec, decommit5d_vec)) in zip(commit5c_vecs, decommit5d_vecs).enumerate() {
+        let phase_5a_decomm_vec_includes_i = decommit5a_vecs[k]
+            .iter()
+            .map(|decommit| decommit.0.clone())
+            .collect::<Vec<Phase5ADecom1<E>>>();
+        let s_i = local_sigs[k]
+            .phase5d(
+                &decommit5d_vec,
+                &commit5c_vec,
+                &phase_5a_decomm_vec_includes_i,
+            )
+            .map_err(|_| {
+                // a commitment that does not open names its party; otherwise the
+                // U_j and T_j together do not check out, which GG18 cannot pin down
+                let i = (0..decommit5d_vec.len()).find(|&i| {
+                    local_sigs[k].phase5d(
+                        &decommit5d_vec[i..=i],
+                        &commit5c_vec[i..=i],
+                        &phase_5a_decomm_vec_includes_i[i..=i],
+                    ) == Err(multi_party_ecdsa::Error::InvalidCom)
+                });
+                match i {
+                    Some(i) => Error::SignError(format!(
+                        "party {}: phase 5D decommitment does not open its commitment",
+                        i + 1
+                    )),
+                    None => Error::SignError(
+                        "phase 5D check failed: some party's s_i is wrong".to_string(),
+                    ),
+                }
+            })?;
+        s_i_vec.push(s_i);
+    }
 
-    // round 9: GG18 Phase(5E)
-    broadcast(
-        &addr,
-        &client,
-        party_num_int,
-        "round9",
-        serde_json::to_string(&s_i).unwrap(),
-        uuid.clone(),
-    )
-    .await;
-    let round9_ans_vec = poll_for_broadcasts(
-        &addr,
-        &client,
+    // round 8: GG18 Phase(5E)
+    let s_i_vecs = exchange_batch(session, "round8", s_i_vec, &()).await?;
+
+    let mut sigs = Vec::new();
+    for ((local_sig, mut s_i_vec), request) in zip(zip(local_sigs, s_i_vecs), requests) {
+        s_i_vec.remove((party_num_int - 1) as usize);
+        let sig = local_sig.output_signature(&s_i_vec).map_err(|_| {
+            Error::SignError("signature shares do not add up to a valid signature".to_string())
+        })?;
+
+        // verify with child pub key
+        check_sig(
+            &sig.r,
+            &sig.s,
+            &BigInt::from_bytes(&request.message),
+            &request.y_sum,
+        );
+        sigs.push(sig);
+    }
+    Ok(sigs)
+}
+
+// the error of a check over the messages of all `peers` that failed: the checks are
+// per peer, so the first peer whose message `fails` on its own is at fault
+fn blame(peers: &[u16], fails: impl Fn(usize) -> bool, what: &str) -> Error {
+    match (0..peers.len()).find(|i| fails(*i)) {
+        Some(i) => Error::SignError(format!("party {}: {}", peers[i], what)),
+        None => Error::SignError(what.to_string()),
+    }
+}
+
+// broadcasts `msgs`, one per message of the session, and returns the messages of
+// every party, ours included, for each message in turn
+async fn exchange_batch<T: Serialize + DeserializeOwned + Validate + Clone>(
+    session: &SignSession<'_>,
+    round: &str,
+    msgs: Vec<T>,
+    ctx: &T::Context,
+) -> Result<Vec<Vec<T>>, Error>
+where
+    T::Context: Clone,
+{
+    let SignSession {
+        addr,
+        client,
+        delay,
         party_num_int,
         parties,
-        delay,
-        "round9",
+        uuid,
+        ..
+    } = session;
+    broadcast(addr, client, *party_num_int, round, &msgs, uuid.clone()).await;
+    let ans_vec = poll_for_broadcasts(
+        addr,
+        client,
+        *party_num_int,
+        *parties,
+        *delay,
+        round,
         uuid.clone(),
     )
     .await;
-    let mut s_i_vec: Vec<Scalar<Secp256k1>> = Vec::new();
+    let count = msgs.len();
+    let mut msgs_vec: Vec<Vec<T>> = Vec::new();
     format_vec_from_reads(
-        &round9_ans_vec,
-        party_num_int.clone() as usize,
-        s_i,
-        &mut s_i_vec,
-    );
+        &ans_vec,
+        *party_num_int as usize,
+        msgs,
+        &mut msgs_vec,
+        &(count, ctx.clone()),
+        round,
+    )?;
+    Ok(transpose(msgs_vec))
+}
+
+// `items` in consecutive chunks of `size`
+fn chunks<T>(items: Vec<T>, size: usize) -> Vec<Vec<T>> {
+    let mut items = items.into_iter();
+    let mut chunks = Vec::new();
+    loop {
+        let chunk = items.by_ref().take(size).collect::<Vec<T>>();
+        if chunk.is_empty() {
+            return chunks;
+        }
+        chunks.push(chunk);
+    }
+}
 
-    s_i_vec.remove((party_num_int - 1) as usize);
-    let sig = local_sig
-        .output_signature(&s_i_vec)
-        .expect("verification failed");
-
-    println!("child pubkey: {:#?} \n", y_sum);
-    println!("verifying signature with child pub key");
-
-    // let ret_dict = json!({
-    //     "r": BigInt::from_bytes(sig.r.to_bytes().as_ref()).to_str_radix(16),
-    //     "s": BigInt::from_bytes(sig.s.to_bytes().as_ref()).to_str_radix(16),
-    //     "status": "signature_ready",
-    //     "recid": sig.recid.clone(),
-    //     "x": &y_sum.x_coord(),
-    //     "y": &y_sum.y_coord(),
-    //     "msg_int": message_int,
-    // });
-    check_sig(&sig.r, &sig.s, &message_bn, &y_sum);
-    println!("party {:?} Output Signature: \n", party_num_int);
-    println!("r: {:#?}", sig.r);
-    println!("s: {:#?} \n", sig.s);
-    println!("recid: {:?} \n", sig.recid.clone());
-    println!("x: {:#?}", &y_sum.x_coord());
-    println!("y: {:#?}", &y_sum.y_coord());
-    println!("msg_int: {}", message_int);
+// messages of each party for each message of a batch, to those of each message
+// from each party
+fn transpose<T>(vecs: Vec<Vec<T>>) -> Vec<Vec<T>> {
+    let mut transposed: Vec<Vec<T>> = Vec::new();
+    for vec in vecs {
+        for (k, item) in vec.into_iter().enumerate() {
+            if transposed.len() == k {
+                transposed.push(Vec::new());
+            }
+            transposed[k].push(item);
+        }
+    }
+    transposed
 }
 
-fn format_vec_from_reads<'a, T: serde::Deserialize<'a> + Clone>(
-    ans_vec: &'a Vec<String>,
+fn format_vec_from_reads<T: DeserializeOwned + Validate + Clone>(
+    ans_vec: &Vec<String>,
     party_num: usize,
     value_i: T,
-    new_vec: &'a mut Vec<T>,
-) {
+    new_vec: &mut Vec<T>,
+    ctx: &T::Context,
+    round: &str,
+) -> Result<(), Error> {
     let mut j = 0;
     for i in 1..ans_vec.len() + 2 {
         if i == party_num {
             new_vec.push(value_i.clone());
         } else {
-            let value_j: T = serde_json::from_str(&ans_vec[j]).unwrap();
+            let value_j: T = parse_msg(&ans_vec[j], ctx, i as u16, round)?;
             new_vec.push(value_j);
             j = j + 1;
         }
     }
+    Ok(())
 }
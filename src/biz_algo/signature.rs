@@ -0,0 +1,218 @@
+// encodings of the signature produced by `sign`
+use std::{fmt, str::FromStr};
+
+use curv::elliptic::curves::{Curve, Point};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::biz_algo::{message::DIGEST_SIZE, Error};
+use crate::mp_ecdsa::party_i::SignatureRecid;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SignatureFormat {
+    Der,
+    /// r || s
+    Compact,
+    /// r || s || v
+    Recoverable,
+    /// {"r", "s", "recid"}
+    Json,
+}
+
+impl SignatureFormat {
+    pub const NAMES: [&'static str; 4] = ["der", "compact", "recoverable", "json"];
+
+    pub fn encode<E: Curve>(&self, sig: &SignatureRecid<E>) -> Value {
+        match self {
+            SignatureFormat::Der => Value::String(hex::encode(sig.to_der())),
+            SignatureFormat::Compact => Value::String(hex::encode(sig.to_compact())),
+            SignatureFormat::Recoverable => Value::String(hex::encode(sig.to_recoverable())),
+            SignatureFormat::Json => json!({
+                "r": hex::encode(&*sig.r.to_bytes()),
+                "s": hex::encode(&*sig.s.to_bytes()),
+                "recid": sig.recid,
+            }),
+        }
+    }
+
+    /// hex for the binary encodings, a JSON object otherwise
+    pub fn encode_to_string<E: Curve>(&self, sig: &SignatureRecid<E>) -> String {
+        match self.encode(sig) {
+            Value::String(s) => s,
+            value => value.to_string(),
+        }
+    }
+
+    /// Inverse of `encode_to_string`. `der` and `compact` carry no recovery id.
+    pub fn decode<E: Curve>(&self, s: &str) -> Result<SignatureRecid<E>, Error> {
+        let invalid = |e: String| Error::ParamInvalid(format!("invalid {} signature: {}", self, e));
+        let sig = match self {
+            SignatureFormat::Json => {
+                let json: JsonSignature =
+                    serde_json::from_str(s).map_err(|e| invalid(e.to_string()))?;
+                let r = hex::decode(&json.r).map_err(|e| invalid(e.to_string()))?;
+                let s = hex::decode(&json.s).map_err(|e| invalid(e.to_string()))?;
+                if r.len() != 32 || s.len() != 32 {
+                    return Err(invalid("r and s must be 32 bytes".to_string()));
+                }
+                SignatureRecid::from_recoverable(&[r, s, vec![json.recid]].concat())
+            }
+            _ => {
+                let bytes = hex::decode(s.strip_prefix("0x").unwrap_or(s))
+                    .map_err(|e| invalid(e.to_string()))?;
+                match self {
+                    SignatureFormat::Der => SignatureRecid::from_der(&bytes),
+                    SignatureFormat::Compact => SignatureRecid::from_compact(&bytes),
+                    _ => SignatureRecid::from_recoverable(&bytes),
+                }
+            }
+        };
+        sig.map_err(|e| invalid(format!("{:?}", e)))
+    }
+
+    pub fn has_recid(&self) -> bool {
+        matches!(self, SignatureFormat::Recoverable | SignatureFormat::Json)
+    }
+}
+
+impl fmt::Display for SignatureFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(SignatureFormat::NAMES[*self as usize])
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonSignature {
+    r: String,
+    s: String,
+    recid: u8,
+}
+
+impl FromStr for SignatureFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "der" => Ok(SignatureFormat::Der),
+            "compact" => Ok(SignatureFormat::Compact),
+            "recoverable" => Ok(SignatureFormat::Recoverable),
+            "json" => Ok(SignatureFormat::Json),
+            _ => Err(Error::ParamInvalid(format!(
+                "unknown signature format {:?}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Content of the `--out` file
+#[derive(Clone, Debug, Serialize)]
+pub struct SignOutput {
+    pub format: String,
+    pub signature: Value,
+    pub digest: String,
+    pub path: String,
+    pub pubkey: String,
+}
+
+impl SignOutput {
+    pub fn new<E: Curve>(
+        sig: &SignatureRecid<E>,
+        format: SignatureFormat,
+        digest: &[u8; DIGEST_SIZE],
+        path: &str,
+        child_pubkey: &Point<E>,
+    ) -> Self {
+        Self {
+            format: format.to_string(),
+            signature: format.encode(sig),
+            digest: hex::encode(digest),
+            path: path.to_string(),
+            pubkey: hex::encode(&*child_pubkey.to_bytes(true)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_ecdsa::party_i::{recover, verify};
+    use curv::{
+        arithmetic::Converter,
+        elliptic::curves::{Scalar, Secp256k1},
+        BigInt,
+    };
+    use sha2::{Digest, Sha256};
+
+    // key 1 signing sha256("Satoshi Nakamoto"), RFC 6979 nonce, low S
+    const R: &str = "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8";
+    const S: &str = "2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5";
+    const DER: &str = "3045022100934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab\
+                       210ee3d802202442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a\
+                       512aafd9e5";
+
+    fn vector() -> (SignatureRecid<Secp256k1>, BigInt) {
+        let recoverable = format!("{}{}1c", R, S);
+        let sig = SignatureFormat::Recoverable.decode(&recoverable).unwrap();
+        let m = BigInt::from_bytes(&Sha256::digest(b"Satoshi Nakamoto"));
+        (sig, m)
+    }
+
+    #[test]
+    fn known_vector() {
+        let (sig, m) = vector();
+        assert_eq!(sig.recid, 1);
+        verify(&sig, &Point::generator().to_point(), &m).unwrap();
+        assert_eq!(recover(&sig, &m).unwrap(), Point::generator().to_point());
+        assert_eq!(SignatureFormat::Der.encode_to_string(&sig), DER);
+        assert_eq!(
+            SignatureFormat::Compact.encode_to_string(&sig),
+            format!("{}{}", R, S)
+        );
+    }
+
+    #[test]
+    fn round_trips() {
+        let (sig, _) = vector();
+        for name in SignatureFormat::NAMES {
+            let format: SignatureFormat = name.parse().unwrap();
+            let decoded: SignatureRecid<Secp256k1> =
+                format.decode(&format.encode_to_string(&sig)).unwrap();
+            assert_eq!((&decoded.r, &decoded.s), (&sig.r, &sig.s));
+            if format.has_recid() {
+                assert_eq!(decoded.recid, sig.recid);
+            }
+        }
+        let v0 = format!("{}{}01", R, S);
+        let decoded: SignatureRecid<Secp256k1> = SignatureFormat::Recoverable.decode(&v0).unwrap();
+        assert_eq!(decoded.recid, 1);
+    }
+
+    #[test]
+    fn der_is_strict() {
+        let decode = |der: &str| SignatureFormat::Der.decode::<Secp256k1>(der);
+        assert!(decode(DER).is_ok());
+        // trailing byte
+        assert!(decode(&format!("{}00", DER)).is_err());
+        // r with the high bit set and no zero byte: negative
+        assert!(decode(&format!("30440220{}0220{}", R, S)).is_err());
+        // s with a needless zero byte
+        assert!(decode(&format!("3046022100{}022100{}", R, S)).is_err());
+        // r = 0
+        assert!(decode(&format!("30250201000220{}", S)).is_err());
+    }
+
+    #[test]
+    fn compact_rejects_out_of_range() {
+        let zero = format!("{}{}", "0".repeat(64), S);
+        assert!(SignatureFormat::Compact.decode::<Secp256k1>(&zero).is_err());
+        let order = hex::encode(Scalar::<Secp256k1>::group_order().to_bytes());
+        let r_is_q = format!("{}{}", order, S);
+        assert!(SignatureFormat::Compact
+            .decode::<Secp256k1>(&r_is_q)
+            .is_err());
+        assert!(SignatureFormat::Recoverable
+            .decode::<Secp256k1>(&format!("{}{}05", R, S))
+            .is_err());
+    }
+}
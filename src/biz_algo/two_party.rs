@@ -0,0 +1,199 @@
+// Two-party ECDSA (Lindell'17) for 2-of-2 keys, on the same transport as GG18
+//
+// keygen: party one commits to Q_1 while party two sends Q_2 with its ring-Pedersen
+// parameters (round 1), then party one opens Q_1 and sends its Paillier key with
+// c_key = enc(x_1) (round 2). The first party to sign up is party one and keeps the
+// Paillier key. The keys file is a `TwoPartyKeys` object instead of the GG18 tuple.
+//
+// sign: after the round 0 agreement of `open_session`, party one commits to R_1 while party
+// two sends R_2 (round 1), party one opens R_1 (round 2), party two sends c_3
+// (round 3) and party one sends the signature back (round 4). HD paths use the
+// tweak of `sign.rs`; the tweak is added by party two inside c_3.
+use std::time;
+
+use crate::mp_ecdsa::{party_i::*, secret::Secret, validate::Validate};
+use crate::mp_lindell17::{
+    KeyGenCommit, KeyGenParty1Msg, KeyGenParty2Msg, PartyOneKeyGen, PartyOneSign, PartyTwoKeyGen,
+    PartyTwoSign, SignCommit, SignParty1Msg2, SignParty2Msg1, SignParty2Msg2, TwoPartyKeys,
+};
+use curv::{
+    arithmetic::Converter,
+    elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar},
+    BigInt,
+};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::biz_algo::{
+    broadcast,
+    message::DIGEST_SIZE,
+    parse_msg, poll_for_broadcasts, poll_for_p2p, sendp2p,
+    sign::{open_session, SignSession},
+    signup, write_secret_file, Error, Params,
+};
+use crate::util::{MEMBER_ID, SESSION_ID};
+
+// round 1 of both protocols: both parties broadcast, each its own message type
+async fn exchange<T: Serialize, U: DeserializeOwned + Validate<Context = ()>>(
+    addr: &String,
+    client: &Client,
+    delay: time::Duration,
+    me: u16,
+    uuid: &str,
+    round: &str,
+    msg: &T,
+) -> Result<U, Error> {
+    broadcast(addr, client, me, round, msg, uuid.to_string()).await;
+    let ans_vec = poll_for_broadcasts(addr, client, me, 2, delay, round, uuid.to_string()).await;
+    parse_msg(&ans_vec[0], &(), 3 - me, round)
+}
+
+async fn send<T: Serialize>(
+    addr: &String,
+    client: &Client,
+    me: u16,
+    uuid: &str,
+    round: &str,
+    msg: &T,
+) {
+    assert!(
+        sendp2p(addr, client, me, 3 - me, round, msg, uuid.to_string(),)
+            .await
+            .is_ok()
+    );
+}
+
+async fn receive<T: DeserializeOwned + Validate>(
+    addr: &String,
+    client: &Client,
+    delay: time::Duration,
+    me: u16,
+    uuid: &str,
+    round: &str,
+    ctx: &T::Context,
+) -> Result<T, Error> {
+    let ans_vec = poll_for_p2p(addr, client, me, 2, delay, round, uuid.to_string()).await;
+    parse_msg(&ans_vec[0], ctx, 3 - me, round)
+}
+
+pub async fn keygen(
+    addr: &String,
+    keysfile_path: &str,
+    params: &[&str],
+) -> Result<Point<Secp256k1>, Error> {
+    if params != ["1", "2"] {
+        return Err(Error::ParamInvalid("two-party keys are 1/2".to_string()));
+    }
+    let client = Client::new();
+    let delay = time::Duration::from_millis(25);
+    let tn_params = Params {
+        threshold: "1".to_string(),
+        parties: "2".to_string(),
+        share_count: "2".to_string(),
+    };
+    let party_signup = signup(addr, &client, "signupkeygen", &tn_params).await;
+    let me = party_signup.number;
+    let uuid = party_signup.uuid;
+    println!("number: {:?}, uuid: {:?}", me, uuid);
+    SESSION_ID.set(uuid.clone()).unwrap();
+    MEMBER_ID.set(me).unwrap();
+
+    let ctx = SessionContext::new(&uuid, "lindell17-keygen");
+    let keys = match me {
+        1 => {
+            let (party, com) = PartyOneKeyGen::new(&ctx);
+            let msg: KeyGenParty2Msg =
+                exchange(addr, &client, delay, me, &uuid, "round1", &com).await?;
+            let (msg, keys) = party
+                .round2(&msg, &ctx)
+                .unwrap_or_else(|e| panic!("party 2 sent an {}", e));
+            send(addr, &client, me, &uuid, "round2", &msg).await;
+            keys
+        }
+        _ => {
+            println!("Generating ring-Pedersen parameters over safe primes...");
+            let (party, msg) = PartyTwoKeyGen::new(&ctx);
+            let com: KeyGenCommit =
+                exchange(addr, &client, delay, me, &uuid, "round1", &msg).await?;
+            let msg: KeyGenParty1Msg =
+                receive(addr, &client, delay, me, &uuid, "round2", &()).await?;
+            party
+                .finish(&com, &msg, &ctx)
+                .unwrap_or_else(|e| panic!("party 1 sent an {}", e))
+        }
+    };
+
+    let keys_json: Secret<String> = serde_json::to_string(&keys).unwrap().into();
+    write_secret_file(keysfile_path, keys_json.as_bytes()).expect("Unable to save !");
+    println!("Keys data written to file: {:?}", keysfile_path);
+    Ok(keys.y_sum)
+}
+
+/// Signs `message` with the key at `path`, whose HD tweak is `tweak_sk` and public
+/// key `y_sum`.
+pub async fn sign(
+    addr: &String,
+    keys: &TwoPartyKeys,
+    params: &Params,
+    message: &[u8; DIGEST_SIZE],
+    path: &str,
+    tweak_sk: &Scalar<Secp256k1>,
+    y_sum: &Point<Secp256k1>,
+) -> Result<SignatureRecid<Secp256k1>, Error> {
+    if (
+        params.threshold.as_str(),
+        params.parties.as_str(),
+        params.share_count.as_str(),
+    ) != ("1", "2", "2")
+    {
+        panic!("two-party keys sign with 1/2/2");
+    }
+    let session = open_session(addr, keys.party_id, &keys.y_sum, params, message, path).await?;
+    let SignSession {
+        client,
+        delay,
+        party_num_int: me,
+        uuid,
+        signers_vec,
+        ..
+    } = &session;
+    let me = *me;
+    let m = BigInt::from_bytes(message);
+    // the party with the Paillier key plays party one, whichever signed up first
+    let tag = SessionContext::new(uuid, "lindell17-sign").tag(1);
+    let sig = match signers_vec[usize::from(me) - 1] {
+        0 => {
+            let (mut party, com) = PartyOneSign::new(&tag);
+            let msg: SignParty2Msg1 =
+                exchange(addr, client, *delay, me, uuid, "round1", &com).await?;
+            let msg = party
+                .round2(&msg)
+                .unwrap_or_else(|e| panic!("party 2 sent an {}", e));
+            send(addr, client, me, uuid, "round2", &msg).await;
+            let msg: SignParty2Msg2 =
+                receive(addr, client, *delay, me, uuid, "round3", &keys.ek).await?;
+            let sig = party
+                .finish(keys, &msg, &m, y_sum)
+                .unwrap_or_else(|e| panic!("party 2 sent an {}", e));
+            send(addr, client, me, uuid, "round4", &sig).await;
+            sig
+        }
+        _ => {
+            let (mut party, msg) = PartyTwoSign::new();
+            let com: SignCommit = exchange(addr, client, *delay, me, uuid, "round1", &msg).await?;
+            let msg: SignParty1Msg2 =
+                receive(addr, client, *delay, me, uuid, "round2", &()).await?;
+            let msg = party
+                .round3(keys, &com, &msg, &tag, &m, tweak_sk)
+                .unwrap_or_else(|e| panic!("party 1 sent an {}", e));
+            send(addr, client, me, uuid, "round3", &msg).await;
+            let sig: SignatureRecid<Secp256k1> =
+                receive(addr, client, *delay, me, uuid, "round4", &()).await?;
+            party
+                .finish(&sig, &m, y_sum)
+                .unwrap_or_else(|e| panic!("party 1 sent an {}", e));
+            sig
+        }
+    };
+    Ok(sig)
+}
@@ -0,0 +1,304 @@
+// Encoding of round messages on the manager
+//
+// Messages are JSON unless every party of the session can read a binary
+// encoding. JSON spells out each byte of a big integer as a decimal array element,
+// so Paillier ciphertexts, range proofs and VSS commitments take about 3.5 times
+// their size; CBOR keeps them as byte strings, and points in compressed form.
+//
+// A binary message is its encoding's tag, a colon and the base64 of its bytes,
+// e.g. `cbor/1:omFj...`; JSON never starts that way, so a receiver tells the two
+// apart and reads whatever it is sent. Right after signup a party posts the tags
+// it reads as `{party}-wire-{uuid}`. Once every other party of the session has
+// announced the tag of `--wire`, and not before, its messages use it. A party
+// that sent a round message without announcing is an older client, and the
+// session stays on JSON. Before its first message a party waits a while for the
+// others to sign up and announce, so that the first round, which carries the
+// Paillier keys and their proofs, is binary too.
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::biz_algo::{postb, Entry, Index, PartySignup};
+use crate::util::{deflate, inflate};
+
+/// largest message a peer can make us inflate
+const MAX_INFLATED: usize = 1 << 26;
+/// longest wait for announcements before the first message
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Encoding {
+    Json,
+    Cbor,
+    /// CBOR, deflated
+    CborDeflate,
+}
+
+/// Binary encodings this version reads
+const SUPPORTED: [Encoding; 2] = [Encoding::Cbor, Encoding::CborDeflate];
+
+impl Encoding {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Encoding::Json),
+            "cbor" => Some(Encoding::Cbor),
+            "cbor-deflate" => Some(Encoding::CborDeflate),
+            _ => None,
+        }
+    }
+
+    /// name and version, the prefix of messages
+    fn tag(self) -> &'static str {
+        match self {
+            Encoding::Json => "json",
+            Encoding::Cbor => "cbor/1",
+            Encoding::CborDeflate => "cbor+deflate/1",
+        }
+    }
+}
+
+static PREFERRED: OnceLock<Encoding> = OnceLock::new();
+
+/// Sets the encoding used once all parties of a session read it; CBOR if unset.
+pub fn init(encoding: Encoding) {
+    PREFERRED.set(encoding).expect("wire encoding already set");
+}
+
+fn preferred() -> Encoding {
+    *PREFERRED.get().unwrap_or(&Encoding::Cbor)
+}
+
+struct Session {
+    uuid: String,
+    me: u16,
+    parties: u16,
+    /// whether each peer known about announced the preferred encoding
+    peers: BTreeMap<u16, bool>,
+    settled: bool,
+}
+
+impl Session {
+    fn encoding(&self) -> Encoding {
+        let all = (1..=self.parties)
+            .filter(|i| *i != self.me)
+            .all(|i| self.peers.get(&i) == Some(&true));
+        if all {
+            preferred()
+        } else {
+            Encoding::Json
+        }
+    }
+}
+
+static SESSION: Mutex<Option<Session>> = Mutex::new(None);
+
+fn wire_key(party: u16, uuid: &str) -> String {
+    format!("{}-wire-{}", party, uuid)
+}
+
+/// Announces the encodings this party reads to the session it signed up to.
+pub async fn announce(addr: &String, client: &Client, signup: &PartySignup, parties: u16) {
+    *SESSION.lock().unwrap() = Some(Session {
+        uuid: signup.uuid.clone(),
+        me: signup.number,
+        parties,
+        peers: BTreeMap::new(),
+        settled: false,
+    });
+    let tags = SUPPORTED.map(Encoding::tag);
+    let entry = Entry {
+        key: wire_key(signup.number, &signup.uuid),
+        value: serde_json::to_string(&tags).unwrap(),
+    };
+    postb(addr, client, "set", entry).await;
+}
+
+async fn get(addr: &String, client: &Client, key: String) -> Option<String> {
+    let res_body = postb(addr, client, "get", Index { key }).await;
+    let answer: Result<Entry, ()> = serde_json::from_str(&res_body).ok()?;
+    answer.ok().map(|entry| entry.value)
+}
+
+// parties of session `uuid` other than this one not known about yet, from `among`
+fn unknown(uuid: &str, among: impl Iterator<Item = u16>) -> Option<Vec<u16>> {
+    match &*SESSION.lock().unwrap() {
+        Some(session) if session.uuid == uuid => Some(
+            among
+                .filter(|i| *i != session.me && !session.peers.contains_key(i))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn record(uuid: &str, party: u16, announcement: Option<String>) {
+    let tag = preferred().tag();
+    let reads = announcement
+        .and_then(|tags| serde_json::from_str::<Vec<String>>(&tags).ok())
+        .is_some_and(|tags| tags.iter().any(|t| t == tag));
+    if let Some(session) = SESSION.lock().unwrap().as_mut() {
+        if session.uuid == uuid {
+            session.peers.insert(party, reads);
+        }
+    }
+}
+
+/// Looks up the announcements of `senders`, whose round messages have arrived
+/// in session `uuid`: they posted them before any message if they ever do.
+pub async fn negotiate(addr: &String, client: &Client, uuid: &str, senders: &[u16]) {
+    for i in unknown(uuid, senders.iter().copied()).unwrap_or_default() {
+        record(uuid, i, get(addr, client, wire_key(i, uuid)).await);
+    }
+}
+
+/// Before this party's first message in session `uuid`: waits for each other
+/// party to announce, or to show that it will not by sending its own message of
+/// the same round, which it would find under `sent(party)`. Parties still
+/// unknown after `SETTLE_TIMEOUT` are left to `negotiate`.
+pub async fn settle(
+    addr: &String,
+    client: &Client,
+    uuid: &str,
+    delay: Duration,
+    sent: impl Fn(u16) -> String,
+) {
+    let parties = match &*SESSION.lock().unwrap() {
+        Some(session) if session.uuid == uuid && !session.settled => session.parties,
+        _ => return,
+    };
+    let start = Instant::now();
+    loop {
+        let pending = unknown(uuid, 1..=parties).unwrap_or_default();
+        if pending.is_empty() || start.elapsed() > SETTLE_TIMEOUT {
+            break;
+        }
+        for i in pending {
+            // the message first: an announcement, if any, was posted before it
+            let sent_msg = get(addr, client, sent(i)).await.is_some();
+            let announcement = get(addr, client, wire_key(i, uuid)).await;
+            if announcement.is_some() || sent_msg {
+                record(uuid, i, announcement);
+            }
+        }
+        tokio::time::sleep(delay).await;
+    }
+    if let Some(session) = SESSION.lock().unwrap().as_mut() {
+        session.settled = true;
+    }
+}
+
+/// `value` in the encoding of the current session
+pub fn encode<T: Serialize + ?Sized>(value: &T) -> String {
+    let encoding = SESSION
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map_or(Encoding::Json, Session::encoding);
+    encode_as(value, encoding)
+}
+
+fn encode_as<T: Serialize + ?Sized>(value: &T, encoding: Encoding) -> String {
+    let mut cbor = Vec::new();
+    match encoding {
+        Encoding::Json => return serde_json::to_string(value).unwrap(),
+        Encoding::Cbor | Encoding::CborDeflate => ciborium::into_writer(value, &mut cbor).unwrap(),
+    }
+    if encoding == Encoding::CborDeflate {
+        cbor = deflate(&cbor);
+    }
+    format!("{}:{}", encoding.tag(), BASE64.encode(cbor))
+}
+
+/// A message in any encoding this version reads
+pub fn decode<T: DeserializeOwned>(msg: &str) -> Result<T, String> {
+    let tagged = |tag: &str| tag.starts_with(|c: char| c.is_ascii_lowercase()) && tag.contains('/');
+    let Some((tag, payload)) = msg.split_once(':').filter(|(tag, _)| tagged(tag)) else {
+        return serde_json::from_str(msg).map_err(|e| e.to_string());
+    };
+    let encoding = SUPPORTED
+        .into_iter()
+        .find(|e| e.tag() == tag)
+        .ok_or_else(|| format!("unsupported wire encoding {}", tag))?;
+    let mut bytes = BASE64.decode(payload).map_err(|e| e.to_string())?;
+    if encoding == Encoding::CborDeflate {
+        bytes = inflate(&bytes, MAX_INFLATED).ok_or("invalid deflate stream")?;
+    }
+    ciborium::from_reader(bytes.as_slice()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_ecdsa::party_i::KeyGenBroadcastMessage1;
+    use ciborium::Value;
+    use curv::{arithmetic::Converter, BigInt};
+    use paillier::EncryptionKey;
+    use zk_paillier::zkproofs::NiCorrectKeyProof;
+
+    fn message() -> KeyGenBroadcastMessage1 {
+        KeyGenBroadcastMessage1 {
+            e: EncryptionKey::from(&BigInt::from(3233)),
+            com: BigInt::from(0x1234),
+            correct_key_proof: NiCorrectKeyProof {
+                sigma_vec: vec![BigInt::from(1000), BigInt::from(65537)],
+            },
+        }
+    }
+
+    #[test]
+    fn round_trips_each_encoding() {
+        let json = serde_json::to_string(&message()).unwrap();
+        for encoding in [Encoding::Json, Encoding::Cbor, Encoding::CborDeflate] {
+            let msg = encode_as(&message(), encoding);
+            assert_eq!(msg.starts_with(encoding.tag()), encoding != Encoding::Json);
+            let decoded: KeyGenBroadcastMessage1 = decode(&msg).unwrap();
+            assert_eq!(decoded.e, message().e);
+            assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn json_keeps_paillier_decimal_strings() {
+        assert_eq!(
+            serde_json::to_string(&message()).unwrap(),
+            r#"{"e":{"n":"3233"},"com":[18,52],"correct_key_proof":{"sigma_vec":["1000","65537"]}}"#
+        );
+    }
+
+    #[test]
+    fn cbor_writes_big_integers_as_bytes() {
+        let msg = encode_as(&message(), Encoding::Cbor);
+        let (_, payload) = msg.split_once(':').unwrap();
+        let value: Value =
+            ciborium::from_reader(BASE64.decode(payload).unwrap().as_slice()).unwrap();
+        let field = |v: &Value, name: &str| {
+            v.as_map()
+                .unwrap()
+                .iter()
+                .find(|(k, _)| k.as_text() == Some(name))
+                .map(|(_, v)| v.clone())
+                .unwrap()
+        };
+        let bytes = |x: u64| Value::Bytes(BigInt::from(x).to_bytes());
+        assert_eq!(field(&field(&value, "e"), "n"), bytes(3233));
+        assert_eq!(field(&value, "com"), bytes(0x1234));
+        assert_eq!(
+            field(&field(&value, "correct_key_proof"), "sigma_vec"),
+            Value::Array(vec![bytes(1000), bytes(65537)])
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_encoding() {
+        let msg = encode_as(&message(), Encoding::Cbor).replacen("cbor/1", "cbor/2", 1);
+        assert!(decode::<KeyGenBroadcastMessage1>(&msg)
+            .unwrap_err()
+            .contains("unsupported wire encoding cbor/2"));
+    }
+}
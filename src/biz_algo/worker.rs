@@ -0,0 +1,34 @@
+// Worker pool for the per-peer computations of a party
+//
+// MtA replies, Paillier decryptions and VSS / dlog / correct-key checks are done
+// once per peer and do not depend on each other. They run on rayon's global pool,
+// one thread per core unless `--threads` caps it. The async runtime keeps polling
+// the manager meanwhile: the tokio worker that starts a computation hands its
+// other tasks to another worker first.
+use rayon::{prelude::*, ThreadPoolBuilder};
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// Caps the worker pool at `threads`; must run before the first computation.
+pub fn init(threads: usize) {
+    ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("worker-{}", i))
+        .build_global()
+        .expect("worker pool already started");
+}
+
+/// Runs `f`, which may fan out on the worker pool, without blocking the async
+/// runtime.
+pub fn compute<T>(f: impl FnOnce() -> T) -> T {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
+/// `f` on each of `items` on the worker pool, in order
+pub fn par_map<T: Send, U: Send>(items: Vec<T>, f: impl Fn(T) -> U + Sync + Send) -> Vec<U> {
+    compute(|| items.into_par_iter().map(f).collect())
+}
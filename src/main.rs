@@ -2,25 +2,85 @@
 #![allow(dead_code)]
 mod biz_algo;
 mod exception;
+mod mp_cggmp;
 mod mp_ecdsa;
 mod mp_ecdsa2;
+mod mp_lindell17;
 mod util;
 
 use bip32::ChainCode;
-use clap::{Arg, ArgMatches, Command};
-use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use clap::{Arg, ArgAction, ArgGroup, ArgMatches, Command};
+use curv::{
+    arithmetic::Converter,
+    elliptic::curves::{secp256_k1::Secp256k1, Curve, Ed25519, Point, Scalar, Secp256r1},
+    BigInt,
+};
 use paillier::*;
+use serde::de::DeserializeOwned;
 use tokio::fs;
 
 use crate::util::*;
-use biz_algo::{hd::get_hd_key, keygen, manager, reshare, sign, Params};
-use mp_ecdsa::{feldman_vss::VerifiableSS, party_i::*};
+use biz_algo::{
+    account::{self, AccountOrigin},
+    btc::{self, AddressType, Bip322},
+    cggmp,
+    eddsa::{self, EddsaKey, EddsaKeys, EddsaSignature},
+    eip712::TypedData,
+    eth::{self, EthPayload, UnsignedTx},
+    export::AccountExport,
+    hd::get_hd_key,
+    keygen, manager,
+    message::{read_message, HashMode, MessageSource, DIGEST_SIZE},
+    ot, pool, presign, psbt, reshare,
+    schnorr::{self, SchnorrKey, SchnorrSignature},
+    sign,
+    sign::SignRequest,
+    signature::{SignOutput, SignatureFormat},
+    two_party, wire, worker, write_secret_file, MnemonicOutput, Params,
+};
+use mp_cggmp::aux::{AuxInfo, PROTOCOL as CGGMP21};
+use mp_ecdsa::{
+    feldman_vss::VerifiableSS,
+    mta::ot::{OtSetup, MTA as OT},
+    party_i::*,
+};
+use mp_lindell17::{TwoPartyKeys, PROTOCOL as LINDELL17};
 
 #[tokio::main]
 async fn main() {
     let args = argparse();
-    init_sampler().await;
-    println!("Sampler initialized.");
+    let threads = match args.subcommand() {
+        Some((_, sub_matches)) => sub_matches.get_one::<u16>("threads"),
+        None => args.get_one::<u16>("threads"),
+    };
+    if let Some(threads) = threads {
+        worker::init(usize::from(*threads));
+    }
+    let wire_name = match args.subcommand() {
+        Some((_, sub_matches)) => sub_matches.get_one::<String>("wire"),
+        None => args.get_one::<String>("wire"),
+    };
+    if let Some(name) = wire_name {
+        wire::init(wire::Encoding::from_name(name).unwrap());
+    }
+    let pool_dir = match args.subcommand() {
+        Some((_, sub_matches)) => sub_matches.get_one::<String>("pool"),
+        None => args.get_one::<String>("pool"),
+    };
+    if let Some(dir) = pool_dir {
+        if let Err(e) = pool::init(dir) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+    // verify, recover and the address commands run offline
+    if !matches!(
+        args.subcommand_name(),
+        Some("verify" | "recover" | "eth-address" | "btc-address" | "export" | "pool")
+    ) {
+        init_sampler().await;
+        println!("Sampler initialized.");
+    }
 
     match args.subcommand() {
         Some(("manager", _matches)) => {
@@ -43,89 +103,573 @@ async fn main() {
                 .unwrap_or("")
                 .split("/")
                 .collect();
-            keygen::run_keygen(&addr, &keysfile_path, &params)
-                .await
-                .unwrap();
+            let mnemonic_out = mnemonic_output(sub_matches);
+            let protocol = sub_matches.get_one::<String>("protocol").unwrap();
+            let ot_mta = sub_matches.get_one::<String>("mta").unwrap() == OT;
+            if ot_mta && protocol != "gg18" {
+                panic!(
+                    "{} keys run their own MtA, --mta {} is for gg18",
+                    protocol, OT
+                );
+            }
+            // CGGMP21 keygen is GG18 keygen followed by a refresh of all key shares
+            let refresh_params = Params {
+                threshold: params[0].to_string(),
+                parties: params[1].to_string(),
+                share_count: params[1].to_string(),
+            };
+            match sub_matches.get_one::<String>("curve").unwrap().as_str() {
+                "ed25519" if protocol != "gg18" => {
+                    panic!("{} keys are ECDSA keys, not ed25519", protocol)
+                }
+                "p256" if protocol == LINDELL17 => {
+                    panic!("{} keys are secp256k1 keys", LINDELL17)
+                }
+                _ if protocol == LINDELL17 => {
+                    if !matches!(mnemonic_out, MnemonicOutput::Skip) {
+                        panic!("{} keys have no mnemonic", LINDELL17);
+                    }
+                    let y_sum = two_party::keygen(&addr, &keysfile_path, &params)
+                        .await
+                        .unwrap();
+                    println!("Public key: {}", hex::encode(&*y_sum.to_bytes(true)));
+                }
+                "ed25519" if ot_mta => panic!("ed25519 keys sign without MtA"),
+                "ed25519" => {
+                    keygen::run_keygen::<Ed25519, EddsaKeys>(
+                        &addr,
+                        &keysfile_path,
+                        &params,
+                        &mnemonic_out,
+                    )
+                    .await
+                    .unwrap();
+                    let (y_sum, _) = load_public_key::<Ed25519>(&keysfile_path).await;
+                    println!("Public key: {}", hex::encode(&*y_sum.to_bytes(true)));
+                }
+                "p256" => {
+                    keygen::run_keygen::<Secp256r1, Keys<_>>(
+                        &addr,
+                        &keysfile_path,
+                        &params,
+                        &mnemonic_out,
+                    )
+                    .await
+                    .unwrap();
+                    if protocol == CGGMP21 {
+                        cggmp::refresh::<Secp256r1>(&addr, &keysfile_path, &refresh_params)
+                            .await
+                            .unwrap();
+                    }
+                    if ot_mta {
+                        ot::setup::<Secp256r1>(&addr, &keysfile_path, &refresh_params)
+                            .await
+                            .unwrap();
+                    }
+                }
+                _ => {
+                    keygen::run_keygen::<Secp256k1, Keys<_>>(
+                        &addr,
+                        &keysfile_path,
+                        &params,
+                        &mnemonic_out,
+                    )
+                    .await
+                    .unwrap();
+                    if protocol == CGGMP21 {
+                        cggmp::refresh::<Secp256k1>(&addr, &keysfile_path, &refresh_params)
+                            .await
+                            .unwrap();
+                    }
+                    if ot_mta {
+                        ot::setup::<Secp256k1>(&addr, &keysfile_path, &refresh_params)
+                            .await
+                            .unwrap();
+                    }
+                }
+            }
         }
         Some(("sign", sub_matches)) => {
             let keysfile_path = sub_matches
                 .get_one::<String>("keysfile")
                 .map(|s| s.as_str())
                 .unwrap_or("");
+            if let Some(keys) = load_two_party_keys(keysfile_path).await {
+                sign_two_party(sub_matches, keys).await;
+                return;
+            }
+            match keys_curve(keysfile_path).await.as_str() {
+                Secp256r1::CURVE_NAME => {
+                    sign_ecdsa::<Secp256r1>(sub_matches, keysfile_path, |path, _, _| {
+                        panic!("{}: HD derivation is only defined on secp256k1", path)
+                    })
+                    .await
+                }
+                _ => sign_ecdsa::<Secp256k1>(sub_matches, keysfile_path, call_hd_key).await,
+            }
+        }
+        Some(("presign", sub_matches)) => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            match keys_curve(keysfile_path).await.as_str() {
+                Secp256r1::CURVE_NAME => {
+                    presign_ecdsa::<Secp256r1>(sub_matches, keysfile_path).await
+                }
+                _ => presign_ecdsa::<Secp256k1>(sub_matches, keysfile_path).await,
+            }
+        }
+        Some(("schnorr-sign", sub_matches)) => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            let (_, shared_keys, party_id, vss_scheme_vec, _, y_sum, chain_code) =
+                load_keys(keysfile_path).await;
 
-            // Read data from keys file
-            let data = fs::read_to_string(keysfile_path).await.expect(
-                format!("Unable to load keys file at location: {}", keysfile_path).as_str(),
-            );
+            let path = sub_matches
+                .get_one::<String>("path")
+                .map(|s| s.as_str())
+                .unwrap_or("");
+            let root_y_sum = y_sum.clone();
+            let (tweak_sk, y_sum) = match path.is_empty() {
+                true => (Scalar::<Secp256k1>::zero(), y_sum),
+                false => call_hd_key(path, y_sum, chain_code),
+            };
+            let key = SchnorrKey::new(&y_sum, &tweak_sk, sub_matches.get_flag("taproot"));
+
+            let message = message_digest(sub_matches);
+            let manager_addr = sub_matches
+                .get_one::<String>("manager_addr")
+                .map(|s| s.as_str())
+                .unwrap_or("http://127.0.0.1:8000")
+                .to_string();
+            let params = threshold_params(sub_matches);
+            let sig = schnorr::sign(
+                &manager_addr,
+                &shared_keys,
+                party_id,
+                &vss_scheme_vec,
+                &root_y_sum,
+                &params,
+                &message,
+                path,
+                &key,
+            )
+            .await
+            .unwrap();
+            println!("Public key: {}", hex::encode(key.x_only()));
+            println!("Signature: {}", hex::encode(sig.to_bytes()));
+            if let Some(out) = sub_matches.get_one::<String>("out") {
+                let output = SignOutput {
+                    format: "schnorr".to_string(),
+                    signature: serde_json::Value::String(hex::encode(sig.to_bytes())),
+                    digest: hex::encode(message),
+                    path: path.to_string(),
+                    pubkey: hex::encode(key.x_only()),
+                };
+                fs::write(out, serde_json::to_string_pretty(&output).unwrap())
+                    .await
+                    .expect("Unable to save !");
+                println!("Signature written to file: {:?}", out);
+            }
+        }
+        Some(("eddsa-sign", sub_matches)) => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            let (_, shared_keys, party_id, vss_scheme_vec, _, y_sum, chain_code): eddsa::KeysFile =
+                load_keys_as::<Ed25519, _>(keysfile_path).await;
+
+            let path = sub_matches
+                .get_one::<String>("path")
+                .map(|s| s.as_str())
+                .unwrap_or("");
+            let key = EddsaKey::new(&y_sum, chain_code, path).unwrap();
+
+            let message = message_bytes(sub_matches);
+            let manager_addr = sub_matches
+                .get_one::<String>("manager_addr")
+                .map(|s| s.as_str())
+                .unwrap_or("http://127.0.0.1:8000")
+                .to_string();
+            let params = threshold_params(sub_matches);
+            let sig = eddsa::sign(
+                &manager_addr,
+                &shared_keys,
+                party_id,
+                &vss_scheme_vec,
+                &y_sum,
+                &params,
+                &message,
+                path,
+                &key,
+            )
+            .await
+            .unwrap();
+            println!("Public key: {}", hex::encode(key.to_bytes()));
+            println!("Signature: {}", hex::encode(sig.to_bytes()));
+            if let Some(out) = sub_matches.get_one::<String>("out") {
+                let output = SignOutput {
+                    format: "ed25519".to_string(),
+                    signature: serde_json::Value::String(hex::encode(sig.to_bytes())),
+                    digest: hex::encode(&message),
+                    path: path.to_string(),
+                    pubkey: hex::encode(key.to_bytes()),
+                };
+                fs::write(out, serde_json::to_string_pretty(&output).unwrap())
+                    .await
+                    .expect("Unable to save !");
+                println!("Signature written to file: {:?}", out);
+            }
+        }
+        Some(("sign-psbt", sub_matches)) => {
+            let keysfile_path = sub_matches
+                .get_one::<String>("keysfile")
+                .map(|s| s.as_str())
+                .unwrap_or("");
+
+            let (
+                party_keys,
+                shared_keys,
+                party_id,
+                vss_scheme_vec,
+                paillier_key_vec,
+                y_sum,
+                chain_code,
+            ) = load_keys(keysfile_path).await;
+            let ot_setup = load_ot_setup(keysfile_path, party_id, vss_scheme_vec.len()).await;
+
+            let psbt_path = sub_matches.get_one::<String>("psbt").unwrap();
+            let psbt_data = read_message(&MessageSource::File(psbt_path.to_string())).unwrap();
+            let mut psbt = psbt::read_psbt(&psbt_data).unwrap();
+            let inputs = psbt::prepare(&psbt, &y_sum, chain_code).unwrap();
+            if inputs.is_empty() {
+                panic!(
+                    "no input of the PSBT derives from key fingerprint {}",
+                    psbt::fingerprint(&y_sum)
+                );
+            }
+            for input in &inputs {
+                println!(
+                    "input {}: path {:?}, sighash {}",
+                    input.index,
+                    input.request.path,
+                    hex::encode(input.request.message)
+                );
+            }
+
+            let manager_addr = sub_matches
+                .get_one::<String>("manager_addr")
+                .map(|s| s.as_str())
+                .unwrap_or("http://127.0.0.1:8000")
+                .to_string();
+
+            let params = threshold_params(sub_matches);
+            let requests = inputs
+                .iter()
+                .map(|input| input.request.clone())
+                .collect::<Vec<_>>();
+            let sigs = sign::sign_batch(
+                &manager_addr,
+                &party_keys,
+                &shared_keys,
+                party_id,
+                &vss_scheme_vec,
+                &paillier_key_vec,
+                mta(sub_matches, keysfile_path, &ot_setup),
+                &y_sum,
+                &params,
+                &requests,
+            )
+            .await
+            .unwrap();
+            psbt::insert_signatures(&mut psbt, &inputs, &sigs).unwrap();
+            match sub_matches.get_one::<String>("out") {
+                Some(out) => {
+                    fs::write(out, psbt.to_string())
+                        .await
+                        .expect("Unable to save !");
+                    println!("PSBT written to file: {:?}", out);
+                }
+                None => println!("PSBT: {}", psbt),
+            }
+        }
+        Some(("eth-address", sub_matches)) => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            let (y_sum, chain_code) = load_public_key(keysfile_path).await;
+            let path = sub_matches
+                .get_one::<String>("path")
+                .map(|s| s.as_str())
+                .unwrap_or("");
+            let y_sum = match path.is_empty() {
+                true => y_sum,
+                false => call_hd_key(path, y_sum, chain_code).1,
+            };
+            println!("{}", eth::address(&y_sum));
+        }
+        Some(("eth-sign", sub_matches)) => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
             let (
                 party_keys,
                 shared_keys,
                 party_id,
-                mut vss_scheme_vec,
+                vss_scheme_vec,
                 paillier_key_vec,
                 y_sum,
                 chain_code,
-            ): (
-                Keys,
-                SharedKeys,
-                u16,
-                Vec<VerifiableSS<Secp256k1>>,
-                Vec<EncryptionKey>,
-                Point<Secp256k1>,
-                ChainCode,
-            ) = serde_json::from_str(&data).unwrap();
-
-            // Get root pub key or HD non-hardened pub key at specified path
+            ) = load_keys(keysfile_path).await;
+            let ot_setup = load_ot_setup(keysfile_path, party_id, vss_scheme_vec.len()).await;
+
             let path = sub_matches
                 .get_one::<String>("path")
                 .map(|s| s.as_str())
                 .unwrap_or("");
+            let root_y_sum = y_sum.clone();
             let (tweak_sk, y_sum) = match path.is_empty() {
                 true => (Scalar::<Secp256k1>::zero(), y_sum),
                 false => call_hd_key(path, y_sum, chain_code),
             };
 
-            // Parse message to sign
-            let message_str = sub_matches
-                .get_one::<String>("message")
-                .map(|s| s.as_str())
-                .unwrap_or("");
-            let message = match hex::decode(message_str) {
-                Ok(x) => x,
-                Err(_e) => message_str.as_bytes().to_vec(),
+            let source = match sub_matches.get_one::<String>("data_file") {
+                Some(file) => MessageSource::File(file.to_string()),
+                None => {
+                    MessageSource::Hex(sub_matches.get_one::<String>("data").unwrap().to_string())
+                }
+            };
+            let data = read_message(&source).unwrap();
+            let kind: EthPayload = sub_matches
+                .get_one::<String>("kind")
+                .unwrap()
+                .parse()
+                .unwrap();
+            // show what is signed so that every party can review it
+            let tx = match kind {
+                EthPayload::Tx => Some(UnsignedTx::decode(&data).unwrap()),
+                _ => None,
+            };
+            let message = match kind {
+                EthPayload::Tx => {
+                    let tx = tx.as_ref().unwrap();
+                    println!("Transaction: {}", tx);
+                    tx.signing_hash()
+                }
+                EthPayload::Personal => {
+                    println!("Message: 0x{}", hex::encode(&data));
+                    eth::personal_message_hash(&data)
+                }
+                EthPayload::TypedData => {
+                    let typed = TypedData::from_json(&data).unwrap();
+                    println!("{}", typed.summary().unwrap());
+                    typed.digest().unwrap()
+                }
             };
-            let message = &message[..];
+            println!("Signer: {}", eth::address(&y_sum));
+
             let manager_addr = sub_matches
                 .get_one::<String>("manager_addr")
                 .map(|s| s.as_str())
                 .unwrap_or("http://127.0.0.1:8000")
                 .to_string();
+            let params = threshold_params(sub_matches);
+            let sig = sign::sign(
+                &manager_addr,
+                party_keys,
+                shared_keys,
+                party_id,
+                &vss_scheme_vec,
+                paillier_key_vec,
+                mta(sub_matches, keysfile_path, &ot_setup),
+                &root_y_sum,
+                &y_sum,
+                &params,
+                &message,
+                path,
+                &tweak_sk,
+            )
+            .await
+            .unwrap();
+            let output = match &tx {
+                Some(tx) => {
+                    let raw = format!("0x{}", hex::encode(tx.encode_signed(&sig).unwrap()));
+                    println!("Signed transaction: {}", raw);
+                    raw
+                }
+                None => {
+                    let sig = format!("0x{}", hex::encode(sig.to_recoverable()));
+                    println!("Signature: {}", sig);
+                    sig
+                }
+            };
+            if let Some(out) = sub_matches.get_one::<String>("out") {
+                fs::write(out, output).await.expect("Unable to save !");
+                println!("Written to file: {:?}", out);
+            }
+        }
+        Some(("btc-address", sub_matches)) => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            let (y_sum, chain_code) = load_public_key(keysfile_path).await;
+            let path = sub_matches
+                .get_one::<String>("path")
+                .map(|s| s.as_str())
+                .unwrap_or("");
+            let y_sum = match path.is_empty() {
+                true => y_sum,
+                false => call_hd_key(path, y_sum, chain_code).1,
+            };
+            let (address_type, network) = btc_address_type(sub_matches, path);
+            println!("{}", btc::address(&y_sum, address_type, network));
+        }
+        Some(("btc-sign-message", sub_matches)) => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            let (
+                party_keys,
+                shared_keys,
+                party_id,
+                vss_scheme_vec,
+                paillier_key_vec,
+                y_sum,
+                chain_code,
+            ) = load_keys(keysfile_path).await;
+            let ot_setup = load_ot_setup(keysfile_path, party_id, vss_scheme_vec.len()).await;
 
-            // Parse threshold params
-            let params: Vec<&str> = sub_matches
-                .get_one::<String>("params")
+            let path = sub_matches
+                .get_one::<String>("path")
                 .map(|s| s.as_str())
-                .unwrap_or("")
-                .split("/")
-                .collect();
-            let params = Params {
-                threshold: params[0].to_string(),
-                parties: params[1].to_string(),
-                share_count: params[2].to_string(),
+                .unwrap_or("");
+            let root_y_sum = y_sum.clone();
+            let (tweak_sk, y_sum) = match path.is_empty() {
+                true => (Scalar::<Secp256k1>::zero(), y_sum),
+                false => call_hd_key(path, y_sum, chain_code),
+            };
+            let (address_type, network) = btc_address_type(sub_matches, path);
+            let address = btc::address(&y_sum, address_type, network);
+            println!("Address: {}", address);
+
+            let source = match sub_matches.get_one::<String>("message_file") {
+                Some(file) => MessageSource::File(file.to_string()),
+                None => MessageSource::Hex(
+                    sub_matches
+                        .get_one::<String>("message")
+                        .unwrap()
+                        .to_string(),
+                ),
             };
-            sign::sign(
+            let data = read_message(&source).unwrap();
+            let bip322 = match sub_matches.get_flag("bip322") {
+                true => Some(Bip322::new(&data, &address).unwrap()),
+                false => None,
+            };
+            let message = match &bip322 {
+                Some(bip322) => bip322.sighash(),
+                None => btc::signed_message_hash(&data),
+            };
+
+            let manager_addr = sub_matches
+                .get_one::<String>("manager_addr")
+                .map(|s| s.as_str())
+                .unwrap_or("http://127.0.0.1:8000")
+                .to_string();
+            let params = threshold_params(sub_matches);
+            let sig = sign::sign(
                 &manager_addr,
                 party_keys,
                 shared_keys,
                 party_id,
-                &mut vss_scheme_vec,
+                &vss_scheme_vec,
                 paillier_key_vec,
+                mta(sub_matches, keysfile_path, &ot_setup),
+                &root_y_sum,
                 &y_sum,
                 &params,
                 &message,
+                path,
                 &tweak_sk,
             )
             .await
+            .unwrap();
+            let output = match bip322 {
+                Some(_) => Bip322::simple_signature(&sig, &y_sum),
+                None => btc::bip137_signature(&sig, address_type),
+            };
+            println!("Signature: {}", output);
+            if let Some(out) = sub_matches.get_one::<String>("out") {
+                fs::write(out, output).await.expect("Unable to save !");
+                println!("Signature written to file: {:?}", out);
+            }
+        }
+        Some(("derive-account", sub_matches)) => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            let (
+                party_keys,
+                shared_keys,
+                party_id,
+                vss_scheme_vec,
+                paillier_key_vec,
+                y_sum,
+                chain_code,
+            ) = load_keys(keysfile_path).await;
+            let ot_setup = load_ot_setup(keysfile_path, party_id, vss_scheme_vec.len()).await;
+            let parent = account::account_of(&fs::read_to_string(keysfile_path).await.unwrap());
+            let path = sub_matches.get_one::<String>("path").unwrap();
+            let out = sub_matches.get_one::<String>("out").unwrap();
+            let manager_addr = sub_matches
+                .get_one::<String>("manager_addr")
+                .map(|s| s.as_str())
+                .unwrap_or("http://127.0.0.1:8000")
+                .to_string();
+            let params = threshold_params(sub_matches);
+
+            let account = account::derive_account(
+                &manager_addr,
+                &shared_keys,
+                party_id,
+                &vss_scheme_vec,
+                &y_sum,
+                chain_code,
+                &params,
+                path,
+            )
+            .await
+            .unwrap();
+            // the base OTs do not depend on the key shares
+            let keys_json = ot::keys_file_json(
+                &(
+                    party_keys,
+                    account.shared_keys,
+                    party_id,
+                    account.vss_scheme_vec,
+                    paillier_key_vec,
+                    &account.y_sum,
+                    account.chain_code,
+                ),
+                None,
+                ot_setup.as_ref(),
+                Some(&AccountOrigin::new(parent.as_ref(), path)),
+            );
+            write_secret_file(out, keys_json.as_bytes()).expect("Unable to save !");
+            println!(
+                "Account public key: {}",
+                hex::encode(&*account.y_sum.to_bytes(true))
+            );
+            println!("Account keys written to file: {:?}", out);
+        }
+        Some(("export", sub_matches)) => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            let (y_sum, chain_code) = load_public_key(keysfile_path).await;
+            let data = fs::read_to_string(keysfile_path).await.unwrap();
+            let path = sub_matches.get_one::<String>("path").unwrap();
+            let (address_type, network) = btc_address_type(sub_matches, path);
+            let export = AccountExport::new(
+                &y_sum,
+                chain_code,
+                path,
+                address_type,
+                network,
+                account::account_of(&data).as_ref(),
+            )
+            .unwrap();
+            let json = serde_json::to_string_pretty(&export).unwrap();
+            match sub_matches.get_one::<String>("out") {
+                Some(out) => {
+                    fs::write(out, json).await.expect("Unable to save !");
+                    println!("Export written to file: {:?}", out);
+                }
+                None => println!("{}", json),
+            }
         }
         Some(("reshare", sub_matches)) => {
             let keysfile_path = sub_matches
@@ -138,18 +682,7 @@ async fn main() {
                 .map(|s| s.as_str())
                 .unwrap_or("http://127.0.0.1:8000")
                 .to_string();
-            // Parse threshold params
-            let params: Vec<&str> = sub_matches
-                .get_one::<String>("params")
-                .map(|s| s.as_str())
-                .unwrap_or("")
-                .split("/")
-                .collect();
-            let params = Params {
-                threshold: params[0].to_string(),
-                parties: params[1].to_string(),
-                share_count: params[2].to_string(),
-            };
+            let params = threshold_params(sub_matches);
             let if_give_str = sub_matches
                 .get_one::<String>("give")
                 .map(|s| s.as_str())
@@ -168,6 +701,7 @@ async fn main() {
                 .unwrap_or("")
                 .to_string();
             let if_receive: bool = if_receive_str == "t" || if_receive_str == "T";
+            let mnemonic_out = mnemonic_output(sub_matches);
             reshare::reshare_all_xi(
                 manager_addr,
                 &params,
@@ -175,45 +709,830 @@ async fn main() {
                 if_give,
                 if_hold,
                 if_receive,
+                &mnemonic_out,
             )
             .await
+            .unwrap();
+        }
+        Some(("refresh", sub_matches)) => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            let manager_addr = sub_matches
+                .get_one::<String>("manager_addr")
+                .map(|s| s.as_str())
+                .unwrap_or("http://127.0.0.1:8000")
+                .to_string();
+            let params: Vec<&str> = sub_matches
+                .get_one::<String>("params")
+                .unwrap()
+                .split("/")
+                .collect();
+            let params = Params {
+                threshold: params[0].to_string(),
+                parties: params[1].to_string(),
+                share_count: params[1].to_string(),
+            };
+            match keys_curve(keysfile_path).await.as_str() {
+                Secp256r1::CURVE_NAME => {
+                    cggmp::refresh::<Secp256r1>(&manager_addr, keysfile_path, &params).await
+                }
+                _ => cggmp::refresh::<Secp256k1>(&manager_addr, keysfile_path, &params).await,
+            }
+            .unwrap();
+        }
+        Some(("pool", sub_matches)) => {
+            let keysfiles = sub_matches
+                .get_many::<String>("keysfile")
+                .map(|files| files.cloned().collect::<Vec<String>>())
+                .unwrap_or_default();
+            let targets = pool::Targets {
+                keypairs: *sub_matches.get_one::<usize>("keys").unwrap(),
+                safe_keypairs: *sub_matches.get_one::<usize>("safe_keys").unwrap(),
+                randomness: *sub_matches.get_one::<usize>("randomness").unwrap(),
+            };
+            pool::run(&keysfiles, &targets, sub_matches.get_flag("once"))
+                .await
+                .unwrap();
+        }
+        Some(("ot-setup", sub_matches)) => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            let manager_addr = sub_matches
+                .get_one::<String>("manager_addr")
+                .map(|s| s.as_str())
+                .unwrap_or("http://127.0.0.1:8000")
+                .to_string();
+            let params: Vec<&str> = sub_matches
+                .get_one::<String>("params")
+                .unwrap()
+                .split("/")
+                .collect();
+            let params = Params {
+                threshold: params[0].to_string(),
+                parties: params[1].to_string(),
+                share_count: params[1].to_string(),
+            };
+            match keys_curve(keysfile_path).await.as_str() {
+                Secp256r1::CURVE_NAME => {
+                    ot::setup::<Secp256r1>(&manager_addr, keysfile_path, &params).await
+                }
+                Secp256k1::CURVE_NAME => {
+                    ot::setup::<Secp256k1>(&manager_addr, keysfile_path, &params).await
+                }
+                curve => panic!("{} keys sign without MtA", curve),
+            }
+            .unwrap();
+        }
+        Some(("verify", sub_matches)) => {
+            if sub_matches.get_one::<String>("format").unwrap() == "ed25519" {
+                verify_eddsa(sub_matches).await;
+                return;
+            }
+            let message = message_digest(sub_matches);
+            if sub_matches.get_one::<String>("format").unwrap() == "schnorr" {
+                verify_schnorr(sub_matches, &message).await;
+                return;
+            }
+            // the curve of a keys file is the one it records
+            let curve = match sub_matches.get_one::<String>("keysfile") {
+                Some(keysfile_path) => keys_curve(keysfile_path).await,
+                None => curve_name(sub_matches).to_string(),
+            };
+            match curve.as_str() {
+                Secp256r1::CURVE_NAME => {
+                    verify_ecdsa::<Secp256r1>(sub_matches, &message, |path, _, _| {
+                        panic!("{}: HD derivation is only defined on secp256k1", path)
+                    })
+                    .await
+                }
+                _ => verify_ecdsa::<Secp256k1>(sub_matches, &message, call_hd_key).await,
+            }
+        }
+        Some(("recover", sub_matches)) => {
+            let format: SignatureFormat = sub_matches
+                .get_one::<String>("format")
+                .unwrap()
+                .parse()
+                .unwrap();
+            let signature = sub_matches.get_one::<String>("signature").unwrap();
+            let message = BigInt::from_bytes(&message_digest(sub_matches));
+            let pubkey = match curve_name(sub_matches) {
+                Secp256r1::CURVE_NAME => {
+                    recover(&format.decode::<Secp256r1>(signature).unwrap(), &message)
+                        .expect("unable to recover")
+                        .to_bytes(true)
+                        .to_vec()
+                }
+                _ => recover(&format.decode::<Secp256k1>(signature).unwrap(), &message)
+                    .expect("unable to recover")
+                    .to_bytes(true)
+                    .to_vec(),
+            };
+            println!("{}", hex::encode(pubkey));
         }
         _ => {}
     };
 }
 
-fn call_hd_key(
-    path_str: &str,
-    par_pk: Point<Secp256k1>,
-    chain_code: ChainCode,
-) -> (Scalar<Secp256k1>, Point<Secp256k1>) {
-    let (tweak_sk, child_pk) =
-        if let Ok((tweak_sk, child_pk)) = get_hd_key(path_str, par_pk, chain_code) {
-            (tweak_sk, child_pk)
-        } else {
-            todo!()
-        };
-    (tweak_sk, child_pk.clone())
-}
+// keys file written by keygen or reshare
+type KeysFile<E = Secp256k1> = (
+    Keys<E>,
+    SharedKeys<E>,
+    u16,
+    Vec<VerifiableSS<E>>,
+    Vec<EncryptionKey>,
+    Point<E>,
+    ChainCode,
+);
 
-fn argparse() -> ArgMatches {
-    Command::new("MPC_HD_GG18")
-        .version("0.1.0")
-        .author("TAIYI TECH")
-        .subcommand_required(true)
-        .arg_required_else_help(true)
-        .subcommands(vec![
-            Command::new("manager").about("Run state manager"),
-            Command::new("keygen")
-                .about("Run keygen")
-                .arg(
-                    Arg::new("keysfile")
-                        .index(1)
-                        .required(true)
-                        .num_args(1)
-                        .help("Target keys file"),
+// GG18 sign with a key share on curve `E`; `derive` gives the HD tweak and child key at a path
+async fn sign_ecdsa<E: Curve>(
+    sub_matches: &ArgMatches,
+    keysfile_path: &str,
+    derive: impl Fn(&str, Point<E>, ChainCode) -> (Scalar<E>, Point<E>),
+) {
+    let (party_keys, shared_keys, party_id, vss_scheme_vec, paillier_key_vec, y_sum, chain_code) =
+        load_keys(keysfile_path).await;
+    let ot_setup = load_ot_setup(keysfile_path, party_id, vss_scheme_vec.len()).await;
+
+    // Get root pub key or HD non-hardened pub key at specified path
+    let path = sub_matches
+        .get_one::<String>("path")
+        .map(|s| s.as_str())
+        .unwrap_or("");
+    let root_y_sum = y_sum.clone();
+    let (tweak_sk, y_sum) = match path.is_empty() {
+        true => (Scalar::<E>::zero(), y_sum),
+        false => derive(path, y_sum, chain_code),
+    };
+
+    let message = message_digest(sub_matches);
+    let manager_addr = sub_matches
+        .get_one::<String>("manager_addr")
+        .map(|s| s.as_str())
+        .unwrap_or("http://127.0.0.1:8000")
+        .to_string();
+
+    let params = threshold_params(sub_matches);
+    let aux = load_aux(keysfile_path).await;
+    let sig = match sub_matches.get_one::<String>("presig") {
+        Some(presig_path) => {
+            let presig = presign::take(presig_path, &shared_keys).unwrap();
+            presign::sign(
+                &manager_addr,
+                party_id,
+                &root_y_sum,
+                &params,
+                &message,
+                path,
+                &tweak_sk,
+                &y_sum,
+                presig,
+            )
+            .await
+            .unwrap()
+        }
+        None => match protocol(sub_matches, keysfile_path, &aux) {
+            Some(aux) => {
+                reject_mta(sub_matches);
+                let request = SignRequest {
+                    message,
+                    path: path.to_string(),
+                    tweak_sk: tweak_sk.clone(),
+                    y_sum: y_sum.clone(),
+                };
+                cggmp::sign_batch(
+                    &manager_addr,
+                    &party_keys,
+                    &shared_keys,
+                    party_id,
+                    &vss_scheme_vec,
+                    &paillier_key_vec,
+                    aux,
+                    &root_y_sum,
+                    &params,
+                    &[request],
                 )
-                .arg(
+                .await
+                .unwrap()
+                .remove(0)
+            }
+            None => sign::sign(
+                &manager_addr,
+                party_keys,
+                shared_keys,
+                party_id,
+                &vss_scheme_vec,
+                paillier_key_vec,
+                mta(sub_matches, keysfile_path, &ot_setup),
+                &root_y_sum,
+                &y_sum,
+                &params,
+                &message,
+                path,
+                &tweak_sk,
+            )
+            .await
+            .unwrap(),
+        },
+    };
+    output_signature(sub_matches, &sig, &message, path, &y_sum).await;
+}
+
+// Lindell'17 sign with a two-party key
+async fn sign_two_party(sub_matches: &ArgMatches, keys: TwoPartyKeys) {
+    if sub_matches.contains_id("presig") {
+        panic!("{} keys sign without presignatures", LINDELL17);
+    }
+    if let Some(protocol) = sub_matches.get_one::<String>("protocol") {
+        if protocol != LINDELL17 {
+            panic!(
+                "{} keys sign with {}, not {}",
+                LINDELL17, LINDELL17, protocol
+            );
+        }
+    }
+    let path = sub_matches
+        .get_one::<String>("path")
+        .map(|s| s.as_str())
+        .unwrap_or("");
+    let (tweak_sk, y_sum) = match path.is_empty() {
+        true => (Scalar::<Secp256k1>::zero(), keys.y_sum.clone()),
+        false => call_hd_key(path, keys.y_sum.clone(), keys.chain_code),
+    };
+    let message = message_digest(sub_matches);
+    let manager_addr = sub_matches
+        .get_one::<String>("manager_addr")
+        .map(|s| s.as_str())
+        .unwrap_or("http://127.0.0.1:8000")
+        .to_string();
+    let params = threshold_params(sub_matches);
+    let sig = two_party::sign(
+        &manager_addr,
+        &keys,
+        &params,
+        &message,
+        path,
+        &tweak_sk,
+        &y_sum,
+    )
+    .await
+    .unwrap();
+    output_signature(sub_matches, &sig, &message, path, &y_sum).await;
+}
+
+// prints the signature in --format and writes it to --out, if given
+async fn output_signature<E: Curve>(
+    sub_matches: &ArgMatches,
+    sig: &SignatureRecid<E>,
+    message: &[u8; DIGEST_SIZE],
+    path: &str,
+    y_sum: &Point<E>,
+) {
+    let format: SignatureFormat = sub_matches
+        .get_one::<String>("format")
+        .unwrap()
+        .parse()
+        .unwrap();
+    println!("Signature: {}", format.encode_to_string(sig));
+    if let Some(out) = sub_matches.get_one::<String>("out") {
+        let output = SignOutput::new(sig, format, message, path, y_sum);
+        fs::write(out, serde_json::to_string_pretty(&output).unwrap())
+            .await
+            .expect("Unable to save !");
+        println!("Signature written to file: {:?}", out);
+    }
+}
+
+async fn presign_ecdsa<E: Curve>(sub_matches: &ArgMatches, keysfile_path: &str) {
+    let (party_keys, shared_keys, party_id, vss_scheme_vec, paillier_key_vec, y_sum, _) =
+        load_keys::<E>(keysfile_path).await;
+    let ot_setup = load_ot_setup(keysfile_path, party_id, vss_scheme_vec.len()).await;
+    let manager_addr = sub_matches
+        .get_one::<String>("manager_addr")
+        .map(|s| s.as_str())
+        .unwrap_or("http://127.0.0.1:8000")
+        .to_string();
+    let params = threshold_params(sub_matches);
+    let count = *sub_matches.get_one::<usize>("count").unwrap();
+    let dir = sub_matches.get_one::<String>("dir").unwrap();
+    let path = sub_matches
+        .get_one::<String>("path")
+        .map(|s| s.as_str())
+        .unwrap_or("");
+    let aux = load_aux(keysfile_path).await;
+    let presigs = match protocol(sub_matches, keysfile_path, &aux) {
+        Some(aux) => {
+            reject_mta(sub_matches);
+            cggmp::presign(
+                &manager_addr,
+                &party_keys,
+                &shared_keys,
+                party_id,
+                &vss_scheme_vec,
+                &paillier_key_vec,
+                aux,
+                &y_sum,
+                &params,
+                path,
+                count,
+            )
+            .await
+            .unwrap()
+        }
+        None => presign::presign(
+            &manager_addr,
+            &party_keys,
+            &shared_keys,
+            party_id,
+            &vss_scheme_vec,
+            &paillier_key_vec,
+            mta(sub_matches, keysfile_path, &ot_setup),
+            &y_sum,
+            &params,
+            path,
+            count,
+        )
+        .await
+        .unwrap(),
+    };
+    for presig in &presigs {
+        let path = presign::store(dir, presig, &shared_keys).expect("Unable to save !");
+        println!("Presignature written to file: {:?}", path);
+    }
+}
+
+async fn load_keys<E: Curve>(keysfile_path: &str) -> KeysFile<E> {
+    load_keys_as::<E, _>(keysfile_path).await
+}
+
+// keys file of curve `E` whose party key is not the ECDSA one
+async fn load_keys_as<E: Curve, T: DeserializeOwned>(keysfile_path: &str) -> T {
+    let curve = keys_curve(keysfile_path).await;
+    if curve != E::CURVE_NAME {
+        panic!(
+            "{} holds {} key shares, this command needs {}",
+            keysfile_path,
+            curve,
+            E::CURVE_NAME
+        );
+    }
+    let data = fs::read_to_string(keysfile_path)
+        .await
+        .unwrap_or_else(|_| panic!("Unable to load keys file at location: {}", keysfile_path));
+    serde_json::from_value(cggmp::split_keys_file(&data).0).unwrap()
+}
+
+// root public key and chain code of a keys file, also of a two-party one
+async fn load_public_key<E: Curve>(keysfile_path: &str) -> (Point<E>, ChainCode) {
+    let curve = keys_curve(keysfile_path).await;
+    if curve != E::CURVE_NAME {
+        panic!(
+            "{} holds a {} key, this command needs {}",
+            keysfile_path,
+            curve,
+            E::CURVE_NAME
+        );
+    }
+    let data = fs::read_to_string(keysfile_path)
+        .await
+        .unwrap_or_else(|_| panic!("Unable to load keys file at location: {}", keysfile_path));
+    let keys: serde_json::Value = serde_json::from_str(&data).unwrap();
+    let (y_sum, chain_code) = match keys.get("y_sum") {
+        Some(y_sum) => (y_sum, &keys["chain_code"]),
+        None => (&keys[5], &keys[6]),
+    };
+    (
+        serde_json::from_value(y_sum.clone()).unwrap(),
+        serde_json::from_value(chain_code.clone()).unwrap(),
+    )
+}
+
+// keys file of a two-party key, None for a GG18 one
+async fn load_two_party_keys(keysfile_path: &str) -> Option<TwoPartyKeys> {
+    let data = fs::read_to_string(keysfile_path)
+        .await
+        .unwrap_or_else(|_| panic!("Unable to load keys file at location: {}", keysfile_path));
+    let keys: serde_json::Value = serde_json::from_str(&data).unwrap();
+    match keys["protocol"].as_str() {
+        Some(LINDELL17) => Some(serde_json::from_value(keys).unwrap()),
+        _ => None,
+    }
+}
+
+// CGGMP21 auxiliary info of a keys file, checked against its Paillier keys
+async fn load_aux(keysfile_path: &str) -> Option<AuxInfo> {
+    let data = fs::read_to_string(keysfile_path)
+        .await
+        .unwrap_or_else(|_| panic!("Unable to load keys file at location: {}", keysfile_path));
+    let (keys, aux) = cggmp::split_keys_file(&data);
+    if let Some(aux) = &aux {
+        let paillier_key_vec: Vec<EncryptionKey> = serde_json::from_value(keys[4].clone()).unwrap();
+        aux.check(&paillier_key_vec)
+            .unwrap_or_else(|e| panic!("{}: {}", keysfile_path, e));
+    }
+    aux
+}
+
+// auxiliary info to run CGGMP21 with, or None for GG18; --protocol defaults to
+// CGGMP21 for keys files that have been refreshed
+fn protocol<'a>(
+    sub_matches: &ArgMatches,
+    keysfile_path: &str,
+    aux: &'a Option<AuxInfo>,
+) -> Option<&'a AuxInfo> {
+    match sub_matches
+        .get_one::<String>("protocol")
+        .map(|s| s.as_str())
+    {
+        Some("gg18") => None,
+        Some(LINDELL17) => panic!("{} holds no {} key", keysfile_path, LINDELL17),
+        Some(_) => Some(aux.as_ref().unwrap_or_else(|| {
+            panic!(
+                "{} has no {} auxiliary info: run `refresh` first",
+                keysfile_path, CGGMP21
+            )
+        })),
+        None => aux.as_ref(),
+    }
+}
+
+// base OTs of a keys file, checked against its share count and key share
+async fn load_ot_setup(keysfile_path: &str, party_id: u16, share_count: usize) -> Option<OtSetup> {
+    let data = fs::read_to_string(keysfile_path)
+        .await
+        .unwrap_or_else(|_| panic!("Unable to load keys file at location: {}", keysfile_path));
+    let ot_setup = ot::ot_setup_of(&data);
+    if let Some(ot_setup) = &ot_setup {
+        ot_setup
+            .check(share_count, usize::from(party_id) - 1)
+            .unwrap_or_else(|e| panic!("{}: {}", keysfile_path, e));
+    }
+    ot_setup
+}
+
+// base OTs to run MtA over, or None for Paillier; --mta defaults to ot for keys
+// files that have base OTs
+fn mta<'a>(
+    sub_matches: &ArgMatches,
+    keysfile_path: &str,
+    ot_setup: &'a Option<OtSetup>,
+) -> Option<&'a OtSetup> {
+    match sub_matches.get_one::<String>("mta").map(|s| s.as_str()) {
+        Some("paillier") => None,
+        Some(_) => {
+            Some(ot_setup.as_ref().unwrap_or_else(|| {
+                panic!("{} has no base OTs: run `ot-setup` first", keysfile_path)
+            }))
+        }
+        None => ot_setup.as_ref(),
+    }
+}
+
+// CGGMP21 multiplies over Paillier with its own range proofs
+fn reject_mta(sub_matches: &ArgMatches) {
+    if let Some(mta) = sub_matches.get_one::<String>("mta") {
+        panic!("{} runs its own MtA, not --mta {}", CGGMP21, mta);
+    }
+}
+
+// curv name of the ECDSA curve given by --curve
+fn curve_name(sub_matches: &ArgMatches) -> &'static str {
+    match sub_matches.get_one::<String>("curve").unwrap().as_str() {
+        "p256" => Secp256r1::CURVE_NAME,
+        _ => Secp256k1::CURVE_NAME,
+    }
+}
+
+// curve recorded with the public key of a keys file
+async fn keys_curve(keysfile_path: &str) -> String {
+    let data = fs::read_to_string(keysfile_path)
+        .await
+        .unwrap_or_else(|_| panic!("Unable to load keys file at location: {}", keysfile_path));
+    let keys: serde_json::Value = serde_json::from_str(&data).unwrap();
+    // two-party keys files are objects
+    keys.get("y_sum").unwrap_or(&keys[5])["curve"]
+        .as_str()
+        .unwrap_or_else(|| panic!("{} records no curve", keysfile_path))
+        .to_string()
+}
+
+// address type given by --type or implied by the path, and --network
+fn btc_address_type(sub_matches: &ArgMatches, path: &str) -> (AddressType, bitcoin::Network) {
+    let address_type = match sub_matches.get_one::<String>("type") {
+        Some(t) => t.parse().unwrap(),
+        None => AddressType::from_path(path),
+    };
+    let network = btc::network(sub_matches.get_one::<String>("network").unwrap()).unwrap();
+    (address_type, network)
+}
+
+fn btc_address_args() -> [Arg; 3] {
+    [
+        Arg::new("path")
+            .short('p')
+            .long("path")
+            .num_args(1)
+            .help("Derivation path, e.g. m/84/0/0/0/0"),
+        Arg::new("type")
+            .long("type")
+            .num_args(1)
+            .value_parser(AddressType::NAMES)
+            .help(
+                "Address type; by default p2pkh for m/44, p2sh-p2wpkh for m/49, p2wpkh otherwise",
+            ),
+        Arg::new("network")
+            .long("network")
+            .num_args(1)
+            .value_parser(btc::NETWORKS)
+            .default_value("bitcoin")
+            .help("Network of the address"),
+    ]
+}
+
+// threshold/parties/share_count of sign and reshare
+fn threshold_params(sub_matches: &ArgMatches) -> Params {
+    let params: Vec<&str> = sub_matches
+        .get_one::<String>("params")
+        .map(|s| s.as_str())
+        .unwrap_or("")
+        .split("/")
+        .collect();
+    Params {
+        threshold: params[0].to_string(),
+        parties: params[1].to_string(),
+        share_count: params[2].to_string(),
+    }
+}
+
+// read and hash the message to sign or verify
+fn message_digest(sub_matches: &ArgMatches) -> [u8; DIGEST_SIZE] {
+    let hash_mode: HashMode = sub_matches
+        .get_one::<String>("hash")
+        .unwrap()
+        .parse()
+        .unwrap();
+    hash_mode.digest(&raw_message(sub_matches)).unwrap()
+}
+
+// EdDSA signs the message itself; --hash none keeps it as is
+fn message_bytes(sub_matches: &ArgMatches) -> Vec<u8> {
+    match sub_matches
+        .get_one::<String>("hash")
+        .unwrap()
+        .parse()
+        .unwrap()
+    {
+        HashMode::None => raw_message(sub_matches),
+        hash_mode => hash_mode
+            .digest(&raw_message(sub_matches))
+            .unwrap()
+            .to_vec(),
+    }
+}
+
+fn raw_message(sub_matches: &ArgMatches) -> Vec<u8> {
+    let source = match sub_matches.get_one::<String>("message_file") {
+        Some(path) => MessageSource::File(path.to_string()),
+        None => MessageSource::Hex(
+            sub_matches
+                .get_one::<String>("message")
+                .unwrap()
+                .to_string(),
+        ),
+    };
+    read_message(&source).unwrap()
+}
+
+fn message_args(index: usize) -> [Arg; 3] {
+    [
+        Arg::new("message")
+            .index(index)
+            .num_args(1)
+            .help("Message in hex format"),
+        Arg::new("message_file")
+            .long("message-file")
+            .num_args(1)
+            .help("Read the raw message from this file, or from stdin if -"),
+        Arg::new("hash")
+            .long("hash")
+            .num_args(1)
+            .value_parser(HashMode::NAMES)
+            .default_value("none")
+            .help("Hash applied to the message; none requires a 32-byte digest"),
+    ]
+}
+
+fn message_group() -> ArgGroup {
+    ArgGroup::new("input")
+        .args(["message", "message_file"])
+        .required(true)
+}
+
+// BIP-340 verification; the key is x-only, or SEC1 of which only x counts
+async fn verify_schnorr(sub_matches: &ArgMatches, message: &[u8; DIGEST_SIZE]) {
+    let sig_hex = sub_matches.get_one::<String>("signature").unwrap();
+    let sig =
+        SchnorrSignature::from_bytes(&hex::decode(sig_hex).expect("signature is not hex")).unwrap();
+    let (y_sum, tweak_sk) = match sub_matches.get_one::<String>("pubkey") {
+        Some(pubkey) => {
+            let mut bytes = hex::decode(pubkey).expect("public key is not hex");
+            if bytes.len() == 32 {
+                bytes.insert(0, 2);
+            }
+            let pubkey = Point::<Secp256k1>::from_bytes(&bytes).expect("invalid public key");
+            (pubkey, Scalar::<Secp256k1>::zero())
+        }
+        None => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            let (y_sum, chain_code) = load_public_key(keysfile_path).await;
+            let path = sub_matches
+                .get_one::<String>("path")
+                .map(|s| s.as_str())
+                .unwrap_or("");
+            match path.is_empty() {
+                true => (y_sum, Scalar::<Secp256k1>::zero()),
+                false => {
+                    let (tweak_sk, y_sum) = call_hd_key(path, y_sum, chain_code);
+                    (y_sum, tweak_sk)
+                }
+            }
+        }
+    };
+    let key = SchnorrKey::new(&y_sum, &tweak_sk, sub_matches.get_flag("taproot"));
+    match sig.verify(message, &key.x_only()) {
+        Ok(()) => println!("valid"),
+        Err(_) => {
+            println!("invalid");
+            std::process::exit(1);
+        }
+    }
+}
+
+// ECDSA verification of a `--format` signature under a SEC1 key, or the root / child
+// key of a keys file on `E`
+async fn verify_ecdsa<E: Curve>(
+    sub_matches: &ArgMatches,
+    message: &[u8; DIGEST_SIZE],
+    derive: impl Fn(&str, Point<E>, ChainCode) -> (Scalar<E>, Point<E>),
+) {
+    let format: SignatureFormat = sub_matches
+        .get_one::<String>("format")
+        .unwrap()
+        .parse()
+        .unwrap();
+    let sig = format
+        .decode(sub_matches.get_one::<String>("signature").unwrap())
+        .unwrap();
+
+    // Public key given directly, or root / HD child key of a keys file
+    let pubkey = match sub_matches.get_one::<String>("pubkey") {
+        Some(pubkey) => {
+            let bytes = hex::decode(pubkey).expect("public key is not hex");
+            Point::<E>::from_bytes(&bytes).expect("invalid public key")
+        }
+        None => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            let (y_sum, chain_code) = load_public_key(keysfile_path).await;
+            let path = sub_matches
+                .get_one::<String>("path")
+                .map(|s| s.as_str())
+                .unwrap_or("");
+            match path.is_empty() {
+                true => y_sum,
+                false => derive(path, y_sum, chain_code).1,
+            }
+        }
+    };
+
+    match verify(&sig, &pubkey, &BigInt::from_bytes(message)) {
+        Ok(()) => println!("valid"),
+        Err(_) => {
+            println!("invalid");
+            std::process::exit(1);
+        }
+    }
+}
+
+// RFC 8032 verification under a 32-byte key, or the root / child key of an ed25519 keys file
+async fn verify_eddsa(sub_matches: &ArgMatches) {
+    let sig_hex = sub_matches.get_one::<String>("signature").unwrap();
+    let sig =
+        EddsaSignature::from_bytes(&hex::decode(sig_hex).expect("signature is not hex")).unwrap();
+    let pubkey = match sub_matches.get_one::<String>("pubkey") {
+        Some(pubkey) => {
+            let bytes = hex::decode(pubkey).expect("public key is not hex");
+            Point::<Ed25519>::from_bytes(&bytes).expect("invalid public key")
+        }
+        None => {
+            let keysfile_path = sub_matches.get_one::<String>("keysfile").unwrap();
+            let (y_sum, chain_code) = load_public_key::<Ed25519>(keysfile_path).await;
+            let path = sub_matches
+                .get_one::<String>("path")
+                .map(|s| s.as_str())
+                .unwrap_or("");
+            EddsaKey::new(&y_sum, chain_code, path).unwrap().pubkey
+        }
+    };
+    match sig.verify(&message_bytes(sub_matches), &pubkey) {
+        Ok(()) => println!("valid"),
+        Err(_) => {
+            println!("invalid");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn call_hd_key(
+    path_str: &str,
+    par_pk: Point<Secp256k1>,
+    chain_code: ChainCode,
+) -> (Scalar<Secp256k1>, Point<Secp256k1>) {
+    match get_hd_key(path_str, par_pk, chain_code) {
+        Ok((tweak_sk, child_pk)) => (tweak_sk, child_pk),
+        // hardened levels need a quorum, not just the public key
+        Err(_) => panic!(
+            "{} has a hardened level: run `derive-account` and sign below it",
+            path_str
+        ),
+    }
+}
+
+fn protocol_arg() -> Arg {
+    Arg::new("protocol")
+        .long("protocol")
+        .num_args(1)
+        .value_parser(["gg18", "cggmp21", "lindell17"])
+        .help("ECDSA protocol; lindell17 for two-party keys, cggmp21 if the keys file has been refreshed, else gg18")
+}
+
+fn mta_arg() -> Arg {
+    Arg::new("mta")
+        .long("mta")
+        .num_args(1)
+        .value_parser(["paillier", "ot"])
+        .help("MtA of GG18 signing; ot if the keys file has base OTs, else paillier")
+}
+
+fn mnemonic_output(sub_matches: &ArgMatches) -> MnemonicOutput {
+    match sub_matches.get_one::<String>("mnemonic_out") {
+        Some(path) => MnemonicOutput::File(path.to_string()),
+        None if sub_matches.get_flag("show_mnemonic") => MnemonicOutput::Prompt,
+        None => MnemonicOutput::Skip,
+    }
+}
+
+fn mnemonic_args() -> [Arg; 2] {
+    [
+        Arg::new("mnemonic_out")
+            .long("mnemonic-out")
+            .num_args(1)
+            .conflicts_with("show_mnemonic")
+            .help("Write the mnemonic of the key share to this file (mode 0600)"),
+        Arg::new("show_mnemonic")
+            .long("show-mnemonic")
+            .action(ArgAction::SetTrue)
+            .help("Show the mnemonic of the key share on the terminal after confirmation"),
+    ]
+}
+
+fn argparse() -> ArgMatches {
+    Command::new("MPC_HD_GG18")
+        .version("0.1.0")
+        .author("TAIYI TECH")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .arg(
+            Arg::new("threads")
+                .long("threads")
+                .global(true)
+                .value_parser(clap::value_parser!(u16).range(1..))
+                .help("Cap the worker threads of per-peer computations (default: one per core)"),
+        )
+        .arg(
+            Arg::new("wire")
+                .long("wire")
+                .global(true)
+                .value_parser(["json", "cbor", "cbor-deflate"])
+                .help("Encoding of round messages once all parties read it (default: cbor)"),
+        )
+        .arg(
+            Arg::new("pool")
+                .long("pool")
+                .global(true)
+                .num_args(1)
+                .help("Take Paillier keys and encryption randomness from the pool in this directory"),
+        )
+        .subcommands(vec![
+            Command::new("manager").about("Run state manager"),
+            Command::new("keygen")
+                .about("Run keygen")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Target keys file"),
+                )
+                .arg(
                     Arg::new("params")
                         .index(2)
                         .required(true)
@@ -226,7 +1545,32 @@ fn argparse() -> ArgMatches {
                         .long("addr")
                         .num_args(1)
                         .help("URL to manager. E.g. http://127.0.0.2:8002"),
-                ),
+                )
+                .arg(
+                    Arg::new("curve")
+                        .long("curve")
+                        .num_args(1)
+                        .value_parser(["secp256k1", "p256", "ed25519"])
+                        .default_value("secp256k1")
+                        .help("Curve of the key: secp256k1 or p256 for ECDSA, ed25519 for EdDSA"),
+                )
+                .arg(
+                    Arg::new("protocol")
+                        .long("protocol")
+                        .num_args(1)
+                        .value_parser(["gg18", "cggmp21", "lindell17"])
+                        .default_value("gg18")
+                        .help("ECDSA protocol the key is for; cggmp21 also refreshes it, lindell17 is 2-of-2 only (1/2)"),
+                )
+                .arg(
+                    Arg::new("mta")
+                        .long("mta")
+                        .num_args(1)
+                        .value_parser(["paillier", "ot"])
+                        .default_value("paillier")
+                        .help("MtA of gg18 signing; ot also sets up base OTs between all key shares"),
+                )
+                .args(mnemonic_args()),
             Command::new("sign")
                 .about("Run sign")
                 .arg(
@@ -241,12 +1585,268 @@ fn argparse() -> ArgMatches {
                         "Threshold params: threshold/parties/share_count (t/t'/n). E.g. 1/2/3.",
                     ),
                 )
+                .args(message_args(3))
+                .group(message_group())
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .num_args(1)
+                        .value_parser(SignatureFormat::NAMES)
+                        .default_value("json")
+                        .help("Signature encoding"),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .num_args(1)
+                        .help("Write signature, digest, path and child pubkey to this JSON file"),
+                )
+                .arg(
+                    Arg::new("path")
+                        .short('p')
+                        .long("path")
+                        .num_args(1)
+                        .help("Derivation path"),
+                )
+                .arg(
+                    Arg::new("presig")
+                        .long("presig")
+                        .num_args(1)
+                        .help("Sign in one round with this presignature file; it is deleted"),
+                )
+                .arg(protocol_arg())
+                .arg(mta_arg())
+                .arg(
+                    Arg::new("manager_addr")
+                        .short('a')
+                        .long("addr")
+                        .num_args(1)
+                        .help("URL to manager"),
+                ),
+            Command::new("presign")
+                .about("Precompute presignatures for `sign --presig`")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Keys file"),
+                )
+                .arg(
+                    Arg::new("params").index(2).required(true).num_args(1).help(
+                        "Threshold params: threshold/parties/share_count (t/t'/n). E.g. 1/2/3.",
+                    ),
+                )
+                .arg(
+                    Arg::new("count")
+                        .short('n')
+                        .long("count")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("1")
+                        .help("Number of presignatures"),
+                )
+                .arg(
+                    Arg::new("path")
+                        .short('p')
+                        .long("path")
+                        .num_args(1)
+                        .help("Derivation path the presignatures sign for"),
+                )
+                .arg(
+                    Arg::new("dir")
+                        .long("dir")
+                        .num_args(1)
+                        .default_value("presig")
+                        .help("Directory of the presignature files"),
+                )
+                .arg(protocol_arg())
+                .arg(mta_arg())
+                .arg(
+                    Arg::new("manager_addr")
+                        .short('a')
+                        .long("addr")
+                        .num_args(1)
+                        .help("URL to manager"),
+                ),
+            Command::new("schnorr-sign")
+                .about("Run FROST-style threshold BIP-340 Schnorr signing")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Keys file"),
+                )
+                .arg(
+                    Arg::new("params").index(2).required(true).num_args(1).help(
+                        "Threshold params: threshold/parties/share_count (t/t'/n). E.g. 1/2/3.",
+                    ),
+                )
+                .args(message_args(3))
+                .group(message_group())
+                .arg(
+                    Arg::new("taproot")
+                        .long("taproot")
+                        .action(ArgAction::SetTrue)
+                        .help("Sign for the BIP-341 output key of a key-path-only output"),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .num_args(1)
+                        .help("Write signature, digest, path and x-only pubkey to this JSON file"),
+                )
+                .arg(
+                    Arg::new("path")
+                        .short('p')
+                        .long("path")
+                        .num_args(1)
+                        .help("Derivation path"),
+                )
                 .arg(
-                    Arg::new("message")
+                    Arg::new("manager_addr")
+                        .short('a')
+                        .long("addr")
+                        .num_args(1)
+                        .help("URL to manager"),
+                ),
+            Command::new("eddsa-sign")
+                .about("Run threshold Ed25519 signing with a keys file of `keygen --curve ed25519`")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Keys file"),
+                )
+                .arg(
+                    Arg::new("params").index(2).required(true).num_args(1).help(
+                        "Threshold params: threshold/parties/share_count (t/t'/n). E.g. 1/2/3.",
+                    ),
+                )
+                .args(message_args(3))
+                .mut_arg("hash", |a| {
+                    a.help("Hash applied to the message; none signs the message as is")
+                })
+                .group(message_group())
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .num_args(1)
+                        .help("Write signature, message, path and pubkey to this JSON file"),
+                )
+                .arg(
+                    Arg::new("path")
+                        .short('p')
+                        .long("path")
+                        .num_args(1)
+                        .help("Non-hardened derivation path"),
+                )
+                .arg(
+                    Arg::new("manager_addr")
+                        .short('a')
+                        .long("addr")
+                        .num_args(1)
+                        .help("URL to manager"),
+                ),
+            Command::new("sign-psbt")
+                .about("Sign the inputs of a PSBT derived from the keys file")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Keys file"),
+                )
+                .arg(
+                    Arg::new("params").index(2).required(true).num_args(1).help(
+                        "Threshold params: threshold/parties/share_count (t/t'/n). E.g. 1/2/3.",
+                    ),
+                )
+                .arg(
+                    Arg::new("psbt")
                         .index(3)
                         .required(true)
                         .num_args(1)
-                        .help("Message to sign in hex format"),
+                        .help("PSBT file in binary or base64, or - for stdin"),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .num_args(1)
+                        .help("Write the signed PSBT in base64 to this file"),
+                )
+                .arg(mta_arg())
+                .arg(
+                    Arg::new("manager_addr")
+                        .short('a')
+                        .long("addr")
+                        .num_args(1)
+                        .help("URL to manager"),
+                ),
+            Command::new("eth-address")
+                .about("Show the EIP-55 address of the root or child public key")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Keys file"),
+                )
+                .arg(
+                    Arg::new("path")
+                        .short('p')
+                        .long("path")
+                        .num_args(1)
+                        .help("Derivation path"),
+                ),
+            Command::new("eth-sign")
+                .about("Sign an Ethereum transaction or personal_sign message")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Keys file"),
+                )
+                .arg(
+                    Arg::new("params").index(2).required(true).num_args(1).help(
+                        "Threshold params: threshold/parties/share_count (t/t'/n). E.g. 1/2/3.",
+                    ),
+                )
+                .arg(
+                    Arg::new("data")
+                        .index(3)
+                        .num_args(1)
+                        .help("Unsigned raw transaction or message in hex format"),
+                )
+                .arg(
+                    Arg::new("data_file")
+                        .long("data-file")
+                        .num_args(1)
+                        .help("Read the raw bytes from this file, or from stdin if -"),
+                )
+                .group(
+                    ArgGroup::new("input")
+                        .args(["data", "data_file"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("kind")
+                        .long("kind")
+                        .num_args(1)
+                        .value_parser(EthPayload::NAMES)
+                        .default_value("tx")
+                        .help(
+                            "tx: EIP-155 legacy or EIP-1559 transaction; personal: EIP-191 message; typed-data: EIP-712 JSON",
+                        ),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .num_args(1)
+                        .help("Write the signed transaction or signature to this file"),
                 )
                 .arg(
                     Arg::new("path")
@@ -255,6 +1855,283 @@ fn argparse() -> ArgMatches {
                         .num_args(1)
                         .help("Derivation path"),
                 )
+                .arg(mta_arg())
+                .arg(
+                    Arg::new("manager_addr")
+                        .short('a')
+                        .long("addr")
+                        .num_args(1)
+                        .help("URL to manager"),
+                ),
+            Command::new("btc-address")
+                .about("Show the Bitcoin address of the root or child public key")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Keys file"),
+                )
+                .args(btc_address_args()),
+            Command::new("btc-sign-message")
+                .about("Sign a message with the Bitcoin Signed Message prefix (BIP-137) or BIP-322")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Keys file"),
+                )
+                .arg(
+                    Arg::new("params").index(2).required(true).num_args(1).help(
+                        "Threshold params: threshold/parties/share_count (t/t'/n). E.g. 1/2/3.",
+                    ),
+                )
+                .args(message_args(3).into_iter().take(2))
+                .group(message_group())
+                .args(btc_address_args())
+                .arg(
+                    Arg::new("bip322")
+                        .long("bip322")
+                        .action(ArgAction::SetTrue)
+                        .help("BIP-322 simple signature, for P2WPKH addresses"),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .num_args(1)
+                        .help("Write the signature to this file"),
+                )
+                .arg(mta_arg())
+                .arg(
+                    Arg::new("manager_addr")
+                        .short('a')
+                        .long("addr")
+                        .num_args(1)
+                        .help("URL to manager"),
+                ),
+            Command::new("derive-account")
+                .about("Jointly derive the key shares of an account with hardened levels")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Keys file"),
+                )
+                .arg(
+                    Arg::new("params").index(2).required(true).num_args(1).help(
+                        "Threshold params: threshold/parties/share_count (t/t'/n). E.g. 1/2/3.",
+                    ),
+                )
+                .arg(
+                    Arg::new("path")
+                        .short('p')
+                        .long("path")
+                        .required(true)
+                        .num_args(1)
+                        .help("Account path, e.g. m/44'/0'/0'"),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .required(true)
+                        .num_args(1)
+                        .help("Target account keys file"),
+                )
+                .arg(
+                    Arg::new("manager_addr")
+                        .short('a')
+                        .long("addr")
+                        .num_args(1)
+                        .help("URL to manager"),
+                ),
+            Command::new("export")
+                .about("Export the account xpub and output descriptors for watch-only wallets")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Keys file"),
+                )
+                .args(btc_address_args())
+                .mut_arg("path", |arg| {
+                    arg.required(true)
+                        .help("Account path, e.g. m/84/0/0")
+                })
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .num_args(1)
+                        .help("Write the export to this JSON file"),
+                ),
+            Command::new("verify")
+                .about("Verify a signature")
+                .arg(
+                    Arg::new("signature")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Signature in the given format"),
+                )
+                .args(message_args(2))
+                .group(message_group())
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .num_args(1)
+                        .value_parser(["der", "compact", "json", "schnorr", "ed25519"])
+                        .default_value("json")
+                        .help(
+                            "Signature encoding; schnorr is a BIP-340 signature in hex, \
+                             ed25519 an RFC 8032 one (with --hash none the message is verified as is)",
+                        ),
+                )
+                .arg(
+                    Arg::new("taproot")
+                        .long("taproot")
+                        .action(ArgAction::SetTrue)
+                        .help("Verify a schnorr signature under the BIP-341 output key"),
+                )
+                .arg(
+                    Arg::new("pubkey")
+                        .long("pubkey")
+                        .num_args(1)
+                        .help("Public key in hex (SEC1, compressed or not; 32 bytes for ed25519)"),
+                )
+                .arg(
+                    Arg::new("keysfile")
+                        .short('k')
+                        .long("keysfile")
+                        .num_args(1)
+                        .help("Keys file whose root or child public key is used"),
+                )
+                .group(
+                    ArgGroup::new("key")
+                        .args(["pubkey", "keysfile"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("curve")
+                        .long("curve")
+                        .num_args(1)
+                        .value_parser(["secp256k1", "p256"])
+                        .default_value("secp256k1")
+                        .help("Curve of --pubkey for an ECDSA signature; a keys file records its own"),
+                )
+                .arg(
+                    Arg::new("path")
+                        .short('p')
+                        .long("path")
+                        .num_args(1)
+                        .requires("keysfile")
+                        .help("Derivation path"),
+                ),
+            Command::new("recover")
+                .about("Recover the public key from a recoverable signature")
+                .arg(
+                    Arg::new("signature")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Signature in the given format"),
+                )
+                .args(message_args(2))
+                .group(message_group())
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .num_args(1)
+                        .value_parser(["recoverable", "json"])
+                        .default_value("recoverable")
+                        .help("Signature encoding"),
+                )
+                .arg(
+                    Arg::new("curve")
+                        .long("curve")
+                        .num_args(1)
+                        .value_parser(["secp256k1", "p256"])
+                        .default_value("secp256k1")
+                        .help("Curve of the signature"),
+                ),
+            Command::new("refresh")
+                .about("Refresh all key shares and their CGGMP21 auxiliary info")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Keys file, rewritten in place"),
+                )
+                .arg(
+                    Arg::new("params")
+                        .index(2)
+                        .required(true)
+                        .num_args(1)
+                        .help("Threshold params: threshold/parties (t/n). E.g. 1/3."),
+                )
+                .arg(
+                    Arg::new("manager_addr")
+                        .short('a')
+                        .long("addr")
+                        .num_args(1)
+                        .help("URL to manager"),
+                ),
+            Command::new("pool")
+                .about("Keep the --pool directory filled with Paillier keys and encryption randomness")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .num_args(0..)
+                        .help("Keys files to precompute encryption randomness for"),
+                )
+                .arg(
+                    Arg::new("keys")
+                        .long("keys")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("4")
+                        .help("Paillier keys to keep for keygen and reshare"),
+                )
+                .arg(
+                    Arg::new("safe_keys")
+                        .long("safe-keys")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("0")
+                        .help("Paillier keys over safe primes to keep for refresh"),
+                )
+                .arg(
+                    Arg::new("randomness")
+                        .long("randomness")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("256")
+                        .help("Encryption randomness values to keep under each Paillier key of the keys files"),
+                )
+                .arg(
+                    Arg::new("once")
+                        .long("once")
+                        .action(ArgAction::SetTrue)
+                        .help("Fill the pool once and exit"),
+                ),
+            Command::new("ot-setup")
+                .about("Set up base OTs between all key shares for `sign --mta ot`")
+                .arg(
+                    Arg::new("keysfile")
+                        .index(1)
+                        .required(true)
+                        .num_args(1)
+                        .help("Keys file, rewritten in place"),
+                )
+                .arg(
+                    Arg::new("params")
+                        .index(2)
+                        .required(true)
+                        .num_args(1)
+                        .help("Threshold params: threshold/parties (t/n). E.g. 1/3."),
+                )
                 .arg(
                     Arg::new("manager_addr")
                         .short('a')
@@ -303,7 +2180,8 @@ fn argparse() -> ArgMatches {
                         .long("addr")
                         .num_args(1)
                         .help("URL to manager"),
-                ),
+                )
+                .args(mnemonic_args()),
         ])
         .get_matches()
 }
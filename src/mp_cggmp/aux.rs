@@ -0,0 +1,109 @@
+//! Auxiliary info: per key share, a Paillier key and ring-Pedersen parameters over
+//! the same modulus N = p * q of safe primes. The proofs `modulus` and `prm` show
+//! that the parameters are well formed, `fac` that N has no small factor.
+
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use paillier::{DecryptionKey, EncryptionKey};
+use serde::{Deserialize, Serialize};
+
+use super::{pow_signed, sample_unit, Error};
+use crate::mp_ecdsa::secret::Secret;
+use crate::mp_ecdsa::validate::{check_encryption_key, Validate, ValidationError};
+
+/// protocol name recorded in keys files that hold auxiliary info
+pub const PROTOCOL: &str = "cggmp21";
+
+/// Ring-Pedersen parameters: t is a random square and s = t^lambda mod N.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct RingPedersen {
+    pub n: BigInt,
+    pub s: BigInt,
+    pub t: BigInt,
+}
+
+impl RingPedersen {
+    /// Parameters over the modulus of `dk`, with the secret lambda
+    pub fn generate(dk: &DecryptionKey) -> (Self, Secret<BigInt>) {
+        let n = &dk.p * &dk.q;
+        let phi = (&dk.p - 1) * (&dk.q - 1);
+        let tau = sample_unit(&n);
+        let t = BigInt::mod_mul(&tau, &tau, &n);
+        let lambda = BigInt::sample_below(&phi);
+        let s = BigInt::mod_pow(&t, &lambda, &n);
+        (Self { n, s, t }, Secret::new(lambda))
+    }
+
+    /// s^x * t^r mod N
+    pub fn commit(&self, x: &BigInt, r: &BigInt) -> BigInt {
+        BigInt::mod_mul(
+            &pow_signed(&self.s, x, &self.n),
+            &pow_signed(&self.t, r, &self.n),
+            &self.n,
+        )
+    }
+
+    /// a^x * t^r mod N, for commitments over another base such as Q in `fac`
+    pub fn commit_with(&self, a: &BigInt, x: &BigInt, r: &BigInt) -> BigInt {
+        BigInt::mod_mul(
+            &pow_signed(a, x, &self.n),
+            &pow_signed(&self.t, r, &self.n),
+            &self.n,
+        )
+    }
+
+    /// x^e * y mod N
+    pub fn mul_pow(&self, x: &BigInt, e: &BigInt, y: &BigInt) -> BigInt {
+        BigInt::mod_mul(&pow_signed(x, e, &self.n), y, &self.n)
+    }
+
+    pub fn is_unit(&self, x: &BigInt) -> bool {
+        x > &BigInt::zero() && x < &self.n && x.gcd(&self.n) == BigInt::one()
+    }
+}
+
+impl Validate for RingPedersen {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_encryption_key(&EncryptionKey::from(&self.n))?;
+        if !self.is_unit(&self.s) || !self.is_unit(&self.t) || self.s == self.t {
+            return Err(ValidationError::OutOfRange("ring-pedersen"));
+        }
+        Ok(())
+    }
+}
+
+/// Auxiliary info of all key shares, saved as the 8th element of a keys file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuxInfo {
+    pub protocol: String,
+    /// indexed by key share, like the Paillier keys of the keys file
+    pub ring_pedersen: Vec<RingPedersen>,
+}
+
+impl AuxInfo {
+    pub fn new(ring_pedersen: Vec<RingPedersen>) -> Self {
+        Self {
+            protocol: PROTOCOL.to_string(),
+            ring_pedersen,
+        }
+    }
+
+    /// Checks that every key share has parameters over the modulus of its Paillier key.
+    pub fn check(&self, paillier_key_vec: &[EncryptionKey]) -> Result<(), Error> {
+        if self.protocol != PROTOCOL {
+            return Err(Error::InvalidAux("unknown protocol"));
+        }
+        if self.ring_pedersen.len() != paillier_key_vec.len()
+            || self
+                .ring_pedersen
+                .iter()
+                .zip(paillier_key_vec)
+                .any(|(rp, ek)| rp.n != ek.n)
+        {
+            return Err(Error::InvalidAux("does not match the paillier keys"));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+/*
+    CGGMP21 threshold ECDSA
+
+    R. Canetti, R. Gennaro, S. Goldfeder, N. Makriyannis, U. Peled,
+    "UC Non-Interactive, Proactive, Threshold ECDSA with Identifiable Aborts",
+    CCS 2020, https://eprint.iacr.org/2021/060
+
+    Paillier and ring-Pedersen helpers shared by the zero-knowledge proofs in `zk`,
+    the auxiliary info of `aux` and the presigning messages of `presign`. Integers
+    are signed: plaintexts are lifted to (-N/2, N/2] and exponents may be negative.
+*/
+
+#![allow(clippy::many_single_char_names)]
+#![allow(clippy::too_many_arguments)]
+
+pub mod aux;
+pub mod presign;
+pub mod signed;
+pub mod zk;
+
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use paillier::{DecryptionKey, EncryptionKey};
+use thiserror::Error;
+
+/// bit length of the group order, the range of k_i, gamma_i and x_i
+pub const L: usize = 256;
+/// range of the additive MtA masks beta
+pub const L_PRIME: usize = 5 * L;
+/// slack of the range proofs
+pub const EPSILON: usize = 2 * L;
+/// range of chi_i = k_i * w_i + sum of MtA shares, up to 2^16 signers
+pub const L_CHI: usize = L_PRIME + 16;
+/// repetitions of the binary-challenge proofs
+pub const M: usize = 80;
+
+#[derive(Error, PartialEq, Eq, Clone, Debug)]
+pub enum Error {
+    #[error("invalid {0} proof")]
+    InvalidProof(&'static str),
+    #[error("invalid auxiliary info: {0}")]
+    InvalidAux(&'static str),
+}
+
+/// base^exp mod modulus for a signed exponent; base must be invertible if exp < 0
+pub fn pow_signed(base: &BigInt, exp: &BigInt, modulus: &BigInt) -> BigInt {
+    let base = base.mod_floor(modulus);
+    if exp < &BigInt::zero() {
+        let inv = BigInt::mod_inv(&base, modulus).unwrap_or_else(BigInt::zero);
+        BigInt::mod_pow(&inv, &(-exp), modulus)
+    } else {
+        BigInt::mod_pow(&base, exp, modulus)
+    }
+}
+
+/// uniform in [-2^bits, 2^bits]
+pub fn sample_pm(bits: usize) -> BigInt {
+    let bound = BigInt::one() << bits;
+    BigInt::sample_below(&(&bound * 2 + 1)) - bound
+}
+
+/// uniform in [-2^bits * n, 2^bits * n]
+pub fn sample_pm_n(bits: usize, n: &BigInt) -> BigInt {
+    let bound = (BigInt::one() << bits) * n;
+    BigInt::sample_below(&(&bound * 2 + 1)) - bound
+}
+
+/// uniform in Z*_n
+pub fn sample_unit(n: &BigInt) -> BigInt {
+    loop {
+        let r = BigInt::sample_below(n);
+        if r > BigInt::zero() && r.gcd(n) == BigInt::one() {
+            return r;
+        }
+    }
+}
+
+/// |x| <= 2^bits
+pub fn in_range(x: &BigInt, bits: usize) -> bool {
+    x.abs() <= BigInt::one() << bits
+}
+
+/// (1 + N)^m mod N^2, also for negative m
+pub fn one_plus_n_pow(ek: &EncryptionKey, m: &BigInt) -> BigInt {
+    (BigInt::one() + m.mod_floor(&ek.n) * &ek.n).mod_floor(&ek.nn)
+}
+
+/// Paillier encryption of a signed plaintext with randomness r
+pub fn encrypt_with(ek: &EncryptionKey, m: &BigInt, r: &BigInt) -> BigInt {
+    BigInt::mod_mul(
+        &one_plus_n_pow(ek, m),
+        &BigInt::mod_pow(r, &ek.n, &ek.nn),
+        &ek.nn,
+    )
+}
+
+/// Paillier encryption of a signed plaintext; returns the ciphertext and its randomness
+pub fn encrypt(ek: &EncryptionKey, m: &BigInt) -> (BigInt, BigInt) {
+    let r = sample_unit(&ek.n);
+    (encrypt_with(ek, m, &r), r)
+}
+
+/// Paillier decryption, lifted to (-N/2, N/2]
+pub fn decrypt(dk: &DecryptionKey, c: &BigInt) -> BigInt {
+    let n = &dk.p * &dk.q;
+    let nn = &n * &n;
+    let phi = (&dk.p - 1) * (&dk.q - 1);
+    // L(c^phi mod N^2) * phi^-1 mod N
+    let u = (BigInt::mod_pow(c, &phi, &nn) - 1) / &n;
+    let m = BigInt::mod_mul(&u, &BigInt::mod_inv(&phi, &n).unwrap(), &n);
+    if m > &n >> 1 {
+        m - n
+    } else {
+        m
+    }
+}
+
+/// Randomness r of a ciphertext c = (1 + N)^m r^N of plaintext m
+pub fn randomness_of(dk: &DecryptionKey, c: &BigInt, m: &BigInt) -> BigInt {
+    let ek = EncryptionKey::from(&(&dk.p * &dk.q));
+    let phi = (&dk.p - 1) * (&dk.q - 1);
+    // r^N mod N = c * (1 + N)^-m mod N; N-th roots are unique since gcd(N, phi) = 1
+    let r_n = BigInt::mod_mul(c, &one_plus_n_pow(&ek, &(-m)), &ek.n);
+    BigInt::mod_pow(&r_n, &BigInt::mod_inv(&ek.n, &phi).unwrap(), &ek.n)
+}
+
+/// c1 * c2 mod N^2: encryption of the sum of the plaintexts
+pub fn add(ek: &EncryptionKey, c1: &BigInt, c2: &BigInt) -> BigInt {
+    BigInt::mod_mul(c1, c2, &ek.nn)
+}
+
+/// c^k mod N^2: encryption of k times the plaintext
+pub fn mul(ek: &EncryptionKey, c: &BigInt, k: &BigInt) -> BigInt {
+    pow_signed(c, k, &ek.nn)
+}
@@ -0,0 +1,673 @@
+#![allow(non_snake_case)]
+//! Three-round presigning of CGGMP21 with identifiable aborts.
+//!
+//! Round 1 encrypts k_i and gamma_i, round 2 runs the two MtAs (for delta = k * gamma
+//! and chi = k * x) on the broadcast ciphertexts, round 3 reveals delta_i and
+//! Delta_i = k_i * Gamma. Every step carries a proof for each verifier, so a party
+//! that deviates is named by the first proof that fails. delta_i alone is not
+//! proven: if delta * G != sum Delta_i, all parties reveal their nonces (which are
+//! discarded anyway) and the MtA masks of the delta path, and the one whose delta_i
+//! does not follow from them is named.
+//!
+//! Parties are numbered 1..=n in signing order; all vectors indexed by party hold
+//! an entry for the receiver itself. Proofs for every other party are ordered by
+//! party number, skipping the sender, see `slot`.
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use paillier::{DecryptionKey, EncryptionKey};
+use serde::{Deserialize, Serialize};
+
+use super::aux::RingPedersen;
+use super::signed;
+use super::zk::aff_g::{AffGProof, AffGStatement};
+use super::zk::enc::EncProof;
+use super::zk::log_star::LogStarProof;
+use super::zk::mul_star::MulStarProof;
+use super::{
+    add, decrypt, encrypt, encrypt_with, in_range, mul, randomness_of, sample_pm, sample_unit,
+    EPSILON, L, L_CHI, L_PRIME,
+};
+use crate::mp_ecdsa::party_i::SessionContext;
+use crate::mp_ecdsa::secret::Secret;
+use crate::mp_ecdsa::validate::{
+    check_ciphertext, check_len, check_point, Validate, ValidationError,
+};
+
+/// Position of the entry for `receiver` among the parties other than `sender`
+pub fn slot(sender: u16, receiver: u16) -> usize {
+    debug_assert_ne!(sender, receiver);
+    if receiver < sender {
+        usize::from(receiver) - 1
+    } else {
+        usize::from(receiver) - 2
+    }
+}
+
+/// Party that deviated from the protocol, by party number
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Fault {
+    pub party: u16,
+    pub reason: String,
+}
+
+impl Fault {
+    fn new(party: u16, reason: impl Into<String>) -> Self {
+        Self {
+            party,
+            reason: reason.into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Round1 {
+    /// enc(k_i)
+    pub K: BigInt,
+    /// enc(gamma_i)
+    pub G: BigInt,
+    /// Π^enc for K, one per verifier
+    pub psi0: Vec<EncProof>,
+}
+
+/// MtA ciphertexts from the sender towards one other party j
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MtaOut {
+    /// K_j^gamma_i * enc_j(y), under the key of j
+    pub D: BigInt,
+    /// enc_i(y), under the key of the sender
+    pub F: BigInt,
+    /// K_j^w_i * enc_j(y_hat)
+    pub D_hat: BigInt,
+    /// enc_i(y_hat)
+    pub F_hat: BigInt,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round2<E: Curve> {
+    /// gamma_i * G
+    pub Gamma: Point<E>,
+    /// one per other party
+    pub mta: Vec<MtaOut>,
+    /// Π^aff-g for D and D_hat, per other party and then per verifier
+    pub psi: Vec<Vec<(AffGProof<E>, AffGProof<E>)>>,
+    /// Π^log* that G and Gamma hide the same gamma_i, per verifier
+    pub psi_prime: Vec<LogStarProof<E>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct Round3<E: Curve> {
+    pub delta: Scalar<E>,
+    /// k_i * Gamma
+    pub Delta: Point<E>,
+    /// chi_i * Gamma
+    pub S: Point<E>,
+    /// K_i^w_i, the k_i * w_i term of chi_i
+    pub H_hat: BigInt,
+    /// Π^log* for Delta, per verifier
+    pub psi_delta: Vec<LogStarProof<E>>,
+    /// Π^mul* for H_hat, per verifier
+    pub psi_hat: Vec<MulStarProof<E>>,
+    /// Π^log* that S hides the plaintext of the chi_i ciphertext, per verifier
+    pub psi_chi: Vec<LogStarProof<E>>,
+}
+
+/// Reveal of the delta path after a failed delta check
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Blame {
+    pub k: BigInt,
+    pub rho: BigInt,
+    pub gamma: BigInt,
+    pub nu: BigInt,
+    /// plaintext y and randomness of enc_j(y) in D, per other party
+    #[serde(with = "signed::vec")]
+    pub y: Vec<BigInt>,
+    pub s: Vec<BigInt>,
+}
+
+/// Result of presigning for one party
+pub struct PresignOutput<E: Curve> {
+    pub k_i: Secret<Scalar<E>>,
+    pub chi_i: Secret<Scalar<E>>,
+    pub R: Point<E>,
+    /// k_j * R and chi_j * R of every party, to check its signature share
+    pub checks: Vec<(Point<E>, Point<E>)>,
+}
+
+/// Public values of the signers, indexed by party
+pub struct Signers<E: Curve> {
+    pub eks: Vec<EncryptionKey>,
+    pub ring_pedersen: Vec<RingPedersen>,
+    /// w_j * G, the Lagrange-weighted public key shares
+    pub W: Vec<Point<E>>,
+}
+
+// secrets of the MtAs towards one other party
+struct MtaSecrets {
+    y: BigInt,
+    s: BigInt,
+    y_hat: BigInt,
+}
+
+pub struct Presigner<E: Curve> {
+    me: u16,
+    signers: Signers<E>,
+    dk: DecryptionKey,
+    w_i: Secret<Scalar<E>>,
+    session: String,
+    label: String,
+    k: Secret<BigInt>,
+    rho: BigInt,
+    gamma: Secret<BigInt>,
+    nu: BigInt,
+    round1: Vec<Round1>,
+    mta: Vec<MtaSecrets>,
+    round2: Vec<Round2<E>>,
+    Gamma: Point<E>,
+    chi: Secret<BigInt>,
+}
+
+impl<E: Curve> Presigner<E> {
+    /// `me` is our party number, `label` scopes the proofs to one presignature.
+    pub fn new(
+        me: u16,
+        signers: Signers<E>,
+        dk: &DecryptionKey,
+        w_i: &Scalar<E>,
+        session: &str,
+        label: &str,
+    ) -> Self {
+        Self {
+            me,
+            signers,
+            dk: dk.clone(),
+            w_i: Secret::new(w_i.clone()),
+            session: session.to_string(),
+            label: label.to_string(),
+            k: Secret::new(Scalar::<E>::random().to_bigint()),
+            rho: BigInt::zero(),
+            gamma: Secret::new(Scalar::<E>::random().to_bigint()),
+            nu: BigInt::zero(),
+            round1: Vec::new(),
+            mta: Vec::new(),
+            round2: Vec::new(),
+            Gamma: Point::zero(),
+            chi: Secret::new(BigInt::zero()),
+        }
+    }
+
+    fn parties(&self) -> u16 {
+        self.signers.eks.len() as u16
+    }
+
+    fn others(&self, party: u16) -> impl Iterator<Item = u16> {
+        (1..=self.parties()).filter(move |j| *j != party)
+    }
+
+    fn ek(&self, party: u16) -> &EncryptionKey {
+        &self.signers.eks[usize::from(party) - 1]
+    }
+
+    fn rp(&self, party: u16) -> &RingPedersen {
+        &self.signers.ring_pedersen[usize::from(party) - 1]
+    }
+
+    fn my_rp(&self) -> &RingPedersen {
+        self.rp(self.me)
+    }
+
+    // tag of the proofs of `what` sent by `party`
+    fn tag(&self, what: &str, party: u16) -> Vec<u8> {
+        SessionContext::new(&self.session, &format!("{}-{}", self.label, what)).tag(party)
+    }
+
+    pub fn round1(&mut self) -> Round1 {
+        let me = self.me;
+        let ek = self.ek(me).clone();
+        let (K, rho) = encrypt(&ek, &self.k);
+        let (G, nu) = encrypt(&ek, &self.gamma);
+        let tag = self.tag("enc", me);
+        let psi0 = self
+            .others(me)
+            .map(|j| EncProof::prove::<E>(&tag, &ek, &K, &self.k, &rho, self.rp(j)))
+            .collect();
+        self.rho = rho;
+        self.nu = nu;
+        Round1 { K, G, psi0 }
+    }
+
+    /// Checks the round 1 messages of all parties, ours included.
+    pub fn receive_round1(&mut self, msgs: Vec<Round1>) -> Result<(), Fault> {
+        let me = self.me;
+        for j in self.others(me) {
+            let msg = &msgs[usize::from(j) - 1];
+            msg.psi0[slot(j, me)]
+                .verify::<E>(&self.tag("enc", j), self.ek(j), &msg.K, self.my_rp())
+                .map_err(|e| Fault::new(j, e.to_string()))?;
+        }
+        self.round1 = msgs;
+        Ok(())
+    }
+
+    fn K(&self, party: u16) -> &BigInt {
+        &self.round1[usize::from(party) - 1].K
+    }
+
+    fn W(&self, party: u16) -> &Point<E> {
+        &self.signers.W[usize::from(party) - 1]
+    }
+
+    pub fn round2(&mut self) -> Round2<E> {
+        let me = self.me;
+        let ek_i = self.ek(me).clone();
+        let Gamma = Point::generator() * Scalar::<E>::from_bigint(&self.gamma);
+        let w_i = self.w_i.to_bigint();
+        let tag = self.tag("aff-g", me);
+        let mut mta = Vec::new();
+        let mut psi = Vec::new();
+        for j in self.others(me) {
+            let ek_j = self.ek(j);
+            let K_j = self.K(j);
+            let y = sample_pm(L_PRIME);
+            let s = sample_unit(&ek_j.n);
+            let r = sample_unit(&ek_i.n);
+            let y_hat = sample_pm(L_PRIME);
+            let s_hat = sample_unit(&ek_j.n);
+            let r_hat = sample_unit(&ek_i.n);
+            let out = MtaOut {
+                D: add(
+                    ek_j,
+                    &mul(ek_j, K_j, &self.gamma),
+                    &encrypt_with(ek_j, &y, &s),
+                ),
+                F: encrypt_with(&ek_i, &y, &r),
+                D_hat: add(
+                    ek_j,
+                    &mul(ek_j, K_j, &w_i),
+                    &encrypt_with(ek_j, &y_hat, &s_hat),
+                ),
+                F_hat: encrypt_with(&ek_i, &y_hat, &r_hat),
+            };
+            let st = AffGStatement {
+                ek0: ek_j,
+                ek1: &ek_i,
+                C: K_j,
+                D: &out.D,
+                Y: &out.F,
+                X: &Gamma,
+            };
+            let W_i = self.W(me).clone();
+            let st_hat = AffGStatement {
+                ek0: ek_j,
+                ek1: &ek_i,
+                C: K_j,
+                D: &out.D_hat,
+                Y: &out.F_hat,
+                X: &W_i,
+            };
+            psi.push(
+                self.others(me)
+                    .map(|l| {
+                        (
+                            AffGProof::prove(&tag, &st, &self.gamma, &y, &s, &r, self.rp(l)),
+                            AffGProof::prove(
+                                &tag,
+                                &st_hat,
+                                &w_i,
+                                &y_hat,
+                                &s_hat,
+                                &r_hat,
+                                self.rp(l),
+                            ),
+                        )
+                    })
+                    .collect(),
+            );
+            mta.push(out);
+            self.mta.push(MtaSecrets { y, s, y_hat });
+        }
+        let G = &self.round1[usize::from(me) - 1].G;
+        let tag = self.tag("log-gamma", me);
+        let g = Point::generator().to_point();
+        let psi_prime = self
+            .others(me)
+            .map(|l| {
+                LogStarProof::prove(
+                    &tag,
+                    L,
+                    &ek_i,
+                    G,
+                    &g,
+                    &Gamma,
+                    &self.gamma,
+                    &self.nu,
+                    self.rp(l),
+                )
+            })
+            .collect();
+        Round2 {
+            Gamma,
+            mta,
+            psi,
+            psi_prime,
+        }
+    }
+
+    /// Checks the MtA proofs of all parties, also those between two other parties.
+    pub fn receive_round2(&mut self, msgs: Vec<Round2<E>>) -> Result<(), Fault> {
+        let me = self.me;
+        let g = Point::generator().to_point();
+        for j in self.others(me) {
+            let msg = &msgs[usize::from(j) - 1];
+            let fault = |e: super::Error| Fault::new(j, e.to_string());
+            let tag = self.tag("aff-g", j);
+            for t in self.others(j) {
+                let out = &msg.mta[slot(j, t)];
+                let (psi, psi_hat) = &msg.psi[slot(j, t)][slot(j, me)];
+                let st = AffGStatement {
+                    ek0: self.ek(t),
+                    ek1: self.ek(j),
+                    C: self.K(t),
+                    D: &out.D,
+                    Y: &out.F,
+                    X: &msg.Gamma,
+                };
+                psi.verify(&tag, &st, self.my_rp()).map_err(fault)?;
+                let st_hat = AffGStatement {
+                    ek0: self.ek(t),
+                    ek1: self.ek(j),
+                    C: self.K(t),
+                    D: &out.D_hat,
+                    Y: &out.F_hat,
+                    X: self.W(j),
+                };
+                psi_hat.verify(&tag, &st_hat, self.my_rp()).map_err(fault)?;
+            }
+            msg.psi_prime[slot(j, me)]
+                .verify(
+                    &self.tag("log-gamma", j),
+                    L,
+                    self.ek(j),
+                    &self.round1[usize::from(j) - 1].G,
+                    &g,
+                    &msg.Gamma,
+                    self.my_rp(),
+                )
+                .map_err(fault)?;
+        }
+        self.Gamma = msgs.iter().map(|m| &m.Gamma).sum();
+        self.round2 = msgs;
+        Ok(())
+    }
+
+    // ciphertext of chi_j under the key of j, from public values only
+    fn chi_ciphertext(&self, j: u16, H_hat: &BigInt) -> BigInt {
+        let ek = self.ek(j);
+        let mut c = H_hat.clone();
+        for t in self.others(j) {
+            c = add(
+                ek,
+                &c,
+                &self.round2[usize::from(t) - 1].mta[slot(t, j)].D_hat,
+            );
+            let F_hat = &self.round2[usize::from(j) - 1].mta[slot(j, t)].F_hat;
+            c = add(ek, &c, &mul(ek, F_hat, &BigInt::from(-1)));
+        }
+        c
+    }
+
+    pub fn round3(&mut self) -> Round3<E> {
+        let me = self.me;
+        let ek = self.ek(me).clone();
+        let q = Scalar::<E>::group_order();
+        let w_i = self.w_i.to_bigint();
+        let mut delta = &*self.k * &*self.gamma;
+        let mut chi = &*self.k * &w_i;
+        for (j, mta) in self.others(me).zip(&self.mta) {
+            let out = &self.round2[usize::from(j) - 1].mta[slot(j, me)];
+            delta = delta + decrypt(&self.dk, &out.D) - &mta.y;
+            chi = chi + decrypt(&self.dk, &out.D_hat) - &mta.y_hat;
+        }
+        let Delta = &self.Gamma * Scalar::<E>::from_bigint(&self.k);
+        let S = &self.Gamma * Scalar::<E>::from_bigint(&chi);
+        let rho_hat = sample_unit(&ek.n);
+        let H_hat = add(
+            &ek,
+            &mul(&ek, self.K(me), &w_i),
+            &encrypt_with(&ek, &BigInt::zero(), &rho_hat),
+        );
+        let C = self.chi_ciphertext(me, &H_hat);
+        let rho_chi = randomness_of(&self.dk, &C, &chi);
+
+        let (tag_delta, tag_hat, tag_chi) = (
+            self.tag("log-delta", me),
+            self.tag("mul", me),
+            self.tag("log-chi", me),
+        );
+        let K = self.K(me);
+        let W_i = self.W(me);
+        let mut msg = Round3 {
+            delta: Scalar::from_bigint(&delta.mod_floor(q)),
+            Delta,
+            S,
+            H_hat: H_hat.clone(),
+            psi_delta: Vec::new(),
+            psi_hat: Vec::new(),
+            psi_chi: Vec::new(),
+        };
+        for l in self.others(me) {
+            let rp = self.rp(l);
+            msg.psi_delta.push(LogStarProof::prove(
+                &tag_delta,
+                L,
+                &ek,
+                K,
+                &self.Gamma,
+                &msg.Delta,
+                &self.k,
+                &self.rho,
+                rp,
+            ));
+            msg.psi_hat.push(MulStarProof::prove(
+                &tag_hat, &ek, K, &H_hat, W_i, &w_i, &rho_hat, rp,
+            ));
+            msg.psi_chi.push(LogStarProof::prove(
+                &tag_chi,
+                L_CHI,
+                &ek,
+                &C,
+                &self.Gamma,
+                &msg.S,
+                &chi,
+                &rho_chi,
+                rp,
+            ));
+        }
+        self.chi = Secret::new(chi);
+        msg
+    }
+
+    /// Checks round 3 and outputs the presignature. `Err(None)` means that delta is
+    /// wrong although every proof holds: run `blame`.
+    pub fn receive_round3(&self, msgs: &[Round3<E>]) -> Result<PresignOutput<E>, Option<Fault>> {
+        let me = self.me;
+        for j in self.others(me) {
+            let msg = &msgs[usize::from(j) - 1];
+            let fault = |e: super::Error| Some(Fault::new(j, e.to_string()));
+            let ek = self.ek(j);
+            let K = self.K(j);
+            let i = slot(j, me);
+            msg.psi_delta[i]
+                .verify(
+                    &self.tag("log-delta", j),
+                    L,
+                    ek,
+                    K,
+                    &self.Gamma,
+                    &msg.Delta,
+                    self.my_rp(),
+                )
+                .map_err(fault)?;
+            msg.psi_hat[i]
+                .verify(
+                    &self.tag("mul", j),
+                    ek,
+                    K,
+                    &msg.H_hat,
+                    self.W(j),
+                    self.my_rp(),
+                )
+                .map_err(fault)?;
+            let C = self.chi_ciphertext(j, &msg.H_hat);
+            msg.psi_chi[i]
+                .verify(
+                    &self.tag("log-chi", j),
+                    L_CHI,
+                    ek,
+                    &C,
+                    &self.Gamma,
+                    &msg.S,
+                    self.my_rp(),
+                )
+                .map_err(fault)?;
+        }
+        let delta: Scalar<E> = msgs.iter().map(|m| &m.delta).sum();
+        let Delta_sum: Point<E> = msgs.iter().map(|m| &m.Delta).sum();
+        if Point::generator() * &delta != Delta_sum {
+            return Err(None);
+        }
+        let delta_inv = delta.invert().ok_or(None)?;
+        Ok(PresignOutput {
+            k_i: Secret::new(Scalar::from_bigint(&self.k)),
+            chi_i: Secret::new(Scalar::from_bigint(&self.chi)),
+            R: &self.Gamma * &delta_inv,
+            checks: msgs
+                .iter()
+                .map(|m| (&m.Delta * &delta_inv, &m.S * &delta_inv))
+                .collect(),
+        })
+    }
+
+    /// Our reveal after a failed delta check; the nonces must not be used afterwards.
+    pub fn blame(&self) -> Blame {
+        Blame {
+            k: (*self.k).clone(),
+            rho: self.rho.clone(),
+            gamma: (*self.gamma).clone(),
+            nu: self.nu.clone(),
+            y: self.mta.iter().map(|m| m.y.clone()).collect(),
+            s: self.mta.iter().map(|m| m.s.clone()).collect(),
+        }
+    }
+
+    /// Names the party whose delta_i does not follow from its reveal, first checking
+    /// every reveal against the ciphertexts of rounds 1 and 2.
+    pub fn find_culprit(&self, reveals: &[Blame], round3: &[Round3<E>]) -> Fault {
+        let reveal = |j: u16| &reveals[usize::from(j) - 1];
+        for j in self.others(self.me) {
+            let b = reveal(j);
+            let ek = self.ek(j);
+            let r1 = &self.round1[usize::from(j) - 1];
+            if encrypt_with(ek, &b.k, &b.rho) != r1.K
+                || encrypt_with(ek, &b.gamma, &b.nu) != r1.G
+                || Point::generator() * Scalar::<E>::from_bigint(&b.gamma)
+                    != self.round2[usize::from(j) - 1].Gamma
+            {
+                return Fault::new(j, "revealed nonces do not match its ciphertexts");
+            }
+            for t in self.others(j) {
+                let (y, s) = (&b.y[slot(j, t)], &b.s[slot(j, t)]);
+                let ek_t = self.ek(t);
+                let D = add(
+                    ek_t,
+                    &mul(ek_t, self.K(t), &b.gamma),
+                    &encrypt_with(ek_t, y, s),
+                );
+                if !in_range(y, L_PRIME + EPSILON)
+                    || D != self.round2[usize::from(j) - 1].mta[slot(j, t)].D
+                {
+                    return Fault::new(
+                        j,
+                        format!("revealed MtA mask towards party {} is wrong", t),
+                    );
+                }
+            }
+        }
+        let q = Scalar::<E>::group_order();
+        for j in self.others(self.me) {
+            let b = reveal(j);
+            let mut delta = &b.k * &b.gamma;
+            for t in self.others(j) {
+                // alpha from t plus the mask j chose towards t
+                delta =
+                    delta + &b.k * &reveal(t).gamma + &reveal(t).y[slot(t, j)] - &b.y[slot(j, t)];
+            }
+            if Scalar::<E>::from_bigint(&delta.mod_floor(q)) != round3[usize::from(j) - 1].delta {
+                return Fault::new(j, "delta_i does not follow from its MtA shares");
+            }
+        }
+        Fault::new(self.me, "delta check failed but every reveal is consistent")
+    }
+}
+
+impl Validate for Round1 {
+    /// key of the sender and number of other parties
+    type Context = (EncryptionKey, usize);
+
+    fn validate(&self, (ek, others): &Self::Context) -> Result<(), ValidationError> {
+        check_ciphertext(&self.K, ek, "K")?;
+        check_ciphertext(&self.G, ek, "G")?;
+        check_len(self.psi0.len(), *others, "psi0")
+    }
+}
+
+impl<E: Curve> Validate for Round2<E> {
+    /// keys of all parties and the sender
+    type Context = (Vec<EncryptionKey>, u16);
+
+    fn validate(&self, (eks, sender): &Self::Context) -> Result<(), ValidationError> {
+        let others = eks.len() - 1;
+        check_point(&self.Gamma, "Gamma")?;
+        check_len(self.mta.len(), others, "mta")?;
+        check_len(self.psi.len(), others, "psi")?;
+        check_len(self.psi_prime.len(), others, "psi_prime")?;
+        let ek_i = &eks[usize::from(*sender) - 1];
+        let targets = (1..=eks.len() as u16).filter(|t| t != sender);
+        for ((out, psi), t) in self.mta.iter().zip(&self.psi).zip(targets) {
+            let ek_t = &eks[usize::from(t) - 1];
+            check_ciphertext(&out.D, ek_t, "D")?;
+            check_ciphertext(&out.D_hat, ek_t, "D_hat")?;
+            check_ciphertext(&out.F, ek_i, "F")?;
+            check_ciphertext(&out.F_hat, ek_i, "F_hat")?;
+            check_len(psi.len(), others, "psi")?;
+        }
+        Ok(())
+    }
+}
+
+impl<E: Curve> Validate for Round3<E> {
+    /// key of the sender and number of other parties
+    type Context = (EncryptionKey, usize);
+
+    fn validate(&self, (ek, others): &Self::Context) -> Result<(), ValidationError> {
+        check_point(&self.Delta, "Delta")?;
+        check_point(&self.S, "S")?;
+        check_ciphertext(&self.H_hat, ek, "H_hat")?;
+        check_len(self.psi_delta.len(), *others, "psi_delta")?;
+        check_len(self.psi_hat.len(), *others, "psi_hat")?;
+        check_len(self.psi_chi.len(), *others, "psi_chi")
+    }
+}
+
+impl Validate for Blame {
+    /// number of other parties
+    type Context = usize;
+
+    fn validate(&self, others: &usize) -> Result<(), ValidationError> {
+        check_len(self.y.len(), *others, "y")?;
+        check_len(self.s.len(), *others, "s")
+    }
+}
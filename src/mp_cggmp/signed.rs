@@ -0,0 +1,38 @@
+//! Serde for signed integers. curv serializes a `BigInt` as its magnitude, which
+//! would drop the sign of proof responses and MtA plaintexts; these are written as
+//! signed hex strings instead, with `#[serde(with = "signed")]`.
+
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S: Serializer>(x: &BigInt, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&x.to_str_radix(16))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BigInt, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    BigInt::from_str_radix(&s, 16).map_err(D::Error::custom)
+}
+
+pub mod vec {
+    use super::*;
+    use serde::ser::SerializeSeq;
+
+    pub fn serialize<S: Serializer>(xs: &[BigInt], serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(xs.len()))?;
+        for x in xs {
+            seq.serialize_element(&x.to_str_radix(16))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<BigInt>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| BigInt::from_str_radix(s, 16).map_err(D::Error::custom))
+            .collect()
+    }
+}
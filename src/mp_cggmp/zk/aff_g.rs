@@ -0,0 +1,267 @@
+#![allow(non_snake_case)]
+//! Π^aff-g: D = C^x * (1 + N0)^y * rho^N0 mod N0^2 is an affine operation on C with
+//! x = log X in ±2^L and y in ±2^L', where Y = (1 + N1)^y * rho_y^N1 encrypts y
+//! under the prover's own key (CGGMP21, Figure 15).
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{Curve, Point};
+use curv::BigInt;
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+
+use super::{challenge, check, point_pow};
+use crate::mp_cggmp::aux::RingPedersen;
+use crate::mp_cggmp::signed;
+use crate::mp_cggmp::{
+    encrypt_with, in_range, mul, pow_signed, sample_pm, sample_pm_n, sample_unit, Error, EPSILON,
+    L, L_PRIME,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct AffGProof<E: Curve> {
+    pub A: BigInt,
+    pub Bx: Point<E>,
+    pub By: BigInt,
+    pub E: BigInt,
+    pub S: BigInt,
+    pub F: BigInt,
+    pub T: BigInt,
+    #[serde(with = "signed")]
+    pub z1: BigInt,
+    #[serde(with = "signed")]
+    pub z2: BigInt,
+    #[serde(with = "signed")]
+    pub z3: BigInt,
+    #[serde(with = "signed")]
+    pub z4: BigInt,
+    pub w: BigInt,
+    pub wy: BigInt,
+}
+
+/// Public values of an Π^aff-g statement
+pub struct AffGStatement<'a, E: Curve> {
+    /// key of C and D
+    pub ek0: &'a EncryptionKey,
+    /// key of Y, the prover's
+    pub ek1: &'a EncryptionKey,
+    pub C: &'a BigInt,
+    pub D: &'a BigInt,
+    pub Y: &'a BigInt,
+    pub X: &'a Point<E>,
+}
+
+impl<E: Curve> AffGStatement<'_, E> {
+    fn challenge(
+        &self,
+        tag: &[u8],
+        rp: &RingPedersen,
+        proof: (
+            &BigInt,
+            &Point<E>,
+            &BigInt,
+            &BigInt,
+            &BigInt,
+            &BigInt,
+            &BigInt,
+        ),
+    ) -> BigInt {
+        let (A, Bx, By, E, S, F, T) = proof;
+        challenge::<E>(
+            tag,
+            &[
+                &self.ek0.n,
+                &self.ek1.n,
+                self.C,
+                self.D,
+                self.Y,
+                &rp.n,
+                &rp.s,
+                &rp.t,
+                A,
+                By,
+                E,
+                S,
+                F,
+                T,
+            ],
+            &[self.X, Bx],
+        )
+    }
+}
+
+impl<E: Curve> AffGProof<E> {
+    /// `rho` is the randomness of the encryption of y in D, `rho_y` that of Y.
+    pub fn prove(
+        tag: &[u8],
+        st: &AffGStatement<E>,
+        x: &BigInt,
+        y: &BigInt,
+        rho: &BigInt,
+        rho_y: &BigInt,
+        rp: &RingPedersen,
+    ) -> Self {
+        let (ek0, ek1) = (st.ek0, st.ek1);
+        let alpha = sample_pm(L + EPSILON);
+        let beta = sample_pm(L_PRIME + EPSILON);
+        let r = sample_unit(&ek0.n);
+        let ry = sample_unit(&ek1.n);
+        let gamma = sample_pm_n(L + EPSILON, &rp.n);
+        let m = sample_pm_n(L, &rp.n);
+        let delta = sample_pm_n(L + EPSILON, &rp.n);
+        let mu = sample_pm_n(L, &rp.n);
+
+        let A = BigInt::mod_mul(
+            &mul(ek0, st.C, &alpha),
+            &encrypt_with(ek0, &beta, &r),
+            &ek0.nn,
+        );
+        let Bx = point_pow(&Point::generator().to_point(), &alpha);
+        let By = encrypt_with(ek1, &beta, &ry);
+        let E = rp.commit(&alpha, &gamma);
+        let S = rp.commit(x, &m);
+        let F = rp.commit(&beta, &delta);
+        let T = rp.commit(y, &mu);
+        let e = st.challenge(tag, rp, (&A, &Bx, &By, &E, &S, &F, &T));
+
+        Self {
+            z1: &alpha + &e * x,
+            z2: &beta + &e * y,
+            z3: gamma + &e * m,
+            z4: delta + &e * mu,
+            w: BigInt::mod_mul(&r, &pow_signed(rho, &e, &ek0.n), &ek0.n),
+            wy: BigInt::mod_mul(&ry, &pow_signed(rho_y, &e, &ek1.n), &ek1.n),
+            A,
+            Bx,
+            By,
+            E,
+            S,
+            F,
+            T,
+        }
+    }
+
+    pub fn verify(
+        &self,
+        tag: &[u8],
+        st: &AffGStatement<E>,
+        rp: &RingPedersen,
+    ) -> Result<(), Error> {
+        let (ek0, ek1) = (st.ek0, st.ek1);
+        let e = st.challenge(
+            tag,
+            rp,
+            (
+                &self.A, &self.Bx, &self.By, &self.E, &self.S, &self.F, &self.T,
+            ),
+        );
+        check(in_range(&self.z1, L + EPSILON), "aff-g")?;
+        check(in_range(&self.z2, L_PRIME + EPSILON), "aff-g")?;
+        check(
+            BigInt::mod_mul(
+                &mul(ek0, st.C, &self.z1),
+                &encrypt_with(ek0, &self.z2, &self.w),
+                &ek0.nn,
+            ) == BigInt::mod_mul(&self.A, &mul(ek0, st.D, &e), &ek0.nn),
+            "aff-g",
+        )?;
+        check(
+            point_pow(&Point::generator().to_point(), &self.z1) == &self.Bx + point_pow(st.X, &e),
+            "aff-g",
+        )?;
+        check(
+            encrypt_with(ek1, &self.z2, &self.wy)
+                == BigInt::mod_mul(&self.By, &mul(ek1, st.Y, &e), &ek1.nn),
+            "aff-g",
+        )?;
+        check(
+            rp.commit(&self.z1, &self.z3) == rp.mul_pow(&self.S, &e, &self.E),
+            "aff-g",
+        )?;
+        check(
+            rp.commit(&self.z2, &self.z4) == rp.mul_pow(&self.T, &e, &self.F),
+            "aff-g",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_cggmp::zk::tests::{setup, TAG};
+    use crate::mp_cggmp::{add, encrypt};
+    use curv::elliptic::curves::Secp256k1;
+
+    struct Values {
+        C: BigInt,
+        D: BigInt,
+        Y: BigInt,
+        X: Point<Secp256k1>,
+        x: BigInt,
+        y: BigInt,
+        rho: BigInt,
+        rho_y: BigInt,
+    }
+
+    // D = C^x * enc(y) under the verifier's key, Y = enc(y) under the prover's
+    fn values(ek0: &EncryptionKey, ek1: &EncryptionKey) -> Values {
+        let (C, _) = encrypt(ek0, &sample_pm(L));
+        let x = sample_pm(L);
+        let y = sample_pm(L_PRIME);
+        let (enc_y, rho) = encrypt(ek0, &y);
+        let D = add(ek0, &mul(ek0, &C, &x), &enc_y);
+        let (Y, rho_y) = encrypt(ek1, &y);
+        let X = point_pow(&Point::generator().to_point(), &x);
+        Values {
+            C,
+            D,
+            Y,
+            X,
+            x,
+            y,
+            rho,
+            rho_y,
+        }
+    }
+
+    #[test]
+    fn proves_affine_operation() {
+        let setup = setup();
+        let (ek0, ek1) = (&setup.verifier.0, &setup.prover.0);
+        let v = values(ek0, ek1);
+        let st = AffGStatement {
+            ek0,
+            ek1,
+            C: &v.C,
+            D: &v.D,
+            Y: &v.Y,
+            X: &v.X,
+        };
+        let proof = AffGProof::prove(TAG, &st, &v.x, &v.y, &v.rho, &v.rho_y, &setup.rp);
+        let proof: AffGProof<Secp256k1> =
+            serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert_eq!(proof.verify(TAG, &st, &setup.rp), Ok(()));
+    }
+
+    #[test]
+    fn rejects_other_encryption_of_y() {
+        let setup = setup();
+        let (ek0, ek1) = (&setup.verifier.0, &setup.prover.0);
+        let v = values(ek0, ek1);
+        let st = AffGStatement {
+            ek0,
+            ek1,
+            C: &v.C,
+            D: &v.D,
+            Y: &v.Y,
+            X: &v.X,
+        };
+        let proof = AffGProof::prove(TAG, &st, &v.x, &v.y, &v.rho, &v.rho_y, &setup.rp);
+        let (other, _) = encrypt(ek1, &(&v.y + 1));
+        let st = AffGStatement { Y: &other, ..st };
+        assert_eq!(
+            proof.verify(TAG, &st, &setup.rp),
+            Err(Error::InvalidProof("aff-g"))
+        );
+    }
+}
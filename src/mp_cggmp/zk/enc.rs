@@ -0,0 +1,116 @@
+#![allow(non_snake_case)]
+//! Π^enc: the plaintext k of a Paillier ciphertext K = (1 + N0)^k * rho^N0 lies in
+//! ±2^L (CGGMP21, Figure 14).
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::Curve;
+use curv::BigInt;
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+
+use super::{challenge, check};
+use crate::mp_cggmp::aux::RingPedersen;
+use crate::mp_cggmp::signed;
+use crate::mp_cggmp::{
+    encrypt_with, in_range, pow_signed, sample_pm, sample_pm_n, sample_unit, Error, EPSILON, L,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EncProof {
+    pub S: BigInt,
+    pub A: BigInt,
+    pub C: BigInt,
+    #[serde(with = "signed")]
+    pub z1: BigInt,
+    pub z2: BigInt,
+    #[serde(with = "signed")]
+    pub z3: BigInt,
+}
+
+impl EncProof {
+    pub fn prove<E: Curve>(
+        tag: &[u8],
+        ek: &EncryptionKey,
+        K: &BigInt,
+        k: &BigInt,
+        rho: &BigInt,
+        rp: &RingPedersen,
+    ) -> Self {
+        let alpha = sample_pm(L + EPSILON);
+        let mu = sample_pm_n(L, &rp.n);
+        let r = sample_unit(&ek.n);
+        let gamma = sample_pm_n(L + EPSILON, &rp.n);
+
+        let S = rp.commit(k, &mu);
+        let A = encrypt_with(ek, &alpha, &r);
+        let C = rp.commit(&alpha, &gamma);
+        let e = challenge::<E>(tag, &[&ek.n, K, &rp.n, &rp.s, &rp.t, &S, &A, &C], &[]);
+
+        Self {
+            z1: &alpha + &e * k,
+            z2: BigInt::mod_mul(&r, &pow_signed(rho, &e, &ek.n), &ek.n),
+            z3: gamma + &e * mu,
+            S,
+            A,
+            C,
+        }
+    }
+
+    pub fn verify<E: Curve>(
+        &self,
+        tag: &[u8],
+        ek: &EncryptionKey,
+        K: &BigInt,
+        rp: &RingPedersen,
+    ) -> Result<(), Error> {
+        let e = challenge::<E>(
+            tag,
+            &[&ek.n, K, &rp.n, &rp.s, &rp.t, &self.S, &self.A, &self.C],
+            &[],
+        );
+        check(in_range(&self.z1, L + EPSILON), "enc")?;
+        check(
+            encrypt_with(ek, &self.z1, &self.z2)
+                == BigInt::mod_mul(&self.A, &pow_signed(K, &e, &ek.nn), &ek.nn),
+            "enc",
+        )?;
+        check(
+            rp.commit(&self.z1, &self.z3) == rp.mul_pow(&self.S, &e, &self.C),
+            "enc",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_cggmp::encrypt;
+    use crate::mp_cggmp::zk::tests::{setup, TAG};
+    use curv::elliptic::curves::Secp256k1;
+
+    #[test]
+    fn proves_plaintext_range() {
+        let setup = setup();
+        let ek = &setup.prover.0;
+        let k = sample_pm(L);
+        let (K, rho) = encrypt(ek, &k);
+        let proof = EncProof::prove::<Secp256k1>(TAG, ek, &K, &k, &rho, &setup.rp);
+        let proof: EncProof =
+            serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert_eq!(proof.verify::<Secp256k1>(TAG, ek, &K, &setup.rp), Ok(()));
+    }
+
+    #[test]
+    fn rejects_other_ciphertext() {
+        let setup = setup();
+        let ek = &setup.prover.0;
+        let k = sample_pm(L);
+        let (K, rho) = encrypt(ek, &k);
+        let proof = EncProof::prove::<Secp256k1>(TAG, ek, &K, &k, &rho, &setup.rp);
+        let (other, _) = encrypt(ek, &(k + 1));
+        assert_eq!(
+            proof.verify::<Secp256k1>(TAG, ek, &other, &setup.rp),
+            Err(Error::InvalidProof("enc"))
+        );
+    }
+}
@@ -0,0 +1,144 @@
+#![allow(non_snake_case)]
+//! Π^fac: N0 = p * q with both factors larger than 2^L, so that N0 has no small
+//! factor (CGGMP21, Figure 28). Committed under the verifier's ring-Pedersen
+//! parameters.
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::Curve;
+use curv::BigInt;
+use paillier::DecryptionKey;
+use serde::{Deserialize, Serialize};
+
+use super::{challenge, check};
+use crate::mp_cggmp::aux::RingPedersen;
+use crate::mp_cggmp::signed;
+use crate::mp_cggmp::{sample_pm_n, Error, EPSILON, L};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FacProof {
+    pub P: BigInt,
+    pub Q: BigInt,
+    pub A: BigInt,
+    pub B: BigInt,
+    pub T: BigInt,
+    #[serde(with = "signed")]
+    pub sigma: BigInt,
+    #[serde(with = "signed")]
+    pub z1: BigInt,
+    #[serde(with = "signed")]
+    pub z2: BigInt,
+    #[serde(with = "signed")]
+    pub w1: BigInt,
+    #[serde(with = "signed")]
+    pub w2: BigInt,
+    #[serde(with = "signed")]
+    pub v: BigInt,
+}
+
+impl FacProof {
+    pub fn prove<E: Curve>(tag: &[u8], dk: &DecryptionKey, rp: &RingPedersen) -> Self {
+        let (p, q) = (&dk.p, &dk.q);
+        let n0 = p * q;
+        let sqrt_n0 = n0.sqrt();
+        let alpha = sample_pm_n(L + EPSILON, &sqrt_n0);
+        let beta = sample_pm_n(L + EPSILON, &sqrt_n0);
+        let mu = sample_pm_n(L, &rp.n);
+        let nu = sample_pm_n(L, &rp.n);
+        let sigma = sample_pm_n(L, &(&n0 * &rp.n));
+        let r = sample_pm_n(L + EPSILON, &(&n0 * &rp.n));
+        let x = sample_pm_n(L + EPSILON, &rp.n);
+        let y = sample_pm_n(L + EPSILON, &rp.n);
+
+        let P = rp.commit(p, &mu);
+        let Q = rp.commit(q, &nu);
+        let A = rp.commit(&alpha, &x);
+        let B = rp.commit(&beta, &y);
+        let T = rp.commit_with(&Q, &alpha, &r);
+        let e = challenge::<E>(
+            tag,
+            &[&n0, &rp.n, &rp.s, &rp.t, &P, &Q, &A, &B, &T, &sigma],
+            &[],
+        );
+
+        let sigma_hat = &sigma - &nu * p;
+        Self {
+            z1: alpha + &e * p,
+            z2: beta + &e * q,
+            w1: x + &e * mu,
+            w2: y + &e * &nu,
+            v: r + &e * sigma_hat,
+            P,
+            Q,
+            A,
+            B,
+            T,
+            sigma,
+        }
+    }
+
+    pub fn verify<E: Curve>(
+        &self,
+        tag: &[u8],
+        n0: &BigInt,
+        rp: &RingPedersen,
+    ) -> Result<(), Error> {
+        let e = challenge::<E>(
+            tag,
+            &[
+                n0,
+                &rp.n,
+                &rp.s,
+                &rp.t,
+                &self.P,
+                &self.Q,
+                &self.A,
+                &self.B,
+                &self.T,
+                &self.sigma,
+            ],
+            &[],
+        );
+        let bound = n0.sqrt() << (L + EPSILON);
+        check(self.z1.abs() <= bound && self.z2.abs() <= bound, "fac")?;
+        let R = rp.commit(n0, &self.sigma);
+        check(
+            rp.commit(&self.z1, &self.w1) == rp.mul_pow(&self.P, &e, &self.A),
+            "fac",
+        )?;
+        check(
+            rp.commit(&self.z2, &self.w2) == rp.mul_pow(&self.Q, &e, &self.B),
+            "fac",
+        )?;
+        check(
+            rp.commit_with(&self.Q, &self.z1, &self.v) == rp.mul_pow(&R, &e, &self.T),
+            "fac",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_cggmp::zk::tests::{setup, TAG};
+    use curv::elliptic::curves::Secp256k1;
+
+    #[test]
+    fn proves_large_factors() {
+        let setup = setup();
+        let (ek, dk) = &setup.prover;
+        let proof = FacProof::prove::<Secp256k1>(TAG, dk, &setup.rp);
+        let proof: FacProof =
+            serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert_eq!(proof.verify::<Secp256k1>(TAG, &ek.n, &setup.rp), Ok(()));
+    }
+
+    #[test]
+    fn rejects_other_modulus() {
+        let setup = setup();
+        let proof = FacProof::prove::<Secp256k1>(TAG, &setup.prover.1, &setup.rp);
+        assert_eq!(
+            proof.verify::<Secp256k1>(TAG, &setup.verifier.0.n, &setup.rp),
+            Err(Error::InvalidProof("fac"))
+        );
+    }
+}
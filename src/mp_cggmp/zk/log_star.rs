@@ -0,0 +1,135 @@
+#![allow(non_snake_case)]
+//! Π^log*: the plaintext x of C = (1 + N0)^x * rho^N0 is the discrete log of
+//! X = x * g and lies in ±2^l (CGGMP21, Figure 25). `l` is L for nonces and L_CHI
+//! for the sums of MtA shares.
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{Curve, Point};
+use curv::BigInt;
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+
+use super::{challenge, check, point_pow};
+use crate::mp_cggmp::aux::RingPedersen;
+use crate::mp_cggmp::signed;
+use crate::mp_cggmp::{
+    encrypt_with, in_range, pow_signed, sample_pm, sample_pm_n, sample_unit, Error, EPSILON,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct LogStarProof<E: Curve> {
+    pub S: BigInt,
+    pub A: BigInt,
+    pub Y: Point<E>,
+    pub D: BigInt,
+    #[serde(with = "signed")]
+    pub z1: BigInt,
+    pub z2: BigInt,
+    #[serde(with = "signed")]
+    pub z3: BigInt,
+}
+
+impl<E: Curve> LogStarProof<E> {
+    pub fn prove(
+        tag: &[u8],
+        l: usize,
+        ek: &EncryptionKey,
+        C: &BigInt,
+        g: &Point<E>,
+        X: &Point<E>,
+        x: &BigInt,
+        rho: &BigInt,
+        rp: &RingPedersen,
+    ) -> Self {
+        let alpha = sample_pm(l + EPSILON);
+        let mu = sample_pm_n(l, &rp.n);
+        let r = sample_unit(&ek.n);
+        let gamma = sample_pm_n(l + EPSILON, &rp.n);
+
+        let S = rp.commit(x, &mu);
+        let A = encrypt_with(ek, &alpha, &r);
+        let Y = point_pow(g, &alpha);
+        let D = rp.commit(&alpha, &gamma);
+        let e = challenge::<E>(
+            tag,
+            &[&ek.n, C, &rp.n, &rp.s, &rp.t, &S, &A, &D],
+            &[g, X, &Y],
+        );
+
+        Self {
+            z1: &alpha + &e * x,
+            z2: BigInt::mod_mul(&r, &pow_signed(rho, &e, &ek.n), &ek.n),
+            z3: gamma + &e * mu,
+            S,
+            A,
+            Y,
+            D,
+        }
+    }
+
+    pub fn verify(
+        &self,
+        tag: &[u8],
+        l: usize,
+        ek: &EncryptionKey,
+        C: &BigInt,
+        g: &Point<E>,
+        X: &Point<E>,
+        rp: &RingPedersen,
+    ) -> Result<(), Error> {
+        let e = challenge::<E>(
+            tag,
+            &[&ek.n, C, &rp.n, &rp.s, &rp.t, &self.S, &self.A, &self.D],
+            &[g, X, &self.Y],
+        );
+        check(in_range(&self.z1, l + EPSILON), "log*")?;
+        check(
+            encrypt_with(ek, &self.z1, &self.z2)
+                == BigInt::mod_mul(&self.A, &pow_signed(C, &e, &ek.nn), &ek.nn),
+            "log*",
+        )?;
+        check(point_pow(g, &self.z1) == &self.Y + point_pow(X, &e), "log*")?;
+        check(
+            rp.commit(&self.z1, &self.z3) == rp.mul_pow(&self.S, &e, &self.D),
+            "log*",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_cggmp::zk::tests::{setup, TAG};
+    use crate::mp_cggmp::{encrypt, L};
+    use curv::elliptic::curves::Secp256k1;
+
+    #[test]
+    fn proves_discrete_log() {
+        let setup = setup();
+        let ek = &setup.prover.0;
+        let g = Point::<Secp256k1>::generator().to_point();
+        let x = sample_pm(L);
+        let (C, rho) = encrypt(ek, &x);
+        let X = point_pow(&g, &x);
+        let proof = LogStarProof::prove(TAG, L, ek, &C, &g, &X, &x, &rho, &setup.rp);
+        let proof: LogStarProof<Secp256k1> =
+            serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert_eq!(proof.verify(TAG, L, ek, &C, &g, &X, &setup.rp), Ok(()));
+    }
+
+    #[test]
+    fn rejects_other_point() {
+        let setup = setup();
+        let ek = &setup.prover.0;
+        let g = Point::<Secp256k1>::generator().to_point();
+        let x = sample_pm(L);
+        let (C, rho) = encrypt(ek, &x);
+        let X = point_pow(&g, &x);
+        let proof = LogStarProof::prove(TAG, L, ek, &C, &g, &X, &x, &rho, &setup.rp);
+        assert_eq!(
+            proof.verify(TAG, L, ek, &C, &g, &(&X + &g), &setup.rp),
+            Err(Error::InvalidProof("log*"))
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! Non-interactive zero-knowledge proofs of CGGMP21, made non-interactive with
+//! Fiat-Shamir. Every proof takes the `SessionContext` tag of its sender and round,
+//! and is checked against the ring-Pedersen parameters of its verifier.
+
+pub mod aff_g;
+pub mod enc;
+pub mod fac;
+pub mod log_star;
+pub mod modulus;
+pub mod mul_star;
+pub mod prm;
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use sha2::{Digest, Sha512};
+
+use super::Error;
+
+// hash of the tag and of every value, each with its sign and length
+fn hash(tag: &[u8], values: &[&BigInt]) -> Sha512 {
+    let mut h = Sha512::new().chain(tag);
+    for v in values {
+        let bytes = v.to_bytes();
+        h = h
+            .chain([(*v < &BigInt::zero()) as u8])
+            .chain((bytes.len() as u64).to_be_bytes())
+            .chain(bytes);
+    }
+    h
+}
+
+/// Challenge in [-q, q) for the proof `tag` with public `values` and `points`.
+pub(crate) fn challenge<E: Curve>(tag: &[u8], values: &[&BigInt], points: &[&Point<E>]) -> BigInt {
+    let q = Scalar::<E>::group_order();
+    let mut h = hash(tag, values);
+    for p in points {
+        h = h.chain(&*p.to_bytes(true));
+    }
+    BigInt::from_bytes(&h.finalize()).mod_floor(&(q * 2)) - q
+}
+
+/// `count` challenge bits for the proofs with binary challenges
+pub(crate) fn challenge_bits(tag: &[u8], values: &[&BigInt], count: usize) -> Vec<bool> {
+    let digest = hash(tag, values).finalize();
+    let mut bits = Vec::new();
+    let mut block = 0u64;
+    while bits.len() < count {
+        let d = Sha512::new()
+            .chain(digest)
+            .chain(block.to_be_bytes())
+            .finalize();
+        bits.extend(
+            d.iter()
+                .flat_map(|b| (0..8).map(move |i| (b >> i) & 1 == 1)),
+        );
+        block += 1;
+    }
+    bits.truncate(count);
+    bits
+}
+
+/// `count` elements of Z_n derived from the hash of `values`
+pub(crate) fn challenge_mod(
+    tag: &[u8],
+    values: &[&BigInt],
+    n: &BigInt,
+    count: usize,
+) -> Vec<BigInt> {
+    let digest = hash(tag, values).finalize();
+    // 256 bits more than n so that the reduction is close to uniform
+    let blocks = (n.bit_length() + 256) / 512 + 1;
+    (0..count as u64)
+        .map(|i| {
+            let bytes = (0..blocks as u64)
+                .flat_map(|j| {
+                    Sha512::new()
+                        .chain(digest)
+                        .chain(i.to_be_bytes())
+                        .chain(j.to_be_bytes())
+                        .finalize()
+                })
+                .collect::<Vec<u8>>();
+            BigInt::from_bytes(&bytes).mod_floor(n)
+        })
+        .collect()
+}
+
+pub(crate) fn check(ok: bool, proof: &'static str) -> Result<(), Error> {
+    if ok {
+        Ok(())
+    } else {
+        Err(Error::InvalidProof(proof))
+    }
+}
+
+/// g^x for a signed integer x
+pub(crate) fn point_pow<E: Curve>(g: &Point<E>, x: &BigInt) -> Point<E> {
+    g * Scalar::<E>::from_bigint(x)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::OnceLock;
+
+    use curv::arithmetic::traits::*;
+    use curv::BigInt;
+    use paillier::{DecryptionKey, EncryptionKey};
+
+    use crate::mp_cggmp::aux::RingPedersen;
+
+    pub(crate) const TAG: &[u8] = b"zk-test";
+
+    /// Paillier keys of a prover and a verifier, and the verifier's ring-Pedersen
+    /// parameters with their lambda
+    pub(crate) struct Setup {
+        pub prover: (EncryptionKey, DecryptionKey),
+        pub verifier: (EncryptionKey, DecryptionKey),
+        pub rp: RingPedersen,
+        pub lambda: BigInt,
+    }
+
+    // prime p = 3 mod 4 of `bits` bits; safe primes take minutes
+    fn blum_prime(bits: usize) -> BigInt {
+        loop {
+            let x = BigInt::sample(bits - 3) + (BigInt::one() << (bits - 3));
+            let p = (x << 2) + 3;
+            if p.is_probable_prime(30) {
+                return p;
+            }
+        }
+    }
+
+    fn blum_keypair() -> (EncryptionKey, DecryptionKey) {
+        let (p, q) = (blum_prime(1024), blum_prime(1024));
+        (EncryptionKey::from(&(&p * &q)), DecryptionKey { p, q })
+    }
+
+    /// shared by the tests of every proof, generated once
+    pub(crate) fn setup() -> &'static Setup {
+        static SETUP: OnceLock<Setup> = OnceLock::new();
+        SETUP.get_or_init(|| {
+            let verifier = blum_keypair();
+            let (rp, lambda) = RingPedersen::generate(&verifier.1);
+            Setup {
+                prover: blum_keypair(),
+                verifier,
+                rp,
+                lambda: (*lambda).clone(),
+            }
+        })
+    }
+}
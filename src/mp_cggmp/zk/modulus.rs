@@ -0,0 +1,160 @@
+//! Π^mod: N is a Paillier-Blum modulus, the product of two primes p, q = 3 mod 4
+//! with gcd(N, phi(N)) = 1, shown with M hash-derived challenges (CGGMP21, Figure 16).
+
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use paillier::DecryptionKey;
+use serde::{Deserialize, Serialize};
+
+use super::{challenge_mod, check};
+use crate::mp_cggmp::{sample_unit, Error, M};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModProof {
+    /// non-square with Jacobi symbol -1
+    pub w: BigInt,
+    /// fourth roots of (-1)^a * w^b * y
+    pub x: Vec<BigInt>,
+    pub a: Vec<bool>,
+    pub b: Vec<bool>,
+    /// N-th roots of y
+    pub z: Vec<BigInt>,
+}
+
+// a^((p - 1) / 2) mod p is 1 for squares
+fn is_square_mod(a: &BigInt, p: &BigInt) -> bool {
+    BigInt::mod_pow(a, &((p - 1) >> 1), p) == BigInt::one()
+}
+
+// Jacobi symbol (a / n) for odd n
+fn jacobi(a: &BigInt, n: &BigInt) -> i8 {
+    let (mut a, mut n) = (a.mod_floor(n), n.clone());
+    let mut result = 1;
+    let three = BigInt::from(3);
+    let five = BigInt::from(5);
+    let eight = BigInt::from(8);
+    let four = BigInt::from(4);
+    while !a.is_zero() {
+        while a.is_even() {
+            a >>= 1;
+            let r = n.mod_floor(&eight);
+            if r == three || r == five {
+                result = -result;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a.mod_floor(&four) == three && n.mod_floor(&four) == three {
+            result = -result;
+        }
+        a = a.mod_floor(&n);
+    }
+    if n == BigInt::one() {
+        result
+    } else {
+        0
+    }
+}
+
+// (-1)^a * w^b * y mod N
+fn twist(y: &BigInt, w: &BigInt, a: bool, b: bool, n: &BigInt) -> BigInt {
+    let mut v = y.clone();
+    if b {
+        v = BigInt::mod_mul(&v, w, n);
+    }
+    if a {
+        v = BigInt::mod_sub(&BigInt::zero(), &v, n);
+    }
+    v
+}
+
+impl ModProof {
+    pub fn prove(tag: &[u8], dk: &DecryptionKey) -> Self {
+        let (p, q) = (&dk.p, &dk.q);
+        let n = p * q;
+        let phi = (p - 1) * (q - 1);
+        let w = loop {
+            let w = sample_unit(&n);
+            if !is_square_mod(&w, p) && is_square_mod(&w, q) {
+                break w;
+            }
+        };
+        let n_inv = BigInt::mod_inv(&n, &phi).unwrap();
+        // fourth roots of squares mod p: ((p + 1) / 4)^2, the root that is itself a square
+        let e_p = BigInt::mod_pow(&((p + 1) >> 2), &BigInt::from(2), &(p - 1));
+        let e_q = BigInt::mod_pow(&((q + 1) >> 2), &BigInt::from(2), &(q - 1));
+        let p_inv = BigInt::mod_inv(p, q).unwrap();
+
+        let mut proof = Self {
+            w: w.clone(),
+            x: Vec::new(),
+            a: Vec::new(),
+            b: Vec::new(),
+            z: Vec::new(),
+        };
+        for y in challenge_mod(tag, &[&n, &w], &n, M) {
+            let (a, b, v) = [(false, false), (true, false), (false, true), (true, true)]
+                .into_iter()
+                .map(|(a, b)| (a, b, twist(&y, &w, a, b, &n)))
+                .find(|(_, _, v)| is_square_mod(v, p) && is_square_mod(v, q))
+                .expect("y is not a unit");
+            let x_p = BigInt::mod_pow(&v, &e_p, p);
+            let x_q = BigInt::mod_pow(&v, &e_q, q);
+            let x = &x_p + p * BigInt::mod_mul(&(&x_q - &x_p), &p_inv, q);
+            proof.x.push(x);
+            proof.a.push(a);
+            proof.b.push(b);
+            proof.z.push(BigInt::mod_pow(&y, &n_inv, &n));
+        }
+        proof
+    }
+
+    pub fn verify(&self, tag: &[u8], n: &BigInt) -> Result<(), Error> {
+        check(
+            n.is_odd() && n > &BigInt::one() && !n.is_probable_prime(30),
+            "mod",
+        )?;
+        check(
+            self.x.len() == M && self.a.len() == M && self.b.len() == M && self.z.len() == M,
+            "mod",
+        )?;
+        check(jacobi(&self.w, n) == -1, "mod")?;
+        let four = BigInt::from(4);
+        for (i, y) in challenge_mod(tag, &[n, &self.w], n, M)
+            .into_iter()
+            .enumerate()
+        {
+            check(BigInt::mod_pow(&self.z[i], n, n) == y, "mod")?;
+            check(
+                BigInt::mod_pow(&self.x[i], &four, n)
+                    == twist(&y, &self.w, self.a[i], self.b[i], n),
+                "mod",
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_cggmp::zk::tests::{setup, TAG};
+
+    #[test]
+    fn proves_blum_modulus() {
+        let (ek, dk) = &setup().prover;
+        let proof = ModProof::prove(TAG, dk);
+        let proof: ModProof =
+            serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert_eq!(proof.verify(TAG, &ek.n), Ok(()));
+    }
+
+    #[test]
+    fn rejects_other_modulus() {
+        let setup = setup();
+        let proof = ModProof::prove(TAG, &setup.prover.1);
+        assert_eq!(
+            proof.verify(TAG, &setup.verifier.0.n),
+            Err(Error::InvalidProof("mod"))
+        );
+    }
+}
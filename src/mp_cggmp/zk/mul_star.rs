@@ -0,0 +1,144 @@
+#![allow(non_snake_case)]
+//! Π^mul*: D = C^x * rho^N0 mod N0^2 multiplies the plaintext of C by x = log X,
+//! with x in ±2^L (CGGMP21, Figure 31).
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{Curve, Point};
+use curv::BigInt;
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+
+use super::{challenge, check, point_pow};
+use crate::mp_cggmp::aux::RingPedersen;
+use crate::mp_cggmp::signed;
+use crate::mp_cggmp::{
+    in_range, mul, pow_signed, sample_pm, sample_pm_n, sample_unit, Error, EPSILON, L,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct MulStarProof<E: Curve> {
+    pub A: BigInt,
+    pub Bx: Point<E>,
+    pub E: BigInt,
+    pub S: BigInt,
+    #[serde(with = "signed")]
+    pub z1: BigInt,
+    #[serde(with = "signed")]
+    pub z2: BigInt,
+    pub w: BigInt,
+}
+
+// C^x * r^N0 mod N0^2
+fn mul_rand(ek: &EncryptionKey, C: &BigInt, x: &BigInt, r: &BigInt) -> BigInt {
+    BigInt::mod_mul(&mul(ek, C, x), &BigInt::mod_pow(r, &ek.n, &ek.nn), &ek.nn)
+}
+
+impl<E: Curve> MulStarProof<E> {
+    pub fn prove(
+        tag: &[u8],
+        ek: &EncryptionKey,
+        C: &BigInt,
+        D: &BigInt,
+        X: &Point<E>,
+        x: &BigInt,
+        rho: &BigInt,
+        rp: &RingPedersen,
+    ) -> Self {
+        let alpha = sample_pm(L + EPSILON);
+        let r = sample_unit(&ek.n);
+        let gamma = sample_pm_n(L + EPSILON, &rp.n);
+        let m = sample_pm_n(L, &rp.n);
+
+        let A = mul_rand(ek, C, &alpha, &r);
+        let Bx = point_pow(&Point::generator().to_point(), &alpha);
+        let E = rp.commit(&alpha, &gamma);
+        let S = rp.commit(x, &m);
+        let e = challenge::<E>(
+            tag,
+            &[&ek.n, C, D, &rp.n, &rp.s, &rp.t, &A, &E, &S],
+            &[X, &Bx],
+        );
+
+        Self {
+            z1: &alpha + &e * x,
+            z2: gamma + &e * m,
+            w: BigInt::mod_mul(&r, &pow_signed(rho, &e, &ek.n), &ek.n),
+            A,
+            Bx,
+            E,
+            S,
+        }
+    }
+
+    pub fn verify(
+        &self,
+        tag: &[u8],
+        ek: &EncryptionKey,
+        C: &BigInt,
+        D: &BigInt,
+        X: &Point<E>,
+        rp: &RingPedersen,
+    ) -> Result<(), Error> {
+        let e = challenge::<E>(
+            tag,
+            &[&ek.n, C, D, &rp.n, &rp.s, &rp.t, &self.A, &self.E, &self.S],
+            &[X, &self.Bx],
+        );
+        check(in_range(&self.z1, L + EPSILON), "mul*")?;
+        check(
+            mul_rand(ek, C, &self.z1, &self.w) == BigInt::mod_mul(&self.A, &mul(ek, D, &e), &ek.nn),
+            "mul*",
+        )?;
+        check(
+            point_pow(&Point::generator().to_point(), &self.z1) == &self.Bx + point_pow(X, &e),
+            "mul*",
+        )?;
+        check(
+            rp.commit(&self.z1, &self.z2) == rp.mul_pow(&self.S, &e, &self.E),
+            "mul*",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_cggmp::encrypt;
+    use crate::mp_cggmp::zk::tests::{setup, TAG};
+    use curv::elliptic::curves::Secp256k1;
+
+    // C, D = C^x * rho^N0, X = x * G, x and rho
+    fn statement(ek: &EncryptionKey) -> (BigInt, BigInt, Point<Secp256k1>, BigInt, BigInt) {
+        let (C, _) = encrypt(ek, &sample_pm(L));
+        let x = sample_pm(L);
+        let rho = sample_unit(&ek.n);
+        let D = mul_rand(ek, &C, &x, &rho);
+        let X = point_pow(&Point::generator().to_point(), &x);
+        (C, D, X, x, rho)
+    }
+
+    #[test]
+    fn proves_multiplication() {
+        let setup = setup();
+        let ek = &setup.prover.0;
+        let (C, D, X, x, rho) = statement(ek);
+        let proof = MulStarProof::prove(TAG, ek, &C, &D, &X, &x, &rho, &setup.rp);
+        let proof: MulStarProof<Secp256k1> =
+            serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert_eq!(proof.verify(TAG, ek, &C, &D, &X, &setup.rp), Ok(()));
+    }
+
+    #[test]
+    fn rejects_other_product() {
+        let setup = setup();
+        let ek = &setup.prover.0;
+        let (C, D, X, x, rho) = statement(ek);
+        let proof = MulStarProof::prove(TAG, ek, &C, &D, &X, &x, &rho, &setup.rp);
+        let other = mul_rand(ek, &C, &(x + 1), &rho);
+        assert_eq!(
+            proof.verify(TAG, ek, &C, &other, &X, &setup.rp),
+            Err(Error::InvalidProof("mul*"))
+        );
+    }
+}
@@ -0,0 +1,91 @@
+#![allow(non_snake_case)]
+//! Π^prm: s = t^lambda mod N for ring-Pedersen parameters (N, s, t), with M binary
+//! challenges (CGGMP21, Figure 17).
+
+use curv::arithmetic::traits::*;
+use curv::BigInt;
+use paillier::DecryptionKey;
+use serde::{Deserialize, Serialize};
+
+use super::{challenge_bits, check};
+use crate::mp_cggmp::aux::RingPedersen;
+use crate::mp_cggmp::{Error, M};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PrmProof {
+    pub A: Vec<BigInt>,
+    pub z: Vec<BigInt>,
+}
+
+impl PrmProof {
+    pub fn prove(tag: &[u8], rp: &RingPedersen, lambda: &BigInt, dk: &DecryptionKey) -> Self {
+        let phi = (&dk.p - 1) * (&dk.q - 1);
+        let a = (0..M)
+            .map(|_| BigInt::sample_below(&phi))
+            .collect::<Vec<_>>();
+        let A = a
+            .iter()
+            .map(|a_i| BigInt::mod_pow(&rp.t, a_i, &rp.n))
+            .collect::<Vec<_>>();
+        let e = Self::challenge(tag, rp, &A);
+        let z = a
+            .iter()
+            .zip(e)
+            .map(|(a_i, e_i)| {
+                if e_i {
+                    (a_i + lambda).mod_floor(&phi)
+                } else {
+                    a_i.clone()
+                }
+            })
+            .collect();
+        Self { A, z }
+    }
+
+    pub fn verify(&self, tag: &[u8], rp: &RingPedersen) -> Result<(), Error> {
+        check(self.A.len() == M && self.z.len() == M, "prm")?;
+        let e = Self::challenge(tag, rp, &self.A);
+        for ((A_i, z_i), e_i) in self.A.iter().zip(&self.z).zip(e) {
+            check(rp.is_unit(A_i) && z_i >= &BigInt::zero(), "prm")?;
+            let rhs = if e_i {
+                BigInt::mod_mul(A_i, &rp.s, &rp.n)
+            } else {
+                A_i.clone()
+            };
+            check(BigInt::mod_pow(&rp.t, z_i, &rp.n) == rhs, "prm")?;
+        }
+        Ok(())
+    }
+
+    fn challenge(tag: &[u8], rp: &RingPedersen, A: &[BigInt]) -> Vec<bool> {
+        let mut values = vec![&rp.n, &rp.s, &rp.t];
+        values.extend(A);
+        challenge_bits(tag, &values, M)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_cggmp::zk::tests::{setup, TAG};
+
+    #[test]
+    fn proves_ring_pedersen() {
+        let setup = setup();
+        let proof = PrmProof::prove(TAG, &setup.rp, &setup.lambda, &setup.verifier.1);
+        let proof: PrmProof =
+            serde_json::from_str(&serde_json::to_string(&proof).unwrap()).unwrap();
+        assert_eq!(proof.verify(TAG, &setup.rp), Ok(()));
+    }
+
+    #[test]
+    fn rejects_wrong_lambda() {
+        let setup = setup();
+        let lambda = &setup.lambda + BigInt::one();
+        let proof = PrmProof::prove(TAG, &setup.rp, &lambda, &setup.verifier.1);
+        assert_eq!(
+            proof.verify(TAG, &setup.rp),
+            Err(Error::InvalidProof("prm"))
+        );
+    }
+}
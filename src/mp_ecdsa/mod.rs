@@ -20,7 +20,10 @@
 
 pub mod feldman_vss;
 pub mod mta;
+pub mod paillier_serde;
 pub mod party_i;
+pub mod secret;
+pub mod validate;
 
 #[derive(Copy, PartialEq, Eq, Clone, Debug)]
 pub enum Error {
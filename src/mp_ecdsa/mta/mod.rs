@@ -15,21 +15,23 @@
 */
 
 /// MtA is described in https://eprint.iacr.org/2019/114.pdf section 3
+pub mod ot;
 pub mod range_proofs;
-use curv::arithmetic::traits::Samplable;
+use curv::arithmetic::traits::{Modulo, Samplable};
 use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
-use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Curve, Point, Scalar};
 use curv::BigInt;
-use paillier::traits::EncryptWithChosenRandomness;
 use paillier::{Add, Decrypt, Mul};
-use paillier::{DecryptionKey, EncryptionKey, Paillier, Randomness, RawCiphertext, RawPlaintext};
+use paillier::{DecryptionKey, EncryptionKey, Paillier, RawCiphertext, RawPlaintext};
 use zk_paillier::zkproofs::DLogStatement;
 
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sha2::Sha256;
 
-use super::party_i::PartyPrivate;
+use super::party_i::{Keys, PartyPrivate, SessionContext, SignKeys};
+use super::validate::Validate;
 use super::Error::{self, InvalidKey};
+use ot::{OtMulBob, OtMulReply, OtMulRequest, OtSetup};
 use range_proofs::AliceProof;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -39,10 +41,51 @@ pub struct MessageA {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct MessageB {
+#[serde(bound = "")]
+pub struct MessageB<E: Curve = Secp256k1> {
     pub c: BigInt, // paillier encryption
-    pub b_proof: DLogProof<Secp256k1, Sha256>,
-    pub beta_tag_proof: DLogProof<Secp256k1, Sha256>,
+    pub b_proof: DLogProof<E, Sha256>,
+    pub beta_tag_proof: DLogProof<E, Sha256>,
+}
+
+/// Paillier encryption randomness r under a key N, with r^N mod N^2, which is
+/// most of the cost of an encryption, computed ahead of time
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PaillierRandomness {
+    pub r: BigInt,
+    pub rn: BigInt,
+}
+
+impl PaillierRandomness {
+    pub fn new(ek: &EncryptionKey, r: BigInt) -> Self {
+        let rn = BigInt::mod_pow(&r, &ek.n, &ek.nn);
+        Self { r, rn }
+    }
+
+    pub fn sample(ek: &EncryptionKey) -> Self {
+        Self::new(ek, BigInt::sample_below(&ek.n))
+    }
+
+    // (1 + N)^m = 1 + m * N mod N^2
+    fn encrypt(&self, ek: &EncryptionKey, m: &BigInt) -> BigInt {
+        let gm = (BigInt::from(1) + m * &ek.n) % &ek.nn;
+        BigInt::mod_mul(&gm, &self.rn, &ek.nn)
+    }
+}
+
+/// Where MtA gets its Paillier encryption randomness from. Every value it gives
+/// must be fresh: randomness used twice under a key reveals the plaintexts' difference.
+pub trait RandomnessSource: Sync {
+    fn take(&self, ek: &EncryptionKey) -> PaillierRandomness;
+}
+
+/// Randomness sampled, and r^N computed, when needed
+pub struct FreshRandomness;
+
+impl RandomnessSource for FreshRandomness {
+    fn take(&self, ek: &EncryptionKey) -> PaillierRandomness {
+        PaillierRandomness::sample(ek)
+    }
 }
 
 impl MessageA {
@@ -50,8 +93,8 @@ impl MessageA {
     /// - other parties' `h1,h2,N_tilde`s for range proofs.
     /// If range proofs are not needed (one example is identification of aborts where we
     /// only want to reconstruct a ciphertext), `dlog_statements` can be an empty slice.
-    pub fn a(
-        a: &Scalar<Secp256k1>,
+    pub fn a<E: Curve>(
+        a: &Scalar<E>,
         alice_ek: &EncryptionKey,
         dlog_statements: &[DLogStatement],
     ) -> (Self, BigInt) {
@@ -60,24 +103,33 @@ impl MessageA {
         (m_a, randomness)
     }
 
-    pub fn a_with_predefined_randomness(
-        a: &Scalar<Secp256k1>,
+    pub fn a_with_predefined_randomness<E: Curve>(
+        a: &Scalar<E>,
         alice_ek: &EncryptionKey,
         randomness: &BigInt,
         dlog_statements: &[DLogStatement],
     ) -> Self {
-        let c_a = Paillier::encrypt_with_chosen_randomness(
-            alice_ek,
-            RawPlaintext::from(a.to_bigint()),
-            &Randomness::from(randomness.clone()),
-        )
-        .0
-        .clone()
-        .into_owned();
+        let randomness = PaillierRandomness::new(alice_ek, randomness.clone());
+        MessageA::a_with_precomputed_randomness(a, alice_ek, &randomness, dlog_statements)
+    }
+
+    pub fn a_with_precomputed_randomness<E: Curve>(
+        a: &Scalar<E>,
+        alice_ek: &EncryptionKey,
+        randomness: &PaillierRandomness,
+        dlog_statements: &[DLogStatement],
+    ) -> Self {
+        let c_a = randomness.encrypt(alice_ek, &a.to_bigint());
         let alice_range_proofs = dlog_statements
             .iter()
             .map(|dlog_statement| {
-                AliceProof::generate(&a.to_bigint(), &c_a, alice_ek, dlog_statement, randomness)
+                AliceProof::generate::<E>(
+                    &a.to_bigint(),
+                    &c_a,
+                    alice_ek,
+                    dlog_statement,
+                    &randomness.r,
+                )
             })
             .collect::<Vec<AliceProof>>();
 
@@ -88,13 +140,16 @@ impl MessageA {
     }
 }
 
-impl MessageB {
+impl<E: Curve> MessageB<E> {
+    /// `ctx` is the Fiat-Shamir context of Bob's dlog proofs, see
+    /// [SessionContext::tag](super::party_i::SessionContext::tag).
     pub fn b(
-        b: &Scalar<Secp256k1>,
+        b: &Scalar<E>,
         alice_ek: &EncryptionKey,
         m_a: MessageA,
         dlog_statements: &[DLogStatement],
-    ) -> Result<(Self, Scalar<Secp256k1>, BigInt, BigInt), Error> {
+        ctx: &[u8],
+    ) -> Result<(Self, Scalar<E>, BigInt, BigInt), Error> {
         let beta_tag = BigInt::sample_below(&alice_ek.n);
         let randomness = BigInt::sample_below(&alice_ek.n);
         let (m_b, beta) = MessageB::b_with_predefined_randomness(
@@ -104,19 +159,42 @@ impl MessageB {
             &randomness,
             &beta_tag,
             dlog_statements,
+            ctx,
         )?;
 
         Ok((m_b, beta, randomness, beta_tag))
     }
 
     pub fn b_with_predefined_randomness(
-        b: &Scalar<Secp256k1>,
+        b: &Scalar<E>,
         alice_ek: &EncryptionKey,
         m_a: MessageA,
         randomness: &BigInt,
         beta_tag: &BigInt,
         dlog_statements: &[DLogStatement],
-    ) -> Result<(Self, Scalar<Secp256k1>), Error> {
+        ctx: &[u8],
+    ) -> Result<(Self, Scalar<E>), Error> {
+        let randomness = PaillierRandomness::new(alice_ek, randomness.clone());
+        MessageB::b_with_precomputed_randomness(
+            b,
+            alice_ek,
+            m_a,
+            &randomness,
+            beta_tag,
+            dlog_statements,
+            ctx,
+        )
+    }
+
+    pub fn b_with_precomputed_randomness(
+        b: &Scalar<E>,
+        alice_ek: &EncryptionKey,
+        m_a: MessageA,
+        randomness: &PaillierRandomness,
+        beta_tag: &BigInt,
+        dlog_statements: &[DLogStatement],
+        ctx: &[u8],
+    ) -> Result<(Self, Scalar<E>), Error> {
         if m_a.range_proofs.len() != dlog_statements.len() {
             return Err(InvalidKey);
         }
@@ -125,17 +203,13 @@ impl MessageB {
             .range_proofs
             .iter()
             .zip(dlog_statements)
-            .map(|(proof, dlog_statement)| proof.verify(&m_a.c, alice_ek, dlog_statement))
+            .map(|(proof, dlog_statement)| proof.verify::<E>(&m_a.c, alice_ek, dlog_statement))
             .all(|x| x)
         {
             return Err(InvalidKey);
         };
-        let beta_tag_fe = Scalar::<Secp256k1>::from(beta_tag);
-        let c_beta_tag = Paillier::encrypt_with_chosen_randomness(
-            alice_ek,
-            RawPlaintext::from(beta_tag),
-            &Randomness::from(randomness.clone()),
-        );
+        let beta_tag_fe = Scalar::<E>::from(beta_tag);
+        let c_beta_tag = RawCiphertext::from(randomness.encrypt(alice_ek, beta_tag));
 
         let b_bn = b.to_bigint();
         let b_c_a = Paillier::mul(
@@ -144,9 +218,9 @@ impl MessageB {
             RawPlaintext::from(b_bn),
         );
         let c_b = Paillier::add(alice_ek, b_c_a, c_beta_tag);
-        let beta = Scalar::<Secp256k1>::zero() - &beta_tag_fe;
-        let dlog_proof_b = DLogProof::prove(b);
-        let dlog_proof_beta_tag = DLogProof::prove(&beta_tag_fe);
+        let beta = Scalar::<E>::zero() - &beta_tag_fe;
+        let dlog_proof_b = DLogProof::prove_with_ctx(b, ctx);
+        let dlog_proof_beta_tag = DLogProof::prove_with_ctx(&beta_tag_fe, ctx);
 
         Ok((
             Self {
@@ -161,15 +235,16 @@ impl MessageB {
     pub fn verify_proofs_get_alpha(
         &self,
         dk: &DecryptionKey,
-        a: &Scalar<Secp256k1>,
-    ) -> Result<(Scalar<Secp256k1>, BigInt), Error> {
+        a: &Scalar<E>,
+        ctx: &[u8],
+    ) -> Result<(Scalar<E>, BigInt), Error> {
         let alice_share = Paillier::decrypt(dk, &RawCiphertext::from(self.c.clone()));
         let g = Point::generator();
-        let alpha = Scalar::<Secp256k1>::from(alice_share.0.as_ref());
+        let alpha = Scalar::<E>::from(alice_share.0.as_ref());
         let g_alpha = g * &alpha;
         let ba_btag = &self.b_proof.pk * a + &self.beta_tag_proof.pk;
-        if DLogProof::verify(&self.b_proof).is_ok()
-            && DLogProof::verify(&self.beta_tag_proof).is_ok()
+        if DLogProof::verify_with_ctx(&self.b_proof, ctx).is_ok()
+            && DLogProof::verify_with_ctx(&self.beta_tag_proof, ctx).is_ok()
             // we prove the correctness of the ciphertext using this check and the proof of knowledge of dlog of beta_tag
             && ba_btag == g_alpha
         {
@@ -183,17 +258,18 @@ impl MessageB {
     //  with the regular version mta can be used in general
     pub fn verify_proofs_get_alpha_gg18(
         &self,
-        private: &PartyPrivate,
-        a: &Scalar<Secp256k1>,
-    ) -> Result<Scalar<Secp256k1>, Error> {
+        private: &PartyPrivate<E>,
+        a: &Scalar<E>,
+        ctx: &[u8],
+    ) -> Result<Scalar<E>, Error> {
         let alice_share = private.decrypt(self.c.clone());
         let g = Point::generator();
-        let alpha = Scalar::<Secp256k1>::from(alice_share.0.as_ref());
+        let alpha = Scalar::<E>::from(alice_share.0.as_ref());
         let g_alpha = g * &alpha;
         let ba_btag = &self.b_proof.pk * a + &self.beta_tag_proof.pk;
 
-        if DLogProof::verify(&self.b_proof).is_ok()
-            && DLogProof::verify(&self.beta_tag_proof).is_ok()
+        if DLogProof::verify_with_ctx(&self.b_proof, ctx).is_ok()
+            && DLogProof::verify_with_ctx(&self.beta_tag_proof, ctx).is_ok()
             && ba_btag == g_alpha
         {
             Ok(alpha)
@@ -202,10 +278,334 @@ impl MessageB {
         }
     }
 
-    pub fn verify_b_against_public(
-        public_gb: &Point<Secp256k1>,
-        mta_gb: &Point<Secp256k1>,
-    ) -> bool {
+    pub fn verify_b_against_public(public_gb: &Point<E>, mta_gb: &Point<E>) -> bool {
         public_gb == mta_gb
     }
 }
+
+/// The other party of a pair of multiplications
+#[derive(Clone, Copy, Debug)]
+pub struct Peer {
+    /// party number in the session
+    pub party: u16,
+    /// key share index
+    pub index: u16,
+}
+
+/// Proof contexts of the k_i * gamma_j and k_i * w_j multiplications
+pub struct MtaContext {
+    /// this party's number in the session
+    pub me: u16,
+    pub gamma: SessionContext,
+    pub w: SessionContext,
+}
+
+/// This party's additive shares of k_i * gamma_j (alpha) and k_i * w_j (miu) as
+/// Alice, and of k_j * gamma_i (beta) and k_j * w_i (ni) as Bob, with the peer's
+/// proofs of knowledge of gamma_j and w_j.
+#[derive(Clone)]
+pub struct MtaShares<E: Curve> {
+    pub alpha: Scalar<E>,
+    pub miu: Scalar<E>,
+    pub beta: Scalar<E>,
+    pub ni: Scalar<E>,
+    pub gamma_proof: DLogProof<E, Sha256>,
+    pub w_proof: DLogProof<E, Sha256>,
+}
+
+/// Bob's shares beta and ni times G, for Alice to check that he multiplied by the
+/// gamma_j and w_j he proved knowledge of: k_i * Gamma_j = (alpha + beta) * G and
+/// k_i * W_j = (miu + ni) * G. Alice can compute them herself, so they tell her
+/// nothing, and Bob cannot make them fit other inputs without k_i * G.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct MtaCheck<E: Curve> {
+    pub beta: Point<E>,
+    pub ni: Point<E>,
+}
+
+impl<E: Curve> MtaShares<E> {
+    /// What to send the peer, as Bob, for its `check`
+    pub fn check_message(&self) -> MtaCheck<E> {
+        MtaCheck {
+            beta: Point::generator() * &self.beta,
+            ni: Point::generator() * &self.ni,
+        }
+    }
+
+    /// Checks, as Alice with `k_i`, the shares of the peer as Bob
+    pub fn check(&self, k_i: &Scalar<E>, msg: &MtaCheck<E>) -> Result<(), Error> {
+        let g = Point::generator();
+        if &self.gamma_proof.pk * k_i != g * &self.alpha + &msg.beta
+            || &self.w_proof.pk * k_i != g * &self.miu + &msg.ni
+        {
+            return Err(InvalidKey);
+        }
+        Ok(())
+    }
+}
+
+/// Multiplication of each signer's k_i by every other signer's gamma_j and w_j, in
+/// two rounds of messages between each pair. Signing picks the implementation per
+/// key: `PaillierMta` or `OtMta`. Round 1 goes out with the signers' agreement if
+/// `early_round1` gives it, else in a round of its own once the signers are known.
+pub trait Mta<E: Curve>: Sync {
+    type Round1: Serialize + DeserializeOwned + Validate + Clone + Send;
+    type Round2: Serialize + DeserializeOwned + Validate + Clone + Send;
+    /// what a party keeps about a peer between rounds
+    type State: Clone + Send;
+    /// whether round 2 does not tie Bob's inputs to Gamma_j and W_j, so that Alice
+    /// checks his shares with `MtaShares::check` after a third message
+    const CHECK_SHARES: bool = false;
+
+    fn round1_context(&self, peer: &Peer) -> <Self::Round1 as Validate>::Context;
+
+    fn round2_context(&self, peer: &Peer) -> <Self::Round2 as Validate>::Context;
+
+    /// Round 1 for every peer, if it only depends on k_i: it can then be sent before
+    /// the signers, and so the key share of each peer, are known.
+    fn early_round1(&self, _sign_keys: &SignKeys<E>) -> Option<(Self::State, Self::Round1)> {
+        None
+    }
+
+    fn round1(
+        &self,
+        ctx: &MtaContext,
+        sign_keys: &SignKeys<E>,
+        peer: &Peer,
+    ) -> (Self::State, Self::Round1);
+
+    fn round2(
+        &self,
+        ctx: &MtaContext,
+        sign_keys: &SignKeys<E>,
+        peer: &Peer,
+        state: &mut Self::State,
+        msg: &Self::Round1,
+    ) -> Result<Self::Round2, Error>;
+
+    fn finish(
+        &self,
+        ctx: &MtaContext,
+        sign_keys: &SignKeys<E>,
+        peer: &Peer,
+        state: Self::State,
+        msg: &Self::Round2,
+    ) -> Result<MtaShares<E>, Error>;
+}
+
+/// GG18 MtA and MtAwc: Alice sends k_i under her Paillier key, Bob replies with
+/// k_i * gamma_j + beta' and k_i * w_j + ni'.
+pub struct PaillierMta<'a, E: Curve> {
+    pub keys: &'a Keys<E>,
+    /// indexed by key share
+    pub paillier_key_vec: &'a [EncryptionKey],
+    pub randomness: &'a dyn RandomnessSource,
+}
+
+/// beta and ni, known once Bob has replied
+#[derive(Clone)]
+pub struct PaillierMtaState<E: Curve> {
+    beta: Scalar<E>,
+    ni: Scalar<E>,
+}
+
+impl<'a, E: Curve> Mta<E> for PaillierMta<'a, E> {
+    type Round1 = MessageA;
+    type Round2 = (MessageB<E>, MessageB<E>);
+    type State = PaillierMtaState<E>;
+
+    fn round1_context(&self, peer: &Peer) -> EncryptionKey {
+        self.paillier_key_vec[usize::from(peer.index)].clone()
+    }
+
+    fn round2_context(&self, _: &Peer) -> (EncryptionKey, EncryptionKey) {
+        (self.keys.ek.clone(), self.keys.ek.clone())
+    }
+
+    // Alice encrypts k_i once, for all peers
+    fn early_round1(&self, sign_keys: &SignKeys<E>) -> Option<(Self::State, MessageA)> {
+        let randomness = self.randomness.take(&self.keys.ek);
+        let m_a_k = MessageA::a_with_precomputed_randomness(
+            &sign_keys.k_i,
+            &self.keys.ek,
+            &randomness,
+            &[],
+        );
+        let state = PaillierMtaState {
+            beta: Scalar::zero(),
+            ni: Scalar::zero(),
+        };
+        Some((state, m_a_k))
+    }
+
+    fn round1(&self, _: &MtaContext, sign_keys: &SignKeys<E>, _: &Peer) -> (Self::State, MessageA) {
+        self.early_round1(sign_keys).unwrap()
+    }
+
+    fn round2(
+        &self,
+        ctx: &MtaContext,
+        sign_keys: &SignKeys<E>,
+        peer: &Peer,
+        state: &mut Self::State,
+        m_a: &MessageA,
+    ) -> Result<Self::Round2, Error> {
+        let alice_ek = &self.paillier_key_vec[usize::from(peer.index)];
+        let (m_b_gamma, beta) = MessageB::b_with_precomputed_randomness(
+            &sign_keys.gamma_i,
+            alice_ek,
+            m_a.clone(),
+            &self.randomness.take(alice_ek),
+            &BigInt::sample_below(&alice_ek.n),
+            &[],
+            &ctx.gamma.tag(ctx.me),
+        )?;
+        let (m_b_w, ni) = MessageB::b_with_precomputed_randomness(
+            &sign_keys.w_i,
+            alice_ek,
+            m_a.clone(),
+            &self.randomness.take(alice_ek),
+            &BigInt::sample_below(&alice_ek.n),
+            &[],
+            &ctx.w.tag(ctx.me),
+        )?;
+        state.beta = beta;
+        state.ni = ni;
+        Ok((m_b_gamma, m_b_w))
+    }
+
+    fn finish(
+        &self,
+        ctx: &MtaContext,
+        sign_keys: &SignKeys<E>,
+        peer: &Peer,
+        state: Self::State,
+        (m_b_gamma, m_b_w): &Self::Round2,
+    ) -> Result<MtaShares<E>, Error> {
+        let (alpha, _) = m_b_gamma.verify_proofs_get_alpha(
+            &self.keys.dk,
+            &sign_keys.k_i,
+            &ctx.gamma.tag(peer.party),
+        )?;
+        let (miu, _) =
+            m_b_w.verify_proofs_get_alpha(&self.keys.dk, &sign_keys.k_i, &ctx.w.tag(peer.party))?;
+        Ok(MtaShares {
+            alpha,
+            miu,
+            beta: state.beta,
+            ni: state.ni,
+            gamma_proof: m_b_gamma.b_proof.clone(),
+            w_proof: m_b_w.b_proof.clone(),
+        })
+    }
+}
+
+/// Multiplication from OT extension over the base OTs set up with each key share,
+/// see [ot]. Bob starts with gamma_j and w_j, Alice replies with k_i. Round 1
+/// depends on the base OTs with the peer's key share, so it waits for the signers.
+/// Nothing in the extension shows that Bob's inputs are the ones of his proofs of
+/// knowledge: Alice checks them with `MtaShares::check`, as in DKLs.
+pub struct OtMta<'a> {
+    pub setup: &'a OtSetup,
+}
+
+/// Bob's round 1 message: the extension, with proofs of knowledge of his inputs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct OtMtaRequest<E: Curve> {
+    pub request: OtMulRequest,
+    pub gamma_proof: DLogProof<E, Sha256>,
+    pub w_proof: DLogProof<E, Sha256>,
+}
+
+#[derive(Clone)]
+pub struct OtMtaState<E: Curve> {
+    bob: OtMulBob,
+    alice: Option<MtaShares<E>>,
+}
+
+// extensions are bound to the session, the message and both parties
+fn ot_tag(ctx: &MtaContext, alice: u16, bob: u16) -> Vec<u8> {
+    [ctx.gamma.tag(alice), ctx.w.tag(bob)].concat()
+}
+
+impl<'a, E: Curve> Mta<E> for OtMta<'a> {
+    type Round1 = OtMtaRequest<E>;
+    type Round2 = OtMulReply<E>;
+    type State = OtMtaState<E>;
+    const CHECK_SHARES: bool = true;
+
+    fn round1_context(&self, _: &Peer) -> usize {
+        2
+    }
+
+    fn round2_context(&self, _: &Peer) -> usize {
+        2
+    }
+
+    fn round1(
+        &self,
+        ctx: &MtaContext,
+        sign_keys: &SignKeys<E>,
+        peer: &Peer,
+    ) -> (Self::State, Self::Round1) {
+        let (bob, request) = OtMulBob::new(
+            self.setup.pair(peer.index),
+            &ot_tag(ctx, peer.party, ctx.me),
+            &[&*sign_keys.gamma_i, &*sign_keys.w_i],
+        );
+        let msg = OtMtaRequest {
+            request,
+            gamma_proof: DLogProof::prove_with_ctx(&sign_keys.gamma_i, &ctx.gamma.tag(ctx.me)),
+            w_proof: DLogProof::prove_with_ctx(&sign_keys.w_i, &ctx.w.tag(ctx.me)),
+        };
+        (OtMtaState { bob, alice: None }, msg)
+    }
+
+    fn round2(
+        &self,
+        ctx: &MtaContext,
+        sign_keys: &SignKeys<E>,
+        peer: &Peer,
+        state: &mut Self::State,
+        msg: &Self::Round1,
+    ) -> Result<Self::Round2, Error> {
+        if DLogProof::verify_with_ctx(&msg.gamma_proof, &ctx.gamma.tag(peer.party)).is_err()
+            || DLogProof::verify_with_ctx(&msg.w_proof, &ctx.w.tag(peer.party)).is_err()
+        {
+            return Err(InvalidKey);
+        }
+        let (shares, reply) = ot::mul_reply(
+            self.setup.pair(peer.index),
+            &ot_tag(ctx, ctx.me, peer.party),
+            &sign_keys.k_i,
+            &msg.request,
+            2,
+        )?;
+        state.alice = Some(MtaShares {
+            alpha: shares[0].clone(),
+            miu: shares[1].clone(),
+            beta: Scalar::zero(),
+            ni: Scalar::zero(),
+            gamma_proof: msg.gamma_proof.clone(),
+            w_proof: msg.w_proof.clone(),
+        });
+        Ok(reply)
+    }
+
+    fn finish(
+        &self,
+        ctx: &MtaContext,
+        _: &SignKeys<E>,
+        peer: &Peer,
+        state: Self::State,
+        reply: &Self::Round2,
+    ) -> Result<MtaShares<E>, Error> {
+        let shares = state.bob.finish(&ot_tag(ctx, peer.party, ctx.me), reply)?;
+        let mut mta_shares = state.alice.ok_or(InvalidKey)?;
+        mta_shares.beta = shares[0].clone();
+        mta_shares.ni = shares[1].clone();
+        Ok(mta_shares)
+    }
+}
@@ -0,0 +1,611 @@
+//! Two-party multiplication from oblivious transfer, as in DKLs: Doerner, Kondi,
+//! Lee, shelat, "Secure Two-party Threshold ECDSA from ECDSA Assumptions", S&P 2018,
+//! <https://eprint.iacr.org/2018/499>, and "Threshold ECDSA from ECDSA Assumptions:
+//! The Multiparty Case", S&P 2019, <https://eprint.iacr.org/2019/523>.
+//!
+//! Every pair of key shares runs KAPPA base OTs once (Chou-Orlandi, with a proof of
+//! knowledge of the sender's key). Each multiplication extends them with KOS
+//! (<https://eprint.iacr.org/2015/546>) under a tag unique to the session, the
+//! message and the pair, so pads are never reused. Alice, the extension sender,
+//! inputs a; Bob, the extension receiver, inputs b through a random encoding, so
+//! that the bits a cheating Alice learns from a failed check say nothing about b.
+
+use curv::arithmetic::traits::*;
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::super::secret::Secret;
+use super::super::Error::{self, InvalidKey};
+
+/// name of the MtA recorded with the base OTs in keys files
+pub const MTA: &str = "ot";
+/// number of base OTs per pair, the computational security of the extension
+pub const KAPPA: usize = 128;
+/// statistical security
+const STAT_SECURITY: usize = 80;
+// bits of the group order
+const Q_BITS: usize = 256;
+/// length of the encoding of Bob's input: the bits of b minus a random subset sum
+/// of Q_BITS + 2 * STAT_SECURITY public scalars, followed by the subset
+pub const XI: usize = 2 * Q_BITS + 2 * STAT_SECURITY;
+/// random rows appended for the KOS consistency check
+pub const PAD: usize = KAPPA + STAT_SECURITY;
+const SEED_LEN: usize = 32;
+
+/// Base OTs between this key share and one other
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OtPair {
+    /// as extension sender: the choice bits of the base OTs, hex
+    pub delta: Secret<String>,
+    /// and the seed received in each, hex
+    pub received: Secret<String>,
+    /// as extension receiver: both seeds of each base OT, hex
+    pub sent: Secret<String>,
+}
+
+/// Base OTs with every other key share, saved after the GG18 part of a keys file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OtSetup {
+    pub mta: String,
+    /// indexed by key share, None at this one
+    pub pairs: Vec<Option<OtPair>>,
+}
+
+impl OtSetup {
+    pub fn new(pairs: Vec<Option<OtPair>>) -> Self {
+        Self {
+            mta: MTA.to_string(),
+            pairs,
+        }
+    }
+
+    /// Checks that there are base OTs with each of the other `share_count - 1` key
+    /// shares, `index` being this one.
+    pub fn check(&self, share_count: usize, index: usize) -> Result<(), &'static str> {
+        if self.mta != MTA {
+            return Err("unknown MtA");
+        }
+        if self.pairs.len() != share_count {
+            return Err("base OTs do not match the share count");
+        }
+        for (j, pair) in self.pairs.iter().enumerate() {
+            match pair {
+                None if j == index => {}
+                Some(pair)
+                    if j != index
+                        && pair.delta.len() == 2 * KAPPA / 8
+                        && pair.received.len() == 2 * KAPPA * SEED_LEN
+                        && pair.sent.len() == 4 * KAPPA * SEED_LEN => {}
+                _ => return Err("malformed base OTs"),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn pair(&self, index: u16) -> &OtPair {
+        self.pairs[usize::from(index)]
+            .as_ref()
+            .expect("no base OTs with this key share")
+    }
+}
+
+fn decode(hex_str: &str) -> Secret<Vec<u8>> {
+    Secret::new(hex::decode(hex_str).expect("base OTs are not hex"))
+}
+
+fn encode(bytes: &[u8]) -> Secret<String> {
+    Secret::new(hex::encode(bytes))
+}
+
+// seed of base OT l: hash of the pair tag, l, R_l and the shared point
+fn base_seed<E: Curve>(tag: &[u8], l: usize, R: &Point<E>, P: &Point<E>) -> [u8; SEED_LEN] {
+    Sha256::new()
+        .chain(b"MPC_HD_GG18 base ot")
+        .chain(tag)
+        .chain((l as u32).to_be_bytes())
+        .chain(&*R.to_bytes(true))
+        .chain(&*P.to_bytes(true))
+        .finalize()
+        .into()
+}
+
+/// Base OT receiver's message: R_l = r_l * G + delta_l * S for each base OT
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct BaseOtChoice<E: Curve> {
+    pub R: Vec<Point<E>>,
+}
+
+/// Receiving side of the base OTs of one pair, which becomes the extension sender
+pub struct BaseOtReceiver {
+    delta: Secret<String>,
+    received: Secret<String>,
+}
+
+impl BaseOtReceiver {
+    /// Chooses random bits delta in the base OTs offered under S = y * G. `tag` binds
+    /// them to the session and the pair.
+    pub fn new<E: Curve>(S: &Point<E>, tag: &[u8]) -> (Self, BaseOtChoice<E>) {
+        let mut delta = Secret::new(vec![0u8; KAPPA / 8]);
+        OsRng.fill_bytes(&mut delta);
+        let mut received = Secret::new(Vec::with_capacity(KAPPA * SEED_LEN));
+        let mut R = Vec::with_capacity(KAPPA);
+        for l in 0..KAPPA {
+            let r = Scalar::<E>::random();
+            let R_l = match bit(&delta, l) {
+                true => Point::generator() * &r + S,
+                false => Point::generator() * &r,
+            };
+            received.extend_from_slice(&base_seed(tag, l, &R_l, &(S * &r)));
+            R.push(R_l);
+        }
+        let receiver = Self {
+            delta: encode(&delta),
+            received: encode(&received),
+        };
+        (receiver, BaseOtChoice { R })
+    }
+
+    /// Base OTs of the pair, with the seeds this party sent as the other side
+    pub fn pair(self, sent: Secret<String>) -> OtPair {
+        OtPair {
+            delta: self.delta.clone(),
+            received: self.received.clone(),
+            sent,
+        }
+    }
+}
+
+/// Sending side of the base OTs of one pair: both seeds of each, hex
+pub fn base_ot_send<E: Curve>(
+    y: &Scalar<E>,
+    S: &Point<E>,
+    choice: &BaseOtChoice<E>,
+    tag: &[u8],
+) -> Result<Secret<String>, Error> {
+    if choice.R.len() != KAPPA {
+        return Err(InvalidKey);
+    }
+    let mut sent = Secret::new(Vec::with_capacity(2 * KAPPA * SEED_LEN));
+    for (l, R_l) in choice.R.iter().enumerate() {
+        sent.extend_from_slice(&base_seed(tag, l, R_l, &(R_l * y)));
+        sent.extend_from_slice(&base_seed(tag, l, R_l, &((R_l - S) * y)));
+    }
+    Ok(encode(&sent))
+}
+
+fn bit(bytes: &[u8], j: usize) -> bool {
+    (bytes[j / 8] >> (j % 8)) & 1 == 1
+}
+
+fn to_bytes(bits: &[bool]) -> Vec<u8> {
+    let mut bytes = vec![0u8; bits.len() / 8];
+    for (j, _) in bits.iter().enumerate().filter(|(_, b)| **b) {
+        bytes[j / 8] |= 1 << (j % 8);
+    }
+    bytes
+}
+
+// SHA-256 in counter mode: `len` pseudorandom bytes from the seed of base OT l
+fn prg(seed: &[u8], tag: &[u8], l: usize, len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len + 32);
+    let mut counter = 0u32;
+    while out.len() < len {
+        out.extend_from_slice(
+            &Sha256::new()
+                .chain(b"MPC_HD_GG18 ot prg")
+                .chain(tag)
+                .chain((l as u32).to_be_bytes())
+                .chain(seed)
+                .chain(counter.to_be_bytes())
+                .finalize(),
+        );
+        counter += 1;
+    }
+    out.truncate(len);
+    out
+}
+
+// product in GF(2^128) modulo x^128 + x^7 + x^2 + x + 1
+fn gf_mul(mut a: u128, mut b: u128) -> u128 {
+    let mut r = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            r ^= a;
+        }
+        b >>= 1;
+        a = (a << 1) ^ ((a >> 127) * 0x87);
+    }
+    r
+}
+
+// rows of the matrix whose column l is `columns[l]`
+fn transpose(columns: &[Vec<u8>], rows: usize) -> Vec<u128> {
+    let mut t = vec![0u128; rows];
+    for (l, column) in columns.iter().enumerate() {
+        for (j, t_j) in t.iter_mut().enumerate() {
+            *t_j |= u128::from(bit(column, j)) << l;
+        }
+    }
+    t
+}
+
+// KOS challenge of every row, from the receiver's columns
+fn kos_chi(tag: &[u8], u: &[u8], rows: usize) -> Vec<u128> {
+    let seed = Sha256::new()
+        .chain(b"MPC_HD_GG18 kos")
+        .chain(tag)
+        .chain(u)
+        .finalize();
+    (0..rows)
+        .map(|j| {
+            let h = Sha256::new()
+                .chain(seed)
+                .chain((j as u32).to_be_bytes())
+                .finalize();
+            u128::from_le_bytes(h[..16].try_into().unwrap())
+        })
+        .collect()
+}
+
+// 512 hashed bits reduced mod q, so that the bias is negligible for any curve
+fn hash_to_scalar<E: Curve>(parts: &[&[u8]]) -> Scalar<E> {
+    let hash = |i: u8| {
+        let mut h = Sha256::new().chain([i]);
+        for part in parts {
+            h.update((part.len() as u64).to_be_bytes());
+            h.update(part);
+        }
+        h.finalize()
+    };
+    let mut bytes = hash(0).to_vec();
+    bytes.extend(hash(1));
+    Scalar::from_bigint(&BigInt::from_bytes(&bytes))
+}
+
+// pads of row j of the correlated OTs, one per coordinate of Alice's input (a, a^)
+fn pads<E: Curve>(tag: &[u8], j: usize, q: u128) -> (Scalar<E>, Scalar<E>) {
+    let j = (j as u32).to_be_bytes();
+    let q = q.to_le_bytes();
+    (
+        hash_to_scalar(&[b"ot pad", tag, &j, &q, &[0]]),
+        hash_to_scalar(&[b"ot pad", tag, &j, &q, &[1]]),
+    )
+}
+
+// g: powers of two, then public random scalars
+fn gadget<E: Curve>() -> Vec<Scalar<E>> {
+    (0..XI)
+        .map(|i| match i < Q_BITS {
+            true => Scalar::from_bigint(&BigInt::from(2).pow(i as u32)),
+            false => hash_to_scalar(&[b"ot gadget", &(i as u32).to_be_bytes()]),
+        })
+        .collect()
+}
+
+// random bits w with <g, w> = b
+fn encode_input<E: Curve>(b: &Scalar<E>, g: &[Scalar<E>]) -> Vec<bool> {
+    let mut random = vec![0u8; (XI - Q_BITS) / 8];
+    OsRng.fill_bytes(&mut random);
+    let random = (0..XI - Q_BITS)
+        .map(|i| bit(&random, i))
+        .collect::<Vec<bool>>();
+    let sum = zip_sum(&g[Q_BITS..], &random);
+    let b = (b - sum).to_bigint();
+    (0..Q_BITS).map(|i| b.test_bit(i)).chain(random).collect()
+}
+
+fn zip_sum<E: Curve>(g: &[Scalar<E>], w: &[bool]) -> Scalar<E> {
+    g.iter().zip(w).filter(|(_, w)| **w).map(|(g, _)| g).sum()
+}
+
+// Alice's check challenge (chi, chi^), once tau is fixed
+fn mul_chi<E: Curve>(tag: &[u8], tau: &[(Scalar<E>, Scalar<E>)]) -> (Scalar<E>, Scalar<E>) {
+    let tau = tau
+        .iter()
+        .flat_map(|(t, t_hat)| [t.to_bytes().to_vec(), t_hat.to_bytes().to_vec()].concat())
+        .collect::<Vec<u8>>();
+    (
+        hash_to_scalar(&[b"ot mul check", tag, &tau, &[0]]),
+        hash_to_scalar(&[b"ot mul check", tag, &tau, &[1]]),
+    )
+}
+
+/// Number of rows of the extension for `count` inputs of Bob
+pub fn rows(count: usize) -> usize {
+    count * XI + PAD
+}
+
+/// Bob's extension message: the masked columns u_l and the KOS check, hex
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OtMulRequest {
+    pub u: String,
+    pub x_check: String,
+    pub t_check: String,
+}
+
+/// Alice's reply: tau_j for every row of Bob's encodings, and the check values
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct OtMulReply<E: Curve> {
+    pub tau: Vec<(Scalar<E>, Scalar<E>)>,
+    pub r: Vec<Scalar<E>>,
+    pub u: Scalar<E>,
+}
+
+/// Bob's side of one extension, kept until Alice replies
+#[derive(Clone)]
+pub struct OtMulBob {
+    x: Secret<Vec<u8>>,
+    t: Vec<u128>,
+    count: usize,
+}
+
+impl OtMulBob {
+    /// Starts the multiplications of Alice's input by each of `bs` with the base OTs of
+    /// `pair`; `tag` must be unique to them.
+    pub fn new<E: Curve>(pair: &OtPair, tag: &[u8], bs: &[&Scalar<E>]) -> (Self, OtMulRequest) {
+        let g = gadget::<E>();
+        let mut pad = vec![0u8; PAD / 8];
+        OsRng.fill_bytes(&mut pad);
+        let bits = bs
+            .iter()
+            .flat_map(|b| encode_input(b, &g))
+            .collect::<Vec<bool>>();
+        let x = Secret::new([to_bytes(&bits), pad].concat());
+        let rows = rows(bs.len());
+        let sent = decode(&pair.sent);
+        let mut t_columns = Vec::with_capacity(KAPPA);
+        let mut u = Vec::with_capacity(KAPPA * rows / 8);
+        for l in 0..KAPPA {
+            let seed_0 = &sent[2 * l * SEED_LEN..(2 * l + 1) * SEED_LEN];
+            let seed_1 = &sent[(2 * l + 1) * SEED_LEN..(2 * l + 2) * SEED_LEN];
+            let t_0 = prg(seed_0, tag, l, rows / 8);
+            let t_1 = prg(seed_1, tag, l, rows / 8);
+            u.extend(
+                t_0.iter()
+                    .zip(&t_1)
+                    .zip(x.iter())
+                    .map(|((a, b), c)| a ^ b ^ c),
+            );
+            t_columns.push(t_0);
+        }
+        let t = transpose(&t_columns, rows);
+        let chi = kos_chi(tag, &u, rows);
+        let (mut x_check, mut t_check) = (0u128, 0u128);
+        for j in 0..rows {
+            if bit(&x, j) {
+                x_check ^= chi[j];
+            }
+            t_check ^= gf_mul(t[j], chi[j]);
+        }
+        let request = OtMulRequest {
+            u: hex::encode(u),
+            x_check: hex::encode(x_check.to_le_bytes()),
+            t_check: hex::encode(t_check.to_le_bytes()),
+        };
+        let bob = Self {
+            x,
+            t,
+            count: bs.len(),
+        };
+        (bob, request)
+    }
+
+    /// Bob's additive shares of a * b for each of his inputs.
+    pub fn finish<E: Curve>(
+        &self,
+        tag: &[u8],
+        reply: &OtMulReply<E>,
+    ) -> Result<Vec<Scalar<E>>, Error> {
+        let n = self.count * XI;
+        if reply.tau.len() != n || reply.r.len() != n {
+            return Err(InvalidKey);
+        }
+        let (chi, chi_hat) = mul_chi(tag, &reply.tau);
+        let mut t_b = Vec::with_capacity(n);
+        for j in 0..n {
+            let (p, p_hat) = pads::<E>(tag, j, self.t[j]);
+            let (t, t_hat, expected) = match bit(&self.x, j) {
+                true => (
+                    &reply.tau[j].0 - p,
+                    &reply.tau[j].1 - p_hat,
+                    reply.u.clone(),
+                ),
+                false => (Scalar::zero() - p, Scalar::zero() - p_hat, Scalar::zero()),
+            };
+            if &reply.r[j] + &chi * &t + &chi_hat * t_hat != expected {
+                return Err(InvalidKey);
+            }
+            t_b.push(t);
+        }
+        let g = gadget::<E>();
+        Ok(t_b
+            .chunks(XI)
+            .map(|t| t.iter().zip(&g).map(|(t, g)| t * g).sum())
+            .collect())
+    }
+}
+
+/// Alice's reply to `request`, with her additive shares of a * b for each of the
+/// `count` inputs of Bob.
+pub fn mul_reply<E: Curve>(
+    pair: &OtPair,
+    tag: &[u8],
+    a: &Scalar<E>,
+    request: &OtMulRequest,
+    count: usize,
+) -> Result<(Vec<Scalar<E>>, OtMulReply<E>), Error> {
+    let rows = rows(count);
+    let u = hex::decode(&request.u).map_err(|_| InvalidKey)?;
+    let check = |s: &str| -> Result<u128, Error> {
+        let bytes = hex::decode(s).map_err(|_| InvalidKey)?;
+        Ok(u128::from_le_bytes(
+            bytes.try_into().map_err(|_| InvalidKey)?,
+        ))
+    };
+    let (x_check, t_check) = (check(&request.x_check)?, check(&request.t_check)?);
+    if u.len() != KAPPA * rows / 8 {
+        return Err(InvalidKey);
+    }
+    let delta = u128::from_le_bytes(decode(&pair.delta)[..].try_into().unwrap());
+    let received = decode(&pair.received);
+    let q_columns = (0..KAPPA)
+        .map(|l| {
+            let q = prg(
+                &received[l * SEED_LEN..(l + 1) * SEED_LEN],
+                tag,
+                l,
+                rows / 8,
+            );
+            match (delta >> l) & 1 == 1 {
+                true => {
+                    let u_l = &u[l * rows / 8..(l + 1) * rows / 8];
+                    q.iter().zip(u_l).map(|(q, u)| q ^ u).collect()
+                }
+                false => q,
+            }
+        })
+        .collect::<Vec<Vec<u8>>>();
+    let q = transpose(&q_columns, rows);
+    let chi = kos_chi(tag, &u, rows);
+    let q_check = q
+        .iter()
+        .zip(&chi)
+        .fold(0u128, |acc, (q, chi)| acc ^ gf_mul(*q, *chi));
+    if q_check != t_check ^ gf_mul(x_check, delta) {
+        return Err(InvalidKey);
+    }
+
+    let a_hat = Scalar::<E>::random();
+    let n = count * XI;
+    let mut t_a = Vec::with_capacity(n);
+    let mut tau = Vec::with_capacity(n);
+    for (j, q_j) in q.iter().take(n).enumerate() {
+        let (v_0, v_0_hat) = pads::<E>(tag, j, *q_j);
+        let (v_1, v_1_hat) = pads::<E>(tag, j, q_j ^ delta);
+        tau.push((v_1 - &v_0 + a, v_1_hat - &v_0_hat + &a_hat));
+        t_a.push((v_0, v_0_hat));
+    }
+    let (chi, chi_hat) = mul_chi(tag, &tau);
+    let r = t_a
+        .iter()
+        .map(|(t, t_hat)| &chi * t + &chi_hat * t_hat)
+        .collect();
+    let g = gadget::<E>();
+    let shares = t_a
+        .chunks(XI)
+        .map(|t| t.iter().zip(&g).map(|((t, _), g)| t * g).sum())
+        .collect();
+    let reply = OtMulReply {
+        tau,
+        r,
+        u: &chi * a + &chi_hat * &a_hat,
+    };
+    Ok((shares, reply))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_ecdsa::mta::{MtaCheck, MtaShares};
+    use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+    use curv::elliptic::curves::Secp256k1;
+
+    const TAG: &[u8] = b"ot-test";
+
+    // base OTs of one direction: Alice's delta and received seeds, Bob's sent seeds
+    fn pair() -> OtPair {
+        let y = Scalar::<Secp256k1>::random();
+        let S = Point::generator() * &y;
+        let (receiver, choice) = BaseOtReceiver::new(&S, TAG);
+        let sent = base_ot_send(&y, &S, &choice, TAG).unwrap();
+        receiver.pair(sent)
+    }
+
+    #[test]
+    fn gf_mul_reduces_by_the_field_polynomial() {
+        // x^127 * x = x^128 = x^7 + x^2 + x + 1
+        assert_eq!(gf_mul(1 << 127, 2), 0x87);
+        assert_eq!(gf_mul(0x87, 1), 0x87);
+        assert_eq!(gf_mul(0x87, 0), 0);
+        let (a, b, c) = (OsRng.next_u64() as u128, u128::MAX / 3, 1 << 100 | 5);
+        assert_eq!(gf_mul(a, b), gf_mul(b, a));
+        assert_eq!(gf_mul(a, b ^ c), gf_mul(a, b) ^ gf_mul(a, c));
+    }
+
+    #[test]
+    fn transpose_turns_columns_into_rows() {
+        // column l has bit j set iff j % (l + 1) == 0
+        let rows = 24;
+        let columns = (0..KAPPA)
+            .map(|l| {
+                let bits = (0..rows).map(|j| j % (l + 1) == 0).collect::<Vec<bool>>();
+                to_bytes(&bits)
+            })
+            .collect::<Vec<Vec<u8>>>();
+        let t = transpose(&columns, rows);
+        assert_eq!(t.len(), rows);
+        assert_eq!(t[0], u128::MAX);
+        for (j, t_j) in t.iter().enumerate() {
+            for l in 0..KAPPA {
+                assert_eq!((t_j >> l) & 1 == 1, j % (l + 1) == 0);
+            }
+        }
+    }
+
+    #[test]
+    fn shares_sum_to_the_product() {
+        let pair = pair();
+        let a = Scalar::<Secp256k1>::random();
+        let bs = [Scalar::random(), Scalar::random()];
+        let (bob, request) = OtMulBob::new(&pair, TAG, &[&bs[0], &bs[1]]);
+        let (alice_shares, reply) = mul_reply(&pair, TAG, &a, &request, 2).unwrap();
+        let bob_shares = bob.finish(TAG, &reply).unwrap();
+        for ((t_a, t_b), b) in alice_shares.iter().zip(&bob_shares).zip(&bs) {
+            assert_eq!(t_a + t_b, &a * b);
+        }
+    }
+
+    #[test]
+    fn rejects_other_tag() {
+        let pair = pair();
+        let a = Scalar::<Secp256k1>::random();
+        let b = Scalar::<Secp256k1>::random();
+        let (_, request) = OtMulBob::new(&pair, TAG, &[&b]);
+        assert!(mul_reply(&pair, b"other", &a, &request, 1).is_err());
+    }
+
+    #[test]
+    fn check_ties_bob_to_his_inputs() {
+        let pair = pair();
+        let k_i = Scalar::<Secp256k1>::random();
+        let (gamma_j, w_j) = (Scalar::random(), Scalar::random());
+        // Bob proves knowledge of w_j, but multiplies by `w`
+        let shares = |w: &Scalar<Secp256k1>| {
+            let (bob, request) = OtMulBob::new(&pair, TAG, &[&gamma_j, w]);
+            let (alice, reply) = mul_reply(&pair, TAG, &k_i, &request, 2).unwrap();
+            let bob = bob.finish(TAG, &reply).unwrap();
+            let alice = MtaShares {
+                alpha: alice[0].clone(),
+                miu: alice[1].clone(),
+                beta: Scalar::zero(),
+                ni: Scalar::zero(),
+                gamma_proof: DLogProof::prove(&gamma_j),
+                w_proof: DLogProof::prove(&w_j),
+            };
+            let check = MtaCheck {
+                beta: Point::generator() * &bob[0],
+                ni: Point::generator() * &bob[1],
+            };
+            (alice, check)
+        };
+        let (alice, check) = shares(&w_j);
+        assert!(alice.check(&k_i, &check).is_ok());
+        let (alice, check) = shares(&(&w_j + Scalar::from(1)));
+        assert!(alice.check(&k_i, &check).is_err());
+    }
+}
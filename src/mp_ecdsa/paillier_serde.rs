@@ -0,0 +1,84 @@
+//! Serde for the Paillier types of round messages. kzen-paillier and zk-paillier
+//! write their big integers as decimal strings in any format; these keep that in
+//! human readable formats such as JSON, and in binary ones such as CBOR write them
+//! as curv does, big-endian bytes. Used with
+//! `#[serde(with = "paillier_serde::encryption_key")]`.
+
+use curv::BigInt;
+use paillier::EncryptionKey;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zk_paillier::zkproofs::NiCorrectKeyProof;
+
+pub mod encryption_key {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct MinimalRef<'a> {
+        n: &'a BigInt,
+    }
+
+    #[derive(Deserialize)]
+    struct Minimal {
+        n: BigInt,
+    }
+
+    pub fn serialize<S: Serializer>(ek: &EncryptionKey, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            ek.serialize(serializer)
+        } else {
+            MinimalRef { n: &ek.n }.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<EncryptionKey, D::Error> {
+        if deserializer.is_human_readable() {
+            EncryptionKey::deserialize(deserializer)
+        } else {
+            let minimal = Minimal::deserialize(deserializer)?;
+            Ok(EncryptionKey::from(&minimal.n))
+        }
+    }
+}
+
+pub mod correct_key_proof {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct ProofRef<'a> {
+        sigma_vec: &'a [BigInt],
+    }
+
+    #[derive(Deserialize)]
+    struct Proof {
+        sigma_vec: Vec<BigInt>,
+    }
+
+    pub fn serialize<S: Serializer>(
+        proof: &NiCorrectKeyProof,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            proof.serialize(serializer)
+        } else {
+            ProofRef {
+                sigma_vec: &proof.sigma_vec,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<NiCorrectKeyProof, D::Error> {
+        if deserializer.is_human_readable() {
+            NiCorrectKeyProof::deserialize(deserializer)
+        } else {
+            let proof = Proof::deserialize(deserializer)?;
+            Ok(NiCorrectKeyProof {
+                sigma_vec: proof.sigma_vec,
+            })
+        }
+    }
+}
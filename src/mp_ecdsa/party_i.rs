@@ -17,8 +17,11 @@
 */
 
 use std::convert::TryFrom;
+use std::iter;
 
 use super::feldman_vss::VerifiableSS;
+use super::paillier_serde;
+use super::secret::Secret;
 use centipede::juggling::proof_system::{Helgamalsegmented, Witness};
 use centipede::juggling::segmentation::Msegmentation;
 use curv::arithmetic::traits::*;
@@ -30,16 +33,28 @@ use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
 use curv::elliptic::curves::{Curve, Point, Scalar, Secp256k1};
 use curv::BigInt;
 use paillier::{
-    Decrypt, DecryptionKey, EncryptionKey, KeyGeneration, Paillier, RawCiphertext, RawPlaintext,
+    extract_nroot, Decrypt, DecryptionKey, EncryptionKey, KeyGeneration, Paillier, RawCiphertext,
+    RawPlaintext,
 };
+use rayon::prelude::*;
 use sha2::Sha256;
-use zk_paillier::zkproofs::NiCorrectKeyProof;
+use zk_paillier::zkproofs::{compute_digest, NiCorrectKeyProof};
 
 use serde::{Deserialize, Serialize};
 
 use multi_party_ecdsa::Error::{self, InvalidCom, InvalidKey, InvalidSS, InvalidSig};
 
 const SECURITY: usize = 256;
+/// rounds of the correct key proof, as in zk-paillier
+const CORRECT_KEY_M2: usize = 11;
+
+/// Context absorbed into the Fiat-Shamir challenge of every zero-knowledge proof,
+/// so that a proof only verifies for the session, prover and round it was made in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionContext {
+    pub session_id: String,
+    pub round: String,
+}
 
 #[derive(Debug)]
 pub struct Parameters {
@@ -48,47 +63,66 @@ pub struct Parameters {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Keys<E: Curve = Secp256k1> {
-    pub u_i: Scalar<E>,
+    pub u_i: Secret<Scalar<E>>,
     pub y_i: Point<E>,
-    pub dk: DecryptionKey,
+    pub dk: Secret<DecryptionKey>,
     pub ek: EncryptionKey,
     pub party_index: u16,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct PartyPrivate {
-    u_i: Scalar<Secp256k1>,
-    x_i: Scalar<Secp256k1>,
-    dk: DecryptionKey,
+#[serde(bound = "")]
+pub struct PartyPrivate<E: Curve = Secp256k1> {
+    u_i: Secret<Scalar<E>>,
+    x_i: Secret<Scalar<E>>,
+    dk: Secret<DecryptionKey>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct KeyGenBroadcastMessage1 {
+    #[serde(with = "paillier_serde::encryption_key")]
     pub e: EncryptionKey,
     pub com: BigInt,
+    #[serde(with = "paillier_serde::correct_key_proof")]
     pub correct_key_proof: NiCorrectKeyProof,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct KeyGenDecommitMessage1 {
+#[serde(bound = "")]
+pub struct KeyGenDecommitMessage1<E: Curve = Secp256k1> {
     pub blind_factor: BigInt,
-    pub y_i: Point<Secp256k1>,
+    pub y_i: Point<E>,
 }
 
+/// Commitment to a party's random contribution to the BIP32 chain code
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct SharedKeys {
-    pub y: Point<Secp256k1>,
-    pub x_i: Scalar<Secp256k1>,
+pub struct ChainCodeCommitMessage {
+    pub com: BigInt,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct SignKeys {
-    pub w_i: Scalar<Secp256k1>,
-    pub g_w_i: Point<Secp256k1>,
-    pub k_i: Scalar<Secp256k1>,
-    pub gamma_i: Scalar<Secp256k1>,
-    pub g_gamma_i: Point<Secp256k1>,
+pub struct ChainCodeDecommitMessage {
+    pub blind_factor: BigInt,
+    pub cc_i: [u8; 32],
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SharedKeys<E: Curve = Secp256k1> {
+    pub y: Point<E>,
+    pub x_i: Secret<Scalar<E>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct SignKeys<E: Curve = Secp256k1> {
+    pub w_i: Secret<Scalar<E>>,
+    pub g_w_i: Point<E>,
+    pub k_i: Secret<Scalar<E>>,
+    pub gamma_i: Secret<Scalar<E>>,
+    pub g_gamma_i: Point<E>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -97,19 +131,21 @@ pub struct SignBroadcastPhase1 {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct SignDecommitPhase1 {
+#[serde(bound = "")]
+pub struct SignDecommitPhase1<E: Curve = Secp256k1> {
     pub blind_factor: BigInt,
-    pub g_gamma_i: Point<Secp256k1>,
+    pub g_gamma_i: Point<E>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct LocalSignature {
-    pub l_i: Scalar<Secp256k1>,
-    pub rho_i: Scalar<Secp256k1>,
-    pub R: Point<Secp256k1>,
-    pub s_i: Scalar<Secp256k1>,
+#[serde(bound = "")]
+pub struct LocalSignature<E: Curve = Secp256k1> {
+    pub l_i: Secret<Scalar<E>>,
+    pub rho_i: Secret<Scalar<E>>,
+    pub R: Point<E>,
+    pub s_i: Scalar<E>,
     pub m: BigInt,
-    pub y: Point<Secp256k1>,
+    pub y: Point<E>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -123,38 +159,90 @@ pub struct Phase5Com2 {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Phase5ADecom1 {
-    pub V_i: Point<Secp256k1>,
-    pub A_i: Point<Secp256k1>,
-    pub B_i: Point<Secp256k1>,
+#[serde(bound = "")]
+pub struct Phase5ADecom1<E: Curve = Secp256k1> {
+    pub V_i: Point<E>,
+    pub A_i: Point<E>,
+    pub B_i: Point<E>,
     pub blind_factor: BigInt,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Phase5DDecom2 {
-    pub u_i: Point<Secp256k1>,
-    pub t_i: Point<Secp256k1>,
+#[serde(bound = "")]
+pub struct Phase5DDecom2<E: Curve = Secp256k1> {
+    pub u_i: Point<E>,
+    pub t_i: Point<E>,
     pub blind_factor: BigInt,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct SignatureRecid {
-    pub r: Scalar<Secp256k1>,
-    pub s: Scalar<Secp256k1>,
+#[serde(bound = "")]
+pub struct SignatureRecid<E: Curve = Secp256k1> {
+    pub r: Scalar<E>,
+    pub s: Scalar<E>,
     pub recid: u8,
 }
 
-impl Keys {
+impl SessionContext {
+    pub fn new(session_id: &str, round: &str) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            round: round.to_string(),
+        }
+    }
+
+    // fixed-length tag of the proof sent by `party_index` in this round
+    pub fn tag(&self, party_index: u16) -> Vec<u8> {
+        Sha256::new()
+            .chain(b"MPC_HD_GG18")
+            .chain((self.session_id.len() as u64).to_be_bytes())
+            .chain(self.session_id.as_bytes())
+            .chain((self.round.len() as u64).to_be_bytes())
+            .chain(self.round.as_bytes())
+            .chain(party_index.to_be_bytes())
+            .finalize()
+            .to_vec()
+    }
+
+    // NiCorrectKeyProof::proof only takes a 'static salt; this is the same prover
+    // with the tag as salt, so that `NiCorrectKeyProof::verify` accepts it
+    pub(crate) fn correct_key_proof(
+        &self,
+        dk: &DecryptionKey,
+        party_index: u16,
+    ) -> NiCorrectKeyProof {
+        let n = &dk.p * &dk.q;
+        let key_length = n.bit_length();
+        let salt = compute_digest(iter::once(BigInt::from_bytes(&self.tag(party_index))));
+        let sigma_vec = (0..CORRECT_KEY_M2)
+            .map(|i| {
+                let seed = compute_digest([&n, &salt, &BigInt::from(i as u32)].into_iter());
+                let rho = mask_generation(key_length, &seed) % &n;
+                extract_nroot(dk, &rho)
+            })
+            .collect();
+        NiCorrectKeyProof { sigma_vec }
+    }
+}
+
+// RFC 8017 MGF1-style expansion of `seed`, as in zk-paillier's NiCorrectKeyProof
+fn mask_generation(out_length: usize, seed: &BigInt) -> BigInt {
+    (0..out_length / 256 + 1).fold(BigInt::zero(), |acc, j| {
+        acc + (compute_digest([seed, &BigInt::from(j as u32)].into_iter()) << (j * 256))
+    })
+}
+
+impl<E: Curve> Keys<E> {
     // modify: vec to map, random
     pub fn create(index: u16) -> Self {
-        let u = Scalar::<Secp256k1>::random();
+        let u = Scalar::<E>::random();
         let y = Point::generator() * &u;
         let (ek, dk) = Paillier::keypair().keys();
 
         Self {
-            u_i: u,
+            u_i: Secret::new(u),
             y_i: y,
-            dk,
+            dk: Secret::new(dk),
             ek,
             party_index: index,
         }
@@ -162,30 +250,38 @@ impl Keys {
 
     // modify: vec to map, random
     // we recommend using safe primes if the code is used in production
-    pub fn create_safe_prime(index: u16) -> Keys {
-        let u = Scalar::<Secp256k1>::random();
+    pub fn create_safe_prime(index: u16) -> Self {
+        let u = Scalar::<E>::random();
         let y = Point::generator() * &u;
 
         let (ek, dk) = Paillier::keypair_safe_primes().keys();
 
-        Keys {
-            u_i: u,
+        Self {
+            u_i: Secret::new(u),
             y_i: y,
-            dk,
+            dk: Secret::new(dk),
             ek,
             party_index: index,
         }
     }
 
     // modify: vec to map
-    pub fn create_from(u: Scalar<Secp256k1>, index: u16) -> Keys {
+    pub fn create_from(u: Scalar<E>, index: u16) -> Self {
+        Self::create_with_keypair(u, index, Paillier::keypair().keys())
+    }
+
+    /// With a Paillier keypair generated beforehand, e.g. taken from a pool
+    pub fn create_with_keypair(
+        u: Scalar<E>,
+        index: u16,
+        (ek, dk): (EncryptionKey, DecryptionKey),
+    ) -> Self {
         let y = Point::generator() * &u;
-        let (ek, dk) = Paillier::keypair().keys();
 
         Self {
-            u_i: u,
+            u_i: Secret::new(u),
             y_i: y,
-            dk,
+            dk: Secret::new(dk),
             ek,
             party_index: index,
         }
@@ -194,10 +290,11 @@ impl Keys {
     // modify: random
     pub fn phase1_broadcast_phase3_proof_of_correct_key(
         &self,
-    ) -> (KeyGenBroadcastMessage1, KeyGenDecommitMessage1) {
+        ctx: &SessionContext,
+    ) -> (KeyGenBroadcastMessage1, KeyGenDecommitMessage1<E>) {
         let blind_factor = BigInt::sample(SECURITY);
 
-        let correct_key_proof = NiCorrectKeyProof::proof(&self.dk, None);
+        let correct_key_proof = ctx.correct_key_proof(&self.dk, self.party_index);
         let com = HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
             &BigInt::from_bytes(self.y_i.to_bytes(true).as_ref()),
             &blind_factor,
@@ -214,19 +311,68 @@ impl Keys {
         (bcm1, decom1)
     }
 
+    // the commitment binds the prover's tag, so a contribution cannot be replayed by another party
+    pub fn phase1_commit_chain_code(
+        party_index: u16,
+        ctx: &SessionContext,
+    ) -> (ChainCodeCommitMessage, ChainCodeDecommitMessage) {
+        let blind_factor = BigInt::sample(SECURITY);
+        let cc_i: [u8; 32] = rand::random();
+        let com = HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+            &BigInt::from_bytes(&[ctx.tag(party_index), cc_i.to_vec()].concat()),
+            &blind_factor,
+        );
+        (
+            ChainCodeCommitMessage { com },
+            ChainCodeDecommitMessage { blind_factor, cc_i },
+        )
+    }
+
+    // chain_code = SHA256(cc_1 || ... || cc_n), so no single party controls it
+    // and it is unknown to anyone who only sees the public key
+    pub fn phase2_verify_com_combine_chain_code(
+        params: &Parameters,
+        com_vec: &[ChainCodeCommitMessage],
+        decom_vec: &[ChainCodeDecommitMessage],
+        ctx: &SessionContext,
+    ) -> Result<[u8; 32], Error> {
+        assert_eq!(com_vec.len(), usize::from(params.share_count));
+        assert_eq!(decom_vec.len(), usize::from(params.share_count));
+
+        let correct_decom_all = (0..com_vec.len()).all(|i| {
+            HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+                &BigInt::from_bytes(&[ctx.tag(i as u16 + 1), decom_vec[i].cc_i.to_vec()].concat()),
+                &decom_vec[i].blind_factor,
+            ) == com_vec[i].com
+        });
+        if !correct_decom_all {
+            return Err(InvalidCom);
+        }
+
+        let chain_code = decom_vec
+            .iter()
+            .fold(
+                Sha256::new().chain(b"MPC_HD_GG18 chain code"),
+                |hasher, decom| hasher.chain(decom.cc_i),
+            )
+            .finalize();
+        Ok(chain_code.into())
+    }
+
     // modify: vec to map
     #[allow(clippy::type_complexity)]
     pub fn phase1_verify_com_phase3_verify_correct_key_phase2_distribute(
         &self,
         params: &Parameters,
-        decom_vec: &[KeyGenDecommitMessage1],
+        decom_vec: &[KeyGenDecommitMessage1<E>],
         bc1_vec: &[KeyGenBroadcastMessage1],
-    ) -> Result<(VerifiableSS<Secp256k1>, Vec<Scalar<Secp256k1>>, u16), Error> {
+        ctx: &SessionContext,
+    ) -> Result<(VerifiableSS<E>, Vec<Scalar<E>>, u16), Error> {
         // test length:
         assert_eq!(decom_vec.len(), usize::from(params.share_count));
         assert_eq!(bc1_vec.len(), usize::from(params.share_count));
         // test paillier correct key and test decommitments
-        let correct_key_correct_decom_all = (0..bc1_vec.len()).all(|i| {
+        let correct_key_correct_decom_all = (0..bc1_vec.len()).into_par_iter().all(|i| {
             // The return of funtion is deterministic, although there's "random" in the name.
             HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
                 &BigInt::from_bytes(decom_vec[i].y_i.to_bytes(true).as_ref()),
@@ -234,7 +380,7 @@ impl Keys {
             ) == bc1_vec[i].com
                 && bc1_vec[i]
                     .correct_key_proof
-                    .verify(&bc1_vec[i].e, zk_paillier::zkproofs::SALT_STRING)
+                    .verify(&bc1_vec[i].e, &ctx.tag(i as u16 + 1))
                     .is_ok()
         });
 
@@ -249,18 +395,18 @@ impl Keys {
 
     // modify: vec to map, index to member_id
     pub fn phase2_verify_vss_construct_keypair_phase3_pok_dlog(
-        &self,
         params: &Parameters,
-        y_vec: &[Point<Secp256k1>],
-        secret_shares_vec: &[Scalar<Secp256k1>],
-        vss_scheme_vec: &[VerifiableSS<Secp256k1>],
+        y_vec: &[Point<E>],
+        secret_shares_vec: &[Scalar<E>],
+        vss_scheme_vec: &[VerifiableSS<E>],
         index: u16,
-    ) -> Result<(SharedKeys, DLogProof<Secp256k1, Sha256>), Error> {
+        ctx: &SessionContext,
+    ) -> Result<(SharedKeys<E>, DLogProof<E, Sha256>), Error> {
         assert_eq!(y_vec.len(), usize::from(params.share_count));
         assert_eq!(secret_shares_vec.len(), usize::from(params.share_count));
         assert_eq!(vss_scheme_vec.len(), usize::from(params.share_count));
 
-        let correct_ss_verify = (0..y_vec.len()).all(|i| {
+        let correct_ss_verify = (0..y_vec.len()).into_par_iter().all(|i| {
             // modify: vss index to member_id
             vss_scheme_vec[i]
                 .validate_share(&secret_shares_vec[i], index)
@@ -269,19 +415,23 @@ impl Keys {
         });
 
         if correct_ss_verify {
-            let y: Point<Secp256k1> = y_vec.iter().sum();
-            let x_i: Scalar<Secp256k1> = secret_shares_vec.iter().sum();
-            let dlog_proof = DLogProof::prove(&x_i);
-            Ok((SharedKeys { y, x_i }, dlog_proof))
+            let y: Point<E> = y_vec.iter().sum();
+            let x_i: Scalar<E> = secret_shares_vec.iter().sum();
+            let dlog_proof = DLogProof::prove_with_ctx(&x_i, &ctx.tag(index));
+            Ok((
+                SharedKeys {
+                    y,
+                    x_i: Secret::new(x_i),
+                },
+                dlog_proof,
+            ))
         } else {
             Err(InvalidSS)
         }
     }
 
     // modify: vec to map
-    pub fn get_commitments_to_xi(
-        vss_scheme_vec: &[VerifiableSS<Secp256k1>],
-    ) -> Vec<Point<Secp256k1>> {
+    pub fn get_commitments_to_xi(vss_scheme_vec: &[VerifiableSS<E>]) -> Vec<Point<E>> {
         let len = vss_scheme_vec.len();
         (1..=u16::try_from(len).unwrap()) // keygen时, index+1就是member_id
             .map(|i| {
@@ -289,33 +439,34 @@ impl Keys {
                     .map(|j| vss_scheme_vec[j].get_point_commitment(i))
                     .sum()
             })
-            .collect::<Vec<Point<Secp256k1>>>()
+            .collect::<Vec<Point<E>>>()
     }
 
     // modify: vec to map, index
     pub fn update_commitments_to_xi(
-        comm: &Point<Secp256k1>,
-        vss_scheme: &VerifiableSS<Secp256k1>,
+        comm: &Point<E>,
+        vss_scheme: &VerifiableSS<E>,
         index: u16,
         s: &[u16],
-    ) -> Point<Secp256k1> {
+    ) -> Point<E> {
         // modify: vss index to member_id
-        let li =
-            VerifiableSS::<Secp256k1>::map_share_to_new_params(&vss_scheme.parameters, index, s);
+        let li = VerifiableSS::<E>::map_share_to_new_params(&vss_scheme.parameters, index, s);
         comm * &li
     }
 
     // modify: vec to map
     pub fn verify_dlog_proofs(
         params: &Parameters,
-        dlog_proofs_vec: &[DLogProof<Secp256k1, Sha256>],
-        y_vec: &[Point<Secp256k1>],
+        dlog_proofs_vec: &[DLogProof<E, Sha256>],
+        y_vec: &[Point<E>],
+        ctx: &SessionContext,
     ) -> Result<(), Error> {
         assert_eq!(y_vec.len(), usize::from(params.share_count));
         assert_eq!(dlog_proofs_vec.len(), usize::from(params.share_count));
 
-        let xi_dlog_verify =
-            (0..y_vec.len()).all(|i| DLogProof::verify(&dlog_proofs_vec[i]).is_ok());
+        let xi_dlog_verify = (0..y_vec.len()).into_par_iter().all(|i| {
+            DLogProof::verify_with_ctx(&dlog_proofs_vec[i], &ctx.tag(i as u16 + 1)).is_ok()
+        });
 
         if xi_dlog_verify {
             Ok(())
@@ -325,8 +476,8 @@ impl Keys {
     }
 }
 
-impl PartyPrivate {
-    pub fn set_private(key: Keys, shared_key: SharedKeys) -> Self {
+impl<E: Curve> PartyPrivate<E> {
+    pub fn set_private(key: Keys<E>, shared_key: SharedKeys<E>) -> Self {
         Self {
             u_i: key.u_i,
             x_i: shared_key.x_i,
@@ -334,24 +485,24 @@ impl PartyPrivate {
         }
     }
 
-    pub fn y_i(&self) -> Point<Secp256k1> {
-        Point::generator() * &self.u_i
+    pub fn y_i(&self) -> Point<E> {
+        Point::generator() * &*self.u_i
     }
 
     pub fn decrypt(&self, ciphertext: BigInt) -> RawPlaintext {
-        Paillier::decrypt(&self.dk, &RawCiphertext::from(ciphertext))
+        Paillier::decrypt(&*self.dk, &RawCiphertext::from(ciphertext))
     }
 
     // modify: vec to map, index
-    pub fn refresh_private_key(&self, factor: &Scalar<Secp256k1>, index: u16) -> Keys {
-        let u: Scalar<Secp256k1> = &self.u_i + factor;
+    pub fn refresh_private_key(&self, factor: &Scalar<E>, index: u16) -> Keys<E> {
+        let u: Scalar<E> = &*self.u_i + factor;
         let y = Point::generator() * &u;
         let (ek, dk) = Paillier::keypair().keys();
 
         Keys {
-            u_i: u,
+            u_i: Secret::new(u),
             y_i: y,
-            dk,
+            dk: Secret::new(dk),
             ek,
             party_index: index,
         }
@@ -359,20 +510,31 @@ impl PartyPrivate {
 
     // modify: vec to map, index
     // we recommend using safe primes if the code is used in production
-    pub fn refresh_private_key_safe_prime(&self, factor: &Scalar<Secp256k1>, index: u16) -> Keys {
-        let u: Scalar<Secp256k1> = &self.u_i + factor;
+    pub fn refresh_private_key_safe_prime(&self, factor: &Scalar<E>, index: u16) -> Keys<E> {
+        let u: Scalar<E> = &*self.u_i + factor;
         let y = Point::generator() * &u;
         let (ek, dk) = Paillier::keypair_safe_primes().keys();
 
         Keys {
-            u_i: u,
+            u_i: Secret::new(u),
             y_i: y,
-            dk,
+            dk: Secret::new(dk),
             ek,
             party_index: index,
         }
     }
 
+    pub fn update_private_key(&self, factor_u_i: &Scalar<E>, factor_x_i: &Scalar<E>) -> Self {
+        PartyPrivate {
+            u_i: Secret::new(&*self.u_i + factor_u_i),
+            x_i: Secret::new(&*self.x_i + factor_x_i),
+            dk: self.dk.clone(),
+        }
+    }
+}
+
+// centipede segments are secp256k1 only
+impl PartyPrivate {
     // used for verifiable recovery
     pub fn to_encrypted_segment(
         &self,
@@ -383,49 +545,54 @@ impl PartyPrivate {
     ) -> (Witness, Helgamalsegmented) {
         Msegmentation::to_encrypted_segments(&self.u_i, &segment_size, num_of_segments, pub_ke_y, g)
     }
-
-    pub fn update_private_key(
-        &self,
-        factor_u_i: &Scalar<Secp256k1>,
-        factor_x_i: &Scalar<Secp256k1>,
-    ) -> Self {
-        PartyPrivate {
-            u_i: &self.u_i + factor_u_i,
-            x_i: &self.x_i + factor_x_i,
-            dk: self.dk.clone(),
-        }
-    }
 }
 
-impl SignKeys {
+impl<E: Curve> SignKeys<E> {
     pub fn create(
-        private: &PartyPrivate,
-        vss_scheme: &VerifiableSS<Secp256k1>,
+        private: &PartyPrivate<E>,
+        vss_scheme: &VerifiableSS<E>,
         index: u16,
         s: &[u16],
     ) -> Self {
-        // here calls the Lagrange interpolation
-        let li =
-            VerifiableSS::<Secp256k1>::map_share_to_new_params(&vss_scheme.parameters, index, s);
-        let w_i = li * &private.x_i;
-        let g = Point::generator();
-        let g_w_i = g * &w_i;
-        let gamma_i = Scalar::<Secp256k1>::random();
-        let g_gamma_i = g * &gamma_i;
+        let mut sign_keys = Self::sample();
+        sign_keys.set_signers(private, vss_scheme, index, s);
+        sign_keys
+    }
+
+    /// k_i and gamma_i, which do not depend on the signers: they can be committed
+    /// to before the signers are known. w_i is zero until `set_signers`.
+    pub fn sample() -> Self {
+        let gamma_i = Scalar::<E>::random();
+        let g_gamma_i = Point::generator() * &gamma_i;
 
         Self {
-            w_i,
-            g_w_i,
-            k_i: Scalar::<Secp256k1>::random(),
-            gamma_i,
+            w_i: Secret::new(Scalar::zero()),
+            g_w_i: Point::zero(),
+            k_i: Secret::new(Scalar::<E>::random()),
+            gamma_i: Secret::new(gamma_i),
             g_gamma_i,
         }
     }
 
-    pub fn phase1_broadcast(&self) -> (SignBroadcastPhase1, SignDecommitPhase1) {
+    /// Sets w_i, the additive share of the key among the signers `s`.
+    pub fn set_signers(
+        &mut self,
+        private: &PartyPrivate<E>,
+        vss_scheme: &VerifiableSS<E>,
+        index: u16,
+        s: &[u16],
+    ) {
+        // here calls the Lagrange interpolation
+        let li = VerifiableSS::<E>::map_share_to_new_params(&vss_scheme.parameters, index, s);
+        let w_i = li * &*private.x_i;
+        self.g_w_i = Point::generator() * &w_i;
+        self.w_i = Secret::new(w_i);
+    }
+
+    pub fn phase1_broadcast(&self) -> (SignBroadcastPhase1, SignDecommitPhase1<E>) {
         let blind_factor = BigInt::sample(SECURITY);
         let g = Point::generator();
-        let g_gamma_i = g * &self.gamma_i;
+        let g_gamma_i = g * &*self.gamma_i;
         let com = HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
             &BigInt::from_bytes(g_gamma_i.to_bytes(true).as_ref()),
             &blind_factor,
@@ -440,40 +607,32 @@ impl SignKeys {
         )
     }
 
-    pub fn phase2_delta_i(
-        &self,
-        alpha_vec: &[Scalar<Secp256k1>],
-        beta_vec: &[Scalar<Secp256k1>],
-    ) -> Scalar<Secp256k1> {
+    pub fn phase2_delta_i(&self, alpha_vec: &[Scalar<E>], beta_vec: &[Scalar<E>]) -> Scalar<E> {
         assert_eq!(alpha_vec.len(), beta_vec.len());
-        let ki_gamma_i = &self.k_i * &self.gamma_i;
-        ki_gamma_i + alpha_vec.iter().chain(beta_vec).sum::<Scalar<Secp256k1>>()
+        let ki_gamma_i = &*self.k_i * &*self.gamma_i;
+        ki_gamma_i + alpha_vec.iter().chain(beta_vec).sum::<Scalar<E>>()
     }
 
-    pub fn phase2_sigma_i(
-        &self,
-        miu_vec: &[Scalar<Secp256k1>],
-        ni_vec: &[Scalar<Secp256k1>],
-    ) -> Scalar<Secp256k1> {
+    pub fn phase2_sigma_i(&self, miu_vec: &[Scalar<E>], ni_vec: &[Scalar<E>]) -> Scalar<E> {
         assert_eq!(miu_vec.len(), ni_vec.len());
-        let ki_w_i = &self.k_i * &self.w_i;
-        ki_w_i + miu_vec.iter().chain(ni_vec).sum::<Scalar<Secp256k1>>()
+        let ki_w_i = &*self.k_i * &*self.w_i;
+        ki_w_i + miu_vec.iter().chain(ni_vec).sum::<Scalar<E>>()
     }
 
-    pub fn phase3_reconstruct_delta(delta_vec: &[Scalar<Secp256k1>]) -> Scalar<Secp256k1> {
+    pub fn phase3_reconstruct_delta(delta_vec: &[Scalar<E>]) -> Scalar<E> {
         delta_vec
             .iter()
-            .sum::<Scalar<Secp256k1>>()
+            .sum::<Scalar<E>>()
             .invert()
             .expect("sum of deltas is zero")
     }
 
     pub fn phase4(
-        delta_inv: &Scalar<Secp256k1>,
-        b_proof_vec: &[&DLogProof<Secp256k1, Sha256>],
-        phase1_decommit_vec: Vec<SignDecommitPhase1>,
+        delta_inv: &Scalar<E>,
+        b_proof_vec: &[&DLogProof<E, Sha256>],
+        phase1_decommit_vec: Vec<SignDecommitPhase1<E>>,
         bc1_vec: &[SignBroadcastPhase1],
-    ) -> Result<Point<Secp256k1>, Error> {
+    ) -> Result<Point<E>, Error> {
         // note: b_proof_vec is populated using the results
         //from the MtAwc, which is handling the proof of knowledge verification of gamma_i such that
         // Gamme_i = gamma_i * G in the verify_proofs_get_alpha()
@@ -487,7 +646,7 @@ impl SignKeys {
 
         if test_b_vec_and_com {
             Ok({
-                let gamma_sum: Point<Secp256k1> = phase1_decommit_vec
+                let gamma_sum: Point<E> = phase1_decommit_vec
                     .iter()
                     .map(|decom| &decom.g_gamma_i)
                     .sum();
@@ -500,26 +659,22 @@ impl SignKeys {
     }
 }
 
-impl LocalSignature {
+impl<E: Curve> LocalSignature<E> {
     pub fn phase5_local_sig(
-        k_i: &Scalar<Secp256k1>,
+        k_i: &Scalar<E>,
         message: &BigInt,
-        R: &Point<Secp256k1>,
-        sigma_i: &Scalar<Secp256k1>,
-        pubkey: &Point<Secp256k1>,
+        R: &Point<E>,
+        sigma_i: &Scalar<E>,
+        pubkey: &Point<E>,
     ) -> Self {
-        let m_fe = Scalar::<Secp256k1>::from(message);
-        let r = Scalar::<Secp256k1>::from(
-            &R.x_coord()
-                .unwrap()
-                .mod_floor(Scalar::<Secp256k1>::group_order()),
-        );
+        let m_fe = Scalar::<E>::from(message);
+        let r = Scalar::<E>::from(&R.x_coord().unwrap().mod_floor(Scalar::<E>::group_order()));
         let s_i = m_fe * k_i + r * sigma_i;
-        let l_i = Scalar::<Secp256k1>::random();
-        let rho_i = Scalar::<Secp256k1>::random();
+        let l_i = Scalar::<E>::random();
+        let rho_i = Scalar::<E>::random();
         Self {
-            l_i,
-            rho_i,
+            l_i: Secret::new(l_i),
+            rho_i: Secret::new(rho_i),
             R: R.clone(),
             s_i,
             m: message.clone(),
@@ -529,18 +684,20 @@ impl LocalSignature {
 
     pub fn phase5a_broadcast_5b_zkproof(
         &self,
+        ctx: &SessionContext,
+        index: u16,
     ) -> (
         Phase5Com1,
-        Phase5ADecom1,
-        HomoELGamalProof<Secp256k1, Sha256>,
-        DLogProof<Secp256k1, Sha256>,
+        Phase5ADecom1<E>,
+        HomoELGamalProof<E, Sha256>,
+        DLogProof<E, Sha256>,
     ) {
         let blind_factor = BigInt::sample(SECURITY);
         let g = Point::generator();
-        let A_i = g * &self.rho_i;
-        let l_i_rho_i = &self.l_i * &self.rho_i;
+        let A_i = g * &*self.rho_i;
+        let l_i_rho_i = &*self.l_i * &*self.rho_i;
         let B_i = g * l_i_rho_i;
-        let V_i = &self.R * &self.s_i + g * &self.l_i;
+        let V_i = &self.R * &self.s_i + g * &*self.l_i;
         let input_hash = Sha256::new()
             .chain_points([&V_i, &A_i, &B_i])
             .result_bigint();
@@ -549,7 +706,7 @@ impl LocalSignature {
             &blind_factor,
         );
         let witness = HomoElGamalWitness {
-            r: self.l_i.clone(),
+            r: (*self.l_i).clone(),
             x: self.s_i.clone(),
         };
         let delta = HomoElGamalStatement {
@@ -559,8 +716,9 @@ impl LocalSignature {
             D: V_i.clone(),
             E: B_i.clone(),
         };
-        let dlog_proof_rho = DLogProof::prove(&self.rho_i);
-        let proof = HomoELGamalProof::prove(&witness, &delta);
+        let tag = ctx.tag(index);
+        let dlog_proof_rho = DLogProof::prove_with_ctx(&self.rho_i, &tag);
+        let proof = HomoELGamalProof::prove_with_ctx(&witness, &delta, &tag);
 
         (
             Phase5Com1 { com },
@@ -577,14 +735,17 @@ impl LocalSignature {
 
     pub fn phase5c(
         &self,
-        decom_vec: &[Phase5ADecom1],
+        decom_vec: &[Phase5ADecom1<E>],
         com_vec: &[Phase5Com1],
-        elgamal_proofs: &[HomoELGamalProof<Secp256k1, Sha256>],
-        dlog_proofs_rho: &[DLogProof<Secp256k1, Sha256>],
-        v_i: &Point<Secp256k1>,
-        R: &Point<Secp256k1>,
-    ) -> Result<(Phase5Com2, Phase5DDecom2), Error> {
+        elgamal_proofs: &[HomoELGamalProof<E, Sha256>],
+        dlog_proofs_rho: &[DLogProof<E, Sha256>],
+        v_i: &Point<E>,
+        R: &Point<E>,
+        ctx: &SessionContext,
+        indices: &[u16],
+    ) -> Result<(Phase5Com2, Phase5DDecom2<E>), Error> {
         assert_eq!(decom_vec.len(), com_vec.len());
+        assert_eq!(decom_vec.len(), indices.len());
 
         let g = Point::generator();
         let test_com_elgamal = (0..com_vec.len()).all(|i| {
@@ -600,35 +761,36 @@ impl LocalSignature {
                 .chain_points([&decom_vec[i].V_i, &decom_vec[i].A_i, &decom_vec[i].B_i])
                 .result_bigint();
 
+            let tag = ctx.tag(indices[i]);
             HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
                 &input_hash,
                 &decom_vec[i].blind_factor,
             ) == com_vec[i].com
-                && elgamal_proofs[i].verify(&delta).is_ok()
-                && DLogProof::verify(&dlog_proofs_rho[i]).is_ok()
+                && elgamal_proofs[i].verify_with_ctx(&delta, &tag).is_ok()
+                && DLogProof::verify_with_ctx(&dlog_proofs_rho[i], &tag).is_ok()
         });
 
         let v_iter = (0..com_vec.len()).map(|i| &decom_vec[i].V_i);
         let a_iter = (0..com_vec.len()).map(|i| &decom_vec[i].A_i);
 
-        let v = v_i + v_iter.sum::<Point<Secp256k1>>();
+        let v = v_i + v_iter.sum::<Point<E>>();
         // V = -mG -ry - sum (vi)
-        let a: Point<Secp256k1> = a_iter.sum();
+        let a: Point<E> = a_iter.sum();
 
-        let r = Scalar::<Secp256k1>::from(
+        let r = Scalar::<E>::from(
             &self
                 .R
                 .x_coord()
                 .ok_or(Error::InvalidSig)?
-                .mod_floor(Scalar::<Secp256k1>::group_order()),
+                .mod_floor(Scalar::<E>::group_order()),
         );
         let yr = &self.y * r;
         let g = Point::generator();
-        let m_fe = Scalar::<Secp256k1>::from(&self.m);
+        let m_fe = Scalar::<E>::from(&self.m);
         let gm = g * m_fe;
         let v = v - &gm - &yr;
-        let u_i = v * &self.rho_i;
-        let t_i = a * &self.l_i;
+        let u_i = v * &*self.rho_i;
+        let t_i = a * &*self.l_i;
         let input_hash = Sha256::new().chain_points([&u_i, &t_i]).result_bigint();
         let blind_factor = BigInt::sample(SECURITY);
         let com = HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
@@ -654,10 +816,10 @@ impl LocalSignature {
 
     pub fn phase5d(
         &self,
-        decom_vec2: &[Phase5DDecom2],
+        decom_vec2: &[Phase5DDecom2<E>],
         com_vec2: &[Phase5Com2],
-        decom_vec1: &[Phase5ADecom1],
-    ) -> Result<Scalar<Secp256k1>, Error> {
+        decom_vec1: &[Phase5ADecom1<E>],
+    ) -> Result<Scalar<E>, Error> {
         assert_eq!(decom_vec2.len(), decom_vec1.len());
         assert_eq!(decom_vec2.len(), com_vec2.len());
 
@@ -676,8 +838,8 @@ impl LocalSignature {
         let b_iter = decom_vec1.iter().map(|decom| &decom.B_i);
 
         let g = Point::generator();
-        let biased_sum_tb = g + t_iter.chain(b_iter).sum::<Point<Secp256k1>>();
-        let biased_sum_tb_minus_u = biased_sum_tb - u_iter.sum::<Point<Secp256k1>>();
+        let biased_sum_tb = g + t_iter.chain(b_iter).sum::<Point<E>>();
+        let biased_sum_tb_minus_u = biased_sum_tb - u_iter.sum::<Point<E>>();
         if test_com {
             if *g.as_point() == biased_sum_tb_minus_u {
                 Ok(self.s_i.clone())
@@ -689,49 +851,162 @@ impl LocalSignature {
         }
     }
 
-    pub fn output_signature(&self, s_vec: &[Scalar<Secp256k1>]) -> Result<SignatureRecid, Error> {
-        let mut s: Scalar<Secp256k1> = &self.s_i + s_vec.iter().sum::<Scalar<Secp256k1>>();
+    pub fn output_signature(&self, s_vec: &[Scalar<E>]) -> Result<SignatureRecid<E>, Error> {
+        let s: Scalar<E> = &self.s_i + s_vec.iter().sum::<Scalar<E>>();
+        SignatureRecid::new(&self.R, s, &self.y, &self.m)
+    }
+}
+
+impl<E: Curve> SignatureRecid<E> {
+    /// Signature (r, s) with r = R.x, normalized to low-S, if it verifies under y
+    pub fn new(R: &Point<E>, mut s: Scalar<E>, y: &Point<E>, m: &BigInt) -> Result<Self, Error> {
         let s_bn = s.to_bigint();
 
-        let r = Scalar::<Secp256k1>::from(
-            &self
-                .R
-                .x_coord()
-                .ok_or(Error::InvalidSig)?
-                .mod_floor(Scalar::<Secp256k1>::group_order()),
-        );
-        let ry: BigInt = self
-            .R
-            .y_coord()
-            .ok_or(Error::InvalidSig)?
-            .mod_floor(Scalar::<Secp256k1>::group_order());
+        let rx = R.x_coord().ok_or(Error::InvalidSig)?;
+        let r = Scalar::<E>::from(&rx.mod_floor(Scalar::<E>::group_order()));
+        // modify: parity of R.y itself, not of R.y mod q
+        let ry: BigInt = R.y_coord().ok_or(Error::InvalidSig)?;
 
         /*
          Calculate recovery id - it is not possible to compute the public key out of the signature
          itself. Recovery id is used to enable extracting the public key uniquely.
          1. id = R.y & 1
-         2. if (s > curve.q / 2) id = id ^ 1
+         2. if (R.x >= curve.q) id = id | 2
+         3. if (s > curve.q / 2) id = id ^ 1, i.e. s is normalized to low-S (BIP-62)
         */
         let is_ry_odd = ry.test_bit(0);
         let mut recid = if is_ry_odd { 1 } else { 0 };
-        let s_tag_bn = Scalar::<Secp256k1>::group_order() - &s_bn;
+        if &rx >= Scalar::<E>::group_order() {
+            recid |= 2;
+        }
+        let s_tag_bn = Scalar::<E>::group_order() - &s_bn;
         if s_bn > s_tag_bn {
-            s = Scalar::<Secp256k1>::from(&s_tag_bn);
+            s = Scalar::<E>::from(&s_tag_bn);
             recid ^= 1;
         }
         let sig = SignatureRecid { r, s, recid };
-        let ver = verify(&sig, &self.y, &self.m).is_ok();
+        let ver = verify(&sig, y, m).is_ok();
         if ver {
             Ok(sig)
         } else {
             Err(InvalidSig)
         }
     }
+
+    /// DER encoding: SEQUENCE { INTEGER r, INTEGER s }
+    pub fn to_der(&self) -> Vec<u8> {
+        let body = [der_integer(&self.r), der_integer(&self.s)].concat();
+        [vec![0x30, body.len() as u8], body].concat()
+    }
+
+    /// r || s, 32 bytes each
+    pub fn to_compact(&self) -> [u8; 64] {
+        let mut out = [0u8; 64];
+        out[..32].copy_from_slice(&self.r.to_bytes());
+        out[32..].copy_from_slice(&self.s.to_bytes());
+        out
+    }
+
+    /// r || s || v with v = 27 + recid
+    pub fn to_recoverable(&self) -> [u8; 65] {
+        let mut out = [0u8; 65];
+        out[..64].copy_from_slice(&self.to_compact());
+        out[64] = 27 + self.recid;
+        out
+    }
+
+    // DER and compact signatures carry no recovery id, recid is set to 0
+    pub fn from_der(der: &[u8]) -> Result<Self, Error> {
+        if der.len() < 2 || der[0] != 0x30 || usize::from(der[1]) != der.len() - 2 {
+            return Err(InvalidSig);
+        }
+        let (r, rest) = der_integer_from(&der[2..])?;
+        let (s, rest) = der_integer_from(rest)?;
+        if !rest.is_empty() {
+            return Err(InvalidSig);
+        }
+        Ok(Self { r, s, recid: 0 })
+    }
+
+    pub fn from_compact(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != 64 {
+            return Err(InvalidSig);
+        }
+        Ok(Self {
+            r: scalar_from_be(&bytes[..32])?,
+            s: scalar_from_be(&bytes[32..])?,
+            recid: 0,
+        })
+    }
+
+    /// accepts v = 27 + recid as well as v = recid
+    pub fn from_recoverable(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != 65 {
+            return Err(InvalidSig);
+        }
+        let recid = match bytes[64] {
+            v @ 0..=3 => v,
+            v @ 27..=30 => v - 27,
+            _ => return Err(InvalidSig),
+        };
+        Ok(Self {
+            recid,
+            ..Self::from_compact(&bytes[..64])?
+        })
+    }
+}
+
+// r and s must lie in [1, q)
+fn scalar_from_be<E: Curve>(bytes: &[u8]) -> Result<Scalar<E>, Error> {
+    let n = BigInt::from_bytes(bytes);
+    if n.is_zero() || &n >= Scalar::<E>::group_order() {
+        return Err(InvalidSig);
+    }
+    Ok(Scalar::<E>::from(&n))
+}
+
+// strict: short form length, positive and minimally encoded
+fn der_integer_from<E: Curve>(der: &[u8]) -> Result<(Scalar<E>, &[u8]), Error> {
+    if der.len() < 3 || der[0] != 0x02 {
+        return Err(InvalidSig);
+    }
+    let len = usize::from(der[1]);
+    if len == 0 || len > 33 || der.len() < 2 + len {
+        return Err(InvalidSig);
+    }
+    let value = &der[2..2 + len];
+    let negative = value[0] & 0x80 != 0;
+    let padded = len > 1 && value[0] == 0 && value[1] & 0x80 == 0;
+    if negative || padded {
+        return Err(InvalidSig);
+    }
+    Ok((scalar_from_be(value)?, &der[2 + len..]))
+}
+
+// minimal big-endian encoding, with a leading zero if the high bit is set
+fn der_integer<E: Curve>(x: &Scalar<E>) -> Vec<u8> {
+    let bytes = x.to_bytes();
+    let start = bytes
+        .iter()
+        .position(|b| *b != 0)
+        .unwrap_or(bytes.len() - 1);
+    let mut value = bytes[start..].to_vec();
+    if value[0] & 0x80 != 0 {
+        value.insert(0, 0);
+    }
+    [vec![0x02, value.len() as u8], value].concat()
 }
 
-pub fn verify(sig: &SignatureRecid, y: &Point<Secp256k1>, message: &BigInt) -> Result<(), Error> {
+pub fn verify<E: Curve>(
+    sig: &SignatureRecid<E>,
+    y: &Point<E>,
+    message: &BigInt,
+) -> Result<(), Error> {
+    if sig.r.is_zero() {
+        return Err(InvalidSig);
+    }
     let b = sig.s.invert().ok_or(Error::InvalidSig)?;
-    let a = Scalar::<Secp256k1>::from(message);
+    let a = Scalar::<E>::from(message);
     let u1 = a * &b;
     let u2 = &sig.r * &b;
 
@@ -741,11 +1016,11 @@ pub fn verify(sig: &SignatureRecid, y: &Point<Secp256k1>, message: &BigInt) -> R
     // can be faster using shamir trick
 
     if sig.r
-        == Scalar::<Secp256k1>::from(
+        == Scalar::<E>::from(
             &(gu1 + yu2)
                 .x_coord()
                 .ok_or(Error::InvalidSig)?
-                .mod_floor(Scalar::<Secp256k1>::group_order()),
+                .mod_floor(Scalar::<E>::group_order()),
         )
     {
         Ok(())
@@ -753,3 +1028,44 @@ pub fn verify(sig: &SignatureRecid, y: &Point<Secp256k1>, message: &BigInt) -> R
         Err(InvalidSig)
     }
 }
+
+/// Public key y such that `sig` verifies for `message` under y
+pub fn recover<E: Curve>(sig: &SignatureRecid<E>, message: &BigInt) -> Result<Point<E>, Error> {
+    if sig.recid > 3 {
+        return Err(InvalidSig);
+    }
+    // R.x = r, or r + q if R.x overflowed the group order
+    let mut rx = sig.r.to_bigint();
+    if sig.recid & 2 != 0 {
+        rx += Scalar::<E>::group_order();
+    }
+    let rx: [u8; 32] = rx.to_bytes_array().ok_or(InvalidSig)?;
+    let R = Point::<E>::from_bytes(&[&[0x02 | (sig.recid & 1)], &rx[..]].concat())
+        .map_err(|_| InvalidSig)?;
+
+    // y = r^-1 (s R - m G)
+    let r_inv = sig.r.invert().ok_or(InvalidSig)?;
+    let m = Scalar::<E>::from(message);
+    let y = (R * &sig.s - Point::generator() * m) * r_inv;
+    if y.is_zero() {
+        return Err(InvalidSig);
+    }
+    verify(sig, &y, message)?;
+    Ok(y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correct_key_proof_is_bound_to_tag() {
+        let (ek, dk) = Paillier::keypair().keys();
+        let ctx = SessionContext::new("session", "keygen-round1");
+        let proof = ctx.correct_key_proof(&dk, 1);
+        assert!(proof.verify(&ek, &ctx.tag(1)).is_ok());
+        assert!(proof.verify(&ek, &ctx.tag(2)).is_err());
+        let other = SessionContext::new("other", "keygen-round1");
+        assert!(proof.verify(&ek, &other.tag(1)).is_err());
+    }
+}
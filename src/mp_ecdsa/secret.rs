@@ -0,0 +1,100 @@
+//! Wrapper for secret key material.
+//!
+//! A `Secret<T>` wipes its value when dropped and never prints it: both `Debug`
+//! and `Display` show `<redacted>`. It serializes transparently, so keys files
+//! keep their format.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+use curv::elliptic::curves::{Curve, Scalar};
+use curv::BigInt;
+use paillier::DecryptionKey;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+/// Overwrites a value in place. Implemented locally because `Zeroize` cannot be
+/// implemented for foreign types such as `Scalar` or `DecryptionKey`.
+pub trait Wipe {
+    fn wipe(&mut self);
+}
+
+impl<E: Curve> Wipe for Scalar<E> {
+    // the replaced raw scalar is zeroized by curv when it is dropped
+    fn wipe(&mut self) {
+        *self = Scalar::zero();
+    }
+}
+
+impl Wipe for BigInt {
+    fn wipe(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Wipe for DecryptionKey {
+    fn wipe(&mut self) {
+        self.p.zeroize();
+        self.q.zeroize();
+    }
+}
+
+impl Wipe for String {
+    fn wipe(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Wipe for Vec<u8> {
+    fn wipe(&mut self) {
+        self.zeroize();
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T: Wipe>(T);
+
+impl<T: Wipe> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Wipe> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: Wipe> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Wipe> DerefMut for Secret<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: Wipe> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.wipe();
+    }
+}
+
+impl<T: Wipe> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+impl<T: Wipe> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
@@ -0,0 +1,362 @@
+//! Structural checks on round messages received from other parties.
+//!
+//! Every message is validated right after deserialization and before it enters any
+//! protocol math. Points are already checked to lie on the curve when deserialized,
+//! so here we reject the identity, zero scalars, Paillier values outside their group
+//! and vectors of the wrong length.
+
+use curv::arithmetic::traits::*;
+use curv::cryptographic_primitives::proofs::sigma_correct_homomorphic_elgamal_enc::HomoELGamalProof;
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::elliptic::curves::{Curve, Point, Scalar};
+use curv::BigInt;
+use paillier::EncryptionKey;
+use sha2::Sha256;
+use thiserror::Error;
+use zk_paillier::zkproofs::NiCorrectKeyProof;
+
+use super::feldman_vss::{ShamirSecretSharing, VerifiableSS};
+use super::mta::ot::{self, BaseOtChoice, OtMulReply, OtMulRequest};
+use super::mta::{MessageA, MessageB, MtaCheck, OtMtaRequest};
+use super::party_i::*;
+
+// Paillier::keypair() samples two 1024-bit primes
+const PAILLIER_MIN_BIT_LENGTH: usize = 2047;
+// number of n-th roots in NiCorrectKeyProof
+const CORRECT_KEY_PROOF_LEN: usize = 11;
+
+#[derive(Error, PartialEq, Clone, Debug)]
+pub enum ValidationError {
+    #[error("{0} is the point at infinity")]
+    IdentityPoint(&'static str),
+    #[error("{0} is zero")]
+    ZeroScalar(&'static str),
+    #[error("{0} is out of range")]
+    OutOfRange(&'static str),
+    #[error("{0} has length {1}, expected {2}")]
+    BadLength(&'static str, usize, usize),
+    #[error("{0} has parameters {1:?}, expected {2:?}")]
+    BadParameters(&'static str, ShamirSecretSharing, ShamirSecretSharing),
+}
+
+pub trait Validate {
+    /// What the receiver must already know to validate the message,
+    /// e.g. the threshold parameters or the Paillier key a ciphertext is under.
+    type Context;
+
+    fn validate(&self, ctx: &Self::Context) -> Result<(), ValidationError>;
+}
+
+pub fn check_point<E: Curve>(p: &Point<E>, what: &'static str) -> Result<(), ValidationError> {
+    if p.is_zero() {
+        Err(ValidationError::IdentityPoint(what))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn check_scalar<E: Curve>(s: &Scalar<E>, what: &'static str) -> Result<(), ValidationError> {
+    if s.is_zero() {
+        Err(ValidationError::ZeroScalar(what))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn check_len(len: usize, expected: usize, what: &'static str) -> Result<(), ValidationError> {
+    if len != expected {
+        Err(ValidationError::BadLength(what, len, expected))
+    } else {
+        Ok(())
+    }
+}
+
+// commitments and blind factors are non-negative hash outputs / samples
+fn check_non_negative(n: &BigInt, what: &'static str) -> Result<(), ValidationError> {
+    if n < &BigInt::zero() {
+        Err(ValidationError::OutOfRange(what))
+    } else {
+        Ok(())
+    }
+}
+
+/// c must be an element of Z*_{N^2}
+pub fn check_ciphertext(
+    c: &BigInt,
+    ek: &EncryptionKey,
+    what: &'static str,
+) -> Result<(), ValidationError> {
+    if c <= &BigInt::zero() || c >= &ek.nn || c.gcd(&ek.n) != BigInt::one() {
+        Err(ValidationError::OutOfRange(what))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn check_encryption_key(ek: &EncryptionKey) -> Result<(), ValidationError> {
+    if ek.n.bit_length() < PAILLIER_MIN_BIT_LENGTH || ek.n.is_even() || ek.nn != &ek.n * &ek.n {
+        Err(ValidationError::OutOfRange("paillier key"))
+    } else {
+        Ok(())
+    }
+}
+
+impl Validate for KeyGenBroadcastMessage1 {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_encryption_key(&self.e)?;
+        check_non_negative(&self.com, "com")?;
+        self.correct_key_proof.validate(&self.e)
+    }
+}
+
+impl Validate for NiCorrectKeyProof {
+    type Context = EncryptionKey;
+
+    fn validate(&self, ek: &EncryptionKey) -> Result<(), ValidationError> {
+        check_len(
+            self.sigma_vec.len(),
+            CORRECT_KEY_PROOF_LEN,
+            "correct_key_proof",
+        )?;
+        if self
+            .sigma_vec
+            .iter()
+            .any(|sigma| sigma <= &BigInt::zero() || sigma >= &ek.n)
+        {
+            return Err(ValidationError::OutOfRange("correct_key_proof"));
+        }
+        Ok(())
+    }
+}
+
+impl<E: Curve> Validate for KeyGenDecommitMessage1<E> {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_non_negative(&self.blind_factor, "blind_factor")?;
+        check_point(&self.y_i, "y_i")
+    }
+}
+
+impl Validate for ChainCodeCommitMessage {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_non_negative(&self.com, "chain_code_com")
+    }
+}
+
+impl Validate for ChainCodeDecommitMessage {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_non_negative(&self.blind_factor, "blind_factor")
+    }
+}
+
+impl<E: Curve> Validate for VerifiableSS<E> {
+    type Context = ShamirSecretSharing;
+
+    fn validate(&self, params: &ShamirSecretSharing) -> Result<(), ValidationError> {
+        if &self.parameters != params {
+            return Err(ValidationError::BadParameters(
+                "vss_scheme",
+                self.parameters.clone(),
+                params.clone(),
+            ));
+        }
+        check_len(
+            self.commitments.len(),
+            usize::from(params.threshold) + 1,
+            "vss_scheme.commitments",
+        )?;
+        self.commitments
+            .iter()
+            .try_for_each(|c| check_point(c, "vss_scheme.commitments"))
+    }
+}
+
+impl<E: Curve> Validate for DLogProof<E, Sha256> {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_point(&self.pk, "dlog_proof.pk")?;
+        check_point(
+            &self.pk_t_rand_commitment,
+            "dlog_proof.pk_t_rand_commitment",
+        )
+    }
+}
+
+impl<E: Curve> Validate for HomoELGamalProof<E, Sha256> {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_point(&self.T, "elgamal_proof.T")?;
+        check_point(&self.A3, "elgamal_proof.A3")
+    }
+}
+
+impl Validate for MessageA {
+    /// Alice's (the sender's) Paillier key
+    type Context = EncryptionKey;
+
+    fn validate(&self, alice_ek: &EncryptionKey) -> Result<(), ValidationError> {
+        check_ciphertext(&self.c, alice_ek, "m_a.c")
+    }
+}
+
+impl<E: Curve> Validate for MessageB<E> {
+    /// Alice's (the receiver's) Paillier key
+    type Context = EncryptionKey;
+
+    fn validate(&self, alice_ek: &EncryptionKey) -> Result<(), ValidationError> {
+        check_ciphertext(&self.c, alice_ek, "m_b.c")?;
+        self.b_proof.validate(&())?;
+        self.beta_tag_proof.validate(&())
+    }
+}
+
+impl<E: Curve> Validate for BaseOtChoice<E> {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_len(self.R.len(), ot::KAPPA, "base_ot.R")?;
+        self.R.iter().try_for_each(|R| check_point(R, "base_ot.R"))
+    }
+}
+
+impl Validate for OtMulRequest {
+    /// number of Bob's inputs
+    type Context = usize;
+
+    fn validate(&self, count: &usize) -> Result<(), ValidationError> {
+        check_len(self.u.len(), ot::KAPPA * ot::rows(*count) / 4, "ot.u")?;
+        check_len(self.x_check.len(), 32, "ot.x_check")?;
+        check_len(self.t_check.len(), 32, "ot.t_check")
+    }
+}
+
+impl<E: Curve> Validate for OtMulReply<E> {
+    /// number of Bob's inputs
+    type Context = usize;
+
+    fn validate(&self, count: &usize) -> Result<(), ValidationError> {
+        check_len(self.tau.len(), count * ot::XI, "ot.tau")?;
+        check_len(self.r.len(), count * ot::XI, "ot.r")
+    }
+}
+
+impl<E: Curve> Validate for OtMtaRequest<E> {
+    type Context = usize;
+
+    fn validate(&self, count: &usize) -> Result<(), ValidationError> {
+        self.request.validate(count)?;
+        self.gamma_proof.validate(&())?;
+        self.w_proof.validate(&())
+    }
+}
+
+impl<E: Curve> Validate for MtaCheck<E> {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_point(&self.beta, "mta_check.beta")?;
+        check_point(&self.ni, "mta_check.ni")
+    }
+}
+
+impl Validate for SignBroadcastPhase1 {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_non_negative(&self.com, "com")
+    }
+}
+
+impl<E: Curve> Validate for SignDecommitPhase1<E> {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_non_negative(&self.blind_factor, "blind_factor")?;
+        check_point(&self.g_gamma_i, "g_gamma_i")
+    }
+}
+
+impl Validate for Phase5Com1 {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_non_negative(&self.com, "com")
+    }
+}
+
+impl Validate for Phase5Com2 {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_non_negative(&self.com, "com")
+    }
+}
+
+impl<E: Curve> Validate for Phase5ADecom1<E> {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_point(&self.V_i, "V_i")?;
+        check_point(&self.A_i, "A_i")?;
+        check_point(&self.B_i, "B_i")?;
+        check_non_negative(&self.blind_factor, "blind_factor")
+    }
+}
+
+impl<E: Curve> Validate for Phase5DDecom2<E> {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_point(&self.u_i, "u_i")?;
+        check_point(&self.t_i, "t_i")?;
+        check_non_negative(&self.blind_factor, "blind_factor")
+    }
+}
+
+/// delta_i and s_i
+impl<E: Curve> Validate for Scalar<E> {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_scalar(self, "scalar")
+    }
+}
+
+impl<A: Validate, B: Validate> Validate for (A, B) {
+    type Context = (A::Context, B::Context);
+
+    fn validate(&self, ctx: &Self::Context) -> Result<(), ValidationError> {
+        self.0.validate(&ctx.0)?;
+        self.1.validate(&ctx.1)
+    }
+}
+
+impl<A: Validate, B: Validate, C: Validate> Validate for (A, B, C) {
+    type Context = (A::Context, B::Context, C::Context);
+
+    fn validate(&self, ctx: &Self::Context) -> Result<(), ValidationError> {
+        self.0.validate(&ctx.0)?;
+        self.1.validate(&ctx.1)?;
+        self.2.validate(&ctx.2)
+    }
+}
+
+/// One message for each signature of a batch; the context is the batch size and
+/// the context of each message.
+impl<T: Validate> Validate for Vec<T> {
+    type Context = (usize, T::Context);
+
+    fn validate(&self, ctx: &Self::Context) -> Result<(), ValidationError> {
+        check_len(self.len(), ctx.0, "batch")?;
+        self.iter().try_for_each(|msg| msg.validate(&ctx.1))
+    }
+}
@@ -0,0 +1,483 @@
+/*
+    Two-party ECDSA
+
+    Y. Lindell, "Fast Secure Two-Party ECDSA Signing", CRYPTO 2017,
+    https://eprint.iacr.org/2017/552
+
+    The key is shared multiplicatively, x = x_1 * x_2. Party one holds a Paillier key
+    and party two c_key = enc(x_1) under it, so that party two alone can compute an
+    encryption of s = k_2^-1 * (m + r * x), which party one decrypts and divides by
+    k_1. Party one checks the signature before releasing it.
+
+    c_key is proven with the non-interactive Π^pdl-with-slack of multi-party-ecdsa,
+    against ring-Pedersen parameters of party two shown with Π^prm of CGGMP21; the
+    interactive Π^pdl would cost keygen three more messages. With the slack, x_1 is
+    only known to lie in ±q^3, which is harmless: the plaintext of c_3 stays far below
+    the Paillier modulus, so it never wraps around.
+*/
+
+#![allow(non_snake_case)]
+
+use bip32::ChainCode;
+use curv::arithmetic::traits::*;
+use curv::cryptographic_primitives::commitments::hash_commitment::HashCommitment;
+use curv::cryptographic_primitives::commitments::traits::Commitment;
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::elliptic::curves::{secp256_k1::Secp256k1, Point, Scalar};
+use curv::BigInt;
+use multi_party_ecdsa::utilities::zk_pdl_with_slack::{
+    PDLwSlackProof, PDLwSlackStatement, PDLwSlackWitness,
+};
+use paillier::{DecryptionKey, EncryptionKey, KeyGeneration, Paillier};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use zk_paillier::zkproofs::NiCorrectKeyProof;
+
+use crate::mp_cggmp::{self, aux::RingPedersen, zk::prm::PrmProof};
+use crate::mp_ecdsa::paillier_serde;
+use crate::mp_ecdsa::party_i::{verify, SessionContext, SignatureRecid};
+use crate::mp_ecdsa::secret::Secret;
+use crate::mp_ecdsa::validate::{
+    check_ciphertext, check_encryption_key, check_scalar, Validate, ValidationError,
+};
+
+/// protocol name recorded in two-party keys files
+pub const PROTOCOL: &str = "lindell17";
+
+const SECURITY: usize = 256;
+
+#[derive(Error, PartialEq, Eq, Clone, Debug)]
+pub enum Error {
+    #[error("opened another commitment")]
+    WrongOpening,
+    #[error("invalid {0} proof")]
+    InvalidProof(&'static str),
+    #[error("invalid signature")]
+    InvalidSig,
+}
+
+/// Keys file of a two-party key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TwoPartyKeys {
+    pub protocol: String,
+    /// 1 for party one, which holds the Paillier key, 2 for party two
+    pub party_id: u16,
+    pub x_i: Secret<Scalar<Secp256k1>>,
+    /// on party one only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dk: Option<Secret<DecryptionKey>>,
+    pub ek: EncryptionKey,
+    /// enc(x_1) under `ek`
+    pub c_key: BigInt,
+    pub y_sum: Point<Secp256k1>,
+    pub chain_code: ChainCode,
+}
+
+// hash commitment to tag || point || extra
+fn commit(tag: &[u8], point: &Point<Secp256k1>, extra: &[u8], blind: &BigInt) -> BigInt {
+    HashCommitment::<Sha256>::create_commitment_with_user_defined_randomness(
+        &BigInt::from_bytes(&[tag, &point.to_bytes(true), extra].concat()),
+        blind,
+    )
+}
+
+fn check(ok: bool, err: Error) -> Result<(), Error> {
+    if ok {
+        Ok(())
+    } else {
+        Err(err)
+    }
+}
+
+fn verify_dlog(proof: &DLogProof<Secp256k1, Sha256>) -> Result<(), Error> {
+    DLogProof::verify(proof).map_err(|_| Error::InvalidProof("dlog"))
+}
+
+// chain_code = SHA256 of both contributions, as for GG18 keys
+fn chain_code(cc_1: &[u8; 32], cc_2: &[u8; 32]) -> ChainCode {
+    Sha256::new()
+        .chain(b"MPC_HD_GG18 chain code")
+        .chain(cc_1)
+        .chain(cc_2)
+        .finalize()
+        .into()
+}
+
+/// Round 1 of keygen from party one: commitment to Q_1 = x_1 * G and its chain code
+/// contribution
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyGenCommit {
+    pub com: BigInt,
+}
+
+/// Round 1 of keygen from party two: Q_2 = x_2 * G with its proof, its chain code
+/// contribution and the ring-Pedersen parameters party one proves c_key against
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyGenParty2Msg {
+    pub proof: DLogProof<Secp256k1, Sha256>,
+    pub cc: [u8; 32],
+    pub ring_pedersen: RingPedersen,
+    pub prm_proof: PrmProof,
+}
+
+/// Round 2 of keygen from party one: opening of its commitment, its Paillier key and
+/// c_key = enc(x_1) with their proofs
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyGenParty1Msg {
+    pub proof: DLogProof<Secp256k1, Sha256>,
+    pub cc: [u8; 32],
+    pub blind: BigInt,
+    #[serde(with = "paillier_serde::encryption_key")]
+    pub ek: EncryptionKey,
+    pub c_key: BigInt,
+    #[serde(with = "paillier_serde::correct_key_proof")]
+    pub correct_key_proof: NiCorrectKeyProof,
+    pub pdl_proof: PDLwSlackProof,
+}
+
+pub struct PartyOneKeyGen {
+    x_1: Secret<Scalar<Secp256k1>>,
+    cc: [u8; 32],
+    blind: BigInt,
+}
+
+impl PartyOneKeyGen {
+    /// Proofs and commitments are bound to the session through `ctx`.
+    pub fn new(ctx: &SessionContext) -> (Self, KeyGenCommit) {
+        let x_1 = Scalar::<Secp256k1>::random();
+        let cc: [u8; 32] = rand::random();
+        let blind = BigInt::sample(SECURITY);
+        let com = commit(&ctx.tag(1), &(Point::generator() * &x_1), &cc, &blind);
+        let party = Self {
+            x_1: Secret::new(x_1),
+            cc,
+            blind,
+        };
+        (party, KeyGenCommit { com })
+    }
+
+    /// Checks the message of party two, then opens the commitment and encrypts x_1.
+    pub fn round2(
+        &self,
+        msg: &KeyGenParty2Msg,
+        ctx: &SessionContext,
+    ) -> Result<(KeyGenParty1Msg, TwoPartyKeys), Error> {
+        verify_dlog(&msg.proof)?;
+        msg.prm_proof
+            .verify(&ctx.tag(2), &msg.ring_pedersen)
+            .map_err(|_| Error::InvalidProof("prm"))?;
+
+        let (ek, dk) = Paillier::keypair().keys();
+        let (c_key, r) = mp_cggmp::encrypt(&ek, &self.x_1.to_bigint());
+        let proof = DLogProof::<Secp256k1, Sha256>::prove(&self.x_1);
+        let statement = PDLwSlackStatement {
+            ciphertext: c_key.clone(),
+            ek: ek.clone(),
+            Q: proof.pk.clone(),
+            G: Point::generator().to_point(),
+            h1: msg.ring_pedersen.s.clone(),
+            h2: msg.ring_pedersen.t.clone(),
+            N_tilde: msg.ring_pedersen.n.clone(),
+        };
+        let witness = PDLwSlackWitness {
+            x: (*self.x_1).clone(),
+            r,
+        };
+        let reply = KeyGenParty1Msg {
+            proof,
+            cc: self.cc,
+            blind: self.blind.clone(),
+            correct_key_proof: ctx.correct_key_proof(&dk, 1),
+            pdl_proof: PDLwSlackProof::prove(&witness, &statement),
+            ek: ek.clone(),
+            c_key: c_key.clone(),
+        };
+        let keys = TwoPartyKeys {
+            protocol: PROTOCOL.to_string(),
+            party_id: 1,
+            x_i: self.x_1.clone(),
+            dk: Some(Secret::new(dk)),
+            ek,
+            c_key,
+            y_sum: &msg.proof.pk * &*self.x_1,
+            chain_code: chain_code(&self.cc, &msg.cc),
+        };
+        Ok((reply, keys))
+    }
+}
+
+pub struct PartyTwoKeyGen {
+    x_2: Secret<Scalar<Secp256k1>>,
+    cc: [u8; 32],
+    ring_pedersen: RingPedersen,
+}
+
+impl PartyTwoKeyGen {
+    /// Generates ring-Pedersen parameters over safe primes, which takes a while.
+    pub fn new(ctx: &SessionContext) -> (Self, KeyGenParty2Msg) {
+        let x_2 = Scalar::<Secp256k1>::random();
+        let cc: [u8; 32] = rand::random();
+        let (_, dk) = Paillier::keypair_safe_primes().keys();
+        let (ring_pedersen, lambda) = RingPedersen::generate(&dk);
+        let msg = KeyGenParty2Msg {
+            proof: DLogProof::prove(&x_2),
+            cc,
+            prm_proof: PrmProof::prove(&ctx.tag(2), &ring_pedersen, &lambda, &dk),
+            ring_pedersen: ring_pedersen.clone(),
+        };
+        let party = Self {
+            x_2: Secret::new(x_2),
+            cc,
+            ring_pedersen,
+        };
+        (party, msg)
+    }
+
+    /// Checks the opening of party one and its proofs for c_key.
+    pub fn finish(
+        &self,
+        com: &KeyGenCommit,
+        msg: &KeyGenParty1Msg,
+        ctx: &SessionContext,
+    ) -> Result<TwoPartyKeys, Error> {
+        check(
+            commit(&ctx.tag(1), &msg.proof.pk, &msg.cc, &msg.blind) == com.com,
+            Error::WrongOpening,
+        )?;
+        verify_dlog(&msg.proof)?;
+        msg.correct_key_proof
+            .verify(&msg.ek, &ctx.tag(1))
+            .map_err(|_| Error::InvalidProof("correct key"))?;
+        let statement = PDLwSlackStatement {
+            ciphertext: msg.c_key.clone(),
+            ek: msg.ek.clone(),
+            Q: msg.proof.pk.clone(),
+            G: Point::generator().to_point(),
+            h1: self.ring_pedersen.s.clone(),
+            h2: self.ring_pedersen.t.clone(),
+            N_tilde: self.ring_pedersen.n.clone(),
+        };
+        msg.pdl_proof
+            .verify(&statement)
+            .map_err(|_| Error::InvalidProof("pdl with slack"))?;
+        Ok(TwoPartyKeys {
+            protocol: PROTOCOL.to_string(),
+            party_id: 2,
+            x_i: self.x_2.clone(),
+            dk: None,
+            ek: msg.ek.clone(),
+            c_key: msg.c_key.clone(),
+            y_sum: &msg.proof.pk * &*self.x_2,
+            chain_code: chain_code(&msg.cc, &self.cc),
+        })
+    }
+}
+
+/// Round 1 of signing from party one: commitment to R_1 = k_1 * G
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignCommit {
+    pub com: BigInt,
+}
+
+/// Round 1 of signing from party two: R_2 = k_2 * G with its proof
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignParty2Msg1 {
+    pub proof: DLogProof<Secp256k1, Sha256>,
+}
+
+/// Round 2 of signing from party one: opening of R_1 with its proof
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignParty1Msg2 {
+    pub proof: DLogProof<Secp256k1, Sha256>,
+    pub blind: BigInt,
+}
+
+/// Round 3 of signing from party two: c_3 = enc(k_2^-1 * (m + r * x) + rho * q)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignParty2Msg2 {
+    pub c3: BigInt,
+}
+
+pub struct PartyOneSign {
+    k_1: Secret<Scalar<Secp256k1>>,
+    blind: BigInt,
+    R_2: Point<Secp256k1>,
+}
+
+impl PartyOneSign {
+    pub fn new(tag: &[u8]) -> (Self, SignCommit) {
+        let k_1 = Scalar::<Secp256k1>::random();
+        let blind = BigInt::sample(SECURITY);
+        let com = commit(tag, &(Point::generator() * &k_1), &[], &blind);
+        let party = Self {
+            k_1: Secret::new(k_1),
+            blind,
+            R_2: Point::zero(),
+        };
+        (party, SignCommit { com })
+    }
+
+    /// Checks R_2 and opens R_1.
+    pub fn round2(&mut self, msg: &SignParty2Msg1) -> Result<SignParty1Msg2, Error> {
+        verify_dlog(&msg.proof)?;
+        self.R_2 = msg.proof.pk.clone();
+        Ok(SignParty1Msg2 {
+            proof: DLogProof::prove(&self.k_1),
+            blind: self.blind.clone(),
+        })
+    }
+
+    /// Decrypts c_3 into the signature under `y`, the public key at the signed path.
+    pub fn finish(
+        &self,
+        keys: &TwoPartyKeys,
+        msg: &SignParty2Msg2,
+        message: &BigInt,
+        y: &Point<Secp256k1>,
+    ) -> Result<SignatureRecid<Secp256k1>, Error> {
+        let dk = keys.dk.as_ref().expect("party one holds the Paillier key");
+        let R = &self.R_2 * &*self.k_1;
+        let s_tag = Scalar::<Secp256k1>::from_bigint(&mp_cggmp::decrypt(dk, &msg.c3));
+        let s = s_tag * self.k_1.invert().ok_or(Error::InvalidSig)?;
+        SignatureRecid::new(&R, s, y, message).map_err(|_| Error::InvalidSig)
+    }
+}
+
+pub struct PartyTwoSign {
+    k_2: Secret<Scalar<Secp256k1>>,
+    R: Point<Secp256k1>,
+}
+
+impl PartyTwoSign {
+    pub fn new() -> (Self, SignParty2Msg1) {
+        let k_2 = Scalar::<Secp256k1>::random();
+        let msg = SignParty2Msg1 {
+            proof: DLogProof::prove(&k_2),
+        };
+        let party = Self {
+            k_2: Secret::new(k_2),
+            R: Point::zero(),
+        };
+        (party, msg)
+    }
+
+    /// Checks the opening of R_1 and encrypts the signature. `tweak_sk` is the HD
+    /// tweak of the signed path: the key there is x + tweak_sk, so r * tweak_sk is
+    /// added to the plaintext next to m.
+    pub fn round3(
+        &mut self,
+        keys: &TwoPartyKeys,
+        com: &SignCommit,
+        msg: &SignParty1Msg2,
+        tag: &[u8],
+        message: &BigInt,
+        tweak_sk: &Scalar<Secp256k1>,
+    ) -> Result<SignParty2Msg2, Error> {
+        check(
+            commit(tag, &msg.proof.pk, &[], &msg.blind) == com.com,
+            Error::WrongOpening,
+        )?;
+        verify_dlog(&msg.proof)?;
+        self.R = &msg.proof.pk * &*self.k_2;
+        let q = Scalar::<Secp256k1>::group_order();
+        let r = Scalar::<Secp256k1>::from_bigint(
+            &self.R.x_coord().ok_or(Error::InvalidSig)?.mod_floor(q),
+        );
+        let k_2_inv = self.k_2.invert().ok_or(Error::InvalidSig)?;
+        let m = Scalar::<Secp256k1>::from_bigint(message);
+        let v = &k_2_inv * (m + &r * tweak_sk);
+        let rho = BigInt::sample_below(&(q * q));
+        let (c1, _) = mp_cggmp::encrypt(&keys.ek, &(v.to_bigint() + rho * q));
+        let w = &k_2_inv * &r * &*keys.x_i;
+        let c2 = mp_cggmp::mul(&keys.ek, &keys.c_key, &w.to_bigint());
+        Ok(SignParty2Msg2 {
+            c3: mp_cggmp::add(&keys.ek, &c1, &c2),
+        })
+    }
+
+    /// Checks the signature party one finished.
+    pub fn finish(
+        &self,
+        sig: &SignatureRecid<Secp256k1>,
+        message: &BigInt,
+        y: &Point<Secp256k1>,
+    ) -> Result<(), Error> {
+        let rx = self.R.x_coord().ok_or(Error::InvalidSig)?;
+        check(
+            sig.r.to_bigint() == rx.mod_floor(Scalar::<Secp256k1>::group_order())
+                && verify(sig, y, message).is_ok(),
+            Error::InvalidSig,
+        )
+    }
+}
+
+impl Validate for KeyGenCommit {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
+impl Validate for KeyGenParty2Msg {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        self.proof.validate(&())?;
+        self.ring_pedersen.validate(&())
+    }
+}
+
+impl Validate for KeyGenParty1Msg {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        self.proof.validate(&())?;
+        check_encryption_key(&self.ek)?;
+        self.correct_key_proof.validate(&self.ek)?;
+        check_ciphertext(&self.c_key, &self.ek, "c_key")
+    }
+}
+
+impl Validate for SignCommit {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        Ok(())
+    }
+}
+
+impl Validate for SignParty2Msg1 {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        self.proof.validate(&())
+    }
+}
+
+impl Validate for SignParty1Msg2 {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        self.proof.validate(&())
+    }
+}
+
+impl Validate for SignParty2Msg2 {
+    /// Paillier key of party one
+    type Context = EncryptionKey;
+
+    fn validate(&self, ek: &EncryptionKey) -> Result<(), ValidationError> {
+        check_ciphertext(&self.c3, ek, "c3")
+    }
+}
+
+impl Validate for SignatureRecid<Secp256k1> {
+    type Context = ();
+
+    fn validate(&self, _: &()) -> Result<(), ValidationError> {
+        check_scalar(&self.r, "r")?;
+        check_scalar(&self.s, "s")
+    }
+}
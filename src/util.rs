@@ -1,14 +1,17 @@
 use std::sync::OnceLock;
 
-use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec};
+use miniz_oxide::{
+    deflate::compress_to_vec,
+    inflate::{decompress_to_vec, decompress_to_vec_with_limit},
+};
 use serde::{de::DeserializeOwned, Serialize};
 use sqlx::{mysql::MySqlPoolOptions, MySqlPool, Row};
 
 // Table: zengo_fn
 // Fields: mod, fn, param, session_id, member_id, param_value
 pub static DB: OnceLock<MySqlPool> = OnceLock::new();
-pub static mut SESSION_ID: OnceLock<String> = OnceLock::new();
-pub static mut MEMBER_ID: OnceLock<u16> = OnceLock::new();
+pub static SESSION_ID: OnceLock<String> = OnceLock::new();
+pub static MEMBER_ID: OnceLock<u16> = OnceLock::new();
 pub static INSERT_TEMPLATE: &str = r"
     INSERT INTO zengo_fn
         (mod, fn, param, session_id, member_id, param_value)
@@ -34,8 +37,8 @@ where
     T: Serialize + DeserializeOwned,
 {
     let param_bytes = param_value.compress();
-    let session_id = unsafe { SESSION_ID.get().unwrap() };
-    let member_id = unsafe { MEMBER_ID.get().unwrap() };
+    let session_id = SESSION_ID.get().unwrap();
+    let member_id = MEMBER_ID.get().unwrap();
     let db = DB.get().unwrap();
     let future = sqlx::query(INSERT_TEMPLATE)
         .bind(module)
@@ -70,6 +73,15 @@ where
     param_values
 }
 
+pub fn deflate(bytes: &[u8]) -> Vec<u8> {
+    compress_to_vec(bytes, 7)
+}
+
+/// None if `bytes` is not deflate or inflates to more than `limit` bytes
+pub fn inflate(bytes: &[u8], limit: usize) -> Option<Vec<u8>> {
+    decompress_to_vec_with_limit(bytes, limit).ok()
+}
+
 pub trait CompressAble<T> {
     fn compress(&self) -> Vec<u8>;
 }
@@ -84,8 +96,7 @@ where
 {
     fn compress(&self) -> Vec<u8> {
         let json = serde_json::to_string(&self).unwrap();
-        let bytes = compress_to_vec(json.as_bytes(), 7);
-        bytes
+        deflate(json.as_bytes())
     }
 }
 